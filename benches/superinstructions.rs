@@ -0,0 +1,54 @@
+//! Compares the evaluation of the examples with and without the
+//! superinstructions of [`Interpreter::with_superinstructions`], run with
+//! `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use lipsum::{
+    interpreter::{Collect, Context, Interpreter},
+    parser::parse,
+};
+
+static EXAMPLES: [(&str, &str); 3] = [
+    ("fib", include_str!("../examples/fib.rinha")),
+    ("sum", include_str!("../examples/sum.rinha")),
+    ("combination", include_str!("../examples/combination.rinha")),
+];
+
+/// How long the benchmark of each example runs, at least.
+static BUDGET: Duration = Duration::from_millis(500);
+
+/// Average time taken to evaluate `source`.
+fn measure(source: &str, superinstructions: bool) -> Duration {
+    let file = parse(source, "bench").unwrap();
+    let start = Instant::now();
+    let mut runs = 0;
+
+    while start.elapsed() < BUDGET {
+        let mut interpreter = Interpreter::new(Collect::default())
+            .with_memoization(false)
+            .with_superinstructions(superinstructions);
+
+        interpreter
+            .eval(file.expression.clone(), &mut Context::new())
+            .unwrap();
+        runs += 1;
+    }
+
+    start.elapsed() / runs
+}
+
+fn main() {
+    println!(
+        "{:12}  {:>12}  {:>12}  {:>7}",
+        "example", "terms", "fused", "speedup"
+    );
+
+    for (name, source) in EXAMPLES {
+        let terms = measure(source, false);
+        let fused = measure(source, true);
+        let speedup = terms.as_secs_f64() / fused.as_secs_f64();
+
+        println!("{name:12}  {terms:>12.2?}  {fused:>12.2?}  {speedup:>6.2}x");
+    }
+}
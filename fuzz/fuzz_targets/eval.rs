@@ -0,0 +1,19 @@
+#![no_main]
+
+use std::time::Duration;
+
+use libfuzzer_sys::fuzz_target;
+use lipsum::{
+    ast::Term,
+    interpreter::{Collect, Context, Interpreter},
+};
+
+// Evaluating any program must end with a value or a runtime error, never
+// panic. The limits keep programs that never stop from hanging the fuzzer.
+fuzz_target!(|term: Term| {
+    let mut interpreter = Interpreter::new(Collect::default())
+        .with_fuel(10_000)
+        .with_timeout(Duration::from_secs(1));
+
+    let _ = interpreter.eval(term, &mut Context::new());
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lipsum::{ast::File, parse};
+
+// Reading programs must fail with an error, never panic.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        let _ = parse(source, "fuzz");
+    }
+
+    let _ = serde_json::from_slice::<File>(data);
+});
@@ -0,0 +1,63 @@
+/*
+ * C bindings of the lipsum rinha interpreter, see src/ffi.rs.
+ *
+ * Build the library with
+ *
+ *     cargo rustc --lib --release --features ffi --crate-type cdylib
+ *
+ * Strings returned by these functions are owned by the caller and freed with
+ * rinha_string_free.
+ */
+
+#ifndef RINHA_H
+#define RINHA_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Outcome of rinha_eval_source. */
+typedef struct RinhaResult RinhaResult;
+
+/*
+ * Evaluates rinha source code, collecting what it prints instead of writing
+ * it to stdout. Returns NULL when source or filename are NULL or not valid
+ * UTF-8.
+ */
+RinhaResult *rinha_eval_source(const char *source, const char *filename);
+
+/* Whether the program ran without errors. */
+bool rinha_result_is_ok(const RinhaResult *result);
+
+/* The final value of the program as text, or NULL when it failed. */
+char *rinha_value_to_string(const RinhaResult *result);
+
+/* Everything the program printed, one value per line. */
+char *rinha_result_output(const RinhaResult *result);
+
+/* Code of the error that stopped the program, like "E0007", or NULL. */
+char *rinha_error_code(const RinhaResult *result);
+
+/* Message of the error that stopped the program, in English, or NULL. */
+char *rinha_error_message(const RinhaResult *result);
+
+/*
+ * Byte range of the source where the error happened. Returns false, leaving
+ * start and end untouched, when the program did not fail.
+ */
+bool rinha_error_span(const RinhaResult *result, size_t *start, size_t *end);
+
+/* Frees a result returned by rinha_eval_source. */
+void rinha_result_free(RinhaResult *result);
+
+/* Frees a string returned by any of these functions. */
+void rinha_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RINHA_H */
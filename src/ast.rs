@@ -1,10 +1,22 @@
+pub mod build;
+pub mod visit;
+
 use std::hash::Hash;
-use std::{fmt::Debug, rc::Rc};
+use std::{
+    fmt::{Debug, Display},
+    mem::ManuallyDrop,
+    ops::Range,
+    ptr,
+    rc::Rc,
+    sync::Arc,
+};
 
 /// File definition, it contains all the statements,
 /// the module name, and a base location for it as anchor
 /// for the statements.
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct File {
     pub name: String,
     pub expression: Term,
@@ -23,7 +35,9 @@ impl<T: Element> Element for Box<T> {
     }
 }
 
-#[derive(Default, Hash, PartialEq, Eq, Clone, serde::Deserialize)]
+#[derive(Default, Hash, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Location {
     pub start: usize,
     pub end: usize,
@@ -39,6 +53,29 @@ impl Location {
             filename: filename.into(),
         }
     }
+
+    /// Byte range of the source file covered by the location.
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Line and column where the location starts in `source`, both counted
+    /// from 1, the column in characters rather than in bytes.
+    pub fn line_column(&self, source: &str) -> (usize, usize) {
+        let before = &source[..self.start.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    /// `filename:line:column` of the start of the location in `source`, the
+    /// way errors are written for people.
+    pub fn position(&self, source: &str) -> String {
+        let (line, column) = self.line_column(source);
+
+        format!("{}:{line}:{column}", self.filename)
+    }
 }
 
 impl Debug for Location {
@@ -47,14 +84,31 @@ impl Debug for Location {
     }
 }
 
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}..{}", self.filename, self.start, self.end)
+    }
+}
+
 /// An element. It can be a declaration, or a term.
 pub trait Element {
     fn location(&self) -> &Location;
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Var {
-    pub text: String,
+    /// Shared between the occurrences of the name when the term went through
+    /// an [`Interner`](crate::intern::Interner).
+    pub text: Arc<str>,
+    /// Type written for a parameter or a `let` binding, like in
+    /// `let y: str = ...`. The interpreter ignores it.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub annotation: Option<Annotation>,
     pub location: Location,
 }
 
@@ -64,7 +118,29 @@ impl Element for Var {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+/// Type written in the source code, checked by
+/// [`Checker`](crate::typecheck::Checker).
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "json", serde(tag = "kind"))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Annotation {
+    Int,
+    Str,
+    Bool,
+    Tuple {
+        first: Box<Annotation>,
+        second: Box<Annotation>,
+    },
+    Function {
+        parameters: Vec<Annotation>,
+        result: Box<Annotation>,
+    },
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct If {
     pub condition: Box<Term>,
     pub then: Box<Term>,
@@ -72,7 +148,9 @@ pub struct If {
     pub location: Location,
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Let {
     pub name: Var,
     pub value: Box<Term>,
@@ -81,9 +159,14 @@ pub struct Let {
 }
 
 /// Int is a integer value like `0`, `1`, `2`, etc.
-#[derive(Default, Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Str {
-    pub value: String,
+    /// Shared between the occurrences of the literal when the term went
+    /// through an [`Interner`](crate::intern::Interner), and with the values
+    /// it evaluates to.
+    pub value: Arc<str>,
 
     /// The location of the source in the source code.
     pub location: Location,
@@ -95,7 +178,9 @@ impl Element for Str {
     }
 }
 
-#[derive(Default, Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Bool {
     pub value: bool,
     pub location: Location,
@@ -108,7 +193,9 @@ impl Element for Bool {
 }
 
 /// Int is a integer value like `0`, `1`, `2`, etc.
-#[derive(Default, Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Int {
     /// The value of the integer.
     pub value: i64,
@@ -123,7 +210,9 @@ impl Element for Int {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum BinaryOp {
     Add, // Add
     Sub, // Subtract
@@ -140,7 +229,9 @@ pub enum BinaryOp {
     Or,  // Or
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Binary {
     pub lhs: Box<Term>,
     pub op: BinaryOp,
@@ -154,10 +245,20 @@ impl Element for Binary {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Call {
     pub callee: Box<Term>,
     pub arguments: Vec<Term>,
+    /// Whether the call was written `become f(x)`, which must be the last
+    /// term of a function, see [`check_tail_calls`](crate::parser::check_tail_calls).
+    /// Every call there is a tail call, this only makes it an error not to be.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub tail: bool,
     pub location: Location,
 }
 
@@ -167,9 +268,17 @@ impl Element for Call {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Function {
     pub parameters: Vec<Var>,
+    /// Type written for the result, like in `fn (x: int): int => ...`.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub annotation: Option<Annotation>,
     pub value: Box<Term>,
     pub location: Location,
 }
@@ -180,7 +289,9 @@ impl Element for Function {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Print {
     pub value: Box<Term>,
     pub location: Location,
@@ -192,7 +303,9 @@ impl Element for Print {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct First {
     pub value: Box<Term>,
     pub location: Location,
@@ -204,7 +317,9 @@ impl Element for First {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Second {
     pub value: Box<Term>,
     pub location: Location,
@@ -216,7 +331,9 @@ impl Element for Second {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Tuple {
     pub first: Box<Term>,
     pub second: Box<Term>,
@@ -229,8 +346,17 @@ impl Element for Tuple {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Hash, PartialEq, Eq)]
-#[serde(tag = "kind")]
+/// A term of the program.
+///
+/// Dropping a term drops its children one at a time instead of recursing
+/// into them, so terms nested hundreds of thousands of times, like the `let`
+/// chains of large programs, don't overflow the stack. Cloning and hashing
+/// them don't recurse either. Their parts can't be moved out of them then,
+/// [`Term::into_node`] takes them apart to do that.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "json", serde(tag = "kind"))]
 pub enum Term {
     Int(Int),
     Str(Str),
@@ -247,6 +373,364 @@ pub enum Term {
     Var(Var),
 }
 
+/// A [`Term`] taken apart by [`Term::into_node`], whose parts can be moved
+/// out, and which turns back into the term with [`Term::from`].
+#[derive(Debug)]
+pub enum Node {
+    Int(Int),
+    Str(Str),
+    Call(Call),
+    Binary(Binary),
+    Function(Function),
+    Let(Let),
+    If(If),
+    Print(Print),
+    First(First),
+    Second(Second),
+    Bool(Bool),
+    Tuple(Tuple),
+    Var(Var),
+}
+
+impl Term {
+    /// Takes the term apart, to move its parts out of it.
+    pub fn into_node(self) -> Node {
+        let term = ManuallyDrop::new(self);
+
+        // SAFETY: the term is never used nor dropped again, so each part is
+        // read once and owned by the node alone.
+        unsafe {
+            match &*term {
+                Term::Int(int) => Node::Int(ptr::read(int)),
+                Term::Str(str) => Node::Str(ptr::read(str)),
+                Term::Call(call) => Node::Call(ptr::read(call)),
+                Term::Binary(binary) => Node::Binary(ptr::read(binary)),
+                Term::Function(function) => Node::Function(ptr::read(function)),
+                Term::Let(let_) => Node::Let(ptr::read(let_)),
+                Term::If(if_) => Node::If(ptr::read(if_)),
+                Term::Print(print) => Node::Print(ptr::read(print)),
+                Term::First(first) => Node::First(ptr::read(first)),
+                Term::Second(second) => Node::Second(ptr::read(second)),
+                Term::Bool(bool) => Node::Bool(ptr::read(bool)),
+                Term::Tuple(tuple) => Node::Tuple(ptr::read(tuple)),
+                Term::Var(var) => Node::Var(ptr::read(var)),
+            }
+        }
+    }
+
+    /// Whether the term is an integer, a string, a boolean or a name.
+    fn is_leaf(&self) -> bool {
+        matches!(
+            self,
+            Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_)
+        )
+    }
+
+    /// Calls `f` with each child of the term.
+    fn each_child(&mut self, mut f: impl FnMut(&mut Term)) {
+        match self {
+            Term::Let(let_) => {
+                f(&mut let_.value);
+                f(&mut let_.next);
+            }
+            Term::Call(call) => {
+                f(&mut call.callee);
+                call.arguments.iter_mut().for_each(f);
+            }
+            Term::If(if_) => {
+                f(&mut if_.condition);
+                f(&mut if_.then);
+                f(&mut if_.otherwise);
+            }
+            Term::Binary(binary) => {
+                f(&mut binary.lhs);
+                f(&mut binary.rhs);
+            }
+            Term::Tuple(tuple) => {
+                f(&mut tuple.first);
+                f(&mut tuple.second);
+            }
+            Term::Function(Function { value, .. })
+            | Term::Print(Print { value, .. })
+            | Term::First(First { value, .. })
+            | Term::Second(Second { value, .. }) => f(value),
+            Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_) => (),
+        }
+    }
+
+    /// Moves the children of the term that are more than one level deep to
+    /// `terms`, leaving a leaf in their place. The others are dropped along
+    /// with the term, which only goes down to their leaves.
+    fn take_children(&mut self, terms: &mut Vec<Term>) {
+        self.each_child(|child| {
+            let mut shallow = true;
+            child.each_child(|grandchild| shallow &= grandchild.is_leaf());
+
+            if !shallow {
+                terms.push(std::mem::replace(child, Term::Bool(Bool::default())));
+            }
+        });
+    }
+}
+
+impl Drop for Term {
+    fn drop(&mut self) {
+        if self.is_leaf() {
+            return;
+        }
+
+        let mut terms = Vec::new();
+        self.take_children(&mut terms);
+
+        // Each term is dropped once its children were taken, so dropping it
+        // doesn't go any deeper.
+        while let Some(mut term) = terms.pop() {
+            term.take_children(&mut terms);
+        }
+    }
+}
+
+/// Placeholder for a child not cloned yet.
+fn hole() -> Box<Term> {
+    Box::new(Term::Bool(Bool::default()))
+}
+
+impl Term {
+    /// Pushes the children of the term to `terms`, the first one last.
+    fn push_children<'a>(&'a self, terms: &mut Vec<&'a Term>) {
+        match self {
+            Term::Let(let_) => terms.extend([&*let_.next, &let_.value]),
+            Term::Call(call) => {
+                terms.extend(call.arguments.iter().rev());
+                terms.push(&call.callee);
+            }
+            Term::If(if_) => terms.extend([&*if_.otherwise, &if_.then, &if_.condition]),
+            Term::Binary(binary) => terms.extend([&*binary.rhs, &binary.lhs]),
+            Term::Tuple(tuple) => terms.extend([&*tuple.second, &tuple.first]),
+            Term::Function(Function { value, .. })
+            | Term::Print(Print { value, .. })
+            | Term::First(First { value, .. })
+            | Term::Second(Second { value, .. }) => terms.push(value),
+            Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_) => (),
+        }
+    }
+
+    /// Pairs each child of the term with the placeholder for its copy in
+    /// `copy`, a copy of the term made by [`Term::clone_shallow`].
+    fn pair_children<'a, 'b>(
+        &'a self,
+        copy: &'b mut Term,
+        pairs: &mut Vec<(&'a Term, &'b mut Term)>,
+    ) {
+        match (self, copy) {
+            (Term::Let(let_), Term::Let(copy)) => {
+                pairs.extend([
+                    (&*let_.value, &mut *copy.value),
+                    (&let_.next, &mut copy.next),
+                ]);
+            }
+            (Term::Call(call), Term::Call(copy)) => {
+                pairs.push((&call.callee, &mut copy.callee));
+                pairs.extend(call.arguments.iter().zip(&mut copy.arguments));
+            }
+            (Term::If(if_), Term::If(copy)) => pairs.extend([
+                (&*if_.condition, &mut *copy.condition),
+                (&if_.then, &mut copy.then),
+                (&if_.otherwise, &mut copy.otherwise),
+            ]),
+            (Term::Binary(binary), Term::Binary(copy)) => {
+                pairs.extend([(&*binary.lhs, &mut *copy.lhs), (&binary.rhs, &mut copy.rhs)]);
+            }
+            (Term::Tuple(tuple), Term::Tuple(copy)) => {
+                pairs.extend([
+                    (&*tuple.first, &mut *copy.first),
+                    (&tuple.second, &mut copy.second),
+                ]);
+            }
+            (Term::Function(function), Term::Function(copy)) => {
+                pairs.push((&function.value, &mut copy.value));
+            }
+            (Term::Print(print), Term::Print(copy)) => pairs.push((&print.value, &mut copy.value)),
+            (Term::First(first), Term::First(copy)) => pairs.push((&first.value, &mut copy.value)),
+            (Term::Second(second), Term::Second(copy)) => {
+                pairs.push((&second.value, &mut copy.value));
+            }
+            _ => (),
+        }
+    }
+
+    /// A copy of the term whose children are placeholders.
+    fn clone_shallow(&self) -> Term {
+        match self {
+            Term::Int(int) => Term::Int(int.clone()),
+            Term::Str(str) => Term::Str(str.clone()),
+            Term::Bool(bool) => Term::Bool(bool.clone()),
+            Term::Var(var) => Term::Var(var.clone()),
+            Term::Let(let_) => Term::Let(Let {
+                name: let_.name.clone(),
+                value: hole(),
+                next: hole(),
+                location: let_.location.clone(),
+            }),
+            Term::Call(call) => Term::Call(Call {
+                callee: hole(),
+                arguments: call
+                    .arguments
+                    .iter()
+                    .map(|_| Term::Bool(Bool::default()))
+                    .collect(),
+                tail: call.tail,
+                location: call.location.clone(),
+            }),
+            Term::If(if_) => Term::If(If {
+                condition: hole(),
+                then: hole(),
+                otherwise: hole(),
+                location: if_.location.clone(),
+            }),
+            Term::Binary(binary) => Term::Binary(Binary {
+                lhs: hole(),
+                op: binary.op.clone(),
+                rhs: hole(),
+                location: binary.location.clone(),
+            }),
+            Term::Tuple(tuple) => Term::Tuple(Tuple {
+                first: hole(),
+                second: hole(),
+                location: tuple.location.clone(),
+            }),
+            Term::Function(function) => Term::Function(Function {
+                parameters: function.parameters.clone(),
+                annotation: function.annotation.clone(),
+                value: hole(),
+                location: function.location.clone(),
+            }),
+            Term::Print(print) => Term::Print(Print {
+                value: hole(),
+                location: print.location.clone(),
+            }),
+            Term::First(first) => Term::First(First {
+                value: hole(),
+                location: first.location.clone(),
+            }),
+            Term::Second(second) => Term::Second(Second {
+                value: hole(),
+                location: second.location.clone(),
+            }),
+        }
+    }
+
+    /// Hashes what the term holds besides its children.
+    fn hash_shallow<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            Term::Int(int) => int.hash(state),
+            Term::Str(str) => str.hash(state),
+            Term::Bool(bool) => bool.hash(state),
+            Term::Var(var) => var.hash(state),
+            Term::Let(let_) => {
+                let_.name.hash(state);
+                let_.location.hash(state);
+            }
+            Term::Call(call) => {
+                call.arguments.len().hash(state);
+                call.tail.hash(state);
+                call.location.hash(state);
+            }
+            Term::Binary(binary) => {
+                binary.op.hash(state);
+                binary.location.hash(state);
+            }
+            Term::Function(function) => {
+                function.parameters.hash(state);
+                function.annotation.hash(state);
+                function.location.hash(state);
+            }
+            Term::If(If { location, .. })
+            | Term::Tuple(Tuple { location, .. })
+            | Term::Print(Print { location, .. })
+            | Term::First(First { location, .. })
+            | Term::Second(Second { location, .. }) => location.hash(state),
+        }
+    }
+}
+
+impl Clone for Term {
+    fn clone(&self) -> Self {
+        let mut term = self.clone_shallow();
+        let mut pairs = Vec::new();
+        self.pair_children(&mut term, &mut pairs);
+
+        // Each copy is made with placeholders in place of its children, which
+        // are copied later, so copying a term doesn't go any deeper.
+        while let Some((original, copy)) = pairs.pop() {
+            *copy = original.clone_shallow();
+            original.pair_children(copy, &mut pairs);
+        }
+
+        term
+    }
+}
+
+impl Hash for Term {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let mut terms = vec![self];
+
+        while let Some(term) = terms.pop() {
+            term.hash_shallow(state);
+            term.push_children(&mut terms);
+        }
+    }
+}
+
+impl From<Node> for Term {
+    fn from(node: Node) -> Self {
+        match node {
+            Node::Int(int) => Term::Int(int),
+            Node::Str(str) => Term::Str(str),
+            Node::Call(call) => Term::Call(call),
+            Node::Binary(binary) => Term::Binary(binary),
+            Node::Function(function) => Term::Function(function),
+            Node::Let(let_) => Term::Let(let_),
+            Node::If(if_) => Term::If(if_),
+            Node::Print(print) => Term::Print(print),
+            Node::First(first) => Term::First(first),
+            Node::Second(second) => Term::Second(second),
+            Node::Bool(bool) => Term::Bool(bool),
+            Node::Tuple(tuple) => Term::Tuple(tuple),
+            Node::Var(var) => Term::Var(var),
+        }
+    }
+}
+
+impl Term {
+    pub fn location_mut(&mut self) -> &mut Location {
+        match self {
+            Term::Int(arg0) => &mut arg0.location,
+            Term::Str(arg0) => &mut arg0.location,
+            Term::Function(arg0) => &mut arg0.location,
+            Term::Call(arg0) => &mut arg0.location,
+            Term::Var(arg0) => &mut arg0.location,
+            Term::Binary(arg0) => &mut arg0.location,
+            Term::Print(arg0) => &mut arg0.location,
+            Term::First(arg0) => &mut arg0.location,
+            Term::Second(arg0) => &mut arg0.location,
+            Term::Let(arg0) => &mut arg0.location,
+            Term::If(arg0) => &mut arg0.location,
+            Term::Bool(arg0) => &mut arg0.location,
+            Term::Tuple(arg0) => &mut arg0.location,
+        }
+    }
+}
+
+/// Runs `f`, which goes one level deeper into a term, on a stack grown on
+/// the heap when little of the current one is left. The functions recursing
+/// into terms go through it, so long `let` chains don't overflow the stack.
+pub(crate) fn deeper<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(64 * 1024, 1024 * 1024, f)
+}
+
 impl Element for Term {
     fn location(&self) -> &Location {
         match self {
@@ -268,6 +752,10 @@ impl Element for Term {
 }
 
 impl Term {
+    /// Whether the term, or the body of the function, is not a `print`.
+    #[deprecated(
+        note = "use `effects::effects` or `effects::latent`, which look into the whole term"
+    )]
     pub fn is_pure(&self) -> bool {
         match self {
             Term::Function(function) => function.value.is_pure(),
@@ -0,0 +1,138 @@
+//! Functions building terms with empty locations, for tests and for programs
+//! generated from Rust.
+//!
+//! ```
+//! use lipsum::ast::{build::*, BinaryOp};
+//!
+//! // let double = fn (n) => { n * 2 }; print(double(21))
+//! let program = let_(
+//!     "double",
+//!     function(&["n"], binary(BinaryOp::Mul, var("n"), int(2))),
+//!     print(call(var("double"), vec![int(21)])),
+//! );
+//! ```
+
+use super::{
+    Binary, BinaryOp, Bool, Call, First, Function, If, Int, Let, Location, Print, Second, Str,
+    Term, Tuple, Var,
+};
+
+fn name(text: &str) -> Var {
+    Var {
+        text: text.into(),
+        annotation: None,
+        location: Location::default(),
+    }
+}
+
+pub fn int(value: i64) -> Term {
+    Term::Int(Int {
+        value,
+        location: Location::default(),
+    })
+}
+
+pub fn str(value: &str) -> Term {
+    Term::Str(Str {
+        value: value.into(),
+        location: Location::default(),
+    })
+}
+
+pub fn bool(value: bool) -> Term {
+    Term::Bool(Bool {
+        value,
+        location: Location::default(),
+    })
+}
+
+pub fn var(text: &str) -> Term {
+    Term::Var(name(text))
+}
+
+/// `let name = value; next`
+pub fn let_(text: &str, value: Term, next: Term) -> Term {
+    Term::Let(Let {
+        name: name(text),
+        value: Box::new(value),
+        next: Box::new(next),
+        location: Location::default(),
+    })
+}
+
+/// `fn (parameters) => { value }`
+pub fn function(parameters: &[&str], value: Term) -> Term {
+    Term::Function(Function {
+        parameters: parameters.iter().map(|parameter| name(parameter)).collect(),
+        annotation: None,
+        value: Box::new(value),
+        location: Location::default(),
+    })
+}
+
+pub fn call(callee: Term, arguments: Vec<Term>) -> Term {
+    Term::Call(Call {
+        callee: Box::new(callee),
+        arguments,
+        tail: false,
+        location: Location::default(),
+    })
+}
+
+/// `become callee(arguments)`
+pub fn become_(callee: Term, arguments: Vec<Term>) -> Term {
+    Term::Call(Call {
+        callee: Box::new(callee),
+        arguments,
+        tail: true,
+        location: Location::default(),
+    })
+}
+
+/// `if (condition) { then } else { otherwise }`
+pub fn if_(condition: Term, then: Term, otherwise: Term) -> Term {
+    Term::If(If {
+        condition: Box::new(condition),
+        then: Box::new(then),
+        otherwise: Box::new(otherwise),
+        location: Location::default(),
+    })
+}
+
+pub fn binary(op: BinaryOp, lhs: Term, rhs: Term) -> Term {
+    Term::Binary(Binary {
+        lhs: Box::new(lhs),
+        op,
+        rhs: Box::new(rhs),
+        location: Location::default(),
+    })
+}
+
+pub fn tuple(first: Term, second: Term) -> Term {
+    Term::Tuple(Tuple {
+        first: Box::new(first),
+        second: Box::new(second),
+        location: Location::default(),
+    })
+}
+
+pub fn first(value: Term) -> Term {
+    Term::First(First {
+        value: Box::new(value),
+        location: Location::default(),
+    })
+}
+
+pub fn second(value: Term) -> Term {
+    Term::Second(Second {
+        value: Box::new(value),
+        location: Location::default(),
+    })
+}
+
+pub fn print(value: Term) -> Term {
+    Term::Print(Print {
+        value: Box::new(value),
+        location: Location::default(),
+    })
+}
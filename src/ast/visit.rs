@@ -0,0 +1,134 @@
+//! Traversals over terms. Implementors override the methods for the nodes
+//! they care about and call `walk_term` or `fold_children` to go on with the
+//! default traversal of the children.
+//!
+//! ```
+//! use lipsum::ast::{build::*, visit::{walk_term, TermVisitor}, Term};
+//!
+//! #[derive(Default)]
+//! struct Vars(Vec<String>);
+//!
+//! impl TermVisitor for Vars {
+//!     fn visit_term(&mut self, term: &Term) {
+//!         if let Term::Var(var) = term {
+//!             self.0.push(var.text.to_string());
+//!         }
+//!
+//!         walk_term(self, term);
+//!     }
+//! }
+//!
+//! let mut vars = Vars::default();
+//! vars.visit_term(&tuple(var("a"), call(var("f"), vec![var("b")])));
+//!
+//! assert_eq!(vars.0, vec!["a", "f", "b"]);
+//! ```
+
+use super::{deeper, Node, Term};
+
+/// Goes through a term without changing it.
+pub trait TermVisitor {
+    fn visit_term(&mut self, term: &Term) {
+        walk_term(self, term);
+    }
+}
+
+/// Visits the children of `term`, in the order they are evaluated.
+pub fn walk_term<V: TermVisitor + ?Sized>(visitor: &mut V, term: &Term) {
+    deeper(|| match term {
+        Term::Let(let_) => {
+            visitor.visit_term(&let_.value);
+            visitor.visit_term(&let_.next);
+        }
+        Term::Function(function) => visitor.visit_term(&function.value),
+        Term::Call(call) => {
+            visitor.visit_term(&call.callee);
+
+            for argument in &call.arguments {
+                visitor.visit_term(argument);
+            }
+        }
+        Term::If(if_) => {
+            visitor.visit_term(&if_.condition);
+            visitor.visit_term(&if_.then);
+            visitor.visit_term(&if_.otherwise);
+        }
+        Term::Binary(binary) => {
+            visitor.visit_term(&binary.lhs);
+            visitor.visit_term(&binary.rhs);
+        }
+        Term::Tuple(tuple) => {
+            visitor.visit_term(&tuple.first);
+            visitor.visit_term(&tuple.second);
+        }
+        Term::Print(print) => visitor.visit_term(&print.value),
+        Term::First(first) => visitor.visit_term(&first.value),
+        Term::Second(second) => visitor.visit_term(&second.value),
+        Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_) => (),
+    })
+}
+
+/// Rebuilds a term, possibly replacing some of its parts.
+pub trait TermFolder {
+    fn fold_term(&mut self, term: Term) -> Term {
+        fold_children(self, term)
+    }
+}
+
+/// Folds the children of `term`, in the order they are evaluated, keeping
+/// the node itself.
+pub fn fold_children<F: TermFolder + ?Sized>(folder: &mut F, term: Term) -> Term {
+    deeper(|| match term.into_node() {
+        Node::Let(mut let_) => {
+            let_.value = fold(folder, *let_.value);
+            let_.next = fold(folder, *let_.next);
+            Term::Let(let_)
+        }
+        Node::Function(mut function) => {
+            function.value = fold(folder, *function.value);
+            Term::Function(function)
+        }
+        Node::Call(mut call) => {
+            call.callee = fold(folder, *call.callee);
+            call.arguments = call
+                .arguments
+                .into_iter()
+                .map(|argument| folder.fold_term(argument))
+                .collect();
+            Term::Call(call)
+        }
+        Node::If(mut if_) => {
+            if_.condition = fold(folder, *if_.condition);
+            if_.then = fold(folder, *if_.then);
+            if_.otherwise = fold(folder, *if_.otherwise);
+            Term::If(if_)
+        }
+        Node::Binary(mut binary) => {
+            binary.lhs = fold(folder, *binary.lhs);
+            binary.rhs = fold(folder, *binary.rhs);
+            Term::Binary(binary)
+        }
+        Node::Tuple(mut tuple) => {
+            tuple.first = fold(folder, *tuple.first);
+            tuple.second = fold(folder, *tuple.second);
+            Term::Tuple(tuple)
+        }
+        Node::Print(mut print) => {
+            print.value = fold(folder, *print.value);
+            Term::Print(print)
+        }
+        Node::First(mut first) => {
+            first.value = fold(folder, *first.value);
+            Term::First(first)
+        }
+        Node::Second(mut second) => {
+            second.value = fold(folder, *second.value);
+            Term::Second(second)
+        }
+        node @ (Node::Int(_) | Node::Str(_) | Node::Bool(_) | Node::Var(_)) => Term::from(node),
+    })
+}
+
+fn fold<F: TermFolder + ?Sized>(folder: &mut F, term: Term) -> Box<Term> {
+    Box::new(folder.fold_term(term))
+}
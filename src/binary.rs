@@ -1,14 +1,15 @@
 use crate::{
     ast::{Binary, BinaryOp, Element, Location},
-    interpreter::{RuntimeError, Value},
+    interpreter::{Rope, RuntimeError, Value},
+    messages::ErrorCode,
 };
 
 fn invalid_comparison(l_value: &Value, r_value: &Value, location: &Location) -> RuntimeError {
-    RuntimeError {
-        message: String::from("invalid comparison"),
-        full_text: format!("{} and {} cannot be compared", l_value, r_value),
-        location: location.clone(),
-    }
+    RuntimeError::new(
+        ErrorCode::InvalidComparison,
+        vec![l_value.inspect().to_string(), r_value.inspect().to_string()],
+        location.clone(),
+    )
 }
 
 impl Value {
@@ -69,108 +70,126 @@ impl Value {
     pub fn and(&self, value: &Value, location: &Location) -> Result<Value, RuntimeError> {
         match (self, value) {
             (Value::Bool(l_bool), Value::Bool(r_bool)) => Ok(Value::Bool(*l_bool && *r_bool)),
-            (_l_val, _r_val) => Err(RuntimeError {
-                message: String::from("invalid AND operation"),
-                full_text: format!("only booleans can be used on short-circuit operations"),
-                location: location.clone(),
-            }),
+            (_l_val, _r_val) => Err(RuntimeError::new(
+                ErrorCode::InvalidAnd,
+                vec![],
+                location.clone(),
+            )),
         }
     }
 
     pub fn or(&self, value: &Value, location: &Location) -> Result<Value, RuntimeError> {
         match (self, value) {
             (Value::Bool(l_bool), Value::Bool(r_bool)) => Ok(Value::Bool(*l_bool || *r_bool)),
-            (_l_val, _r_val) => Err(RuntimeError {
-                message: String::from("invalid OR operation"),
-                full_text: format!("only booleans can be used on short-circuit operations"),
-                location: location.clone(),
-            }),
+            (_l_val, _r_val) => Err(RuntimeError::new(
+                ErrorCode::InvalidOr,
+                vec![],
+                location.clone(),
+            )),
         }
     }
 
     pub fn add(&self, value: &Value, location: &Location) -> Result<Value, RuntimeError> {
         match (self, value) {
-            (Value::Int(l_int), Value::Int(r_int)) => Ok(Value::Int(l_int + r_int)),
-            (Value::Str(l_str), Value::Str(r_str)) => Ok(Value::Str(format!("{l_str}{r_str}"))),
-            (Value::Str(l_str), Value::Int(r_int)) => Ok(Value::Str(format!("{l_str}{r_int}"))),
-            (Value::Int(l_int), Value::Str(r_str)) => Ok(Value::Str(format!("{l_int}{r_str}"))),
-            (l_val, r_val) => Err(RuntimeError {
-                message: String::from("invalid addition"),
-                full_text: format!("{l_val} cannot be added to {r_val}",),
-                location: location.clone(),
-            }),
+            (Value::Int(l_int), Value::Int(r_int)) => Ok(Value::Int(l_int.wrapping_add(*r_int))),
+            (Value::Str(l_str), Value::Str(r_str)) => {
+                Ok(Value::Str(Rope::concat(l_str.clone(), r_str.clone())))
+            }
+            (Value::Str(l_str), Value::Int(r_int)) => Ok(Value::Str(Rope::concat(
+                l_str.clone(),
+                r_int.to_string().into(),
+            ))),
+            (Value::Int(l_int), Value::Str(r_str)) => Ok(Value::Str(Rope::concat(
+                l_int.to_string().into(),
+                r_str.clone(),
+            ))),
+            (l_val, r_val) => Err(RuntimeError::new(
+                ErrorCode::InvalidAddition,
+                vec![l_val.inspect().to_string(), r_val.inspect().to_string()],
+                location.clone(),
+            )),
         }
     }
 
     pub fn sub(&self, value: &Value, location: &Location) -> Result<Value, RuntimeError> {
         match (self, value) {
-            (Value::Int(l_int), Value::Int(r_int)) => Ok(Value::Int(l_int - r_int)),
-            (l_val, r_val) => Err(RuntimeError {
-                message: String::from("invalid subtraction"),
-                full_text: format!("{l_val} cannot be subtracted by {r_val}",),
-                location: location.clone(),
-            }),
+            (Value::Int(l_int), Value::Int(r_int)) => Ok(Value::Int(l_int.wrapping_sub(*r_int))),
+            (l_val, r_val) => Err(RuntimeError::new(
+                ErrorCode::InvalidSubtraction,
+                vec![l_val.inspect().to_string(), r_val.inspect().to_string()],
+                location.clone(),
+            )),
         }
     }
 
     pub fn mul(&self, value: &Value, location: &Location) -> Result<Value, RuntimeError> {
         match (self, value) {
-            (Value::Int(l_int), Value::Int(r_int)) => Ok(Value::Int(l_int * r_int)),
-            (l_val, r_val) => Err(RuntimeError {
-                message: String::from("invalid multiplication"),
-                full_text: format!("{l_val} cannot be multiplied by {r_val} ",),
-                location: location.clone(),
-            }),
+            (Value::Int(l_int), Value::Int(r_int)) => Ok(Value::Int(l_int.wrapping_mul(*r_int))),
+            (l_val, r_val) => Err(RuntimeError::new(
+                ErrorCode::InvalidMultiplication,
+                vec![l_val.inspect().to_string(), r_val.inspect().to_string()],
+                location.clone(),
+            )),
         }
     }
 
     pub fn div(&self, value: &Value, location: &Location) -> Result<Value, RuntimeError> {
         match (self, value) {
-            (Value::Int(_l_int), Value::Int(0)) => Err(RuntimeError {
-                message: String::from("division by zero"),
-                full_text: String::from("zero cannot be divised"),
-                location: location.clone(),
-            }),
-            (Value::Int(l_int), Value::Int(r_int)) => Ok(Value::Int(l_int / r_int)),
-            (l_val, r_val) => Err(RuntimeError {
-                message: String::from("invalid division"),
-                full_text: format!("{l_val} cannot be divised by {r_val}",),
-                location: location.clone(),
-            }),
+            (Value::Int(_l_int), Value::Int(0)) => Err(RuntimeError::new(
+                ErrorCode::DivisionByZero,
+                vec![],
+                location.clone(),
+            )),
+            (Value::Int(l_int), Value::Int(r_int)) => Ok(Value::Int(l_int.wrapping_div(*r_int))),
+            (l_val, r_val) => Err(RuntimeError::new(
+                ErrorCode::InvalidDivision,
+                vec![l_val.inspect().to_string(), r_val.inspect().to_string()],
+                location.clone(),
+            )),
         }
     }
 
     pub fn rem(&self, value: &Value, location: &Location) -> Result<Value, RuntimeError> {
         match (self, value) {
-            (Value::Int(_l_val), Value::Int(0)) => Err(RuntimeError {
-                message: String::from("division by zero"),
-                full_text: String::from("cannot get remainder from a zero division"),
-                location: location.clone(),
-            }),
-            (Value::Int(l_int), Value::Int(r_int)) => Ok(Value::Int(l_int % r_int)),
-            (l_val, r_val) => Err(RuntimeError {
-                message: String::from("invalid remainder operation"),
-                full_text: format!("cannot get remainder from {l_val} and {r_val} division"),
-                location: location.clone(),
-            }),
+            (Value::Int(_l_val), Value::Int(0)) => Err(RuntimeError::new(
+                ErrorCode::RemainderByZero,
+                vec![],
+                location.clone(),
+            )),
+            (Value::Int(l_int), Value::Int(r_int)) => Ok(Value::Int(l_int.wrapping_rem(*r_int))),
+            (l_val, r_val) => Err(RuntimeError::new(
+                ErrorCode::InvalidRemainder,
+                vec![l_val.inspect().to_string(), r_val.inspect().to_string()],
+                location.clone(),
+            )),
         }
     }
 
     pub fn binary_op(self, binary: Binary, rhs: Value) -> Result<Value, RuntimeError> {
-        match binary.op {
-            BinaryOp::Eq => self.eq(&rhs, binary.lhs.location()),
-            BinaryOp::Neq => self.neq(&rhs, binary.lhs.location()),
-            BinaryOp::Lt => self.lt(&rhs, binary.lhs.location()),
-            BinaryOp::Lte => self.lte(&rhs, binary.lhs.location()),
-            BinaryOp::Gt => self.gt(&rhs, binary.lhs.location()),
-            BinaryOp::Gte => self.gte(&rhs, binary.lhs.location()),
-            BinaryOp::And => self.and(&rhs, binary.lhs.location()),
-            BinaryOp::Or => self.or(&rhs, binary.lhs.location()),
-            BinaryOp::Add => self.add(&rhs, binary.lhs.location()),
-            BinaryOp::Sub => self.sub(&rhs, binary.lhs.location()),
-            BinaryOp::Mul => self.mul(&rhs, binary.lhs.location()),
-            BinaryOp::Div => self.div(&rhs, binary.lhs.location()),
-            BinaryOp::Rem => self.rem(&rhs, binary.lhs.location()),
+        self.operate(&binary.op, &rhs, binary.lhs.location())
+    }
+
+    /// Applies `op` to the value and `rhs`, failing at `location`.
+    pub fn operate(
+        &self,
+        op: &BinaryOp,
+        rhs: &Value,
+        location: &Location,
+    ) -> Result<Value, RuntimeError> {
+        match op {
+            BinaryOp::Eq => self.eq(rhs, location),
+            BinaryOp::Neq => self.neq(rhs, location),
+            BinaryOp::Lt => self.lt(rhs, location),
+            BinaryOp::Lte => self.lte(rhs, location),
+            BinaryOp::Gt => self.gt(rhs, location),
+            BinaryOp::Gte => self.gte(rhs, location),
+            BinaryOp::And => self.and(rhs, location),
+            BinaryOp::Or => self.or(rhs, location),
+            BinaryOp::Add => self.add(rhs, location),
+            BinaryOp::Sub => self.sub(rhs, location),
+            BinaryOp::Mul => self.mul(rhs, location),
+            BinaryOp::Div => self.div(rhs, location),
+            BinaryOp::Rem => self.rem(rhs, location),
         }
     }
 }
@@ -184,7 +203,7 @@ mod tests {
     }
 
     fn str(str: &str) -> Value {
-        Value::Str(str.to_string())
+        Value::Str(str.into())
     }
 
     fn location() -> Location {
@@ -251,6 +270,18 @@ mod tests {
         assert!(is_err);
     }
 
+    #[test]
+    fn overflow_wraps() {
+        let max_add_one = int(i64::MAX).add(&int(1), &location()).unwrap();
+        assert!(eq(&max_add_one, &int(i64::MIN)));
+
+        let min_div_minus_one = int(i64::MIN).div(&int(-1), &location()).unwrap();
+        assert!(eq(&min_div_minus_one, &int(i64::MIN)));
+
+        let min_rem_minus_one = int(i64::MIN).rem(&int(-1), &location()).unwrap();
+        assert!(eq(&min_rem_minus_one, &int(0)));
+    }
+
     #[test]
     fn rem() {
         let four_rem_two = int(4).rem(&int(2), &location()).unwrap();
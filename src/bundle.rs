@@ -0,0 +1,158 @@
+//! Executables with a program embedded in them, written by `lipsum bundle`.
+//!
+//! The JSON syntax tree of the program is appended to the executable,
+//! followed by its length, as a little endian `u64`, and [`MAGIC`]. Loaders
+//! ignore what comes after the code they map, so the bundle still runs, and
+//! it finds its program by reading the end of its own file.
+
+use std::{
+    fs,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::{
+    ast::File,
+    json::{read_file, write_file},
+};
+
+/// Marks the end of a bundle.
+pub const MAGIC: &[u8; 8] = b"rinhabdl";
+
+const TRAILER: usize = 8 + MAGIC.len();
+
+/// Appends `program` to `executable`, replacing the program it may already
+/// have.
+pub fn bundle(executable: &[u8], program: &File) -> Vec<u8> {
+    let json = write_file(program);
+    let mut bundle = strip(executable).to_vec();
+
+    bundle.extend_from_slice(json.as_bytes());
+    bundle.extend_from_slice(&(json.len() as u64).to_le_bytes());
+    bundle.extend_from_slice(MAGIC);
+
+    bundle
+}
+
+/// The executable without the program appended to it, if any.
+pub fn strip(executable: &[u8]) -> &[u8] {
+    match trailer(executable) {
+        Some(length) if length <= executable.len() - TRAILER => {
+            &executable[..executable.len() - TRAILER - length]
+        }
+        _ => executable,
+    }
+}
+
+/// Reads the JSON syntax tree of the program embedded in the executable at
+/// `path`, without reading the rest of it.
+pub fn payload(path: &Path) -> io::Result<Option<String>> {
+    let mut file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    if size < TRAILER as u64 {
+        return Ok(None);
+    }
+
+    let mut end = [0; TRAILER];
+    file.seek(SeekFrom::End(-(TRAILER as i64)))?;
+    file.read_exact(&mut end)?;
+
+    let Some(length) = trailer(&end) else {
+        return Ok(None);
+    };
+
+    if length as u64 > size - TRAILER as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the embedded program is longer than the executable",
+        ));
+    }
+
+    let mut json = vec![0; length];
+    file.seek(SeekFrom::End(-((TRAILER + length) as i64)))?;
+    file.read_exact(&mut json)?;
+
+    String::from_utf8(json)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Reads the program embedded in the executable at `path`.
+pub fn embedded(path: &Path) -> io::Result<Option<File>> {
+    let Some(json) = payload(path)? else {
+        return Ok(None);
+    };
+
+    read_file(&json, &path.to_string_lossy())
+        .map(|(program, _)| Some(program))
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Length of the program appended before `bytes`, when they end with a
+/// trailer.
+fn trailer(bytes: &[u8]) -> Option<usize> {
+    let end = bytes.strip_suffix(MAGIC)?;
+    let length = end.get(end.len().checked_sub(8)?..)?;
+
+    Some(u64::from_le_bytes(length.try_into().ok()?) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Term, parser::parse};
+
+    use super::{bundle, embedded, strip};
+
+    #[test]
+    fn embed_and_read() {
+        let executable = b"\x7fELF not really an executable".to_vec();
+        let first = parse("print(1)", "first.rinha").unwrap();
+        let second = parse("print(2)", "second.rinha").unwrap();
+
+        let once = bundle(&executable, &first);
+        let twice = bundle(&once, &second);
+
+        assert_eq!(strip(&once), executable.as_slice());
+        assert_eq!(strip(&twice), executable.as_slice());
+        assert_eq!(strip(&executable), executable.as_slice());
+
+        let path = std::env::temp_dir().join(format!("bundle-{}", std::process::id()));
+
+        std::fs::write(&path, &twice).unwrap();
+        let program = embedded(&path).unwrap().unwrap();
+        assert_eq!(program.name, "second.rinha");
+
+        std::fs::write(&path, &executable).unwrap();
+        assert!(embedded(&path).unwrap().is_none());
+
+        let mut corrupt = once.clone();
+        corrupt[executable.len()] = b'[';
+        std::fs::write(&path, &corrupt).unwrap();
+        assert!(embedded(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn embed_deep_programs() {
+        let depth = 20_000;
+        let source = format!("{}print(x)", "let x = 1;\n".repeat(depth));
+        let program = parse(&source, "deep.rinha").unwrap();
+
+        let path = std::env::temp_dir().join(format!("deep-bundle-{}", std::process::id()));
+        std::fs::write(&path, bundle(b"\x7fELF", &program)).unwrap();
+        let embedded = embedded(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut term = &embedded.unwrap().unwrap().expression;
+        let mut lets = 0;
+        while let Term::Let(binding) = term {
+            lets += 1;
+            term = &binding.next;
+        }
+
+        assert_eq!(lets, depth);
+        assert!(matches!(term, Term::Print(_)));
+    }
+}
@@ -0,0 +1,128 @@
+//! Programs cached next to their source, in `.rinhac` files, so that later
+//! runs skip lexing, parsing and the passes.
+//!
+//! A cached program is keyed by the hash of its source, of the passes that
+//! ran over it and of the version of lipsum, and any change in them makes
+//! the cache miss, so a stale file is never used, only replaced.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ast::File,
+    json::{read_file, write_file},
+};
+
+/// Extension of the cached programs.
+pub static EXTENSION: &str = "rinhac";
+
+/// Where the program at `source` is cached, `fib.rinha` in `fib.rinhac` and
+/// `fib.json` in `fib.json.rinhac`.
+pub fn path(source: &Path) -> PathBuf {
+    if source
+        .extension()
+        .is_some_and(|extension| extension == "rinha")
+    {
+        return source.with_extension(EXTENSION);
+    }
+
+    let mut path = source.as_os_str().to_owned();
+    path.push(".");
+    path.push(EXTENSION);
+    path.into()
+}
+
+/// Key of the program with the given `source` after the given `passes`.
+pub fn key(source: &[u8], passes: &[&str]) -> String {
+    // Hashes of another build of lipsum may differ, the cache then misses.
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    passes.hash(&mut hasher);
+    source.hash(&mut hasher);
+
+    format!("{:016x}", hasher.finish())
+}
+
+/// Reads the program cached at `path`, when it was cached with `key`.
+pub fn read(path: &Path, key: &str) -> Option<File> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let (cached, program) = text.split_once('\n')?;
+
+    if cached != key {
+        return None;
+    }
+
+    read_file(program, &path.to_string_lossy())
+        .ok()
+        .map(|(program, _)| program)
+}
+
+/// Caches `program` at `path` with `key`, written on the first line, before
+/// the JSON syntax tree.
+pub fn write(path: &Path, key: &str, program: &File) -> std::io::Result<()> {
+    std::fs::write(path, format!("{key}\n{}", write_file(program)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{ast::Term, parser::parse, pass::Pipeline};
+
+    use super::{key, path, read, write};
+
+    #[test]
+    fn hits_only_with_the_same_key() {
+        let source = "let f = fn (n) => { n + 1 }; print(f(1))";
+        let mut program = parse(source, "cache.rinha").unwrap();
+        let mut pipeline = Pipeline::default();
+        let passes = pipeline.names();
+        program.expression = pipeline.run(program.expression, None).0;
+
+        let current = key(source.as_bytes(), &passes);
+        let cached = std::env::temp_dir().join(format!("cache-{}.rinhac", std::process::id()));
+        write(&cached, &current, &program).unwrap();
+
+        assert_eq!(read(&cached, &current).unwrap().name, "cache.rinha");
+        assert!(read(&cached, &key(b"print(2)", &passes)).is_none());
+        assert!(read(&cached, &key(source.as_bytes(), &[])).is_none());
+
+        std::fs::remove_file(&cached).unwrap();
+        assert!(read(&cached, &current).is_none());
+
+        assert_eq!(
+            path(Path::new("examples/fib.rinha")),
+            Path::new("examples/fib.rinhac")
+        );
+        assert_eq!(
+            path(Path::new("examples/fib.json")),
+            Path::new("examples/fib.json.rinhac")
+        );
+    }
+
+    #[test]
+    fn hits_on_deep_programs() {
+        let depth = 20_000;
+        let source = format!("{}print(x)", "let x = 1;\n".repeat(depth));
+        let program = parse(&source, "deep.rinha").unwrap();
+
+        let current = key(source.as_bytes(), &[]);
+        let cached = std::env::temp_dir().join(format!("deep-{}.rinhac", std::process::id()));
+        write(&cached, &current, &program).unwrap();
+        let hit = read(&cached, &current);
+        std::fs::remove_file(&cached).unwrap();
+
+        let mut term = &hit.unwrap().expression;
+        let mut lets = 0;
+        while let Term::Let(binding) = term {
+            lets += 1;
+            term = &binding.next;
+        }
+
+        assert_eq!(lets, depth);
+        assert!(matches!(term, Term::Print(_)));
+    }
+}
@@ -0,0 +1,342 @@
+use std::{collections::HashSet, fmt::Write, sync::Arc};
+
+use crate::ast::{
+    deeper,
+    visit::{walk_term, TermVisitor},
+    Element, Function, Location, Term,
+};
+
+/// A function of the program, or the program itself, which is the first node
+/// of every graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct Node {
+    /// Name of the `let` binding the function, `<anonymous>` for functions
+    /// that are not bound to names and `<program>` for the program.
+    pub name: String,
+    pub location: Location,
+    /// Whether the function can end up calling itself.
+    pub recursive: bool,
+}
+
+/// A call made in the body of a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct Edge {
+    /// Index of the calling function in [`CallGraph::nodes`].
+    pub caller: usize,
+    /// Index of the called function, when it is known without running the
+    /// program. Parameters, natives and functions taken out of tuples are
+    /// only known while running.
+    pub callee: Option<usize>,
+    /// The name called, or `<expression>` when the callee is not a name.
+    pub text: String,
+    pub location: Location,
+}
+
+/// The functions of a program and the calls between them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct CallGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl CallGraph {
+    /// Indexes of the functions called by `node`.
+    fn callees(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges
+            .iter()
+            .filter(move |edge| edge.caller == node)
+            .filter_map(|edge| edge.callee)
+    }
+
+    /// Whether `node` can be reached from itself.
+    fn reaches_itself(&self, node: usize) -> bool {
+        let mut seen = HashSet::new();
+        let mut pending = self.callees(node).collect::<Vec<_>>();
+
+        while let Some(next) = pending.pop() {
+            if next == node {
+                return true;
+            }
+
+            if seen.insert(next) {
+                pending.extend(self.callees(next));
+            }
+        }
+
+        false
+    }
+
+    /// The graph in the DOT language of Graphviz. Recursive functions are
+    /// drawn in bold and the calls that are only known while running go to
+    /// dashed nodes named after the callee.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph calls {\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let style = if node.recursive { ", style=bold" } else { "" };
+            let _ = writeln!(
+                dot,
+                "  {index} [label=\"{}\\n{}\"{style}];",
+                escape(&node.name),
+                escape(&node.location.to_string())
+            );
+        }
+
+        let mut unknown = HashSet::new();
+        let mut drawn = HashSet::new();
+
+        for edge in &self.edges {
+            let target = match edge.callee {
+                Some(callee) => callee.to_string(),
+                None => {
+                    let target = format!("\"?{}\"", escape(&edge.text));
+
+                    if unknown.insert(edge.text.clone()) {
+                        let _ = writeln!(
+                            dot,
+                            "  {target} [label=\"{}\", style=dashed];",
+                            escape(&edge.text)
+                        );
+                    }
+
+                    target
+                }
+            };
+
+            if drawn.insert((edge.caller, target.clone())) {
+                let _ = writeln!(dot, "  {} -> {target};", edge.caller);
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+struct Builder {
+    graph: CallGraph,
+    /// Function bound to each name in scope, `None` for the names bound to
+    /// values only known while running.
+    scopes: Vec<(Arc<str>, Option<usize>)>,
+    /// Index of the function whose body is being visited.
+    current: usize,
+}
+
+impl Builder {
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|(bound, _)| &**bound == name)
+            .and_then(|(_, node)| *node)
+    }
+
+    fn node(&mut self, name: &str, location: &Location) -> usize {
+        self.graph.nodes.push(Node {
+            name: name.to_string(),
+            location: location.clone(),
+            recursive: false,
+        });
+
+        self.graph.nodes.len() - 1
+    }
+
+    /// Visits the body of `function`, whose node is `node`.
+    fn function(&mut self, node: usize, function: &Function) {
+        let caller = std::mem::replace(&mut self.current, node);
+
+        for parameter in &function.parameters {
+            self.scopes.push((parameter.text.clone(), None));
+        }
+
+        self.visit_term(&function.value);
+
+        self.scopes
+            .truncate(self.scopes.len() - function.parameters.len());
+        self.current = caller;
+    }
+}
+
+impl TermVisitor for Builder {
+    fn visit_term(&mut self, term: &Term) {
+        deeper(|| {
+            match term {
+                Term::Let(let_) => {
+                    let bound = match let_.value.as_ref() {
+                        // Functions can see their own name, so they can call
+                        // themselves.
+                        Term::Function(function) => {
+                            let node = self.node(&let_.name.text, &let_.name.location);
+                            self.scopes.push((let_.name.text.clone(), Some(node)));
+                            self.function(node, function);
+                            self.scopes.pop();
+
+                            Some(node)
+                        }
+                        Term::Var(var) => self.lookup(&var.text),
+                        value => {
+                            self.visit_term(value);
+                            None
+                        }
+                    };
+
+                    self.scopes.push((let_.name.text.clone(), bound));
+                    self.visit_term(&let_.next);
+                    self.scopes.pop();
+                }
+                Term::Function(function) => {
+                    let node = self.node("<anonymous>", &function.location);
+                    self.function(node, function);
+                }
+                Term::Call(call) => {
+                    let (callee, text) = match call.callee.as_ref() {
+                        Term::Var(var) => (self.lookup(&var.text), var.text.to_string()),
+                        Term::Function(function) => {
+                            let node = self.node("<anonymous>", &function.location);
+                            self.function(node, function);
+
+                            (Some(node), String::from("<anonymous>"))
+                        }
+                        callee => {
+                            self.visit_term(callee);
+
+                            (None, String::from("<expression>"))
+                        }
+                    };
+
+                    self.graph.edges.push(Edge {
+                        caller: self.current,
+                        callee,
+                        text,
+                        location: call.location().clone(),
+                    });
+
+                    for argument in &call.arguments {
+                        self.visit_term(argument);
+                    }
+                }
+                term => walk_term(self, term),
+            }
+        })
+    }
+}
+
+/// The call graph of `term`, resolving the calls to functions bound with
+/// `let`, directly or through other names, without running the program.
+pub fn callgraph(term: &Term) -> CallGraph {
+    let mut builder = Builder {
+        graph: CallGraph::default(),
+        scopes: Vec::new(),
+        current: 0,
+    };
+
+    builder.node("<program>", term.location());
+    builder.visit_term(term);
+
+    let mut graph = builder.graph;
+
+    for node in 0..graph.nodes.len() {
+        graph.nodes[node].recursive = graph.reaches_itself(node);
+    }
+
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+
+    use super::{callgraph, CallGraph};
+
+    fn graph(source: &str) -> CallGraph {
+        callgraph(&parse(source, "tests").unwrap().expression)
+    }
+
+    /// Names of the caller and of the callee of each call.
+    fn calls(graph: &CallGraph) -> Vec<(&str, Option<&str>)> {
+        graph
+            .edges
+            .iter()
+            .map(|edge| {
+                (
+                    &*graph.nodes[edge.caller].name,
+                    edge.callee.map(|callee| &*graph.nodes[callee].name),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recursion() {
+        let graph = graph(
+            "let fib = fn (n) => if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }; print(fib(10))",
+        );
+
+        assert_eq!(
+            calls(&graph),
+            vec![
+                ("fib", Some("fib")),
+                ("fib", Some("fib")),
+                ("<program>", Some("fib"))
+            ]
+        );
+        assert!(graph.nodes[1].recursive);
+        assert!(!graph.nodes[0].recursive);
+    }
+
+    #[test]
+    fn mutual_recursion() {
+        let graph = graph(
+            "let odd = fn (n) => if (n == 0) { false } else { even(n - 1) };
+             let even = fn (n) => if (n == 0) { true } else { odd(n - 1) };
+             even(10)",
+        );
+
+        // `even` is not bound yet inside `odd`.
+        assert_eq!(
+            calls(&graph),
+            vec![
+                ("odd", None),
+                ("even", Some("odd")),
+                ("<program>", Some("even"))
+            ]
+        );
+        assert!(graph.nodes.iter().all(|node| !node.recursive));
+    }
+
+    #[test]
+    fn aliases_and_unknown_calls() {
+        let graph = graph(
+            "let apply = fn (f, x) => f(x);
+             let inc = fn (x) => x + 1;
+             let g = inc;
+             apply(g, g(1))",
+        );
+
+        assert_eq!(
+            calls(&graph),
+            vec![
+                ("apply", None),
+                ("<program>", Some("apply")),
+                ("<program>", Some("inc"))
+            ]
+        );
+        assert_eq!(graph.edges[0].text, "f");
+    }
+
+    #[test]
+    fn dot() {
+        let dot = graph("let f = fn (x) => print(x); f(1)").to_dot();
+
+        assert!(dot.starts_with("digraph calls {\n"));
+        assert!(dot.contains("  1 [label=\"f\\ntests:4..5\"];\n"));
+        assert!(dot.contains("  0 -> 1;\n"));
+    }
+}
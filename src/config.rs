@@ -0,0 +1,240 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    interpreter::{Interpreter, Printer},
+    lint::{Level, Levels},
+};
+
+/// Name of the per-project configuration file.
+pub static CONFIG_FILE: &str = "rinha.toml";
+
+/// Settings read from `rinha.toml`. Every field is optional so that files
+/// can be merged with the command line flags, which take precedence. There
+/// is a single evaluator, so no backend to choose besides `memoization`.
+///
+/// ```toml
+/// memoization = true
+/// spec = false
+/// prelude = "prelude.rinha.json"
+///
+/// [limits]
+/// fuel = 1000000
+/// max-depth = 10000
+/// timeout = 10
+/// max-memory = 512
+/// detect-loops = 3
+///
+/// [lints]
+/// warnings = "deny"
+/// unused-parameter = "allow"
+/// W0002 = "warn"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Whether calls to pure functions are memoized.
+    pub memoization: Option<bool>,
+
+    /// Whether programs follow the semantics of the rinha specification to
+    /// the letter, see [`crate::spec`].
+    pub spec: Option<bool>,
+
+    /// Program evaluated before the main one, whose bindings are visible to
+    /// it. Relative paths are resolved from the configuration file.
+    pub prelude: Option<PathBuf>,
+
+    pub limits: Limits,
+
+    /// Level of each lint, by code or name, or of every lint with
+    /// `warnings`, see [`Levels`].
+    pub lints: BTreeMap<String, Level>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Limits {
+    /// Maximum number of function calls.
+    pub fuel: Option<u64>,
+
+    /// Maximum number of nested function calls.
+    pub max_depth: Option<usize>,
+
+    /// Maximum running time, in seconds.
+    pub timeout: Option<u64>,
+
+    /// Maximum memory held by bindings and memoized results, in megabytes.
+    pub max_memory: Option<usize>,
+
+    /// How many times a function can be running in the same state before
+    /// the evaluation stops as a probable infinite loop, without fuel or
+    /// timeout.
+    pub detect_loops: Option<u32>,
+}
+
+impl Config {
+    /// Reads the configuration file at `path`.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| format!("failed to read {}: {error}", path.display()))?;
+
+        let mut config: Config = toml::from_str(&text)
+            .map_err(|error| format!("failed to parse {}: {error}", path.display()))?;
+
+        if let (Some(prelude), Some(dir)) = (&config.prelude, path.parent()) {
+            config.prelude = Some(dir.join(prelude));
+        }
+
+        Ok(config)
+    }
+
+    /// Looks for [`CONFIG_FILE`] in `dir` and then in its ancestors.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Creates an interpreter writing to `io` with these settings.
+    pub fn interpreter<I: Printer>(&self, io: I) -> Interpreter<I> {
+        let mut interpreter = Interpreter::new(io)
+            .with_memoization(self.memoization.unwrap_or(true))
+            .with_spec(self.spec.unwrap_or(false));
+
+        if let Some(fuel) = self.limits.fuel {
+            interpreter = interpreter.with_fuel(fuel);
+        }
+
+        if let Some(max_depth) = self.limits.max_depth {
+            interpreter = interpreter.with_max_depth(max_depth);
+        }
+
+        if let Some(timeout) = self.limits.timeout {
+            interpreter = interpreter.with_timeout(Duration::from_secs(timeout));
+        }
+
+        if let Some(max_memory) = self.limits.max_memory {
+            interpreter = interpreter.with_max_memory(max_memory.saturating_mul(1 << 20));
+        }
+
+        if let Some(repeats) = self.limits.detect_loops {
+            interpreter = interpreter.with_loop_detection(repeats);
+        }
+
+        interpreter
+    }
+
+    /// The levels of the lints, failing on names that are not lints.
+    pub fn levels(&self) -> Result<Levels, String> {
+        let mut levels = Levels::default();
+
+        for (lint, level) in &self.lints {
+            levels.set(lint, *level)?;
+        }
+
+        Ok(levels)
+    }
+
+    /// Combines two configurations, `overrides` winning where both are set.
+    pub fn merge(self, overrides: Config) -> Config {
+        Config {
+            memoization: overrides.memoization.or(self.memoization),
+            spec: overrides.spec.or(self.spec),
+            prelude: overrides.prelude.or(self.prelude),
+            limits: Limits {
+                fuel: overrides.limits.fuel.or(self.limits.fuel),
+                max_depth: overrides.limits.max_depth.or(self.limits.max_depth),
+                timeout: overrides.limits.timeout.or(self.limits.timeout),
+                max_memory: overrides.limits.max_memory.or(self.limits.max_memory),
+                detect_loops: overrides.limits.detect_loops.or(self.limits.detect_loops),
+            },
+            lints: self.lints.into_iter().chain(overrides.lints).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::lint::Level;
+
+    use super::{Config, Limits};
+
+    #[test]
+    fn parse() {
+        let config: Config = toml::from_str(
+            r#"
+            memoization = false
+            spec = true
+            prelude = "prelude.json"
+
+            [limits]
+            max-depth = 100
+            max-memory = 64
+
+            [lints]
+            shadowed-name = "deny"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                memoization: Some(false),
+                spec: Some(true),
+                prelude: Some(PathBuf::from("prelude.json")),
+                limits: Limits {
+                    fuel: None,
+                    max_depth: Some(100),
+                    timeout: None,
+                    max_memory: Some(64),
+                    detect_loops: None,
+                },
+                lints: [(String::from("shadowed-name"), Level::Deny)].into(),
+            }
+        );
+    }
+
+    #[test]
+    fn reject_unknown_keys() {
+        assert!(toml::from_str::<Config>("memoisation = false").is_err());
+    }
+
+    #[test]
+    fn reject_unknown_lints() {
+        let config: Config = toml::from_str("[lints]\nshadowing = \"deny\"").unwrap();
+        assert!(config.levels().is_err());
+        assert!(toml::from_str::<Config>("[lints]\nwarnings = \"error\"").is_err());
+    }
+
+    #[test]
+    fn merge_prefers_overrides() {
+        let file = Config {
+            memoization: Some(false),
+            limits: Limits {
+                fuel: Some(10),
+                timeout: Some(5),
+                ..Limits::default()
+            },
+            ..Config::default()
+        };
+        let flags = Config {
+            limits: Limits {
+                timeout: Some(1),
+                ..Limits::default()
+            },
+            ..Config::default()
+        };
+
+        let config = file.merge(flags);
+
+        assert_eq!(config.memoization, Some(false));
+        assert_eq!(config.limits.fuel, Some(10));
+        assert_eq!(config.limits.timeout, Some(1));
+    }
+}
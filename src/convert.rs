@@ -0,0 +1,181 @@
+use std::fmt::Display;
+
+use crate::interpreter::{Tuple, Value};
+
+/// A value that is not of the kind a conversion expected.
+#[derive(Debug, Clone)]
+pub struct ConversionError {
+    pub expected: &'static str,
+    pub found: Value,
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "expected {}, found {} {}",
+            self.expected,
+            self.found.kind(),
+            self.found
+        )
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl Value {
+    /// Name of the kind of the value, the same used by the JSON output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Closure(_) => "Closure",
+            Value::Native(_) => "Native",
+            Value::Int(_) => "Int",
+            Value::Str(_) => "Str",
+            Value::Bool(_) => "Bool",
+            Value::Tuple(_) => "Tuple",
+            #[cfg(feature = "callcc")]
+            Value::Callcc(crate::interpreter::Callcc::Builtin) => "Native",
+            #[cfg(feature = "callcc")]
+            Value::Callcc(crate::interpreter::Callcc::Continuation(_)) => "Continuation",
+            #[cfg(feature = "generators")]
+            Value::Generator(generator) => match generator.name() {
+                Some(_) => "Native",
+                None => "Generator",
+            },
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(int: i64) -> Self {
+        Value::Int(int)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(bool: bool) -> Self {
+        Value::Bool(bool)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(str: &str) -> Self {
+        Value::Str(str.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(str: String) -> Self {
+        Value::Str(str.into())
+    }
+}
+
+impl<A: Into<Value>, B: Into<Value>> From<(A, B)> for Value {
+    fn from((first, second): (A, B)) -> Self {
+        Value::Tuple(Tuple {
+            first: Box::new(first.into()),
+            second: Box::new(second.into()),
+        })
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int(int) => Ok(int),
+            found => Err(ConversionError {
+                expected: "Int",
+                found,
+            }),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(bool) => Ok(bool),
+            found => Err(ConversionError {
+                expected: "Bool",
+                found,
+            }),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Str(str) => Ok(str.to_string()),
+            found => Err(ConversionError {
+                expected: "Str",
+                found,
+            }),
+        }
+    }
+}
+
+impl<A, B> TryFrom<Value> for (A, B)
+where
+    A: TryFrom<Value, Error = ConversionError>,
+    B: TryFrom<Value, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Tuple(tuple) => Ok((A::try_from(*tuple.first)?, B::try_from(*tuple.second)?)),
+            found => Err(ConversionError {
+                expected: "Tuple",
+                found,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{Collect, Context, Interpreter, Value},
+        parser::parse,
+    };
+
+    #[test]
+    fn round_trip() {
+        let value = Value::from((1, ("rinha", true)));
+        let (int, (str, bool)): (i64, (String, bool)) = value.try_into().unwrap();
+
+        assert_eq!((int, str.as_str(), bool), (1, "rinha", true));
+    }
+
+    #[test]
+    fn wrong_kind() {
+        let error = i64::try_from(Value::from("1")).unwrap_err();
+
+        assert_eq!(error.to_string(), "expected Int, found Str 1");
+    }
+
+    #[test]
+    fn call_closure() {
+        let file = parse("fn (a, b) => { (b, a + 1) }", "tests").unwrap();
+        let mut interpreter = Interpreter::new(Collect::default());
+        let function = interpreter
+            .eval(file.expression, &mut Context::new())
+            .unwrap();
+
+        let result = interpreter
+            .call(&function, vec![41.into(), "x".into()])
+            .unwrap();
+
+        assert_eq!(
+            <(String, i64)>::try_from(result).unwrap(),
+            ("x".to_string(), 42)
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::fmt::Display;
+
+use crate::{
+    ast::Location,
+    lexer::{Spanned, Token},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriviaKind {
+    Whitespace,
+    /// `// comment`, without the line break that ends it.
+    LineComment,
+    /// `/* comment */`
+    BlockComment,
+}
+
+/// Text of the source that is not part of any token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub location: Location,
+}
+
+impl Trivia {
+    pub fn is_comment(&self) -> bool {
+        self.kind != TriviaKind::Whitespace
+    }
+}
+
+/// A token along with its text and the trivia right before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CstToken {
+    pub leading: Vec<Trivia>,
+    pub token: Token,
+    /// The text of the token as written, like `"a\n"` with the escape.
+    pub text: String,
+    pub location: Location,
+}
+
+/// What a node of the concrete syntax tree is, which is mostly the kind of
+/// the term it becomes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    Program,
+    Int,
+    Str,
+    Bool,
+    Var,
+    Call,
+    Binary,
+    Function,
+    Let,
+    If,
+    Print,
+    First,
+    Second,
+    Tuple,
+    /// `(term)`, which has no node of its own in the syntax tree.
+    Paren,
+    /// `{ term }`, which has no node of its own in the syntax tree.
+    Block,
+    /// A name being bound, with its annotation if any.
+    Binder,
+    Annotation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Child {
+    Token(CstToken),
+    Node(Node),
+}
+
+/// A node of the concrete syntax tree, whose children are all the tokens it
+/// was parsed from, in order, grouped into the nodes of its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub kind: SyntaxKind,
+    pub children: Vec<Child>,
+}
+
+impl Node {
+    /// Every token of the node, in order.
+    pub fn tokens(&self) -> Box<dyn Iterator<Item = &CstToken> + '_> {
+        Box::new(self.children.iter().flat_map(|child| match child {
+            Child::Token(token) => Box::new(std::iter::once(token)),
+            Child::Node(node) => node.tokens(),
+        }))
+    }
+
+    /// The nodes that are children of this one.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|child| match child {
+            Child::Node(node) => Some(node),
+            Child::Token(_) => None,
+        })
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for token in self.tokens() {
+            for trivia in &token.leading {
+                f.write_str(&trivia.text)?;
+            }
+
+            f.write_str(&token.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Concrete syntax tree of a program, which keeps every character of its
+/// source, comments and whitespace included, for the tools that rewrite the
+/// source, like formatters. Its [`Display`] writes the source back as it
+/// was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cst {
+    /// A [`SyntaxKind::Program`] node, with the node of the program term.
+    pub root: Node,
+    /// The trivia after the last token.
+    pub trailing: Vec<Trivia>,
+}
+
+impl Cst {
+    /// Every comment of the program, in order.
+    pub fn comments(&self) -> impl Iterator<Item = &Trivia> {
+        self.root
+            .tokens()
+            .flat_map(|token| &token.leading)
+            .chain(&self.trailing)
+            .filter(|trivia| trivia.is_comment())
+    }
+}
+
+impl Display for Cst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.root.fmt(f)?;
+
+        for trivia in &self.trailing {
+            f.write_str(&trivia.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds the concrete syntax tree along with the parser, which adds each
+/// token it reads and groups the ones of a node once the node is read.
+pub(crate) struct Builder<'a> {
+    source: &'a str,
+    leading: std::vec::IntoIter<Vec<Trivia>>,
+    children: Vec<Child>,
+}
+
+impl<'a> Builder<'a> {
+    pub(crate) fn new(source: &'a str, leading: Vec<Vec<Trivia>>) -> Self {
+        Builder {
+            source,
+            leading: leading.into_iter(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Adds the next token, which must be the next one of the source.
+    pub(crate) fn token(&mut self, spanned: &Spanned) {
+        self.children.push(Child::Token(CstToken {
+            leading: self.leading.next().unwrap_or_default(),
+            token: spanned.token.clone(),
+            text: self.source[spanned.location.span()].to_string(),
+            location: spanned.location.clone(),
+        }));
+    }
+
+    /// Marks where a node starts, before reading its first token.
+    pub(crate) fn checkpoint(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Groups everything added since `checkpoint` into a node.
+    pub(crate) fn wrap(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        let children = self.children.split_off(checkpoint);
+        self.children.push(Child::Node(Node { kind, children }));
+    }
+
+    pub(crate) fn finish(mut self, trailing: Vec<Trivia>) -> Cst {
+        self.wrap(0, SyntaxKind::Program);
+
+        let Some(Child::Node(root)) = self.children.pop() else {
+            unreachable!("the program was just wrapped");
+        };
+
+        Cst { root, trailing }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse, parse_lossless};
+
+    use super::{Child, Node, SyntaxKind};
+
+    fn kinds(node: &Node) -> Vec<SyntaxKind> {
+        node.nodes().map(|node| node.kind).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        for name in ["combination", "fib", "hello-world", "sum"] {
+            let source = std::fs::read_to_string(format!("examples/{name}.rinha")).unwrap();
+            let (file, cst) = parse_lossless(&source, name).unwrap();
+
+            assert_eq!(cst.to_string(), source, "{name}");
+            assert_eq!(file.expression, parse(&source, name).unwrap().expression);
+        }
+    }
+
+    #[test]
+    fn nodes() {
+        let (_, cst) =
+            parse_lossless("let f = fn (x: int) => { (x) + 1 }; f(2) // done", "tests").unwrap();
+
+        assert_eq!(kinds(&cst.root), vec![SyntaxKind::Let]);
+
+        let let_ = cst.root.nodes().next().unwrap();
+        assert_eq!(
+            kinds(let_),
+            vec![SyntaxKind::Binder, SyntaxKind::Function, SyntaxKind::Call]
+        );
+
+        let function = let_.nodes().nth(1).unwrap();
+        assert_eq!(kinds(function), vec![SyntaxKind::Binder, SyntaxKind::Block]);
+        assert_eq!(function.to_string(), " fn (x: int) => { (x) + 1 }");
+
+        let block = function.nodes().nth(1).unwrap();
+        let Some(Child::Node(binary)) = block.children.get(1) else {
+            panic!("expected a node");
+        };
+        assert_eq!(kinds(binary), vec![SyntaxKind::Paren, SyntaxKind::Int]);
+
+        let comments = cst
+            .comments()
+            .map(|trivia| &*trivia.text)
+            .collect::<Vec<_>>();
+        assert_eq!(comments, vec!["// done"]);
+    }
+}
@@ -0,0 +1,770 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    ast::{Element, Term},
+    interpreter::{
+        Collect, Context, InspectLimits, Interpreter, RuntimeError, Session, Step, Trace, Value,
+    },
+    messages::Lang,
+    parser::parse,
+};
+
+mod breakpoint;
+pub mod remote;
+
+pub use breakpoint::{Breakpoint, Condition};
+
+static PROMPT: &str = "(rinha) ";
+
+static HELP: &str = "\
+commands:
+  where, bt        show the call stack, innermost call first
+  location, l      show where the error happened
+  env              list the bindings visible to the failing term
+  print, p <name>  show the value bound to <name>
+  set <limit> <n>  show values up to a depth, length or string of n
+  help, h          show this message
+  quit, q          leave the debugger";
+
+/// Interactive prompt to inspect the state of a failed run.
+///
+/// Reads commands from `input` until `quit` or end of input, writing the
+/// answers to `output`.
+pub fn post_mortem<R: BufRead, W: Write>(
+    error: &RuntimeError,
+    lang: Lang,
+    input: R,
+    mut output: W,
+) -> io::Result<()> {
+    writeln!(
+        output,
+        "post-mortem debugger: {} at {}",
+        error.message(lang),
+        error.location
+    )?;
+    writeln!(output, "type \"help\" for the available commands")?;
+
+    let mut lines = input.lines();
+    let mut limits = InspectLimits::default();
+
+    loop {
+        write!(output, "{PROMPT}")?;
+        output.flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+
+        let mut words = line.split_whitespace();
+
+        match (words.next(), words.next()) {
+            (None, _) => continue,
+            (Some("set"), name) => set(&mut limits, name, words.next(), &mut output)?,
+            (Some("quit" | "q"), _) => break,
+            (Some("help" | "h"), _) => writeln!(output, "{HELP}")?,
+            (Some("location" | "l"), _) => writeln!(
+                output,
+                "{} at {}\n  {}",
+                error.message(lang),
+                error.location,
+                error.full_text(lang)
+            )?,
+            (Some("where" | "bt"), _) => {
+                if error.stack.is_empty() {
+                    writeln!(output, "error happened outside of any function call")?;
+                }
+
+                for (depth, frame) in error.stack.iter().enumerate() {
+                    writeln!(output, "#{depth} {} at {}", frame.name, frame.location)?;
+                }
+            }
+            (Some("env"), _) => {
+                let mut bindings = error
+                    .context
+                    .iter()
+                    .flat_map(|context| context.iter())
+                    .map(|(name, value)| {
+                        format!("{name} = {}", value.inspect().with_limits(limits))
+                    })
+                    .collect::<Vec<_>>();
+                bindings.sort();
+
+                for binding in bindings {
+                    writeln!(output, "{binding}")?;
+                }
+            }
+            (Some("print" | "p"), Some(name)) => {
+                match error.context.as_ref().and_then(|context| context.get(name)) {
+                    Some(value) => {
+                        writeln!(output, "{name} = {}", value.inspect().with_limits(limits))?
+                    }
+                    None => writeln!(output, "\"{name}\" is not bound here")?,
+                }
+            }
+            (Some("print" | "p"), None) => writeln!(output, "usage: print <name>")?,
+            (Some(command), _) => writeln!(
+                output,
+                "unknown command \"{command}\", type \"help\" for the available commands"
+            )?,
+        }
+    }
+
+    writeln!(output)
+}
+
+static TIME_TRAVEL_HELP: &str = "\
+commands:
+  step, s [n]      evaluate the next n terms, 1 by default
+  back, b [n]      go back n terms, 1 by default
+  goto, g <n>      go right before the n-th term evaluated, counting from 0
+  continue, c      run to the next breakpoint or the end of the program
+  history <name>   show every value bound to <name> up to here, and where
+  where, bt        show the call stack, innermost call first
+  location, l      show the term about to be evaluated
+  env              list the bindings visible to the term
+  print, p <name>  show the value bound to <name>
+  watch, w <expr>  show the value of <expr> at every stop
+  watch, w         show the watched expressions
+  unwatch <n>      stop watching the n-th expression
+  break, br <line> [if <expr> | hits <n>]
+                   pause at <line>, or at <file>:<line>, when continuing,
+                   only when <expr> is true or from its n-th hit
+  break, br        show the breakpoints
+  delete, d <n>    remove the n-th breakpoint
+  set <limit> <n>  show values up to a depth, length or string of n
+  help, h          show this message
+  quit, q          leave the debugger";
+
+/// Calls a watch expression can make, so that one that doesn't end doesn't
+/// hang the debugger.
+static WATCH_FUEL: u64 = 100_000;
+
+/// Runs the `set` command, setting the limit called `name` of the values
+/// shown, or showing them all without one.
+fn set<W: Write>(
+    limits: &mut InspectLimits,
+    name: Option<&str>,
+    value: Option<&str>,
+    output: &mut W,
+) -> io::Result<()> {
+    match (name, value) {
+        (None, _) => writeln!(output, "{limits}"),
+        (Some(name), Some(value)) => match limits.set(name, value) {
+            Ok(()) => writeln!(output, "{limits}"),
+            Err(error) => writeln!(output, "{error}"),
+        },
+        (Some(_), None) => writeln!(output, "usage: set <depth | length | string> <n>"),
+    }
+}
+
+/// Interpreter replaying `trace`, so that every run of the program makes the
+/// same calls to native functions, and stopping at every term.
+fn replay(trace: &Trace) -> Interpreter<Collect> {
+    Interpreter::new(Collect::default())
+        .with_replay(trace.clone())
+        .with_superinstructions(false)
+}
+
+/// Runs `session` up to the next term it evaluates, telling whether there is
+/// one before the end of the program.
+fn advance(session: &mut Session<Collect>) -> bool {
+    loop {
+        if let Step::Done(_) | Step::Failed(_) = session.step() {
+            return false;
+        }
+
+        if session.term().is_some() {
+            return true;
+        }
+    }
+}
+
+/// Runs `session`, stopped right before its first term, up to the term
+/// evaluated `position`-th, or to the end of the program if it is over
+/// before, returning where it stopped.
+fn seek(session: &mut Session<Collect>, position: usize) -> usize {
+    let mut at = 0;
+
+    while at < position && advance(session) {
+        at += 1;
+    }
+
+    at
+}
+
+fn show_stop<W: Write>(
+    session: &mut Session<Collect>,
+    position: usize,
+    lang: Lang,
+    limits: InspectLimits,
+    output: &mut W,
+) -> io::Result<()> {
+    if let Some(term) = session.term() {
+        return writeln!(output, "#{position} at {}", term.location());
+    }
+
+    match session.step() {
+        Step::Done(value) => writeln!(
+            output,
+            "#{position} the program returned {}",
+            value.inspect().with_limits(limits)
+        ),
+        Step::Failed(error) => writeln!(
+            output,
+            "#{position} the program failed: {} at {}",
+            error.message(lang),
+            error.location
+        ),
+        Step::Pending => unreachable!("the session stopped at the end of the program"),
+    }
+}
+
+/// Evaluates the watch expression `source` with `bindings`, in an interpreter
+/// of its own and a copy of the bindings, so that the program being debugged
+/// is left as it was. The closures of the copy still share their bindings
+/// with the program, but evaluating a term only ever adds to the bindings of
+/// the closures it makes itself. What the expression prints isn't shown, and
+/// it has no input to read.
+fn evaluate(source: &str, bindings: &Context, lang: Lang) -> Result<Value, String> {
+    let file = parse(source, "<watch>").map_err(|error| error.message(lang))?;
+    let mut interpreter = Interpreter::new(Collect::default())
+        .with_input(io::empty())
+        .with_fuel(WATCH_FUEL);
+
+    interpreter
+        .eval(file.expression, &mut bindings.clone())
+        .map_err(|error| error.message(lang))
+}
+
+fn watch(source: &str, bindings: &Context, lang: Lang, limits: InspectLimits) -> String {
+    match evaluate(source, bindings, lang) {
+        Ok(value) => value.inspect().with_limits(limits).to_string(),
+        Err(error) => format!("error: {error}"),
+    }
+}
+
+/// Where the lines of a source start, to find the line of a term.
+fn line_starts(source: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+        .collect()
+}
+
+/// Runs `session` up to the next breakpoint of `breakpoints` it reaches,
+/// like [`seek`] does up to a position, returning how many terms it went
+/// through and the breakpoint it paused at, with why when its condition
+/// couldn't be evaluated.
+///
+/// A line is reached when the evaluation enters it, or jumps back in it,
+/// like in a call to a function defined on it.
+fn seek_breakpoint<W: Write>(
+    session: &mut Session<Collect>,
+    breakpoints: &mut [Breakpoint],
+    starts: &[usize],
+    lang: Lang,
+    output: &mut W,
+) -> io::Result<usize> {
+    let line = |start: usize| starts.partition_point(|line| *line <= start);
+    let mut previous = session.term().map(|term| term.location().start);
+    let mut moved = 0;
+
+    while advance(session) {
+        moved += 1;
+
+        let Some(location) = session.term().map(|term| term.location().clone()) else {
+            continue;
+        };
+        let at = line(location.start);
+        let entered =
+            previous.is_none_or(|previous| line(previous) != at || location.start < previous);
+        previous = Some(location.start);
+
+        if !entered {
+            continue;
+        }
+
+        for (index, breakpoint) in breakpoints.iter_mut().enumerate() {
+            if !breakpoint.is_at(at, &location) {
+                continue;
+            }
+
+            match breakpoint.hit(session.bindings(), lang) {
+                Ok(false) => continue,
+                Ok(true) => writeln!(output, "breakpoint [{index}] {breakpoint}")?,
+                Err(error) => writeln!(output, "breakpoint [{index}] {breakpoint}: {error}")?,
+            }
+
+            return Ok(moved);
+        }
+    }
+
+    Ok(moved)
+}
+
+fn show_watches<W: Write>(
+    watches: &[String],
+    bindings: &Context,
+    lang: Lang,
+    limits: InspectLimits,
+    output: &mut W,
+) -> io::Result<()> {
+    for (index, source) in watches.iter().enumerate() {
+        writeln!(
+            output,
+            "  [{index}] {source} = {}",
+            watch(source, bindings, lang, limits)
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Interactive prompt to run a program one term at a time, going back and
+/// forth in its evaluation.
+///
+/// The program is run with the native functions answered by `trace`, see
+/// [`Interpreter::with_replay`], so every run is the same, and going back
+/// runs the program again from its start up to the term asked for. Programs
+/// that call more natives than recorded fail once they call them.
+///
+/// The expressions given to `watch` are evaluated again at every stop, see
+/// [`evaluate`], and the program pauses at the breakpoints when continued.
+/// The breakpoints are set by line, which needs the `source` of the
+/// program.
+pub fn time_travel<R: BufRead, W: Write>(
+    program: &Term,
+    source: Option<&str>,
+    trace: &Trace,
+    lang: Lang,
+    input: R,
+    mut output: W,
+) -> io::Result<()> {
+    let mut interpreter = replay(trace);
+    let mut session =
+        Session::new(&mut interpreter, program.clone(), Context::new()).with_bounces(1);
+    let mut position = 0;
+    let mut printed = 0;
+    let mut watches = Vec::new();
+    let mut breakpoints = Vec::new();
+    let mut limits = InspectLimits::default();
+    let starts = source.map(line_starts);
+
+    writeln!(
+        output,
+        "time travel debugger: type \"help\" for the available commands"
+    )?;
+    show_stop(&mut session, position, lang, limits, &mut output)?;
+
+    let mut lines = input.lines();
+
+    loop {
+        write!(output, "{PROMPT}")?;
+        output.flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+
+        let mut words = line.split_whitespace();
+        let command = words.next();
+        let argument = words.next();
+        // Everything after the command, for the commands taking an
+        // expression.
+        let rest = line
+            .trim()
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest.trim());
+        let count = argument.and_then(|count| count.parse::<usize>().ok());
+
+        let target = match (command, argument) {
+            (None, _) => continue,
+            (Some("quit" | "q"), _) => break,
+            (Some("help" | "h"), _) => {
+                writeln!(output, "{TIME_TRAVEL_HELP}")?;
+                continue;
+            }
+            (Some("step" | "s"), _) => position + count.unwrap_or(1),
+            (Some("continue" | "c"), _) => usize::MAX,
+            (Some("back" | "b"), _) => position.saturating_sub(count.unwrap_or(1)),
+            (Some("goto" | "g"), Some(_)) => match count {
+                Some(count) => count,
+                None => {
+                    writeln!(output, "usage: goto <n>")?;
+                    continue;
+                }
+            },
+            (Some("goto" | "g"), None) => {
+                writeln!(output, "usage: goto <n>")?;
+                continue;
+            }
+            (Some("location" | "l"), _) => {
+                show_stop(&mut session, position, lang, limits, &mut output)?;
+                show_watches(&watches, session.bindings(), lang, limits, &mut output)?;
+                continue;
+            }
+            (Some("watch" | "w"), Some(_)) => {
+                writeln!(
+                    output,
+                    "[{}] {rest} = {}",
+                    watches.len(),
+                    watch(rest, session.bindings(), lang, limits)
+                )?;
+                watches.push(rest.to_string());
+                continue;
+            }
+            (Some("watch" | "w"), None) => {
+                if watches.is_empty() {
+                    writeln!(output, "no expressions are watched")?;
+                }
+
+                show_watches(&watches, session.bindings(), lang, limits, &mut output)?;
+                continue;
+            }
+            (Some("set"), name) => {
+                set(&mut limits, name, words.next(), &mut output)?;
+                continue;
+            }
+            (Some("break" | "br"), Some(_)) => {
+                match (&starts, Breakpoint::parse(rest)) {
+                    (None, _) => writeln!(
+                        output,
+                        "breakpoints need the source of the program, not its JSON AST"
+                    )?,
+                    (Some(_), Ok(breakpoint)) => {
+                        writeln!(output, "[{}] {breakpoint}", breakpoints.len())?;
+                        breakpoints.push(breakpoint);
+                    }
+                    (Some(_), Err(error)) => writeln!(output, "{error}")?,
+                }
+
+                continue;
+            }
+            (Some("break" | "br"), None) => {
+                if breakpoints.is_empty() {
+                    writeln!(output, "there are no breakpoints")?;
+                }
+
+                for (index, breakpoint) in breakpoints.iter().enumerate() {
+                    writeln!(output, "[{index}] {breakpoint}")?;
+                }
+
+                continue;
+            }
+            (Some("delete" | "d"), _) => {
+                match count.filter(|index| *index < breakpoints.len()) {
+                    Some(index) => {
+                        breakpoints.remove(index);
+                    }
+                    None => writeln!(output, "usage: delete <n>, see \"break\"")?,
+                }
+
+                continue;
+            }
+            (Some("unwatch"), _) => {
+                match count.filter(|index| *index < watches.len()) {
+                    Some(index) => {
+                        watches.remove(index);
+                    }
+                    None => writeln!(output, "usage: unwatch <n>, see \"watch\"")?,
+                }
+
+                continue;
+            }
+            (Some("where" | "bt"), _) => {
+                let frames = session.stack().collect::<Vec<_>>();
+
+                if frames.is_empty() {
+                    writeln!(output, "outside of any function call")?;
+                }
+
+                for (depth, frame) in frames.iter().enumerate() {
+                    writeln!(output, "#{depth} {} at {}", frame.name, frame.location)?;
+                }
+
+                continue;
+            }
+            (Some("env"), _) => {
+                let mut bindings = session
+                    .bindings()
+                    .iter()
+                    .map(|(name, value)| {
+                        format!("{name} = {}", value.inspect().with_limits(limits))
+                    })
+                    .collect::<Vec<_>>();
+                bindings.sort();
+
+                for binding in bindings {
+                    writeln!(output, "{binding}")?;
+                }
+
+                continue;
+            }
+            (Some("print" | "p"), Some(name)) => {
+                match session.bindings().get(name) {
+                    Some(value) => {
+                        writeln!(output, "{name} = {}", value.inspect().with_limits(limits))?
+                    }
+                    None => writeln!(output, "\"{name}\" is not bound here")?,
+                }
+
+                continue;
+            }
+            (Some("history"), Some(name)) => {
+                let mut interpreter = replay(trace);
+                let mut earlier =
+                    Session::new(&mut interpreter, program.clone(), Context::new()).with_bounces(1);
+                let mut last = None;
+
+                for at in 0..=position {
+                    let value = earlier
+                        .bindings()
+                        .get(name)
+                        .map(|value| value.inspect().with_limits(limits).to_string());
+
+                    if value.is_some() && value != last {
+                        let location = earlier.term().map(Element::location);
+                        let value = value.as_deref().unwrap_or_default();
+
+                        match location {
+                            Some(location) => {
+                                writeln!(output, "#{at} {name} = {value} at {location}")?
+                            }
+                            None => writeln!(output, "#{at} {name} = {value}")?,
+                        }
+                    }
+
+                    last = value;
+
+                    if at < position && !advance(&mut earlier) {
+                        break;
+                    }
+                }
+
+                if last.is_none() {
+                    writeln!(output, "\"{name}\" was not bound up to here")?;
+                }
+
+                continue;
+            }
+            (Some("print" | "p" | "history"), None) => {
+                writeln!(output, "usage: {} <name>", command.unwrap_or_default())?;
+                continue;
+            }
+            (Some(command), _) => {
+                writeln!(
+                    output,
+                    "unknown command \"{command}\", type \"help\" for the available commands"
+                )?;
+                continue;
+            }
+        };
+
+        if target < position {
+            // Going back runs the program again, answering the natives the
+            // same way, so it stops in the same state as before.
+            drop(session);
+            interpreter = replay(trace);
+            session =
+                Session::new(&mut interpreter, program.clone(), Context::new()).with_bounces(1);
+            position = seek(&mut session, target);
+            printed = session.interpreter().io.0.len();
+        } else {
+            position += match (&starts, command) {
+                (Some(starts), Some("continue" | "c")) if !breakpoints.is_empty() => {
+                    seek_breakpoint(&mut session, &mut breakpoints, starts, lang, &mut output)?
+                }
+                _ => seek(&mut session, target - position),
+            };
+
+            for value in &session.interpreter().io.0[printed..] {
+                writeln!(output, "printed {value}")?;
+            }
+
+            printed = session.interpreter().io.0.len();
+        }
+
+        show_stop(&mut session, position, lang, limits, &mut output)?;
+        show_watches(&watches, session.bindings(), lang, limits, &mut output)?;
+    }
+
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Location,
+        interpreter::{Context, Frame, RuntimeError, Trace, Value},
+        messages::{ErrorCode, Lang},
+        parser::parse,
+    };
+
+    use super::{post_mortem, time_travel};
+
+    fn location(start: usize) -> Location {
+        Location::new(start, start + 1, "tests")
+    }
+
+    fn error() -> RuntimeError {
+        let mut context = Context::new();
+        context.insert("n".into(), Value::Int(0));
+
+        let mut error = RuntimeError::new(ErrorCode::DivisionByZero, vec![], location(10));
+        error.context = Some(Box::new(context));
+        error.stack.push(Frame {
+            name: "inner".to_string(),
+            location: location(5),
+        });
+        error.stack.push(Frame {
+            name: "outer".to_string(),
+            location: location(1),
+        });
+
+        error
+    }
+
+    fn run(script: &str) -> String {
+        let mut output = Vec::new();
+        post_mortem(&error(), Lang::En, script.as_bytes(), &mut output).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn show_stack() {
+        let output = run("where\nquit\n");
+
+        assert!(output.contains("#0 inner at tests:5..6\n#1 outer at tests:1..2"));
+    }
+
+    #[test]
+    fn print_bindings() {
+        let output = run("print n\np m\n");
+
+        assert!(output.contains("n = 0"));
+        assert!(output.contains("\"m\" is not bound here"));
+    }
+
+    fn travel(source: &str, script: &str) -> String {
+        let file = parse(source, "tests").unwrap();
+        let mut output = Vec::new();
+        time_travel(
+            &file.expression,
+            Some(source),
+            &Trace::default(),
+            Lang::En,
+            script.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    static COUNTER: &str = "let f = fn (n) => { if (n == 0) { 0 } else { f(n - 1) } }; print(f(2))";
+
+    #[test]
+    fn step_back_and_forth() {
+        let output = travel(COUNTER, "s 8\nwhere\np n\nb 3\np n\nc\nq\n");
+
+        assert!(output.contains("#8 at tests:"));
+        assert!(output.contains("#0 f at tests:"));
+        assert!(output.contains("n = 2\n"));
+        // Back before the call, where `n` is not bound yet.
+        assert!(output.contains("#5 at tests:"));
+        assert!(output.contains("\"n\" is not bound here"));
+        assert!(output.contains("printed 0\n"));
+        assert!(output.contains("the program returned 0"));
+    }
+
+    #[test]
+    fn watches() {
+        let script =
+            "w n * 10\nwatch print(read_line())\nunwatch 1\ns 8\ns 7\nwatch\nunwatch 5\nc\n";
+        let output = travel(COUNTER, script);
+
+        assert!(output.contains("[0] n * 10 = error: unbound variable \"n\"\n"));
+        assert!(output.contains("[1] print(read_line()) = error: "));
+        assert!(output.contains("  [0] n * 10 = 20\n"));
+        assert!(output.contains("  [0] n * 10 = 10\n"));
+        assert!(!output.contains("  [1]"));
+        assert!(output.contains("usage: unwatch <n>"));
+        // What the watch expressions print isn't what the program printed.
+        assert!(output.contains("printed 0\n"));
+        assert!(!output.contains("printed \n"));
+    }
+
+    #[test]
+    fn watches_leave_the_program_alone() {
+        let source = "let k = 1; let f = fn () => { k }; let x = 0; print(f())";
+        let output = travel(source, "s 6\nwatch let k = f; k\ns\nc\n");
+
+        assert!(
+            output.contains("[0] let k = f; k = <#closure>\n"),
+            "{output}"
+        );
+        assert!(output.contains("printed 1\n"), "{output}");
+    }
+
+    static LINES: &str = "\
+let f = fn (n) => {
+  if (n == 0) { 0 } else {
+    f(n - 1)
+  }
+};
+print(f(4))";
+
+    #[test]
+    fn breakpoints() {
+        let output = travel(
+            LINES,
+            "break 2\nbr tests:3 if n == 2\nbr\nc\np n\nd 0\nc\np n\nc\n",
+        );
+
+        assert!(output.contains("[0] 2 (0 hits)\n[1] tests:3 if n == 2 (0 hits)\n"));
+        assert!(output.contains("breakpoint [0] 2 (1 hits)\n"));
+        assert!(output.contains("n = 4\n"));
+        // Line 3 is reached with n at 4 and 3 first.
+        assert!(output.contains("breakpoint [0] tests:3 if n == 2 (1 hits)\n"));
+        assert!(output.contains("n = 2\n"));
+        assert!(output.contains("the program returned 0"));
+
+        let output = travel(LINES, "br 3 hits 3\nc\np n\nd 0\nbr 3 if x\nc\n");
+        assert!(output.contains("n = 2\n"));
+        assert!(output.contains("breakpoint [0] 3 if x (0 hits): the condition failed: "));
+
+        let output = travel(COUNTER, "br 1\nc\nc\nc\np n\n");
+        // The function is called three times on the line it is defined on.
+        assert!(output.contains("breakpoint [0] 1 (3 hits)\n"));
+
+        assert!(travel(LINES, "br 3 if\n").contains("usage: break"));
+    }
+
+    #[test]
+    fn limits() {
+        let output = travel(
+            "let list = (1, (2, (3, 4))); list",
+            "set length 2\nc\nset depth 0\nset\n",
+        );
+
+        assert!(output.contains("depth 6, length 2, string 256\n"));
+        assert!(output.contains("the program returned (1, (2, ...))\n"));
+        assert!(output.contains("\"0\" is not a positive number"));
+    }
+
+    #[test]
+    fn history() {
+        let output = travel(COUNTER, "c\nb 1\nhistory n\nhistory m\n");
+
+        let values = output
+            .lines()
+            .filter(|line| line.contains(" n = "))
+            .collect::<Vec<_>>();
+        assert_eq!(values.len(), 3, "{output}");
+        assert!(values[0].contains("n = 2"));
+        assert!(values[2].contains("n = 0"));
+        assert!(output.contains("\"m\" was not bound up to here"));
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt::{self, Display};
+
+use crate::{
+    ast::Location,
+    interpreter::{Context, Value},
+    messages::Lang,
+};
+
+use super::evaluate;
+
+/// When a breakpoint pauses the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Always,
+    /// When the rinha expression is `true`, evaluated like a watch
+    /// expression.
+    If(String),
+    /// From the nth time the line is reached.
+    Hits(usize),
+}
+
+/// A line the program pauses at when it is continued.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    /// The file of the line, any of them when `None`.
+    pub filename: Option<String>,
+    pub line: usize,
+    pub condition: Condition,
+    /// Times the program paused at the line, or reached it for the hit
+    /// counts, since the breakpoint was set.
+    pub hits: usize,
+}
+
+impl Breakpoint {
+    /// Reads a breakpoint like `main.rinha:42 if n == 17`, `42 hits 3` or
+    /// `42`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (location, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+
+        let (filename, line) = match location.rsplit_once(':') {
+            Some((filename, line)) => (Some(filename.to_string()), line),
+            None => (None, location),
+        };
+        let line = line
+            .parse::<usize>()
+            .ok()
+            .filter(|line| *line > 0)
+            .ok_or_else(|| format!("\"{location}\" is not a line, like main.rinha:42"))?;
+
+        let rest = rest.trim();
+        let condition = match rest.split_once(char::is_whitespace).unwrap_or((rest, "")) {
+            ("", _) => Condition::Always,
+            ("if", expression) if !expression.trim().is_empty() => {
+                Condition::If(expression.trim().to_string())
+            }
+            ("hits", count) => count
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|count| *count > 0)
+                .map(Condition::Hits)
+                .ok_or_else(|| format!("\"{}\" is not a hit count", count.trim()))?,
+            _ => {
+                return Err(String::from(
+                    "usage: break [file:]<line> [if <expr> | hits <n>]",
+                ))
+            }
+        };
+
+        Ok(Self {
+            filename,
+            line,
+            condition,
+            hits: 0,
+        })
+    }
+
+    /// Whether the breakpoint is on `line` of the file of `location`.
+    pub fn is_at(&self, line: usize, location: &Location) -> bool {
+        self.line == line
+            && self
+                .filename
+                .as_ref()
+                .is_none_or(|filename| location.filename.ends_with(filename.as_str()))
+    }
+
+    /// Counts that the line was reached with `bindings`, telling whether
+    /// the program pauses. A condition that fails or isn't a boolean pauses
+    /// it, with why.
+    pub fn hit(&mut self, bindings: &Context, lang: Lang) -> Result<bool, String> {
+        let pause = match &self.condition {
+            Condition::Always => true,
+            Condition::Hits(count) => {
+                self.hits += 1;
+                return Ok(self.hits >= *count);
+            }
+            Condition::If(expression) => match evaluate(expression, bindings, lang) {
+                Ok(Value::Bool(holds)) => holds,
+                Ok(value) => {
+                    return Err(format!(
+                        "the condition is {}, not a boolean",
+                        value.inspect()
+                    ))
+                }
+                Err(error) => return Err(format!("the condition failed: {error}")),
+            },
+        };
+
+        if pause {
+            self.hits += 1;
+        }
+
+        Ok(pause)
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(filename) = &self.filename {
+            write!(f, "{filename}:")?;
+        }
+
+        write!(f, "{}", self.line)?;
+
+        match &self.condition {
+            Condition::Always => (),
+            Condition::If(expression) => write!(f, " if {expression}")?,
+            Condition::Hits(count) => write!(f, " hits {count}")?,
+        }
+
+        write!(f, " ({} hits)", self.hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Location,
+        interpreter::{Context, Value},
+        messages::Lang,
+    };
+
+    use super::{Breakpoint, Condition};
+
+    #[test]
+    fn parses_breakpoints() {
+        let breakpoint = Breakpoint::parse("main.rinha:42 if n == 17").unwrap();
+        assert_eq!(breakpoint.filename.as_deref(), Some("main.rinha"));
+        assert_eq!(breakpoint.line, 42);
+        assert_eq!(breakpoint.condition, Condition::If(String::from("n == 17")));
+        assert!(breakpoint.is_at(42, &Location::new(0, 1, "src/main.rinha")));
+        assert!(!breakpoint.is_at(42, &Location::new(0, 1, "other.rinha")));
+
+        let breakpoint = Breakpoint::parse("7 hits 3").unwrap();
+        assert_eq!(breakpoint.condition, Condition::Hits(3));
+        assert_eq!(breakpoint.to_string(), "7 hits 3 (0 hits)");
+
+        assert!(Breakpoint::parse("0").is_err());
+        assert!(Breakpoint::parse("7 hits none").is_err());
+        assert!(Breakpoint::parse("7 if").is_err());
+    }
+
+    #[test]
+    fn conditions() {
+        let mut context = Context::new();
+        context.insert("n".into(), Value::Int(17));
+
+        let mut breakpoint = Breakpoint::parse("1 if n == 17").unwrap();
+        assert_eq!(breakpoint.hit(&context, Lang::En), Ok(true));
+
+        let mut breakpoint = Breakpoint::parse("1 if n == 1").unwrap();
+        assert_eq!(breakpoint.hit(&context, Lang::En), Ok(false));
+        assert_eq!(breakpoint.hits, 0);
+
+        let mut breakpoint = Breakpoint::parse("1 if n + 1").unwrap();
+        assert!(breakpoint.hit(&context, Lang::En).is_err());
+
+        let mut breakpoint = Breakpoint::parse("1 hits 2").unwrap();
+        assert_eq!(breakpoint.hit(&context, Lang::En), Ok(false));
+        assert_eq!(breakpoint.hit(&context, Lang::En), Ok(true));
+        assert_eq!(breakpoint.hit(&context, Lang::En), Ok(true));
+    }
+}
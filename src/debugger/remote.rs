@@ -0,0 +1,191 @@
+//! The debugger over TCP, for programs running where there is no terminal
+//! to debug them in, like the container of the competition. The first line
+//! a client sends is a token known only to whoever started the debugger.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How long a client has to send the token once connected, so that one that
+/// doesn't can't keep the debugger from the others.
+static TOKEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest first line read from a client, longer ones are not the token.
+const MAX_LINE: usize = 64;
+
+/// A token for [`accept`], 32 hexadecimal digits read from `/dev/urandom`.
+pub fn token() -> String {
+    let mut bytes = [0; 16];
+
+    match std::fs::File::open("/dev/urandom").and_then(|mut file| file.read_exact(&mut bytes)) {
+        Ok(()) => bytes.iter().map(|byte| format!("{byte:02x}")).collect(),
+        Err(_) => seeded_token(),
+    }
+}
+
+/// A token drawn from the random keys the standard library seeds its hash
+/// maps with, where there is no `/dev/urandom`.
+fn seeded_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+
+    (0..2)
+        .map(|half| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(nanos);
+            hasher.write_u32(std::process::id());
+            hasher.write_u8(half);
+
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// Waits on `listener` for a client whose first line is `token`, returning
+/// the connection to debug over, read from and written to. The clients that
+/// send another line are told so and dropped.
+pub fn accept(
+    listener: &TcpListener,
+    token: &str,
+) -> io::Result<(BufReader<TcpStream>, TcpStream)> {
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+
+        // A client gone or too slow doesn't stop the debugger.
+        let Ok(line) = first_line(&mut reader, &stream) else {
+            continue;
+        };
+
+        let line = String::from_utf8_lossy(&line);
+        if !same(line.trim_end_matches(['\n', '\r']), token) {
+            let _ = writeln!(stream, "invalid token");
+            continue;
+        }
+
+        stream.set_read_timeout(None)?;
+
+        return Ok((reader, stream));
+    }
+
+    Err(io::ErrorKind::ConnectionAborted.into())
+}
+
+/// Reads the first line sent over `stream`, or its first [`MAX_LINE`] bytes
+/// when it is longer, failing when it doesn't come in [`TOKEN_TIMEOUT`].
+fn first_line(reader: &mut BufReader<TcpStream>, stream: &TcpStream) -> io::Result<Vec<u8>> {
+    let deadline = Instant::now() + TOKEN_TIMEOUT;
+    let mut line = Vec::new();
+
+    // Each read waits only for what is left of the time, so that a client
+    // sending a byte at a time can't take longer.
+    while !line.ends_with(b"\n") && line.len() < MAX_LINE {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+
+        stream.set_read_timeout(Some(left))?;
+        let read = reader.fill_buf()?;
+        if read.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let length = match read.iter().position(|&byte| byte == b'\n') {
+            Some(end) => end + 1,
+            None => read.len(),
+        }
+        .min(MAX_LINE - line.len());
+
+        line.extend_from_slice(&read[..length]);
+        reader.consume(length);
+    }
+
+    Ok(line)
+}
+
+/// Compares the tokens taking the same time wherever they differ, not to
+/// tell how much of one was guessed right.
+fn same(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |differ, (a, b)| differ | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use crate::{interpreter::Trace, messages::Lang, parser::parse};
+
+    use super::{super::time_travel, accept, token};
+
+    #[test]
+    fn tokens_differ() {
+        let token = token();
+
+        assert_eq!(token.len(), 32);
+        assert!(token.chars().all(|char| char.is_ascii_hexdigit()));
+        assert_ne!(token, super::token());
+    }
+
+    #[test]
+    fn debugs_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let token = token();
+
+        let client = {
+            let token = token.clone();
+
+            thread::spawn(move || {
+                let mut intruder = TcpStream::connect(address).unwrap();
+                writeln!(intruder, "0000").unwrap();
+                let mut answer = String::new();
+                BufReader::new(intruder).read_line(&mut answer).unwrap();
+                assert_eq!(answer, "invalid token\n");
+
+                let mut flooder = TcpStream::connect(address).unwrap();
+                flooder.write_all(&[b'0'; 1 << 10]).unwrap();
+                let mut answer = String::new();
+                BufReader::new(flooder).read_line(&mut answer).unwrap();
+                assert_eq!(answer, "invalid token\n");
+
+                let mut client = TcpStream::connect(address).unwrap();
+                write!(client, "{token}\nstep 2\nquit\n").unwrap();
+                let mut output = String::new();
+                client.read_to_string(&mut output).unwrap();
+
+                output
+            })
+        };
+
+        let (input, output) = accept(&listener, &token).unwrap();
+        let file = parse("let x = 1; print(x)", "tests").unwrap();
+        time_travel(
+            &file.expression,
+            None,
+            &Trace::default(),
+            Lang::En,
+            input,
+            output,
+        )
+        .unwrap();
+
+        let output = client.join().unwrap();
+        assert!(output.starts_with("time travel debugger"));
+        assert!(output.contains("#2 at tests:"));
+    }
+}
@@ -0,0 +1,188 @@
+//! Line diffs between the expected output of a program and the one it
+//! wrote, in the unified format of `diff -u`.
+
+/// Lines of context around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// A line in both texts, at these indices.
+    Keep(usize, usize),
+    /// A line only in the expected text.
+    Delete(usize),
+    /// A line only in the actual text.
+    Insert(usize),
+}
+
+/// The unified diff from `expected` to `actual`, labelled with the given
+/// names, or `None` when they have the same lines.
+pub fn unified(expected: &str, actual: &str, from: &str, to: &str) -> Option<String> {
+    let old = expected.lines().collect::<Vec<_>>();
+    let new = actual.lines().collect::<Vec<_>>();
+
+    if old == new {
+        return None;
+    }
+
+    let edits = edits(&old, &new);
+    let mut diff = format!("--- {from}\n+++ {to}\n");
+
+    for hunk in hunks(&edits) {
+        let hunk = &edits[hunk.0..hunk.1];
+        let (old_start, old_count) = span(hunk, |edit| match edit {
+            Edit::Keep(line, _) | Edit::Delete(line) => Some(line),
+            Edit::Insert(_) => None,
+        });
+        let (new_start, new_count) = span(hunk, |edit| match edit {
+            Edit::Keep(_, line) | Edit::Insert(line) => Some(line),
+            Edit::Delete(_) => None,
+        });
+
+        diff.push_str(&format!(
+            "@@ -{old_start},{old_count} +{new_start},{new_count} @@\n"
+        ));
+
+        for edit in hunk {
+            let line = match *edit {
+                Edit::Keep(line, _) => format!(" {}", old[line]),
+                Edit::Delete(line) => format!("-{}", old[line]),
+                Edit::Insert(line) => format!("+{}", new[line]),
+            };
+
+            diff.push_str(&line);
+            diff.push('\n');
+        }
+    }
+
+    Some(diff)
+}
+
+/// The shortest edits turning `old` into `new`, found with the algorithm of
+/// Myers.
+fn edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let offset = n + m + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let mut trace = Vec::new();
+
+    'search: for d in 0..=n + m {
+        trace.push(v.clone());
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            v[index(k)] = x;
+
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let previous = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = v[index(previous)];
+        let previous_y = previous_x - previous;
+
+        while x > previous_x && y > previous_y {
+            edits.push(Edit::Keep(x as usize - 1, y as usize - 1));
+            x -= 1;
+            y -= 1;
+        }
+
+        if d > 0 {
+            if x == previous_x {
+                edits.push(Edit::Insert(y as usize - 1));
+            } else {
+                edits.push(Edit::Delete(x as usize - 1));
+            }
+        }
+
+        (x, y) = (previous_x, previous_y);
+    }
+
+    edits.reverse();
+    edits
+}
+
+/// Ranges of `edits` shown together, the changes with the lines of context
+/// around them, merged when their context overlaps.
+fn hunks(edits: &[Edit]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+
+    for (i, edit) in edits.iter().enumerate() {
+        if matches!(edit, Edit::Keep(..)) {
+            continue;
+        }
+
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + 1 + CONTEXT).min(edits.len());
+
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    hunks
+}
+
+/// First line, counted from one, and number of lines of one side of a hunk.
+/// Since hunks keep the lines around the changes, a side is only empty when
+/// the whole text is, and it then starts at line zero like in `diff -u`.
+fn span(hunk: &[Edit], side: impl Fn(&Edit) -> Option<&usize>) -> (usize, usize) {
+    let mut lines = hunk.iter().filter_map(side);
+
+    match lines.next() {
+        Some(first) => (first + 1, lines.count() + 1),
+        None => (0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unified;
+
+    #[test]
+    fn same_lines() {
+        assert_eq!(unified("1\n2\n", "1\n2", "expected", "actual"), None);
+    }
+
+    #[test]
+    fn changes_with_context() {
+        let expected = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let actual = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n";
+
+        assert_eq!(
+            unified(expected, actual, "expected", "actual").unwrap(),
+            "--- expected\n+++ actual\n\
+             @@ -2,9 +2,10 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n 9\n 10\n+11\n"
+        );
+
+        assert_eq!(
+            unified("", "hello\n", "expected", "actual").unwrap(),
+            "--- expected\n+++ actual\n@@ -0,0 +1,1 @@\n+hello\n"
+        );
+    }
+}
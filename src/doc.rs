@@ -0,0 +1,303 @@
+//! Documentation of the functions of a program, written by `lipsum doc`
+//! from the comments starting with `///` right before their `let`:
+//!
+//! ```rinha
+//! /// The nth Fibonacci number.
+//! ///
+//! /// ```
+//! /// fib(10)
+//! /// ```
+//! let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+//! ```
+//!
+//! Only the functions bound by the outermost `let`s of the program are
+//! documented, the ones inside functions are local to them. The code blocks
+//! of a comment are its examples.
+
+use std::fmt::Write;
+
+use crate::{
+    ast::{Location, Term, Var},
+    cst::{Cst, TriviaKind},
+    lexer::{SyntaxError, Token},
+    parser::parse_lossless,
+    typecheck::{Checker, Type},
+};
+
+/// Documentation of a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Doc {
+    pub name: String,
+    /// The parameters, with their types when annotated.
+    pub parameters: Vec<String>,
+    /// The type of the function, as inferred by the [`Checker`], or `None`
+    /// when the program doesn't typecheck.
+    pub ty: Option<String>,
+    /// The text of the comment, without its examples.
+    pub text: String,
+    /// The code blocks of the comment.
+    pub examples: Vec<String>,
+    pub location: Location,
+}
+
+/// Documents the functions of the program in `source`.
+pub fn docs(source: &str, filename: &str) -> Result<Vec<Doc>, SyntaxError> {
+    let (file, cst) = parse_lossless(source, filename)?;
+    let comments = comments(&cst);
+
+    let mut checker = Checker::new();
+    // Functions of a program that doesn't typecheck are documented without
+    // their types.
+    let typechecks = checker.prelude(&file.expression).is_ok();
+
+    let mut docs = Vec::new();
+    let mut term = &file.expression;
+
+    while let Term::Let(let_) = term {
+        if let Term::Function(function) = &*let_.value {
+            let comment = comments
+                .iter()
+                .find(|(start, _)| *start == let_.location.start)
+                .map(|(_, lines)| lines.as_slice())
+                .unwrap_or_default();
+            let (text, examples) = split_examples(comment);
+
+            let ty = typechecks
+                .then(|| {
+                    checker.check(&Term::Var(Var {
+                        annotation: None,
+                        ..let_.name.clone()
+                    }))
+                })
+                .and_then(Result::ok)
+                .map(|ty: Type| ty.to_string());
+
+            docs.push(Doc {
+                name: let_.name.text.to_string(),
+                parameters: function
+                    .parameters
+                    .iter()
+                    .map(|parameter| match &parameter.annotation {
+                        Some(annotation) => {
+                            format!("{}: {}", parameter.text, Type::from(annotation))
+                        }
+                        None => parameter.text.to_string(),
+                    })
+                    .collect(),
+                ty,
+                text,
+                examples,
+                location: let_.location.clone(),
+            });
+        }
+
+        term = &let_.next;
+    }
+
+    Ok(docs)
+}
+
+/// The lines of the doc comments of the program, by where the `let` they
+/// document starts.
+fn comments(cst: &Cst) -> Vec<(usize, Vec<String>)> {
+    let mut comments = Vec::new();
+
+    for token in cst.root.tokens() {
+        if token.token != Token::Let {
+            continue;
+        }
+
+        let mut lines = Vec::new();
+
+        // The doc comments right before the `let`, up to a blank line or
+        // another comment.
+        for trivia in token.leading.iter().rev() {
+            match trivia.kind {
+                TriviaKind::Whitespace if trivia.text.matches('\n').count() < 2 => continue,
+                TriviaKind::LineComment if trivia.text.starts_with("///") => {
+                    let line = &trivia.text[3..];
+                    lines.push(line.strip_prefix(' ').unwrap_or(line).to_string());
+                }
+                _ => break,
+            }
+        }
+
+        if !lines.is_empty() {
+            lines.reverse();
+            comments.push((token.location.start, lines));
+        }
+    }
+
+    comments
+}
+
+/// Splits the lines of a comment into its text and the code blocks fenced
+/// by ```` ``` ````.
+fn split_examples(lines: &[String]) -> (String, Vec<String>) {
+    let mut text = Vec::new();
+    let mut examples = Vec::new();
+    let mut example: Option<Vec<&str>> = None;
+
+    for line in lines {
+        match (&mut example, line.trim_start().starts_with("```")) {
+            (None, true) => example = Some(Vec::new()),
+            (Some(code), true) => {
+                examples.push(code.join("\n"));
+                example = None;
+            }
+            (Some(code), false) => code.push(line),
+            (None, false) => text.push(line.as_str()),
+        }
+    }
+
+    // A block left open ends with the comment.
+    if let Some(code) = example {
+        examples.push(code.join("\n"));
+    }
+
+    (text.join("\n").trim().to_string(), examples)
+}
+
+/// The documentation as Markdown, under a `title`.
+pub fn markdown(title: &str, docs: &[Doc]) -> String {
+    let mut markdown = format!("# {title}\n");
+
+    for doc in docs {
+        let _ = writeln!(
+            markdown,
+            "\n## `{}({})`",
+            doc.name,
+            doc.parameters.join(", ")
+        );
+
+        if let Some(ty) = &doc.ty {
+            let _ = writeln!(markdown, "\nType: `{ty}`");
+        }
+
+        if !doc.text.is_empty() {
+            let _ = writeln!(markdown, "\n{}", doc.text);
+        }
+
+        if !doc.examples.is_empty() {
+            markdown.push_str("\n### Examples\n");
+
+            for example in &doc.examples {
+                let _ = writeln!(markdown, "\n```rinha\n{example}\n```");
+            }
+        }
+    }
+
+    markdown
+}
+
+/// The documentation as a standalone HTML page, under a `title`.
+pub fn html(title: &str, docs: &[Doc]) -> String {
+    let title = escape(title);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>\n\
+         body {{ font-family: sans-serif; max-width: 48rem; margin: 2rem auto; }}\n\
+         pre, code {{ background: #f4f4f4; }}\n\
+         pre {{ padding: 0.5rem; }}\n\
+         </style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+    );
+
+    for doc in docs {
+        let _ = writeln!(
+            html,
+            "<h2 id=\"{}\"><code>{}({})</code></h2>",
+            escape(&doc.name),
+            escape(&doc.name),
+            escape(&doc.parameters.join(", "))
+        );
+
+        if let Some(ty) = &doc.ty {
+            let _ = writeln!(html, "<p>Type: <code>{}</code></p>", escape(ty));
+        }
+
+        for paragraph in doc.text.split("\n\n").filter(|text| !text.is_empty()) {
+            let _ = writeln!(html, "<p>{}</p>", escape(paragraph));
+        }
+
+        if !doc.examples.is_empty() {
+            html.push_str("<h3>Examples</h3>\n");
+
+            for example in &doc.examples {
+                let _ = writeln!(html, "<pre><code>{}</code></pre>", escape(example));
+            }
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{docs, html, markdown};
+
+    static SOURCE: &str = "\
+// Not documentation.
+
+/// Adds one to `n`.
+///
+/// ```
+/// inc(1)
+/// ```
+let inc = fn (n: int) => { n + 1 };
+
+let pair = (1, 2);
+
+/// Forgotten, there is a blank line after it.
+
+let id = fn (x) => { x };
+let twice = fn (f, x) => {
+  /// Local to `twice`.
+  let y = f(x);
+  f(y)
+};
+twice(inc, 1)
+";
+
+    #[test]
+    fn documents_functions() {
+        let docs = docs(SOURCE, "doc.rinha").unwrap();
+        let names = docs.iter().map(|doc| doc.name.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["inc", "id", "twice"]);
+        assert_eq!(docs[0].parameters, vec!["n: Int"]);
+        assert_eq!(docs[0].ty.as_deref(), Some("(Int) -> Int"));
+        assert_eq!(docs[0].text, "Adds one to `n`.");
+        assert_eq!(docs[0].examples, vec!["inc(1)"]);
+        assert_eq!(docs[1].text, "");
+        assert_eq!(docs[1].ty.as_deref(), Some("('a) -> 'a"));
+        assert_eq!(docs[2].parameters, vec!["f", "x"]);
+
+        let markdown = markdown("doc.rinha", &docs);
+        assert!(markdown.starts_with("# doc.rinha\n\n## `inc(n: Int)`\n\nType: `(Int) -> Int`\n"));
+        assert!(markdown.contains("### Examples\n\n```rinha\ninc(1)\n```\n"));
+
+        let html = html("doc.rinha", &docs);
+        assert!(html.contains("<p>Type: <code>('a) -&gt; 'a</code></p>"));
+        assert!(html.contains("<pre><code>inc(1)</code></pre>"));
+    }
+
+    #[test]
+    fn untyped_programs() {
+        let docs = docs(
+            "/// Fails.\nlet f = fn (x) => { x + true }; f(1)",
+            "doc.rinha",
+        )
+        .unwrap();
+
+        assert_eq!(docs[0].text, "Fails.");
+        assert_eq!(docs[0].ty, None);
+    }
+}
@@ -0,0 +1,215 @@
+use std::{ops::BitOr, sync::Arc};
+
+use crate::ast::{deeper, Function, Term};
+
+/// What evaluating a term may do besides computing its value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize, serde::Serialize))]
+pub struct Effects {
+    /// Writes with `print`.
+    pub print: bool,
+    /// Calls native functions, which may read input, like `read_line`, or
+    /// answer differently each time.
+    pub native: bool,
+    /// Calls functions that are not known before running, like the ones
+    /// given as arguments, which may do anything.
+    pub unknown: bool,
+}
+
+impl Effects {
+    pub const NONE: Effects = Effects {
+        print: false,
+        native: false,
+        unknown: false,
+    };
+
+    pub const PRINT: Effects = Effects {
+        print: true,
+        ..Effects::NONE
+    };
+
+    pub const NATIVE: Effects = Effects {
+        native: true,
+        ..Effects::NONE
+    };
+
+    pub const UNKNOWN: Effects = Effects {
+        unknown: true,
+        ..Effects::NONE
+    };
+
+    /// Whether evaluating the term only computes its value, so it can be
+    /// memoized.
+    pub fn is_pure(&self) -> bool {
+        *self == Effects::NONE
+    }
+}
+
+impl BitOr for Effects {
+    type Output = Effects;
+
+    fn bitor(self, rhs: Effects) -> Effects {
+        Effects {
+            print: self.print || rhs.print,
+            native: self.native || rhs.native,
+            unknown: self.unknown || rhs.unknown,
+        }
+    }
+}
+
+/// Effects of calling the functions bound to each name, `None` when what is
+/// bound is not a function known before running.
+struct Analysis<'a> {
+    scopes: Vec<(Arc<str>, Option<Effects>)>,
+    captured: &'a dyn Fn(&str) -> Option<Effects>,
+}
+
+impl Analysis<'_> {
+    /// Effects of calling what `name` is bound to.
+    fn lookup(&self, name: &str) -> Effects {
+        match self.scopes.iter().rev().find(|(bound, _)| &**bound == name) {
+            Some((_, effects)) => effects.unwrap_or(Effects::UNKNOWN),
+            // Names that are not bound yet are the ones of recursive
+            // functions, whose calls do what the rest of the body does.
+            None => (self.captured)(name).unwrap_or(Effects::NONE),
+        }
+    }
+
+    fn latent(&mut self, function: &Function) -> Effects {
+        for parameter in &function.parameters {
+            self.scopes.push((parameter.text.clone(), None));
+        }
+
+        let effects = self.term(&function.value);
+        self.scopes
+            .truncate(self.scopes.len() - function.parameters.len());
+
+        effects
+    }
+
+    fn term(&mut self, term: &Term) -> Effects {
+        deeper(|| match term {
+            Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_) | Term::Function(_) => {
+                Effects::NONE
+            }
+            Term::Print(print) => Effects::PRINT | self.term(&print.value),
+            Term::Call(call) => {
+                let called = match call.callee.as_ref() {
+                    Term::Var(var) => self.lookup(&var.text),
+                    Term::Function(function) => self.latent(function),
+                    callee => self.term(callee) | Effects::UNKNOWN,
+                };
+
+                call.arguments
+                    .iter()
+                    .fold(called, |effects, argument| effects | self.term(argument))
+            }
+            Term::Let(let_) => {
+                let (effects, bound) = match let_.value.as_ref() {
+                    Term::Function(function) => (Effects::NONE, Some(self.latent(function))),
+                    Term::Var(var) => (
+                        Effects::NONE,
+                        self.scopes
+                            .iter()
+                            .rev()
+                            .find(|(bound, _)| bound == &var.text)
+                            .map_or_else(|| (self.captured)(&var.text), |(_, effects)| *effects),
+                    ),
+                    value => (self.term(value), None),
+                };
+
+                self.scopes.push((let_.name.text.clone(), bound));
+                let next = self.term(&let_.next);
+                self.scopes.pop();
+
+                effects | next
+            }
+            Term::If(if_) => {
+                self.term(&if_.condition) | self.term(&if_.then) | self.term(&if_.otherwise)
+            }
+            Term::Binary(binary) => self.term(&binary.lhs) | self.term(&binary.rhs),
+            Term::Tuple(tuple) => self.term(&tuple.first) | self.term(&tuple.second),
+            Term::First(first) => self.term(&first.value),
+            Term::Second(second) => self.term(&second.value),
+        })
+    }
+}
+
+/// Effects of evaluating `term`. Calls to names not bound in the term are
+/// taken as calls to recursive functions, that add no effects.
+pub fn effects(term: &Term) -> Effects {
+    Analysis {
+        scopes: Vec::new(),
+        captured: &|_| None,
+    }
+    .term(term)
+}
+
+/// Effects of calling `function`, where `captured` gives the effects of
+/// calling each of the functions it captured, or `None` when the name is not
+/// captured, like the name of the function itself.
+pub fn latent(function: &Function, captured: &dyn Fn(&str) -> Option<Effects>) -> Effects {
+    Analysis {
+        scopes: Vec::new(),
+        captured,
+    }
+    .latent(function)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{build::*, Term},
+        parser::parse,
+    };
+
+    use super::{effects, latent, Effects};
+
+    /// Effects of calling the function in `source`, which captured `log`
+    /// and `read_line`.
+    fn calling(source: &str) -> Effects {
+        let file = parse(source, "tests").unwrap();
+        let Term::Function(function) = &file.expression else {
+            panic!("expected a function");
+        };
+
+        latent(function, &|name| match name {
+            "log" => Some(Effects::PRINT),
+            "read_line" => Some(Effects::NATIVE),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn terms() {
+        assert!(effects(&int(1)).is_pure());
+        assert_eq!(effects(&tuple(int(1), print(int(2)))), Effects::PRINT);
+        assert!(effects(&function(&["n"], print(var("n")))).is_pure());
+    }
+
+    #[test]
+    fn nested_prints() {
+        assert_eq!(
+            calling("fn (n) => if (n == 0) { print(n) } else { 1 }"),
+            Effects::PRINT
+        );
+        assert!(calling("fn (n) => fn () => print(n)").is_pure());
+    }
+
+    #[test]
+    fn through_calls() {
+        assert_eq!(calling("fn (n) => log(n)"), Effects::PRINT);
+        assert_eq!(calling("fn () => read_line()"), Effects::NATIVE);
+        assert_eq!(
+            calling("fn (n) => { let p = fn (x) => print(x); let q = p; q(n) }"),
+            Effects::PRINT
+        );
+        assert!(calling("fn (n) => if (n < 2) { n } else { fib(n - 1) }").is_pure());
+    }
+
+    #[test]
+    fn unknown_calls() {
+        assert_eq!(calling("fn (f) => f(1)"), Effects::UNKNOWN);
+        assert_eq!(calling("fn (p) => first(p)(1)"), Effects::UNKNOWN);
+    }
+}
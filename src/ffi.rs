@@ -0,0 +1,259 @@
+//! C bindings, enabled by the `ffi` feature. The library is built for C
+//! hosts with
+//!
+//! ```text
+//! cargo rustc --lib --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! and `include/rinha.h` declares the functions below. Strings returned by
+//! them are owned by the caller, who frees them with [`rinha_string_free`].
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr,
+};
+
+use crate::{
+    interpreter::{Context, Interpreter, Sink},
+    messages::{ErrorCode, Lang},
+    parser::parse,
+};
+
+struct Error {
+    code: ErrorCode,
+    message: String,
+    start: usize,
+    end: usize,
+}
+
+/// Outcome of [`rinha_eval_source`], opaque to C.
+pub struct RinhaResult {
+    result: Result<String, Error>,
+    output: String,
+}
+
+/// Copies `string` into a new C string, or returns null when it holds a nul
+/// byte.
+fn to_c(string: &str) -> *mut c_char {
+    CString::new(string).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Evaluates rinha source code, collecting what it prints instead of writing
+/// it to stdout. Returns null when `source` or `filename` are null or not
+/// valid UTF-8.
+///
+/// # Safety
+///
+/// `source` and `filename` must be null or point to nul terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn rinha_eval_source(
+    source: *const c_char,
+    filename: *const c_char,
+) -> *mut RinhaResult {
+    if source.is_null() || filename.is_null() {
+        return ptr::null_mut();
+    }
+
+    let (Ok(source), Ok(filename)) = (
+        CStr::from_ptr(source).to_str(),
+        CStr::from_ptr(filename).to_str(),
+    ) else {
+        return ptr::null_mut();
+    };
+
+    let file = match parse(source, filename) {
+        Ok(file) => file,
+        Err(error) => {
+            return Box::into_raw(Box::new(RinhaResult {
+                result: Err(Error {
+                    code: error.code,
+                    message: error.message(Lang::En),
+                    start: error.location.start,
+                    end: error.location.end,
+                }),
+                output: String::new(),
+            }))
+        }
+    };
+
+    let mut interpreter = Interpreter::new(Sink(Vec::new()));
+    let result = interpreter
+        .eval(file.expression, &mut Context::new())
+        .map(|value| value.to_string())
+        .map_err(|error| Error {
+            code: error.code,
+            message: error.message(Lang::En),
+            start: error.location.start,
+            end: error.location.end,
+        });
+
+    Box::into_raw(Box::new(RinhaResult {
+        result,
+        output: String::from_utf8_lossy(&interpreter.io.0).into_owned(),
+    }))
+}
+
+/// Whether the program ran without errors.
+///
+/// # Safety
+///
+/// `result` must be null or returned by [`rinha_eval_source`] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rinha_result_is_ok(result: *const RinhaResult) -> bool {
+    result.as_ref().is_some_and(|result| result.result.is_ok())
+}
+
+/// The final value of the program as text, or null when it failed.
+///
+/// # Safety
+///
+/// `result` must be null or returned by [`rinha_eval_source`] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rinha_value_to_string(result: *const RinhaResult) -> *mut c_char {
+    match result.as_ref().map(|result| &result.result) {
+        Some(Ok(value)) => to_c(value),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Everything the program printed, one value per line.
+///
+/// # Safety
+///
+/// `result` must be null or returned by [`rinha_eval_source`] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rinha_result_output(result: *const RinhaResult) -> *mut c_char {
+    match result.as_ref() {
+        Some(result) => to_c(&result.output),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Code of the error that stopped the program, like `E0007`, or null when it
+/// did not fail.
+///
+/// # Safety
+///
+/// `result` must be null or returned by [`rinha_eval_source`] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rinha_error_code(result: *const RinhaResult) -> *mut c_char {
+    match result.as_ref().map(|result| &result.result) {
+        Some(Err(error)) => to_c(error.code.as_str()),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Message of the error that stopped the program, in English, or null when
+/// it did not fail.
+///
+/// # Safety
+///
+/// `result` must be null or returned by [`rinha_eval_source`] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rinha_error_message(result: *const RinhaResult) -> *mut c_char {
+    match result.as_ref().map(|result| &result.result) {
+        Some(Err(error)) => to_c(&error.message),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Byte range of the source where the error happened, written to `start`
+/// and `end`. Returns false, leaving them untouched, when the program did not
+/// fail.
+///
+/// # Safety
+///
+/// `result` must be null or returned by [`rinha_eval_source`] and not freed,
+/// and `start` and `end` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rinha_error_span(
+    result: *const RinhaResult,
+    start: *mut usize,
+    end: *mut usize,
+) -> bool {
+    match result.as_ref().map(|result| &result.result) {
+        Some(Err(error)) => {
+            *start = error.start;
+            *end = error.end;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Frees a result returned by [`rinha_eval_source`].
+///
+/// # Safety
+///
+/// `result` must be null or returned by [`rinha_eval_source`] and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rinha_result_free(result: *mut RinhaResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result));
+    }
+}
+
+/// Frees a string returned by any of these functions.
+///
+/// # Safety
+///
+/// `string` must be null or returned by these functions and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn rinha_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+
+    use super::*;
+
+    unsafe fn take(string: *mut c_char) -> String {
+        let text = CStr::from_ptr(string).to_str().unwrap().to_string();
+        rinha_string_free(string);
+        text
+    }
+
+    #[test]
+    fn eval_source() {
+        let source = CString::new("print(1 + 2)").unwrap();
+        let filename = CString::new("tests").unwrap();
+
+        unsafe {
+            let result = rinha_eval_source(source.as_ptr(), filename.as_ptr());
+
+            assert!(rinha_result_is_ok(result));
+            assert_eq!(take(rinha_value_to_string(result)), "3");
+            assert_eq!(take(rinha_result_output(result)), "3\n");
+            assert!(rinha_error_code(result).is_null());
+
+            rinha_result_free(result);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let source = CString::new("1 / 0").unwrap();
+        let filename = CString::new("tests").unwrap();
+
+        unsafe {
+            let result = rinha_eval_source(source.as_ptr(), filename.as_ptr());
+            let (mut start, mut end) = (0, 0);
+
+            assert!(!rinha_result_is_ok(result));
+            assert!(rinha_value_to_string(result).is_null());
+            assert_eq!(take(rinha_error_code(result)), "E0007");
+            assert!(rinha_error_span(result, &mut start, &mut end));
+            assert_eq!((start, end), (0, 1));
+
+            rinha_result_free(result);
+        }
+
+        unsafe {
+            assert!(rinha_eval_source(std::ptr::null(), filename.as_ptr()).is_null());
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Heap profiling, which counts the allocations made during each phase of
+//! the evaluation, to tell which ones allocate the most.
+//!
+//! The counts are only taken by programs that install [`Counting`] as their
+//! global allocator, like the lipsum binary built with the `heap-profile`
+//! feature:
+//!
+//! ```no_run
+//! #[global_allocator]
+//! static ALLOCATOR: lipsum::heap::Counting = lipsum::heap::Counting;
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    fmt::Display,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+/// What the interpreter is doing when it allocates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Anything not covered by the other phases, like parsing.
+    Other,
+    /// Copying the bindings a closure captured and adding its parameters,
+    /// and the bindings of `let`s.
+    Context,
+    /// The vectors of the arguments of calls.
+    Arguments,
+    /// Computing the keys of the memoization cache, and storing results in
+    /// it.
+    Cache,
+    /// The values computed by operations, like concatenated strings.
+    Values,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 5] = [
+        Phase::Other,
+        Phase::Context,
+        Phase::Arguments,
+        Phase::Cache,
+        Phase::Values,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Other => "other",
+            Phase::Context => "context",
+            Phase::Arguments => "arguments",
+            Phase::Cache => "cache",
+            Phase::Values => "values",
+        }
+    }
+}
+
+thread_local! {
+    static PHASE: Cell<Phase> = const { Cell::new(Phase::Other) };
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU64 = AtomicU64::new(0);
+
+static ALLOCATIONS: [AtomicU64; 5] = [ZERO; 5];
+static BYTES: [AtomicU64; 5] = [ZERO; 5];
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Global allocator that counts the allocations of each [`Phase`] before
+/// handing them to the system allocator.
+pub struct Counting;
+
+fn count(size: usize) {
+    // The phase can't be read while the thread is being torn down.
+    let phase = PHASE.try_with(Cell::get).unwrap_or(Phase::Other) as usize;
+
+    ALLOCATIONS[phase].fetch_add(1, Ordering::Relaxed);
+    BYTES[phase].fetch_add(size as u64, Ordering::Relaxed);
+
+    let live = LIVE.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        count(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Restores the phase that was current before [`enter`] when dropped.
+pub struct PhaseGuard(Phase);
+
+impl Drop for PhaseGuard {
+    fn drop(&mut self) {
+        PHASE.with(|phase| phase.set(self.0));
+    }
+}
+
+/// Attributes the allocations of the current thread to `phase`, until the
+/// returned guard is dropped.
+pub fn enter(phase: Phase) -> PhaseGuard {
+    PhaseGuard(PHASE.with(|current| current.replace(phase)))
+}
+
+/// Allocations made during a phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseCount {
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+/// The allocations counted since the program started, see [`report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapReport {
+    pub phases: Vec<(Phase, PhaseCount)>,
+    /// Most bytes allocated at once.
+    pub peak: usize,
+}
+
+/// The allocations counted so far by [`Counting`], which are all zero when
+/// it is not the global allocator.
+pub fn report() -> HeapReport {
+    HeapReport {
+        phases: Phase::ALL
+            .into_iter()
+            .map(|phase| {
+                let count = PhaseCount {
+                    allocations: ALLOCATIONS[phase as usize].load(Ordering::Relaxed),
+                    bytes: BYTES[phase as usize].load(Ordering::Relaxed),
+                };
+
+                (phase, count)
+            })
+            .collect(),
+        peak: PEAK.load(Ordering::Relaxed),
+    }
+}
+
+impl Display for HeapReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:10}  {:>12}  {:>14}", "phase", "allocations", "bytes")?;
+
+        for (phase, count) in &self.phases {
+            writeln!(
+                f,
+                "{:10}  {:>12}  {:>14}",
+                phase.name(),
+                count.allocations,
+                count.bytes
+            )?;
+        }
+
+        write!(f, "peak: {} bytes", self.peak)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{enter, Phase, PHASE};
+
+    #[test]
+    fn phases_nest() {
+        {
+            let _context = enter(Phase::Context);
+            assert_eq!(PHASE.with(|phase| phase.get()), Phase::Context);
+
+            {
+                let _cache = enter(Phase::Cache);
+                assert_eq!(PHASE.with(|phase| phase.get()), Phase::Cache);
+            }
+
+            assert_eq!(PHASE.with(|phase| phase.get()), Phase::Context);
+        }
+
+        assert_eq!(PHASE.with(|phase| phase.get()), Phase::Other);
+    }
+}
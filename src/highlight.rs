@@ -0,0 +1,223 @@
+//! Syntax highlighting of rinha source files, written by `lipsum highlight`
+//! with ANSI colors for terminals or as a standalone HTML page.
+//!
+//! The source is split by the lexer itself, so what is highlighted is always
+//! what the parser reads: a source that doesn't lex isn't highlighted.
+
+use std::{fmt::Write, ops::Range};
+
+use crate::{
+    cst::{Trivia, TriviaKind},
+    lexer::{lex_lossless, SyntaxError, Token},
+};
+
+/// What a piece of the source is, which gives its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Class {
+    /// `let`, `fn`, `if`, `else` and `become`.
+    Keyword,
+    /// `print`, `first` and `second`.
+    Builtin,
+    Int,
+    Str,
+    /// `true` and `false`.
+    Bool,
+    Name,
+    /// A name being called, like `fib` in `fib(n - 1)`.
+    Call,
+    Operator,
+    Punctuation,
+    Comment,
+    Whitespace,
+}
+
+impl Class {
+    fn of(token: &Token) -> Self {
+        match token {
+            Token::Let | Token::Fn | Token::If | Token::Else | Token::Become => Class::Keyword,
+            Token::Print | Token::First | Token::Second => Class::Builtin,
+            Token::Int(_) => Class::Int,
+            Token::Str(_) => Class::Str,
+            Token::True | Token::False => Class::Bool,
+            Token::Ident(_) => Class::Name,
+            Token::LParen
+            | Token::RParen
+            | Token::LBrace
+            | Token::RBrace
+            | Token::Comma
+            | Token::Semicolon
+            | Token::Colon => Class::Punctuation,
+            _ => Class::Operator,
+        }
+    }
+
+    /// SGR parameters of the class, `None` for the uncolored ones.
+    fn ansi(self) -> Option<&'static str> {
+        match self {
+            Class::Keyword => Some("1;35"),
+            Class::Builtin => Some("36"),
+            Class::Int | Class::Bool => Some("33"),
+            Class::Str => Some("32"),
+            Class::Call => Some("34"),
+            Class::Comment => Some("2;3"),
+            Class::Operator => Some("31"),
+            Class::Name | Class::Punctuation | Class::Whitespace => None,
+        }
+    }
+
+    /// Name of the CSS class of the HTML spans, `None` for the unstyled
+    /// ones.
+    fn css(self) -> Option<&'static str> {
+        match self {
+            Class::Keyword => Some("keyword"),
+            Class::Builtin => Some("builtin"),
+            Class::Int | Class::Bool => Some("literal"),
+            Class::Str => Some("string"),
+            Class::Call => Some("call"),
+            Class::Comment => Some("comment"),
+            Class::Operator => Some("operator"),
+            Class::Name | Class::Punctuation | Class::Whitespace => None,
+        }
+    }
+}
+
+static STYLE: &str = "\
+body { background: #fdfdfd; color: #222; }
+pre { font-size: 14px; line-height: 1.4; }
+.keyword { color: #a626a4; font-weight: bold; }
+.builtin { color: #0184bc; }
+.literal { color: #986801; }
+.string { color: #50a14f; }
+.call { color: #4078f2; }
+.comment { color: #a0a1a7; font-style: italic; }
+.operator { color: #e45649; }";
+
+/// Splits `source` into the byte ranges of its pieces, covering all of it.
+pub fn classify(source: &str, filename: &str) -> Result<Vec<(Class, Range<usize>)>, SyntaxError> {
+    let lossless = lex_lossless(source, filename)?;
+    let mut pieces = Vec::new();
+
+    let trivia = |pieces: &mut Vec<_>, trivia: &[Trivia]| {
+        for trivia in trivia {
+            let class = match trivia.kind {
+                TriviaKind::Whitespace => Class::Whitespace,
+                TriviaKind::LineComment | TriviaKind::BlockComment => Class::Comment,
+            };
+
+            pieces.push((class, trivia.location.span()));
+        }
+    };
+
+    for (index, spanned) in lossless.tokens.iter().enumerate() {
+        trivia(&mut pieces, &lossless.leading[index]);
+
+        let called = matches!(
+            lossless.tokens.get(index + 1),
+            Some(next) if next.token == Token::LParen
+        );
+        let class = match Class::of(&spanned.token) {
+            Class::Name if called => Class::Call,
+            class => class,
+        };
+
+        pieces.push((class, spanned.location.span()));
+    }
+
+    trivia(&mut pieces, &lossless.trailing);
+
+    Ok(pieces)
+}
+
+/// The source with ANSI colors, to be written to a terminal.
+pub fn ansi(source: &str, filename: &str) -> Result<String, SyntaxError> {
+    let mut colored = String::new();
+
+    for (class, range) in classify(source, filename)? {
+        match class.ansi() {
+            Some(style) => {
+                let _ = write!(colored, "\x1b[{style}m{}\x1b[0m", &source[range]);
+            }
+            None => colored.push_str(&source[range]),
+        }
+    }
+
+    Ok(colored)
+}
+
+/// The source as a standalone HTML page, titled `filename`.
+pub fn html(source: &str, filename: &str) -> Result<String, SyntaxError> {
+    let title = escape(filename);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>\n{STYLE}\n</style>\n</head>\n<body>\n<pre><code>"
+    );
+
+    for (class, range) in classify(source, filename)? {
+        let text = escape(&source[range]);
+
+        match class.css() {
+            Some(css) => {
+                let _ = write!(html, "<span class=\"{css}\">{text}</span>");
+            }
+            None => html.push_str(&text),
+        }
+    }
+
+    html.push_str("</code></pre>\n</body>\n</html>\n");
+    Ok(html)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ansi, classify, html, Class};
+
+    #[test]
+    fn covers_the_source() {
+        let source = "// fib\nlet fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) } };\nprint(\"a\" == \"b\")\n";
+        let pieces = classify(source, "fib.rinha").unwrap();
+
+        let text = pieces
+            .iter()
+            .map(|(_, range)| &source[range.clone()])
+            .collect::<String>();
+        assert_eq!(text, source);
+
+        let class = |text: &str| {
+            pieces
+                .iter()
+                .filter(|(_, range)| &source[range.clone()] == text)
+                .map(|(class, _)| *class)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(class("// fib"), vec![Class::Comment]);
+        assert_eq!(class("let"), vec![Class::Keyword]);
+        assert_eq!(class("fib"), vec![Class::Name, Class::Call]);
+        assert_eq!(class("print"), vec![Class::Builtin]);
+        assert_eq!(class("\"a\""), vec![Class::Str]);
+        assert_eq!(class("<"), vec![Class::Operator]);
+        assert_eq!(class("2"), vec![Class::Int]);
+    }
+
+    #[test]
+    fn ansi_and_html() {
+        assert_eq!(
+            ansi("let x = 1; x", "x.rinha").unwrap(),
+            "\x1b[1;35mlet\x1b[0m x \x1b[31m=\x1b[0m \x1b[33m1\x1b[0m; x"
+        );
+
+        let html = html("print(\"<b>\")", "x.rinha").unwrap();
+        assert!(html.contains(
+            "<span class=\"builtin\">print</span>(<span class=\"string\">&quot;&lt;b&gt;&quot;</span>)"
+        ));
+
+        assert!(ansi("\"unterminated", "x.rinha").is_err());
+    }
+}
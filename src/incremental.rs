@@ -0,0 +1,382 @@
+use std::ops::Range;
+
+use crate::{
+    ast::{
+        visit::{fold_children, TermFolder},
+        Element, File, Let, Location, Term, Var,
+    },
+    intern::Interner,
+    lexer::{lex, SyntaxError, Token},
+    messages::ErrorCode,
+    parser::{check_tail_calls, Parser},
+};
+
+/// A statement at the top of a program: one of the `let` bindings the
+/// program starts with, or the term after them.
+#[derive(Debug, Clone)]
+enum Item {
+    Binding {
+        /// Where the `let` keyword starts.
+        start: usize,
+        name: Var,
+        value: Term,
+    },
+    Last(Term),
+}
+
+/// An item along with the bytes of the source it was parsed from, which go
+/// from the end of the previous item, so that they cover the whole source.
+#[derive(Debug, Clone)]
+struct Segment {
+    span: Range<usize>,
+    item: Item,
+}
+
+/// Moves every location of a term by the same number of bytes.
+struct Shift(isize);
+
+impl Shift {
+    fn location(&self, location: &mut Location) {
+        location.start = location.start.wrapping_add_signed(self.0);
+        location.end = location.end.wrapping_add_signed(self.0);
+    }
+}
+
+impl TermFolder for Shift {
+    fn fold_term(&mut self, mut term: Term) -> Term {
+        self.location(term.location_mut());
+
+        match &mut term {
+            Term::Let(let_) => self.location(&mut let_.name.location),
+            Term::Function(function) => {
+                for parameter in &mut function.parameters {
+                    self.location(&mut parameter.location);
+                }
+            }
+            _ => (),
+        }
+
+        fold_children(self, term)
+    }
+}
+
+impl Segment {
+    fn shift(self, delta: isize) -> Segment {
+        let mut shift = Shift(delta);
+
+        let item = match self.item {
+            Item::Binding {
+                start,
+                mut name,
+                value,
+            } => {
+                shift.location(&mut name.location);
+
+                Item::Binding {
+                    start: start.wrapping_add_signed(delta),
+                    name,
+                    value: shift.fold_term(value),
+                }
+            }
+            Item::Last(term) => Item::Last(shift.fold_term(term)),
+        };
+
+        Segment {
+            span: self.span.start.wrapping_add_signed(delta)
+                ..self.span.end.wrapping_add_signed(delta),
+            item,
+        }
+    }
+}
+
+/// The source code of a program being edited, like the ones of an editor,
+/// along with its syntax tree.
+///
+/// Edits only read again the top level bindings they touch: the source is
+/// lexed and parsed from the first binding changed up to the first binding
+/// after the edit that still starts a binding, and the bindings after it are
+/// kept, moved by the number of bytes added or removed.
+///
+/// ```
+/// use lipsum::incremental::Document;
+///
+/// let mut document = Document::new("let x = 1;\nlet y = 2;\nprint(x + y)", "example.rinha");
+/// assert_eq!(document.edit(19..20, "40").unwrap(), 1);
+///
+/// assert_eq!(document.source(), "let x = 1;\nlet y = 40;\nprint(x + y)");
+/// ```
+#[derive(Debug)]
+pub struct Document {
+    filename: String,
+    source: String,
+    interner: Interner,
+    segments: Vec<Segment>,
+    /// Why the source can't be parsed, in which case `segments` are the ones
+    /// of an older source.
+    error: Option<SyntaxError>,
+}
+
+impl Document {
+    /// Parses `source`, which may have syntax errors, given by
+    /// [`Document::file`].
+    pub fn new(source: &str, filename: &str) -> Self {
+        let mut document = Document {
+            filename: filename.to_string(),
+            source: source.to_string(),
+            interner: Interner::new(),
+            segments: Vec::new(),
+            error: None,
+        };
+
+        if let Err(error) = document.reparse(0, Vec::new()) {
+            document.error = Some(error);
+        }
+
+        document
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Replaces the bytes in `range` of the source with `text`, telling how
+    /// many top level items were parsed again. Edits of sources that can't
+    /// be parsed parse the whole source.
+    ///
+    /// # Panics
+    ///
+    /// When `range` is out of the source or doesn't start and end at
+    /// character boundaries.
+    pub fn edit(&mut self, range: Range<usize>, text: &str) -> Result<usize, SyntaxError> {
+        self.source.replace_range(range.clone(), text);
+
+        let result = if self.error.is_some() {
+            self.segments.clear();
+            self.reparse(0, Vec::new())
+        } else {
+            let delta = text.len() as isize - range.len() as isize;
+            let mut segments = std::mem::take(&mut self.segments);
+
+            // An edit right at the end of a segment may continue it.
+            let first = segments
+                .iter()
+                .position(|segment| segment.span.end >= range.start)
+                .unwrap_or(segments.len().saturating_sub(1));
+
+            let tail = segments
+                .split_off(first)
+                .into_iter()
+                .skip_while(|segment| segment.span.start < range.end)
+                .map(|segment| segment.shift(delta))
+                .collect();
+
+            let start = segments.last().map_or(0, |segment| segment.span.end);
+            self.segments = segments;
+
+            self.reparse(start, tail)
+        };
+
+        self.error = result.as_ref().err().cloned();
+        result
+    }
+
+    /// Parses the source from `start`, stopping at the start of the first
+    /// segment of `tail`, which are kept, that is right after an item.
+    fn reparse(&mut self, start: usize, mut tail: Vec<Segment>) -> Result<usize, SyntaxError> {
+        let mut skipped = 1;
+
+        loop {
+            let end = tail
+                .first()
+                .map_or(self.source.len(), |segment| segment.span.start);
+
+            match self.parse_range(start..end) {
+                Ok(parsed) => {
+                    let count = parsed.len();
+                    self.segments.extend(parsed);
+                    self.segments.append(&mut tail);
+
+                    return Ok(count);
+                }
+                // The error may come from a binding going on past `end`, so
+                // the next segments are read again too, twice as many each
+                // time so that errors that are really there don't make every
+                // segment be read again one by one.
+                Err(_) if !tail.is_empty() => {
+                    tail.drain(..skipped.min(tail.len()));
+                    skipped *= 2;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Parses the items in `range`, the last item of the source being the
+    /// only one that is not a binding.
+    fn parse_range(&self, range: Range<usize>) -> Result<Vec<Segment>, SyntaxError> {
+        let last = range.end == self.source.len();
+        let eof = Location::new(range.end, range.end, &self.filename);
+
+        let mut tokens =
+            lex(&self.source[range.clone()], &self.filename).map_err(|mut error| {
+                Shift(range.start as isize).location(&mut error.location);
+                error
+            })?;
+
+        let shift = Shift(range.start as isize);
+
+        for spanned in &mut tokens {
+            shift.location(&mut spanned.location);
+        }
+
+        let mut parser = Parser::new(tokens, eof, &self.interner);
+        let mut segments = Vec::new();
+        let mut at = range.start;
+
+        loop {
+            let item = match parser.peek() {
+                Some(Token::Let) => {
+                    let start = parser.next()?.location.start;
+                    let name = parser.binder()?;
+                    parser.expect(Token::Assign)?;
+                    let value = parser.term()?;
+                    let end = parser.expect(Token::Semicolon)?.end;
+
+                    segments.push(Segment {
+                        span: at..end,
+                        item: Item::Binding { start, name, value },
+                    });
+
+                    at = end;
+                    continue;
+                }
+                None if !last => return Ok(segments),
+                // Only the last item of the source can be something else than
+                // a binding.
+                _ if !last => return Err(parser.expect(Token::Let).unwrap_err()),
+                _ => parser.last()?,
+            };
+
+            if let Ok(spanned) = parser.next() {
+                return Err(SyntaxError::new(
+                    ErrorCode::TrailingInput,
+                    vec![spanned.token.to_string()],
+                    spanned.location,
+                ));
+            }
+
+            segments.push(Segment {
+                span: at..range.end,
+                item: Item::Last(item),
+            });
+
+            return Ok(segments);
+        }
+    }
+
+    /// The syntax tree of the source, the same one [`parse`] reads, or why it
+    /// can't be parsed.
+    ///
+    /// [`parse`]: crate::parser::parse
+    pub fn file(&self) -> Result<File, SyntaxError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+
+        let mut segments = self.segments.iter().rev();
+
+        let Some(Segment {
+            item: Item::Last(last),
+            ..
+        }) = segments.next()
+        else {
+            unreachable!("parsed sources end with a term");
+        };
+
+        let expression = segments.fold(last.clone(), |next, segment| {
+            let Item::Binding { start, name, value } = &segment.item else {
+                unreachable!("only the last item is not a binding");
+            };
+
+            Term::Let(Let {
+                location: Location::new(*start, next.location().end, &self.filename),
+                name: name.clone(),
+                value: Box::new(value.clone()),
+                next: Box::new(next),
+            })
+        });
+
+        check_tail_calls(&expression)?;
+
+        Ok(File {
+            name: self.filename.clone(),
+            expression,
+            location: Location::new(0, self.source.len(), &self.filename),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{messages::ErrorCode, parser::parse};
+
+    use super::Document;
+
+    /// Checks that the tree of `document` is the one read from scratch.
+    fn check(document: &Document) {
+        let expected = parse(document.source(), "tests").unwrap();
+        let file = document.file().unwrap();
+
+        assert_eq!(file.expression, expected.expression);
+        assert_eq!(file.location, expected.location);
+    }
+
+    fn edit(document: &mut Document, find: &str, text: &str) -> usize {
+        let start = document.source().find(find).unwrap();
+        let count = document.edit(start..start + find.len(), text).unwrap();
+        check(document);
+
+        count
+    }
+
+    const SOURCE: &str = "let a = 1;\nlet f = fn (n) => { n + a };\nlet b = f(2);\nprint(b)";
+
+    #[test]
+    fn edits_reparse_one_binding() {
+        let mut document = Document::new(SOURCE, "tests");
+        check(&document);
+
+        assert_eq!(edit(&mut document, "n + a", "n * a + 100"), 1);
+        assert_eq!(edit(&mut document, "1;", "10;"), 1);
+        assert_eq!(edit(&mut document, "print(b)", "print((a, b))"), 1);
+        assert_eq!(edit(&mut document, "let b", "let c = 3;\nlet b"), 2);
+        assert_eq!(edit(&mut document, "let c = 3;\n", ""), 1);
+    }
+
+    #[test]
+    fn edits_changing_the_bindings() {
+        let mut document = Document::new(SOURCE, "tests");
+
+        // The semicolons of the new `let` end it, not `f`.
+        assert_eq!(edit(&mut document, "{ n + a }", "let g = n; g"), 1);
+        assert_eq!(edit(&mut document, "a = 1;\nlet f", "f"), 1);
+        // The line break after `b` goes with the last term.
+        assert_eq!(edit(&mut document, "let b = f(2);\n", ""), 1);
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let mut document = Document::new(SOURCE, "tests");
+
+        let start = document.source().find("f(2)").unwrap();
+        let error = document.edit(start..start + 4, "f(2").unwrap_err();
+        assert_eq!(error.code, ErrorCode::UnexpectedToken);
+        assert!(document.file().is_err());
+
+        document.edit(start + 3..start + 3, ")").unwrap();
+        check(&document);
+
+        let error = Document::new("let x = 1; x )", "tests").file().unwrap_err();
+        assert_eq!(error.code, ErrorCode::TrailingInput);
+    }
+}
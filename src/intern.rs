@@ -0,0 +1,211 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use crate::ast::{
+    visit::{fold_children, TermFolder},
+    Term,
+};
+
+/// Set of names and string literals shared by every term that goes through
+/// it, so that each one is stored once however many programs, and
+/// interpreters, use it. It can be cloned and sent to other threads, the
+/// clones share the same names.
+///
+/// Names of bindings are [`Arc<str>`]s, so binding them, and copying the
+/// bindings when calling closures, only bumps a counter. So are string
+/// literals, which become values without being copied.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    names: Names,
+    /// Names of the interner this one is a layer over, see [`Interner::layer`].
+    base: Option<Names>,
+}
+
+type Names = Arc<Mutex<HashSet<Arc<str>>>>;
+
+/// Locks `names`, which can't be left inconsistent by a panic.
+fn lock(names: &Names) -> std::sync::MutexGuard<'_, HashSet<Arc<str>>> {
+    names.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An interner sharing the names of this one without adding to them, its
+    /// new names going away with it. Programs that come and go, like the
+    /// requests of a server, are parsed with a layer so that they don't grow
+    /// the names kept for the others forever.
+    pub fn layer(&self) -> Self {
+        Self {
+            names: Names::default(),
+            base: Some(self.names.clone()),
+        }
+    }
+
+    /// The shared copy of `name`, added to the interner when it is new.
+    pub fn intern(&self, name: &str) -> Arc<str> {
+        if let Some(name) = self
+            .base
+            .as_ref()
+            .and_then(|base| lock(base).get(name).cloned())
+        {
+            return name;
+        }
+
+        let mut names = lock(&self.names);
+
+        match names.get(name) {
+            Some(name) => name.clone(),
+            None => {
+                let name: Arc<str> = name.into();
+                names.insert(name.clone());
+                name
+            }
+        }
+    }
+
+    /// Replaces the names and the string literals used by `term` by their
+    /// shared copies, which is useful for terms that were not parsed with the
+    /// interner, like the ones read from JSON.
+    pub fn intern_term(&self, term: Term) -> Term {
+        InternNames(self).fold_term(term)
+    }
+
+    /// How many distinct names the interner holds.
+    pub fn len(&self) -> usize {
+        lock(&self.names).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+struct InternNames<'a>(&'a Interner);
+
+impl TermFolder for InternNames<'_> {
+    fn fold_term(&mut self, term: Term) -> Term {
+        let mut term = fold_children(self, term);
+
+        match &mut term {
+            Term::Var(var) => var.text = self.0.intern(&var.text),
+            Term::Let(let_) => let_.name.text = self.0.intern(&let_.name.text),
+            Term::Str(str) => str.value = self.0.intern(&str.value),
+            Term::Function(function) => {
+                for parameter in &mut function.parameters {
+                    parameter.text = self.0.intern(&parameter.text);
+                }
+            }
+            _ => (),
+        }
+
+        term
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use crate::{
+        ast::{build::*, Term},
+        interpreter::{Collect, Context, Interpreter, Value},
+        parser::parse_with,
+    };
+
+    use super::Interner;
+
+    #[test]
+    fn shared_between_threads() {
+        let interner = Interner::new();
+
+        let names = (0..4)
+            .map(|_| {
+                let interner = interner.clone();
+                thread::spawn(move || interner.intern("fib"))
+            })
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(names.windows(2).all(|pair| Arc::ptr_eq(&pair[0], &pair[1])));
+        assert_eq!(interner.len(), 1);
+    }
+
+    #[test]
+    fn parse() {
+        let interner = Interner::new();
+        let file = parse_with("let x = 1; x", "tests", &interner).unwrap();
+
+        let Term::Let(let_) = &file.expression else {
+            panic!("expected a let");
+        };
+        let Term::Var(var) = &*let_.next else {
+            panic!("expected a variable");
+        };
+
+        assert!(Arc::ptr_eq(&let_.name.text, &var.text));
+        assert!(Arc::ptr_eq(&var.text, &interner.intern("x")));
+    }
+
+    #[test]
+    fn intern_term() {
+        let interner = Interner::new();
+        let name = interner.intern("f");
+
+        let term = interner.intern_term(function(&["f"], var("y")));
+        let Term::Function(function) = &term else {
+            panic!("expected a function");
+        };
+
+        assert!(Arc::ptr_eq(&function.parameters[0].text, &name));
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn layers() {
+        let interner = Interner::new();
+        let shared = interner.intern("f");
+
+        let layer = interner.layer();
+        assert!(Arc::ptr_eq(&layer.intern("f"), &shared));
+        assert!(Arc::ptr_eq(
+            &layer.intern("request"),
+            &layer.intern("request")
+        ));
+
+        assert_eq!(interner.len(), 1);
+        assert_eq!(layer.len(), 1);
+    }
+
+    #[test]
+    fn string_literals() {
+        let interner = Interner::new();
+        let file = parse_with(r#"("abc", "abc")"#, "tests", &interner).unwrap();
+
+        let expression = file.expression.clone();
+        let Term::Tuple(tuple) = &expression else {
+            panic!("expected a tuple");
+        };
+        let (Term::Str(first), Term::Str(second)) = (&*tuple.first, &*tuple.second) else {
+            panic!("expected strings");
+        };
+        assert!(Arc::ptr_eq(&first.value, &second.value));
+
+        // Evaluating the literals doesn't copy them.
+        let mut interpreter = Interpreter::new(Collect::default());
+        let value = interpreter
+            .eval(file.expression, &mut Context::new())
+            .unwrap();
+        let Value::Tuple(tuple) = value else {
+            panic!("expected a tuple");
+        };
+        let Value::Str(str) = *tuple.first else {
+            panic!("expected a string");
+        };
+        assert!(Arc::ptr_eq(&str.flatten(), &first.value));
+    }
+}
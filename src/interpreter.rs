@@ -2,25 +2,99 @@ use std::{
     cell::RefCell,
     collections::{hash_map::DefaultHasher, HashMap},
     fmt::Display,
+    future::Future,
     hash::{Hash, Hasher},
+    io::{BufRead, BufReader, Write},
+    ops::Range,
+    pin::Pin,
     rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    task::Poll,
+    time::{Duration, Instant},
 };
 
-use crate::ast::{
-    Binary, Call, Element, First, Function, If, Let, Location, Print, Second, Term, Var,
+mod builtins;
+mod inspect;
+mod memory;
+mod printed;
+mod replay;
+mod rope;
+mod session;
+#[cfg(feature = "json")]
+mod snapshot;
+mod stats;
+
+pub use inspect::{Inspect, InspectLimits};
+pub use memory::MemoryStats;
+pub use printed::Printed;
+pub use replay::Trace;
+pub use rope::Rope;
+#[cfg(feature = "callcc")]
+pub use session::{Callcc, Captured};
+#[cfg(feature = "generators")]
+pub use session::{Generator, Routine};
+pub use session::{Session, Step};
+#[cfg(feature = "json")]
+pub use snapshot::{Snapshot, SnapshotError};
+pub use stats::EvalStats;
+
+use crate::{
+    ast::{BinaryOp, Location, Term, Var},
+    effects::Effects,
+    lexer::SyntaxError,
+    messages::{self, ErrorCode, Lang},
+    observer::EvalObserver,
+    parser::parse,
 };
 
 #[derive(Clone, Debug)]
 pub struct Closure {
-    parameters: Vec<Var>,
-    body: Box<Term>,
+    /// Shared by the copies of the closure, so that copying the bindings
+    /// that hold it when calling functions doesn't copy its code.
+    parameters: Rc<[Var]>,
+    body: Rc<Term>,
     context: Rc<RefCell<Context>>,
+    /// What calling the closure may do, only pure closures are memoized.
+    effects: Effects,
+}
+
+/// Function given by the host program, see [`Interpreter::register_native`].
+pub type NativeFn = dyn Fn(&[Value]) -> Result<Value, String>;
+
+/// Calls a function value given to a native function, answering with what
+/// it returned or why it failed.
+pub type CallFn<'a> = dyn FnMut(&Value, Vec<Value>) -> Result<Value, String> + 'a;
+
+/// Native function that calls the functions it is given, see
+/// [`Interpreter::register_higher_order`].
+pub type HigherOrderFn = dyn Fn(&[Value], &mut CallFn) -> Result<Value, String>;
+
+#[derive(Clone)]
+pub struct Native {
+    name: String,
+    function: NativeFunction,
+}
+
+#[derive(Clone)]
+enum NativeFunction {
+    Plain(Rc<NativeFn>),
+    HigherOrder(Rc<HigherOrderFn>),
+}
+
+impl std::fmt::Debug for Native {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Native").field("name", &self.name).finish()
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Tuple {
-    first: Box<Value>,
-    second: Box<Value>,
+    pub first: Box<Value>,
+    pub second: Box<Value>,
 }
 
 impl Display for Tuple {
@@ -35,20 +109,68 @@ impl Display for Tuple {
 #[derive(Clone, Debug)]
 pub enum Value {
     Closure(Closure),
+    Native(Native),
     Int(i64),
-    Str(String),
+    Str(Rope),
     Bool(bool),
     Tuple(Tuple),
+    /// The `callcc` builtin and the continuations it captures.
+    #[cfg(feature = "callcc")]
+    Callcc(Callcc),
+    /// The builtins of generators and the generators they make.
+    #[cfg(feature = "generators")]
+    Generator(Generator),
 }
 
 impl Hash for Value {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
-            Self::Closure(_closure) => panic!("this should never be executed"),
+            // Closures are only equal to themselves, so they are hashed by
+            // identity. They are never part of memoization keys, see
+            // `has_closure`.
+            Self::Closure(closure) => Rc::as_ptr(&closure.context).hash(state),
+            Self::Native(native) => native.name.hash(state),
             Self::Int(int) => format!("Int({int})").hash(state),
-            Self::Str(string) => format!("Str({string})").hash(state),
+            Self::Str(string) => {
+                "Str".hash(state);
+                string.hash(state);
+            }
             Self::Bool(bool) => format!("Bool({bool})").hash(state),
-            Self::Tuple(tuple) => format!("Tuple({tuple})").hash(state),
+            Self::Tuple(tuple) => {
+                "Tuple".hash(state);
+                tuple.first.hash(state);
+                tuple.second.hash(state);
+            }
+            #[cfg(feature = "callcc")]
+            Self::Callcc(callcc) => callcc.hash(state),
+            #[cfg(feature = "generators")]
+            Self::Generator(generator) => generator.hash(state),
+        }
+    }
+}
+
+impl Value {
+    /// Effects of calling the value. Natives are taken as effectful, since
+    /// they may read input or answer differently each time.
+    fn effects(&self) -> Effects {
+        match self {
+            Value::Closure(closure) => closure.effects,
+            Value::Native(_) => Effects::NATIVE,
+            _ => Effects::NONE,
+        }
+    }
+
+    /// Whether the value is a function or a tuple holding one.
+    #[cfg(feature = "memo")]
+    fn has_closure(&self) -> bool {
+        match self {
+            Value::Closure(_) | Value::Native(_) => true,
+            #[cfg(feature = "callcc")]
+            Value::Callcc(_) => true,
+            #[cfg(feature = "generators")]
+            Value::Generator(_) => true,
+            Value::Tuple(tuple) => tuple.first.has_closure() || tuple.second.has_closure(),
+            _ => false,
         }
     }
 }
@@ -56,69 +178,367 @@ impl Hash for Value {
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let value = match self {
-            Self::Closure(_closure) => String::from("[closure]"),
+            Self::Closure(_closure) => String::from("<#closure>"),
+            Self::Native(native) => format!("[native {}]", native.name),
             Self::Int(int) => int.to_string(),
             Self::Str(str) => str.to_string(),
             Self::Bool(bool) => bool.to_string(),
             Self::Tuple(tuple) => {
-                format!(
-                    "({}, {})",
-                    tuple.first.to_string(),
-                    tuple.second.to_string()
-                )
+                format!("({}, {})", tuple.first, tuple.second)
             }
+            #[cfg(feature = "callcc")]
+            Self::Callcc(Callcc::Builtin) => String::from("[native callcc]"),
+            #[cfg(feature = "callcc")]
+            Self::Callcc(Callcc::Continuation(_)) => String::from("[continuation]"),
+            #[cfg(feature = "generators")]
+            Self::Generator(generator) => match generator.name() {
+                Some(name) => format!("[native {name}]"),
+                None => String::from("[generator]"),
+            },
         };
 
         f.write_str(&value)
     }
 }
 
+/// Values are serialized tagged by `kind`, the same way terms are in the
+/// JSON AST.
+#[cfg(feature = "json")]
+impl serde::Serialize for Value {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        match self {
+            Self::Closure(_closure) => {
+                let mut state = serializer.serialize_struct("Value", 1)?;
+                state.serialize_field("kind", "Closure")?;
+                state.end()
+            }
+            Self::Native(native) => {
+                let mut state = serializer.serialize_struct("Value", 2)?;
+                state.serialize_field("kind", "Native")?;
+                state.serialize_field("name", &native.name)?;
+                state.end()
+            }
+            Self::Int(int) => {
+                let mut state = serializer.serialize_struct("Value", 2)?;
+                state.serialize_field("kind", "Int")?;
+                state.serialize_field("value", int)?;
+                state.end()
+            }
+            Self::Str(str) => {
+                let mut state = serializer.serialize_struct("Value", 2)?;
+                state.serialize_field("kind", "Str")?;
+                state.serialize_field("value", str)?;
+                state.end()
+            }
+            Self::Bool(bool) => {
+                let mut state = serializer.serialize_struct("Value", 2)?;
+                state.serialize_field("kind", "Bool")?;
+                state.serialize_field("value", bool)?;
+                state.end()
+            }
+            Self::Tuple(tuple) => {
+                let mut state = serializer.serialize_struct("Value", 3)?;
+                state.serialize_field("kind", "Tuple")?;
+                state.serialize_field("first", &tuple.first)?;
+                state.serialize_field("second", &tuple.second)?;
+                state.end()
+            }
+            #[cfg(feature = "callcc")]
+            Self::Callcc(Callcc::Builtin) => {
+                let mut state = serializer.serialize_struct("Value", 2)?;
+                state.serialize_field("kind", "Native")?;
+                state.serialize_field("name", "callcc")?;
+                state.end()
+            }
+            #[cfg(feature = "callcc")]
+            Self::Callcc(Callcc::Continuation(_)) => {
+                let mut state = serializer.serialize_struct("Value", 1)?;
+                state.serialize_field("kind", "Continuation")?;
+                state.end()
+            }
+            #[cfg(feature = "generators")]
+            Self::Generator(generator) => match generator.name() {
+                Some(name) => {
+                    let mut state = serializer.serialize_struct("Value", 2)?;
+                    state.serialize_field("kind", "Native")?;
+                    state.serialize_field("name", name)?;
+                    state.end()
+                }
+                None => {
+                    let mut state = serializer.serialize_struct("Value", 1)?;
+                    state.serialize_field("kind", "Generator")?;
+                    state.end()
+                }
+            },
+        }
+    }
+}
+
 pub type Cache = std::collections::HashMap<String, Value>;
-pub type Context = HashMap<String, Value>;
+pub type Context = HashMap<Arc<str>, Value>;
+
+/// A function call that was active when an error happened.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Name the callee was bound to, or `<anonymous>`.
+    pub name: String,
+    pub location: Location,
+}
 
 #[derive(Debug, Clone)]
 pub struct RuntimeError {
-    pub message: String,
-    pub full_text: String,
+    pub code: ErrorCode,
+    pub args: Vec<String>,
     pub location: Location,
+
+    /// Calls that were active when the error happened, innermost first.
+    pub stack: Vec<Frame>,
+
+    /// Bindings visible to the term that failed.
+    pub context: Option<Box<Context>>,
 }
 
-fn eval_let<I: Printer>(
-    let_: Let,
-    context: &mut Context,
-    cache: &mut Cache,
-    io: &mut I,
-) -> Result<Value, RuntimeError> {
-    let name = let_.name.text;
-
-    match eval(let_.value, context, cache, io)? {
-        Value::Closure(closure) => {
-            let self_ = Value::Closure(Closure {
-                parameters: closure.parameters,
-                body: closure.body,
-                context: closure.context.clone(),
-            });
-
-            closure
-                .context
-                .borrow_mut()
-                .insert(name.clone(), self_.clone());
-
-            context.insert(name, self_.clone());
+impl RuntimeError {
+    pub fn new(code: ErrorCode, args: Vec<String>, location: Location) -> Self {
+        Self {
+            code,
+            args,
+            location,
+            stack: Vec::new(),
+            context: None,
         }
-        value => {
-            context.insert(name, value);
+    }
+
+    /// Records the context of the innermost term the error went through,
+    /// ignoring the outer ones.
+    fn with_context(mut self, context: &Context) -> Self {
+        if self.context.is_none() {
+            self.context = Some(Box::new(context.clone()));
         }
+
+        self
+    }
+
+    fn with_frame(mut self, frame: Frame) -> Self {
+        self.stack.push(frame);
+
+        self
+    }
+
+    /// Short description of the error, rendered in `lang`.
+    pub fn message(&self, lang: Lang) -> String {
+        messages::message(self.code, lang, &self.args)
     }
 
-    eval(let_.next, context, cache, io)
+    /// Full explanation of the error, rendered in `lang`.
+    pub fn full_text(&self, lang: Lang) -> String {
+        messages::full_text(self.code, lang, &self.args)
+    }
+
+    /// Byte range of the source file covered by the term that failed.
+    pub fn span(&self) -> Range<usize> {
+        self.location.span()
+    }
 }
 
-fn cache_key(body: &Box<Term>, arguments: Vec<Value>) -> Option<String> {
+/// Renders the error in English, use [`RuntimeError::message`] for other
+/// languages.
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} at {}",
+            self.code,
+            self.message(Lang::En),
+            self.location
+        )
+    }
+}
+
+/// The error holds the values visible to the failing term, which are not
+/// `Send`, so it fits in a `Box<dyn Error>` but not in error types that must
+/// be `Send + Sync`, like `anyhow::Error`.
+impl std::error::Error for RuntimeError {}
+
+/// Error that stopped the evaluation of source code, see
+/// [`Interpreter::eval_source`].
+#[derive(Debug, Clone)]
+pub enum EvalError {
+    Syntax(SyntaxError),
+    Runtime(RuntimeError),
+}
+
+impl EvalError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Syntax(error) => error.code,
+            Self::Runtime(error) => error.code,
+        }
+    }
+
+    /// Byte range of the source where the error happened.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Self::Syntax(error) => error.span(),
+            Self::Runtime(error) => error.span(),
+        }
+    }
+
+    /// Short description of the error, rendered in `lang`.
+    pub fn message(&self, lang: Lang) -> String {
+        match self {
+            Self::Syntax(error) => error.message(lang),
+            Self::Runtime(error) => error.message(lang),
+        }
+    }
+
+    /// Full explanation of the error, rendered in `lang`.
+    pub fn full_text(&self, lang: Lang) -> String {
+        match self {
+            Self::Syntax(error) => error.full_text(lang),
+            Self::Runtime(error) => error.full_text(lang),
+        }
+    }
+}
+
+impl From<SyntaxError> for EvalError {
+    fn from(error: SyntaxError) -> Self {
+        Self::Syntax(error)
+    }
+}
+
+impl From<RuntimeError> for EvalError {
+    fn from(error: RuntimeError) -> Self {
+        Self::Runtime(error)
+    }
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Syntax(error) => error.fmt(f),
+            Self::Runtime(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Syntax(error) => Some(error),
+            Self::Runtime(error) => Some(error),
+        }
+    }
+}
+
+/// Flag shared with the host to stop an evaluation from another thread, see
+/// [`Interpreter::with_cancel`].
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the evaluations using the token fail with
+    /// [`ErrorCode::Cancelled`] at their next step.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Lets the evaluations using the token run again, like the next input
+    /// of a REPL once the one running was stopped.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Printer that writes the printed values to stdout, the default one.
+#[derive(Default)]
+pub struct IO;
+
+pub trait Printer {
+    fn print(&mut self, value: Value) -> Value;
+}
+impl Printer for IO {
+    fn print(&mut self, value: Value) -> Value {
+        println!("{}", value.printed());
+
+        value
+    }
+}
+
+/// Printer that writes every printed value on its own line to any
+/// [`Write`], like a file, a socket or a buffer.
+pub struct Sink<W: Write>(pub W);
+
+impl<W: Write> Printer for Sink<W> {
+    fn print(&mut self, value: Value) -> Value {
+        // Like the programs in other languages, a rinha program keeps running
+        // when its output is closed.
+        let _ = writeln!(self.0, "{}", value.printed());
+
+        value
+    }
+}
+
+/// Printer that hands every printed value to a function as soon as it is
+/// printed, like a GUI appending it to its output.
+pub struct Callback<F: FnMut(&Value)>(pub F);
+
+impl<F: FnMut(&Value)> Printer for Callback<F> {
+    fn print(&mut self, value: Value) -> Value {
+        (self.0)(&value);
+
+        value
+    }
+}
+
+/// Printer that sends the text of every printed value through a channel, to
+/// be shown by another thread while the program runs.
+pub struct Channel(pub Sender<String>);
+
+impl Printer for Channel {
+    fn print(&mut self, value: Value) -> Value {
+        // Like `Sink`, the program keeps running when nobody is listening.
+        let _ = self.0.send(value.printed().to_string());
+
+        value
+    }
+}
+
+/// Printer that keeps the printed values instead of writing them.
+#[derive(Default)]
+pub struct Collect(pub Vec<String>);
+
+impl Printer for Collect {
+    fn print(&mut self, value: Value) -> Value {
+        self.0.push(value.printed().to_string());
+
+        value
+    }
+}
+
+#[cfg(not(feature = "memo"))]
+fn cache_key(_body: &DefaultHasher, _arguments: Vec<Value>) -> Option<String> {
+    None
+}
+
+/// Key of a call in the memoization cache, `body` being a hasher that
+/// already hashed the body of the closure, see [`Session`].
+#[cfg(feature = "memo")]
+fn cache_key(body: &DefaultHasher, arguments: Vec<Value>) -> Option<String> {
     let arguments = arguments
         .into_iter()
         .map(|argument| match argument {
-            Value::Closure(_) => None,
+            value if value.has_closure() => None,
             value => {
                 let mut s = DefaultHasher::new();
                 // TODO: is ok to define the hasher on each iteration?
@@ -128,225 +548,557 @@ fn cache_key(body: &Box<Term>, arguments: Vec<Value>) -> Option<String> {
         })
         .collect::<Option<Vec<String>>>()?;
 
-    let mut s = DefaultHasher::new();
-    (*body.clone(), arguments).hash(&mut s);
+    let mut s = body.clone();
+    arguments.hash(&mut s);
 
     Some(s.finish().to_string())
 }
 
-fn eval_memo<I: Printer>(
-    body: Box<Term>,
-    arguments: Vec<Value>,
-    context: &mut Context,
-    cache: &mut Cache,
-    io: &mut I,
-) -> Result<Value, RuntimeError> {
-    match cache_key(&body, arguments.clone()) {
-        Some(cache_key) => match cache.get(&cache_key) {
-            Some(cached_value) => Ok(cached_value.clone()),
-            None => {
-                let value = eval(body, context, cache, io)?;
-                cache.insert(cache_key, value.clone());
-
-                Ok(value)
-            }
-        },
-        None => eval(body, context, cache, io),
-    }
-}
+/// How many calls can be nested when no maximum depth is given, so runaway
+/// recursion fails with a [`RuntimeError`] before exhausting the memory.
+static DEFAULT_MAX_DEPTH: usize = 100_000;
 
-fn eval_call<I: Printer>(
-    call: Call,
-    context: &mut Context,
-    cache: &mut Cache,
-    io: &mut I,
-) -> Result<Value, RuntimeError> {
-    match eval(call.callee, context, cache, io)? {
-        Value::Closure(closure) => {
-            let mut new_context = closure.context.borrow_mut().clone();
-            let mut arguments = Vec::new();
+/// How many calls are evaluated between two checks of the clock.
+static TIMEOUT_CHECK_INTERVAL: u64 = 1024;
 
-            for (parameter, argument) in closure.parameters.clone().into_iter().zip(call.arguments)
-            {
-                let argument = eval(Box::new(argument), context, cache, io)?;
-                arguments.push(argument.clone());
+/// Where the input builtins, like `read_line`, read from.
+type Input = Rc<RefCell<Box<dyn BufRead>>>;
 
-                new_context.insert(parameter.text, argument);
-            }
+/// Reads a line from `input`, without its line break.
+fn read_line(input: &Input) -> Result<Value, String> {
+    let mut line = String::new();
 
-            match closure.body.is_pure() {
-                true => eval_memo(closure.body, arguments, &mut new_context, cache, io),
-                false => eval(closure.body, &mut new_context, cache, io),
-            }
+    match input.borrow_mut().read_line(&mut line) {
+        Ok(0) => Err(String::from("there is no more input")),
+        Ok(_) => {
+            let end = line.trim_end_matches(['\n', '\r']).len();
+            line.truncate(end);
+
+            Ok(Value::Str(line.into()))
         }
-        value => Err(RuntimeError {
-            message: String::from("invalid function call"),
-            full_text: format!("{} cannot be called as a function", value),
-            location: call.location,
-        }),
+        Err(error) => Err(error.to_string()),
     }
 }
 
-fn eval_if<I: Printer>(
-    if_: If,
-    context: &mut Context,
-    cache: &mut Cache,
-    io: &mut I,
-) -> Result<Value, RuntimeError> {
-    let condition_result = eval(if_.condition.clone(), context, cache, io)?;
-    let condition = match condition_result {
-        Value::Bool(bool) => Ok(bool),
-        _ => Err(RuntimeError {
-            message: String::from("invalid if condition"),
-            full_text: format!(
-                "{} can't be used as an if condition. use a boolean instead",
-                condition_result
-            ),
-            location: if_.condition.location().clone(),
-        }),
-    }?;
+/// Reads what is left of `input`.
+fn read_all(input: &Input) -> Result<Value, String> {
+    let mut text = String::new();
 
-    match condition {
-        true => eval(if_.then, context, cache, io),
-        false => eval(if_.otherwise, context, cache, io),
+    match input.borrow_mut().read_to_string(&mut text) {
+        Ok(_) => Ok(Value::Str(text.into())),
+        Err(error) => Err(error.to_string()),
     }
 }
 
-fn eval_binary<I: Printer>(
-    binary: Binary,
-    context: &mut Context,
-    cache: &mut Cache,
-    io: &mut I,
-) -> Result<Value, RuntimeError> {
-    let lhs = eval(binary.lhs.clone(), context, cache, io)?;
-    let rhs = eval(binary.rhs.clone(), context, cache, io)?;
+/// Reads the next integer of `input`, skipping the whitespace before it,
+/// line breaks included, like the `scanf("%d")` of competitive programming.
+fn read_int(input: &Input) -> Result<Value, String> {
+    let mut input = input.borrow_mut();
+    let mut word = Vec::new();
 
-    lhs.binary_op(binary, rhs)
-}
+    loop {
+        let buffer = input.fill_buf().map_err(|error| error.to_string())?;
+        if buffer.is_empty() {
+            break;
+        }
 
-fn eval_var(var: Var, context: &mut Context) -> Result<Value, RuntimeError> {
-    context
-        .get(&var.text)
-        .ok_or(RuntimeError {
-            message: format!("unbound variable \"{}\"", var.text),
-            full_text: format!(
-                "variable \"{}\" was not defined in the current scope",
-                var.text
-            ),
-            location: var.location,
-        })
-        .map(|value| value.clone())
-}
+        let skipped = match word.is_empty() {
+            true => buffer
+                .iter()
+                .take_while(|byte| byte.is_ascii_whitespace())
+                .count(),
+            false => 0,
+        };
+        let length = buffer[skipped..]
+            .iter()
+            .take_while(|byte| !byte.is_ascii_whitespace())
+            .count();
+        word.extend_from_slice(&buffer[skipped..skipped + length]);
 
-fn eval_tuple<I: Printer>(
-    tuple: crate::ast::Tuple,
-    context: &mut Context,
-    cache: &mut Cache,
-    io: &mut I,
-) -> Result<Value, RuntimeError> {
-    let first = eval(tuple.first, context, cache, io)?;
-    let second = eval(tuple.second, context, cache, io)?;
+        let done = skipped + length < buffer.len() && !word.is_empty();
+        input.consume(skipped + length);
 
-    Ok(Value::Tuple(Tuple {
-        first: Box::new(first),
-        second: Box::new(second),
-    }))
-}
+        if done {
+            break;
+        }
+    }
 
-fn eval_first<I: Printer>(
-    first: First,
-    context: &mut Context,
-    cache: &mut Cache,
-    io: &mut I,
-) -> Result<Value, RuntimeError> {
-    match eval(first.value, context, cache, io)? {
-        Value::Tuple(Tuple { first, second: _ }) => Ok(*first),
-        _value => Err(RuntimeError {
-            message: String::from("invalid expression"),
-            full_text: String::from("cannot use first operation from anything but a tuple"),
-            location: first.location,
-        }),
+    if word.is_empty() {
+        return Err(String::from("there is no more input"));
     }
+
+    let word = String::from_utf8_lossy(&word);
+    word.parse()
+        .map(Value::Int)
+        .map_err(|_| format!("\"{word}\" is not an integer"))
 }
 
-fn eval_second<I: Printer>(
-    second: Second,
-    context: &mut Context,
-    cache: &mut Cache,
-    io: &mut I,
-) -> Result<Value, RuntimeError> {
-    match eval(second.value, context, cache, io)? {
-        Value::Tuple(Tuple { first: _, second }) => Ok(*second),
-        _value => Err(RuntimeError {
-            message: String::from("invalid expression"),
-            full_text: String::from("cannot use second operation from anything but a tuple"),
-            location: second.location,
-        }),
+/// Evaluation state shared by all the terms of a program: the memoization
+/// cache, the output, the input and the execution limits.
+pub struct Interpreter<I: Printer> {
+    pub cache: Cache,
+    pub io: I,
+    input: Input,
+    /// Bindings visible to every program, see [`Interpreter::with_globals`].
+    globals: Context,
+    /// Bindings kept between calls to [`Interpreter::eval_source`].
+    bindings: Context,
+    natives: Context,
+    observers: Vec<Box<dyn EvalObserver>>,
+    cancel: Option<CancelToken>,
+    memoization: bool,
+    /// Whether operations follow the specification to the letter, see
+    /// [`Interpreter::with_spec`].
+    spec: bool,
+    /// Whether common patterns are evaluated in a single bounce, see
+    /// [`Interpreter::with_superinstructions`].
+    superinstructions: bool,
+    fuel: Option<u64>,
+    max_depth: Option<usize>,
+    timeout: Option<(Duration, Instant)>,
+    /// How many times a call can be running in the same state, see
+    /// [`Interpreter::with_loop_detection`].
+    loop_detection: Option<u32>,
+    max_memory: Option<usize>,
+    memory: MemoryStats,
+    stats: EvalStats,
+    /// Native calls being recorded or replayed.
+    tape: Option<replay::Tape>,
+    calls: u64,
+    depth: usize,
+    calls_until_check: u64,
+    /// How many native functions were called, to avoid memoizing the calls
+    /// that depend on them.
+    native_calls: u64,
+}
+
+impl Default for Interpreter<IO> {
+    /// Creates an interpreter that prints to stdout.
+    fn default() -> Self {
+        Self::new(IO)
     }
 }
 
-pub struct IO;
+impl<I: Printer> Interpreter<I> {
+    pub fn new(io: I) -> Self {
+        let input: Input = Rc::new(RefCell::new(Box::new(BufReader::new(std::io::stdin()))));
+
+        let mut interpreter = Self {
+            cache: Cache::new(),
+            io,
+            input: input.clone(),
+            globals: Context::new(),
+            bindings: Context::new(),
+            natives: Context::new(),
+            observers: Vec::new(),
+            cancel: None,
+            memoization: true,
+            spec: false,
+            superinstructions: true,
+            fuel: None,
+            max_depth: None,
+            timeout: None,
+            loop_detection: None,
+            max_memory: None,
+            memory: MemoryStats::default(),
+            stats: EvalStats::default(),
+            tape: None,
+            calls: 0,
+            depth: 0,
+            calls_until_check: TIMEOUT_CHECK_INTERVAL,
+            native_calls: 0,
+        };
 
-pub trait Printer {
-    fn print(&mut self, value: Value) -> Value;
+        let all = input.clone();
+        interpreter.register_native("read_all", move |_arguments| read_all(&all));
+        let int = input.clone();
+        interpreter.register_native("read_int", move |_arguments| read_int(&int));
+        interpreter.register_native("read_line", move |_arguments| read_line(&input));
+        interpreter.register_builtins();
+
+        #[cfg(feature = "callcc")]
+        interpreter
+            .natives
+            .insert("callcc".into(), Value::Callcc(Callcc::Builtin));
+
+        #[cfg(feature = "generators")]
+        for generator in [Generator::New, Generator::Next, Generator::Yield] {
+            let name = generator.name().expect("builtins have a name");
+            interpreter
+                .natives
+                .insert(name.into(), Value::Generator(generator));
+        }
+
+        interpreter
+    }
+
+    /// Sends the events of every evaluation to `observer`, after the ones
+    /// installed before it.
+    pub fn with_observer<O: EvalObserver + 'static>(mut self, observer: O) -> Self {
+        self.observers.push(Box::new(observer));
+
+        self
+    }
+
+    /// Makes the input builtins read from `input` instead of stdin.
+    pub fn with_input<R: BufRead + 'static>(self, input: R) -> Self {
+        *self.input.borrow_mut() = Box::new(input);
+
+        self
+    }
+
+    /// Makes the given values visible to every program evaluated by the
+    /// interpreter, as if they were bound before it. Programs can still bind
+    /// the same names, shadowing them.
+    pub fn with_globals<K, V>(mut self, globals: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<Arc<str>>,
+        V: Into<Value>,
+    {
+        self.globals.extend(
+            globals
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into())),
+        );
+
+        self
+    }
+
+    /// Makes `function` callable from programs as `name`, unless they bind
+    /// `name` themselves. An `Err` returned by `function` stops the program
+    /// with a [`RuntimeError`] carrying its message.
+    ///
+    /// Native functions are assumed to have side effects, so calls that end
+    /// up calling them are never memoized.
+    pub fn register_native<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + 'static,
+    {
+        let native = Native {
+            name: name.to_string(),
+            function: NativeFunction::Plain(Rc::new(function)),
+        };
+
+        self.natives.insert(name.into(), Value::Native(native));
+    }
+
+    /// Makes `function` callable from programs as `name`, like
+    /// [`Interpreter::register_native`], giving it a [`CallFn`] to call the
+    /// functions it is given, like a closure to compare values with.
+    ///
+    /// The callback evaluates the call to its end and fails with its error,
+    /// which the program stops with when `function` passes it on. The calls
+    /// are evaluated again when a run is replayed, so the function itself is
+    /// never recorded, but the native functions they call are.
+    pub fn register_higher_order<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value], &mut CallFn) -> Result<Value, String> + 'static,
+    {
+        let native = Native {
+            name: name.to_string(),
+            function: NativeFunction::HigherOrder(Rc::new(function)),
+        };
+
+        self.natives.insert(name.into(), Value::Native(native));
+    }
+
+    /// Names of the native functions, the registered ones and the builtins
+    /// like `read_line`.
+    pub fn natives(&self) -> impl Iterator<Item = &str> {
+        self.natives.keys().map(|name| &**name)
+    }
+
+    /// Enables or disables the memoization of calls to pure functions, which
+    /// is enabled by default. Without the `memo` feature calls are never
+    /// memoized.
+    pub fn with_memoization(mut self, memoization: bool) -> Self {
+        self.memoization = memoization;
+
+        self
+    }
+
+    /// Enables or disables the semantics of the rinha specification, where
+    /// lipsum is more lenient, see [`Value::operate_spec`].
+    pub fn with_spec(mut self, spec: bool) -> Self {
+        self.spec = spec;
+
+        self
+    }
+
+    /// Applies `op` with the semantics the interpreter was created with.
+    fn operate(
+        &self,
+        lhs: &Value,
+        op: &BinaryOp,
+        rhs: &Value,
+        location: &Location,
+    ) -> Result<Value, RuntimeError> {
+        match self.spec {
+            true => lhs.operate_spec(op, rhs, location),
+            false => lhs.operate(op, rhs, location),
+        }
+    }
+
+    /// Enables or disables evaluating comparisons that pick a branch,
+    /// operations on variables and literals, and calls with such arguments
+    /// in a single bounce of a [`Session`], which is enabled by default.
+    /// Disabling it makes sessions stop at every term, like debuggers need.
+    /// Observers see every term either way.
+    pub fn with_superinstructions(mut self, superinstructions: bool) -> Self {
+        self.superinstructions = superinstructions;
+
+        self
+    }
+
+    /// Aborts the evaluation once the program makes more than `fuel` calls.
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = Some(fuel);
+
+        self
+    }
+
+    /// Aborts the evaluation once more than `max_depth` calls are nested,
+    /// 100 000 by default.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+
+        self
+    }
+
+    /// Aborts the evaluation, with [`ErrorCode::Cancelled`], once `token` is
+    /// cancelled.
+    pub fn with_cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+
+        self
+    }
+
+    /// Aborts the evaluation once it runs for longer than `timeout`, counting
+    /// from now.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some((timeout, Instant::now() + timeout));
+
+        self
+    }
+
+    /// Aborts the evaluation, with [`ErrorCode::ProbableInfiniteLoop`], once
+    /// a function is running `repeats` times at once in the same state: with
+    /// the same arguments and the same bindings, even when native functions
+    /// were called in between, unlike [`ErrorCode::InfiniteRecursion`].
+    ///
+    /// It may stop programs that would have ended, like ones waiting for an
+    /// input to change, so it is ignored when fuel or a timeout already
+    /// bound the evaluation.
+    pub fn with_loop_detection(mut self, repeats: u32) -> Self {
+        self.loop_detection = Some(repeats.max(2));
+
+        self
+    }
+
+    /// How many repeated states make a probable infinite loop, when the
+    /// detection applies.
+    fn loop_repeats(&self) -> Option<u32> {
+        match (self.fuel, self.timeout) {
+            (None, None) => self.loop_detection,
+            _ => None,
+        }
+    }
+
+    fn check_limits(&mut self, location: &Location) -> Result<(), RuntimeError> {
+        self.calls += 1;
+
+        match (self.fuel, self.max_depth.unwrap_or(DEFAULT_MAX_DEPTH)) {
+            (Some(fuel), _) if self.calls > fuel => {
+                return Err(RuntimeError::new(
+                    ErrorCode::OutOfFuel,
+                    vec![fuel.to_string()],
+                    location.clone(),
+                ))
+            }
+            (_, max_depth) if self.depth >= max_depth => {
+                return Err(RuntimeError::new(
+                    ErrorCode::StackOverflow,
+                    vec![max_depth.to_string()],
+                    location.clone(),
+                ))
+            }
+            _ => (),
+        }
+
+        self.check_memory(location)?;
+
+        self.calls_until_check -= 1;
+
+        if self.calls_until_check > 0 {
+            return Ok(());
+        }
+
+        self.calls_until_check = TIMEOUT_CHECK_INTERVAL;
+
+        match self.timeout {
+            Some((timeout, deadline)) if Instant::now() >= deadline => Err(RuntimeError::new(
+                ErrorCode::Timeout,
+                vec![timeout.as_secs().to_string()],
+                location.clone(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Calls a function value, like a closure returned by a program, from
+    /// Rust. The call counts towards the limits of the interpreter.
+    pub fn call(&mut self, function: &Value, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let frame = Frame {
+            name: String::from("<host>"),
+            location: Location::default(),
+        };
+
+        Session::call(self, function.clone(), arguments, frame)
+            .finish()
+            .0
+    }
+
+    /// Evaluates `term` to its end, the bindings made by its outermost
+    /// `let`s are left in `context`. See [`Session`] to evaluate a bit at a
+    /// time instead.
+    pub fn eval(&mut self, term: Term, context: &mut Context) -> Result<Value, RuntimeError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("eval").entered();
+
+        let session = Session::new(self, term, std::mem::take(context));
+        let (result, bindings) = session.finish();
+        *context = bindings;
+
+        result
+    }
+
+    /// Parses and evaluates a fragment of a program, like a line typed in a
+    /// REPL. The bindings made by the outermost `let`s of each fragment are
+    /// kept and visible to the next ones.
+    pub fn eval_source(&mut self, source: &str) -> Result<Value, EvalError> {
+        let file = parse(source, "<source>")?;
+        let mut bindings = std::mem::take(&mut self.bindings);
+        let result = self.eval(file.expression, &mut bindings);
+        self.bindings = bindings;
+
+        Ok(result?)
+    }
+
+    /// Bindings kept by [`Interpreter::eval_source`].
+    pub fn bindings(&self) -> &Context {
+        &self.bindings
+    }
+
+    /// Evaluates `term` like [`Interpreter::eval`], giving control back to
+    /// the executor every few thousand bounces of the evaluation, so it does
+    /// not block other tasks and can be raced against timeouts.
+    ///
+    /// Like the interpreter, the future is not `Send`, so with tokio it runs
+    /// in a `LocalSet` or with `block_on`.
+    pub async fn eval_async(
+        &mut self,
+        term: Term,
+        context: &mut Context,
+    ) -> Result<Value, RuntimeError> {
+        let mut session = Session::new(self, term, std::mem::take(context));
+
+        while let Step::Pending = session.step() {
+            YieldNow(false).await;
+        }
+
+        let (result, bindings) = session.finish();
+        *context = bindings;
+
+        result
+    }
 }
-impl Printer for IO {
-    fn print(&mut self, value: Value) -> Value {
-        println!("{}", &value);
 
-        value
+/// Future that is pending the first time it is polled, waking its task right
+/// away so it is polled again after the other ready tasks.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+
+        self.0 = true;
+        cx.waker().wake_by_ref();
+
+        Poll::Pending
     }
 }
 
-fn eval_print<I: Printer>(
-    print_: Print,
-    context: &mut Context,
-    cache: &mut Cache,
-    io: &mut I,
-) -> Result<Value, RuntimeError> {
-    let value = eval(print_.value, context, cache, io)?;
+/// Evaluates `term` in a fresh interpreter, returning its result along with
+/// everything it printed.
+pub fn capture(term: Term) -> (Result<Value, RuntimeError>, String) {
+    let mut interpreter = Interpreter::new(Sink(Vec::new()));
+    let result = interpreter.eval(term, &mut Context::new());
 
-    Ok(io.print(value))
+    (
+        result,
+        String::from_utf8_lossy(&interpreter.io.0).into_owned(),
+    )
 }
 
-fn eval_function(function: Function, context: &mut Context) -> Result<Value, RuntimeError> {
-    let context = Rc::new(RefCell::new(context.clone()));
+/// Parses and runs `source` in a fresh interpreter, returning its value, or
+/// the code of the error that stopped it, and the lines it printed.
+#[cfg(test)]
+pub(crate) fn run(source: &str) -> (Result<String, ErrorCode>, Vec<String>) {
+    run_with(Interpreter::new(Collect::default()), source)
+}
 
-    Ok(Value::Closure(Closure {
-        parameters: function.parameters,
-        body: function.value.clone(),
-        context,
-    }))
+/// Like [`run`], with the given `interpreter`.
+#[cfg(test)]
+pub(crate) fn run_with(
+    mut interpreter: Interpreter<Collect>,
+    source: &str,
+) -> (Result<String, ErrorCode>, Vec<String>) {
+    let result = evaluate(&mut interpreter, source);
+
+    (
+        result
+            .map(|value| value.to_string())
+            .map_err(|error| error.code),
+        interpreter.io.0,
+    )
 }
 
-pub fn eval<I: Printer>(
-    term: Box<Term>,
-    context: &mut Context,
-    cache: &mut Cache,
-    io: &mut I,
+/// Parses and evaluates `source` with `interpreter`.
+#[cfg(test)]
+pub(crate) fn evaluate<I: Printer>(
+    interpreter: &mut Interpreter<I>,
+    source: &str,
 ) -> Result<Value, RuntimeError> {
-    match *term {
-        Term::Let(let_) => eval_let(let_, context, cache, io),
-        Term::Int(int) => Ok(Value::Int(int.value)),
-        Term::Str(str) => Ok(Value::Str(str.value)),
-        Term::Bool(bool) => Ok(Value::Bool(bool.value)),
-        Term::Function(function) => eval_function(function, context),
-        Term::Call(call) => eval_call(call, context, cache, io),
-        Term::If(if_) => eval_if(if_, context, cache, io),
-        Term::Binary(binary) => eval_binary(binary, context, cache, io),
-        Term::Var(var) => eval_var(var, context),
-        Term::Tuple(tuple) => eval_tuple(tuple, context, cache, io),
-        Term::First(first) => eval_first(first, context, cache, io),
-        Term::Second(second) => eval_second(second, context, cache, io),
-        Term::Print(print) => eval_print(print, context, cache, io),
-    }
+    let file = crate::parser::parse(source, "tests").unwrap();
+
+    interpreter.eval(file.expression, &mut Context::new())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ast::{Location, Term, Tuple, Var};
-
-    use super::{eval, Cache, Context, Printer, Value};
+    use std::{
+        cell::Cell,
+        future::Future,
+        pin::pin,
+        rc::Rc,
+        sync::{mpsc, Arc},
+        task::{Poll, Wake, Waker},
+        thread,
+        time::Duration,
+    };
+
+    use crate::ast::{build::*, BinaryOp, Location, Term};
+
+    use crate::messages::ErrorCode;
+
+    use super::{
+        capture, evaluate, Callback, CancelToken, Channel, Collect, Context, EvalError,
+        Interpreter, Printer, Value,
+    };
 
     #[derive(Default)]
     struct DummyIO(String);
@@ -367,13 +1119,6 @@ mod tests {
         }
     }
 
-    fn int(int: i64) -> Term {
-        Term::Int(crate::ast::Int {
-            value: int,
-            location: location(),
-        })
-    }
-
     fn v_int(int: i64) -> Value {
         Value::Int(int)
     }
@@ -385,51 +1130,12 @@ mod tests {
         })
     }
 
-    fn var(str: &str) -> Var {
-        Var {
-            text: str.to_string(),
-            location: location(),
-        }
-    }
-
-    fn let_(name: &str, value: Term, next: Term) -> Term {
-        Term::Let(crate::ast::Let {
-            name: var(name),
-            value: Box::new(value),
-            next: Box::new(next),
-            location: location(),
-        })
-    }
-
-    fn print_(value: Term) -> Term {
-        Term::Print(crate::ast::Print {
-            value: Box::new(value),
-            location: location(),
-        })
-    }
-
-    fn tuple(first: Term, second: Term) -> Term {
-        Term::Tuple(Tuple {
-            first: Box::new(first),
-            second: Box::new(second),
-            location: location(),
-        })
-    }
-
     fn add(lhs: Term, rhs: Term) -> Term {
-        Term::Binary(super::Binary {
-            lhs: Box::new(lhs),
-            rhs: Box::new(rhs),
-            op: crate::ast::BinaryOp::Add,
-            location: location(),
-        })
+        binary(BinaryOp::Add, lhs, rhs)
     }
 
-    fn var_(text: &str) -> Term {
-        Term::Var(Var {
-            text: text.to_string(),
-            location: location(),
-        })
+    fn div(lhs: Term, rhs: Term) -> Term {
+        binary(BinaryOp::Div, lhs, rhs)
     }
 
     fn eq(l: Value, r: Value) -> bool {
@@ -441,44 +1147,549 @@ mod tests {
 
     #[test]
     fn print_inner_and_outer_scope() {
-        let mut io = DummyIO::default();
-
-        let let_ = let_("_", print_(int(1)), print_(int(2)));
-        let mut context = Context::new();
-        let mut cache = Cache::new();
-        let result = eval(Box::new(let_), &mut context, &mut cache, &mut io).unwrap();
+        let let_ = let_("_", print(int(1)), print(int(2)));
+        let mut interpreter = Interpreter::new(DummyIO::default());
+        let result = interpreter.eval(let_, &mut Context::new()).unwrap();
 
         assert!(eq(result, v_int(2)));
-        assert_eq!(io.0, "1\n2\n");
+        assert_eq!(interpreter.io.0, "1\n2\n");
     }
 
     #[test]
     fn print_inside_var_scope_and_var() {
-        let mut io = DummyIO::default();
-
         let let_ = let_(
             "tuple",
-            tuple(print_(int(1)), print_(int(2))),
-            print_(var_("tuple")),
+            tuple(print(int(1)), print(int(2))),
+            print(var("tuple")),
         );
-        let mut context = Context::new();
-        let mut cache = Cache::new();
-        let result = eval(Box::new(let_), &mut context, &mut cache, &mut io).unwrap();
+        let mut interpreter = Interpreter::new(DummyIO::default());
+        let result = interpreter.eval(let_, &mut Context::new()).unwrap();
 
         assert_eq!(result.to_string(), v_tuple(v_int(1), v_int(2)).to_string());
-        assert_eq!(io.0, "1\n2\n(1, 2)\n");
+        assert_eq!(interpreter.io.0, "1\n2\n(1, 2)\n");
     }
 
     #[test]
     fn print_sum_operation_and_operated() {
-        let mut io = DummyIO::default();
+        let print = print(add(print(int(1)), print(int(2))));
+        let mut interpreter = Interpreter::new(DummyIO::default());
+        let result = interpreter.eval(print, &mut Context::new()).unwrap();
+
+        assert!(eq(result, v_int(3)));
+        assert_eq!(interpreter.io.0, "1\n2\n3\n");
+    }
 
-        let print = print_(add(print_(int(1)), print_(int(2))));
+    #[test]
+    fn error_records_stack_and_context() {
+        let let_ = let_(
+            "f",
+            function(&["n"], div(var("n"), int(0))),
+            call(var("f"), vec![int(1)]),
+        );
+        let mut interpreter = Interpreter::new(DummyIO::default());
+        let error = interpreter.eval(let_, &mut Context::new()).unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::DivisionByZero);
+        assert_eq!(error.stack.len(), 1);
+        assert_eq!(error.stack[0].name, "f");
+
+        let n = error.context.as_ref().unwrap().get("n").cloned().unwrap();
+        assert!(eq(n, v_int(1)));
+        assert_eq!(error.span(), 0..0);
+        assert!(error.to_string().starts_with("E0"));
+    }
+
+    #[test]
+    fn error_positions() {
+        let source = "let nome = \"ção\";\nlet n = 0;\n  nome + \"é\" + 1 / n";
+        let error = evaluate(&mut Interpreter::new(Collect::default()), source).unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::DivisionByZero);
+        assert_eq!(error.location.position(source), "tests:3:16");
+    }
+
+    fn exponential_calls() -> Term {
+        // Passing a closure as argument disables memoization, so this makes
+        // 2^12 calls.
+        let_(
+            "f",
+            function(
+                &["n", "g"],
+                if_(
+                    binary(BinaryOp::Eq, var("n"), int(0)),
+                    int(0),
+                    add(
+                        call(
+                            var("g"),
+                            vec![binary(BinaryOp::Sub, var("n"), int(1)), var("g")],
+                        ),
+                        call(
+                            var("g"),
+                            vec![binary(BinaryOp::Sub, var("n"), int(1)), var("g")],
+                        ),
+                    ),
+                ),
+            ),
+            call(var("f"), vec![int(11), var("f")]),
+        )
+    }
+
+    #[test]
+    fn timeout() {
+        let mut interpreter = Interpreter::new(DummyIO::default()).with_timeout(Duration::ZERO);
+        let error = interpreter
+            .eval(exponential_calls(), &mut Context::new())
+            .unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::Timeout);
+    }
+
+    #[test]
+    fn cancel() {
+        let token = CancelToken::new();
+        let mut interpreter = Interpreter::new(DummyIO::default()).with_cancel(token.clone());
+
+        assert!(interpreter
+            .eval(exponential_calls(), &mut Context::new())
+            .is_ok());
+
+        let remote = token.clone();
+        thread::spawn(move || remote.cancel()).join().unwrap();
+
+        let error = interpreter
+            .eval(exponential_calls(), &mut Context::new())
+            .unwrap_err();
+
+        assert!(token.is_cancelled());
+        assert_eq!(error.code, ErrorCode::Cancelled);
+    }
+
+    /// Polls `future` until it is ready, returning its output and how many
+    /// times it was polled.
+    fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+        struct Noop;
+
+        impl Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let waker = Waker::from(Arc::new(Noop));
+        let mut cx = std::task::Context::from_waker(&waker);
+        let mut future = pin!(future);
+        let mut polls = 1;
+
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return (output, polls),
+                Poll::Pending => polls += 1,
+            }
+        }
+    }
+
+    #[test]
+    fn eval_async_yields() {
+        let mut interpreter = Interpreter::new(DummyIO::default());
         let mut context = Context::new();
-        let mut cache = Cache::new();
-        let result = eval(Box::new(print), &mut context, &mut cache, &mut io).unwrap();
+        let (result, polls) = block_on(interpreter.eval_async(exponential_calls(), &mut context));
 
-        assert!(eq(result, v_int(3)));
-        assert_eq!(io.0, "1\n2\n3\n");
+        assert!(eq(result.unwrap(), v_int(0)));
+        assert!(polls > 1);
+        assert!(context.contains_key("f"));
+    }
+
+    #[test]
+    fn fuel() {
+        let mut interpreter = Interpreter::new(DummyIO::default()).with_fuel(100);
+        let error = interpreter
+            .eval(exponential_calls(), &mut Context::new())
+            .unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::OutOfFuel);
+    }
+
+    #[test]
+    fn max_depth() {
+        let mut interpreter = Interpreter::new(DummyIO::default()).with_max_depth(5);
+        let error = interpreter
+            .eval(exponential_calls(), &mut Context::new())
+            .unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::StackOverflow);
+        assert_eq!(error.stack.len(), 5);
+    }
+
+    #[test]
+    fn unbounded_recursion() {
+        // Tail calls run in constant stack, so the call is added to.
+        let program = let_(
+            "f",
+            function(
+                &["n"],
+                add(int(1), call(var("f"), vec![add(var("n"), int(1))])),
+            ),
+            call(var("f"), vec![int(1)]),
+        );
+
+        let mut interpreter = Interpreter::new(DummyIO::default());
+        let error = interpreter.eval(program, &mut Context::new()).unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::StackOverflow);
+    }
+
+    #[test]
+    fn infinite_recursion() {
+        let program = || {
+            let_(
+                "f",
+                function(&["n"], call(var("f"), vec![var("n")])),
+                call(var("f"), vec![int(1)]),
+            )
+        };
+
+        let mut interpreter = Interpreter::new(DummyIO::default()).with_memoization(false);
+        let error = interpreter
+            .eval(program(), &mut Context::new())
+            .unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::InfiniteRecursion);
+        assert_eq!(error.args, vec!["f"]);
+        assert_eq!(error.stack.len(), 1);
+
+        // Memoized calls are left to the other limits.
+        #[cfg(feature = "memo")]
+        {
+            let mut interpreter = Interpreter::new(DummyIO::default()).with_fuel(1000);
+            let error = interpreter
+                .eval(program(), &mut Context::new())
+                .unwrap_err();
+
+            assert_eq!(error.code, ErrorCode::OutOfFuel);
+        }
+
+        // Native functions may answer differently each time they are called.
+        let mut interpreter = Interpreter::new(DummyIO::default()).with_max_depth(100);
+        interpreter.register_native("tick", |_| Ok(Value::Int(0)));
+
+        let program = let_(
+            "f",
+            function(
+                &["n"],
+                let_(
+                    "_",
+                    call(var("tick"), vec![]),
+                    add(int(1), call(var("f"), vec![var("n")])),
+                ),
+            ),
+            call(var("f"), vec![int(1)]),
+        );
+        let error = interpreter.eval(program, &mut Context::new()).unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::StackOverflow);
+    }
+
+    #[test]
+    fn probable_infinite_loop() {
+        let program = || {
+            let_(
+                "f",
+                function(
+                    &["n"],
+                    let_(
+                        "_",
+                        call(var("tick"), vec![]),
+                        call(var("f"), vec![var("n")]),
+                    ),
+                ),
+                call(var("f"), vec![int(1)]),
+            )
+        };
+
+        let mut interpreter = Interpreter::new(DummyIO::default()).with_loop_detection(3);
+        interpreter.register_native("tick", |_| Ok(Value::Int(0)));
+        let error = interpreter
+            .eval(program(), &mut Context::new())
+            .unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::ProbableInfiniteLoop);
+        assert_eq!(error.args, vec!["f", "3"]);
+        // The calls replaced by their tail call leave the stack.
+        assert_eq!(error.stack.len(), 1);
+
+        // Fuel bounds the evaluation already.
+        let mut interpreter = Interpreter::new(DummyIO::default())
+            .with_loop_detection(3)
+            .with_fuel(50);
+        interpreter.register_native("tick", |_| Ok(Value::Int(0)));
+        let error = interpreter
+            .eval(program(), &mut Context::new())
+            .unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::OutOfFuel);
+
+        // Loops that change their state are left alone.
+        let program = let_(
+            "f",
+            function(
+                &["n"],
+                if_(
+                    binary(BinaryOp::Eq, var("n"), int(0)),
+                    int(0),
+                    call(var("f"), vec![binary(BinaryOp::Sub, var("n"), int(1))]),
+                ),
+            ),
+            call(var("f"), vec![int(10)]),
+        );
+        let mut interpreter = Interpreter::new(DummyIO::default()).with_loop_detection(2);
+
+        let value = interpreter.eval(program, &mut Context::new()).unwrap();
+
+        assert!(matches!(value, Value::Int(0)));
+    }
+
+    #[test]
+    fn closures_in_tuples_are_not_memoized() {
+        let program = let_(
+            "f",
+            function(
+                &["p"],
+                let_("g", first(var("p")), call(var("g"), vec![int(0)])),
+            ),
+            add(
+                call(var("f"), vec![tuple(function(&["x"], int(1)), int(0))]),
+                call(var("f"), vec![tuple(function(&["x"], int(2)), int(0))]),
+            ),
+        );
+
+        let mut interpreter = Interpreter::new(DummyIO::default());
+        let value = interpreter.eval(program, &mut Context::new()).unwrap();
+
+        assert!(eq(value, v_int(3)));
+    }
+
+    #[test]
+    fn closures_with_other_bindings_are_memoized_apart() {
+        let source = "let mk = fn (k) => { fn (x) => { x + k } };
+            let a = mk(1);
+            let b = mk(2);
+            print((a(1), b(1)))";
+
+        for memoization in [true, false] {
+            let interpreter = Interpreter::new(Collect::default()).with_memoization(memoization);
+            let (result, printed) = super::run_with(interpreter, source);
+
+            assert_eq!(result, Ok(String::from("(2, 3)")));
+            assert_eq!(printed, vec!["(2, 3)"]);
+        }
+    }
+
+    #[test]
+    fn nested_prints_are_not_memoized() {
+        // let f = fn (n) => if (n == 0) { print(n) } else { n }; (f(0), f(0))
+        let program = let_(
+            "f",
+            function(
+                &["n"],
+                if_(
+                    binary(BinaryOp::Eq, var("n"), int(0)),
+                    print(var("n")),
+                    var("n"),
+                ),
+            ),
+            tuple(call(var("f"), vec![int(0)]), call(var("f"), vec![int(0)])),
+        );
+
+        let mut interpreter = Interpreter::new(Collect::default());
+        interpreter.eval(program, &mut Context::new()).unwrap();
+
+        assert_eq!(interpreter.io.0, vec!["0", "0"]);
+    }
+
+    #[test]
+    fn native_functions() {
+        let mut interpreter = Interpreter::new(DummyIO::default());
+        interpreter.register_native("double", |arguments| match arguments {
+            [Value::Int(int)] => Ok(Value::Int(int * 2)),
+            _ => Err(String::from("expected an integer")),
+        });
+
+        let value = interpreter
+            .eval(call(var("double"), vec![int(21)]), &mut Context::new())
+            .unwrap();
+        assert!(eq(value, v_int(42)));
+
+        let error = interpreter
+            .eval(call(var("double"), vec![]), &mut Context::new())
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::NativeFailed);
+        assert_eq!(error.args, vec!["double", "expected an integer"]);
+    }
+
+    #[test]
+    fn globals() {
+        let mut interpreter =
+            Interpreter::new(DummyIO::default()).with_globals([("x", 20), ("y", 1)]);
+
+        let value = interpreter
+            .eval(add(var("x"), var("y")), &mut Context::new())
+            .unwrap();
+        assert!(eq(value, v_int(21)));
+
+        let value = interpreter
+            .eval(
+                let_("x", int(1), add(var("x"), var("y"))),
+                &mut Context::new(),
+            )
+            .unwrap();
+        assert!(eq(value, v_int(2)));
+    }
+
+    #[test]
+    fn native_calls_are_not_memoized() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+
+        let mut interpreter = Interpreter::new(DummyIO::default());
+        interpreter.register_native("tick", move |_arguments| {
+            counter.set(counter.get() + 1);
+            Ok(Value::Int(counter.get()))
+        });
+
+        let program = let_(
+            "f",
+            function(&["n"], call(var("tick"), vec![var("n")])),
+            add(call(var("f"), vec![int(1)]), call(var("f"), vec![int(1)])),
+        );
+        let value = interpreter.eval(program, &mut Context::new()).unwrap();
+
+        assert!(eq(value, v_int(3)));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn streaming_printers() {
+        let program = let_("_", print(int(1)), print(int(2)));
+
+        let mut printed = Vec::new();
+        let mut interpreter = Interpreter::new(Callback(|value: &Value| {
+            printed.push(value.to_string());
+        }));
+        interpreter
+            .eval(program.clone(), &mut Context::new())
+            .unwrap();
+        drop(interpreter);
+        assert_eq!(printed, vec!["1", "2"]);
+
+        let (sender, receiver) = mpsc::channel();
+        let mut interpreter = Interpreter::new(Channel(sender));
+        interpreter.eval(program, &mut Context::new()).unwrap();
+        drop(interpreter);
+        assert_eq!(receiver.iter().collect::<Vec<_>>(), vec!["1", "2"]);
+    }
+
+    #[test]
+    fn eval_source() {
+        let mut interpreter = Interpreter::new(DummyIO::default());
+
+        interpreter.eval_source("let x = 20; x").unwrap();
+        let value = interpreter.eval_source("let y = x + 1; y").unwrap();
+
+        assert_eq!(value.to_string(), "21");
+        assert!(interpreter.bindings().contains_key("y"));
+
+        let error = interpreter.eval_source("x +").unwrap_err();
+        assert!(matches!(error, EvalError::Syntax(_)));
+
+        let error = interpreter.eval_source("z").unwrap_err();
+        assert_eq!(error.code(), ErrorCode::UnboundVariable);
+        assert_eq!(interpreter.bindings().len(), 2);
+    }
+
+    #[test]
+    fn lexical_scoping() {
+        let eval = |source: &str| Interpreter::new(DummyIO::default()).eval_source(source);
+
+        // Closures see the bindings of where they were created, not the ones
+        // defined later or the ones of their callers.
+        let error = eval("let f = fn () => y; let y = 2; f()").unwrap_err();
+        assert_eq!(error.code(), ErrorCode::UnboundVariable);
+
+        let value = eval("let x = 1; let f = fn () => x; let g = fn (x) => f(); g(5)").unwrap();
+        assert_eq!(value.to_string(), "1");
+
+        // Bindings of nested lets end with them.
+        let error = eval("((let a = 1; a), a)").unwrap_err();
+        assert_eq!(error.code(), ErrorCode::UnboundVariable);
+
+        let value = eval("let a = 1; let t = ((let a = 2; a), a); t").unwrap();
+        assert_eq!(value.to_string(), "(2, 1)");
+
+        // Binding a closure made earlier doesn't add the name to its bindings.
+        let value = eval("let y = 5; let f = fn () => { y }; let y = f; f()").unwrap();
+        assert_eq!(value.to_string(), "5");
+
+        let error = eval("let f = fn () => { k }; let k = f; f()").unwrap_err();
+        assert_eq!(error.code(), ErrorCode::UnboundVariable);
+    }
+
+    #[test]
+    fn capture_output() {
+        let (result, output) = capture(print(tuple(print(int(1)), int(2))));
+
+        assert_eq!(result.unwrap().to_string(), "(1, 2)");
+        assert_eq!(output, "1\n(1, 2)\n");
+    }
+
+    #[test]
+    fn scripted_input() {
+        let mut interpreter =
+            Interpreter::new(DummyIO::default()).with_input(std::io::Cursor::new("rin\r\nha\n"));
+
+        let value = interpreter
+            .eval(
+                add(
+                    call(var("read_line"), vec![]),
+                    call(var("read_line"), vec![]),
+                ),
+                &mut Context::new(),
+            )
+            .unwrap();
+        assert_eq!(value.to_string(), "rinha");
+
+        let error = interpreter
+            .eval(call(var("read_line"), vec![]), &mut Context::new())
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::NativeFailed);
+    }
+
+    #[test]
+    fn reads_integers_and_the_rest() {
+        let mut interpreter = Interpreter::new(DummyIO::default())
+            .with_input(std::io::Cursor::new(" 12\n-3   4\n\nrest of\nthe input\n"));
+        let mut read = |native: &str| {
+            interpreter
+                .eval(call(var(native), vec![]), &mut Context::new())
+                .map(|value| value.to_string())
+                .map_err(|error| error.code)
+        };
+
+        assert_eq!(read("read_int"), Ok(String::from("12")));
+        assert_eq!(read("read_int"), Ok(String::from("-3")));
+        assert_eq!(read("read_int"), Ok(String::from("4")));
+        assert_eq!(read("read_int"), Err(ErrorCode::NativeFailed));
+        // The word that is not an integer is read all the same.
+        assert_eq!(read("read_all"), Ok(String::from(" of\nthe input\n")));
+        assert_eq!(read("read_all"), Ok(String::new()));
+        assert_eq!(read("read_int"), Err(ErrorCode::NativeFailed));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn serialize_value() {
+        let tuple = v_tuple(v_int(1), Value::Str("a".into()));
+
+        assert_eq!(
+            serde_json::to_string(&tuple).unwrap(),
+            r#"{"kind":"Tuple","first":{"kind":"Int","value":1},"second":{"kind":"Str","value":"a"}}"#
+        );
     }
 }
@@ -0,0 +1,264 @@
+use std::{cell::RefCell, fmt::Write, rc::Rc};
+
+use super::{CallFn, Interpreter, Native, NativeFn, NativeFunction, Printer, Tuple, Value};
+
+/// Name of the functions made by `builder`, which `push` and `build` take.
+static BUILDER: &str = "string builder";
+
+impl<I: Printer> Interpreter<I> {
+    /// Registers the builtins of the standard library, besides `read_line`.
+    pub(super) fn register_builtins(&mut self) {
+        self.register_native("builder", |_arguments| Ok(builder()));
+        self.register_native("push", push);
+        self.register_native("build", |arguments| match arguments {
+            [builder] => {
+                buffer(builder).ok_or_else(|| String::from("expects a string builder"))?(&[])
+            }
+            _ => Err(String::from("expects a string builder")),
+        });
+        self.register_higher_order("sort", sort);
+        self.register_native("len", |arguments| match arguments {
+            [value] => len(value),
+            _ => Err(String::from("expects the string or the list to measure")),
+        });
+    }
+}
+
+/// The function of the string builder `value`, if it is one.
+fn buffer(value: &Value) -> Option<&NativeFn> {
+    match value {
+        Value::Native(Native {
+            name,
+            function: NativeFunction::Plain(function),
+        }) if name == BUILDER => Some(function.as_ref()),
+        _ => None,
+    }
+}
+
+/// A string builder: a native function appending the strings and integers
+/// it is called with to a buffer, which it returns when called without
+/// arguments. Pushing a piece copies only the piece, unlike joining strings
+/// with `+` over and over, which copies the text again once read.
+fn builder() -> Value {
+    let buffer = Rc::new(RefCell::new(String::new()));
+
+    Value::Native(Native {
+        name: String::from(BUILDER),
+        function: NativeFunction::Plain(Rc::new(move |arguments: &[Value]| {
+            let mut buffer = buffer.borrow_mut();
+
+            if arguments.is_empty() {
+                return Ok(Value::Str(buffer.as_str().into()));
+            }
+
+            for argument in arguments {
+                match argument {
+                    Value::Str(str) => buffer.push_str(&str[..]),
+                    Value::Int(int) => write!(buffer, "{int}").expect("writing to a string"),
+                    value => return Err(format!("can't push {}", value.inspect())),
+                }
+            }
+
+            Ok(Value::Bool(true))
+        })),
+    })
+}
+
+/// `push(b, s)`, appending `s` to the string builder `b` and returning `b`,
+/// so pushes can be chained.
+fn push(arguments: &[Value]) -> Result<Value, String> {
+    match arguments {
+        [builder, piece] => {
+            let function =
+                buffer(builder).ok_or_else(|| String::from("expects a string builder"))?;
+            function(std::slice::from_ref(piece))?;
+
+            Ok(builder.clone())
+        }
+        _ => Err(String::from(
+            "expects a string builder and the string to push",
+        )),
+    }
+}
+
+/// `sort(list, before)`, the elements of `list` in the order of `before`,
+/// called with two of them and returning whether the first one goes before
+/// the second. The sort is stable, elements that go neither before nor after
+/// each other are left in the order they were in.
+///
+/// Lists are tuples nested in the second element of each other, ended by any
+/// other value, which ends the sorted list too.
+fn sort(arguments: &[Value], call: &mut CallFn) -> Result<Value, String> {
+    let [list, before] = arguments else {
+        return Err(String::from(
+            "expects a list and the function telling which elements go first",
+        ));
+    };
+
+    let mut elements = Vec::new();
+    let mut end = list;
+
+    while let Value::Tuple(tuple) = end {
+        elements.push(Value::clone(&tuple.first));
+        end = &tuple.second;
+    }
+
+    let mut before = |a: &Value, b: &Value| match call(before, vec![a.clone(), b.clone()])? {
+        Value::Bool(bool) => Ok(bool),
+        value => Err(format!(
+            "the comparison returned {}, not a boolean",
+            value.inspect()
+        )),
+    };
+
+    let sorted = merge_sort(elements, &mut before)?;
+
+    Ok(sorted.into_iter().rev().fold(end.clone(), |list, element| {
+        Value::Tuple(Tuple {
+            first: Box::new(element),
+            second: Box::new(list),
+        })
+    }))
+}
+
+/// Sorts `elements` by merging sorted halves, taking from the second half
+/// only the elements that go before the ones left of the first, which keeps
+/// the sort stable whatever `before` answers.
+fn merge_sort(
+    mut elements: Vec<Value>,
+    before: &mut impl FnMut(&Value, &Value) -> Result<bool, String>,
+) -> Result<Vec<Value>, String> {
+    if elements.len() < 2 {
+        return Ok(elements);
+    }
+
+    let second = elements.split_off(elements.len() / 2);
+    let mut first = merge_sort(elements, before)?.into_iter().peekable();
+    let mut second = merge_sort(second, before)?.into_iter().peekable();
+    let mut merged = Vec::with_capacity(first.len() + second.len());
+
+    while let (Some(a), Some(b)) = (first.peek(), second.peek()) {
+        let next = match before(b, a)? {
+            true => second.next(),
+            false => first.next(),
+        };
+
+        merged.extend(next);
+    }
+
+    merged.extend(first);
+    merged.extend(second);
+
+    Ok(merged)
+}
+
+/// `len(x)`, how many characters the string `x` has, or how many elements
+/// the list `x` has, counting the tuples nested in the second element of
+/// each other.
+fn len(value: &Value) -> Result<Value, String> {
+    let len = match value {
+        Value::Str(str) => str.chars().count(),
+        Value::Tuple(_) => {
+            let mut len = 0;
+            let mut list = value;
+
+            while let Value::Tuple(tuple) = list {
+                len += 1;
+                list = &tuple.second;
+            }
+
+            len
+        }
+        value => {
+            return Err(format!(
+                "{} has no length, only strings and lists do",
+                value.inspect()
+            ))
+        }
+    };
+
+    i64::try_from(len)
+        .map(Value::Int)
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::run, messages::ErrorCode};
+
+    #[test]
+    fn builds_strings() {
+        assert_eq!(
+            run("let b = builder();
+                let loop = fn (n) => { if (n == 3) { b } else { let _ = push(b, n); loop(n + 1) } };
+                build(push(push(loop(0), \", \"), \"go\"))")
+            .0,
+            Ok(String::from("012, go"))
+        );
+        assert_eq!(run("build(builder())").0, Ok(String::new()));
+    }
+
+    #[test]
+    fn builders_are_separate() {
+        assert_eq!(
+            run("let a = builder(); let b = builder(); let _ = push(a, \"a\"); (build(a), build(b))").0,
+            Ok(String::from("(a, )"))
+        );
+    }
+
+    #[test]
+    fn rejects_other_values() {
+        assert_eq!(run("push(builder(), true)").0, Err(ErrorCode::NativeFailed));
+        assert_eq!(
+            run("push(read_line, \"a\")").0,
+            Err(ErrorCode::NativeFailed)
+        );
+        assert_eq!(run("build(\"a\")").0, Err(ErrorCode::NativeFailed));
+    }
+
+    #[test]
+    fn lengths() {
+        assert_eq!(run("len(\"rinhá\")").0, Ok(String::from("5")));
+        assert_eq!(run("len(\"\")").0, Ok(String::from("0")));
+        assert_eq!(run("len((1, (2, (3, 0))))").0, Ok(String::from("3")));
+        assert_eq!(run("len(((1, 2), 3))").0, Ok(String::from("1")));
+
+        for value in ["1", "true", "fn () => { 1 }", "len"] {
+            assert_eq!(
+                run(&format!("len({value})")).0,
+                Err(ErrorCode::NativeFailed),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn sorts_stably() {
+        assert_eq!(
+            run("sort((3, (1, (2, 0))), fn (a, b) => { a < b })").0,
+            Ok(String::from("(1, (2, (3, 0)))"))
+        );
+        // Pairs sorted by their first element keep the order of their second.
+        assert_eq!(
+            run("sort(((2, 1), ((1, 2), ((2, 3), ((1, 4), false)))), fn (a, b) => { first(a) < first(b) })").0,
+            Ok(String::from("((1, 2), ((1, 4), ((2, 1), ((2, 3), false))))"))
+        );
+        assert_eq!(
+            run("sort(0, fn (a, b) => { true })").0,
+            Ok(String::from("0"))
+        );
+    }
+
+    #[test]
+    fn comparisons_fail() {
+        assert_eq!(
+            run("sort((1, (0, 0)), fn (a, b) => { 1 / a < 1 })").0,
+            Err(ErrorCode::DivisionByZero)
+        );
+        assert_eq!(
+            run("sort((1, (0, 0)), fn (a, b) => { a })").0,
+            Err(ErrorCode::NativeFailed)
+        );
+        assert_eq!(run("sort((1, 0))").0, Err(ErrorCode::NativeFailed));
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt::{self, Display};
+
+use super::Value;
+
+/// How much of a value [`Inspect`] shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InspectLimits {
+    /// Tuples nested deeper than this in the first element of others are
+    /// shown as `(...)`.
+    pub max_depth: usize,
+    /// Elements shown of a list, the tuples nested in the second element of
+    /// others, before `...`.
+    pub max_length: usize,
+    /// Characters shown of a string before `...`.
+    pub max_string: usize,
+}
+
+impl Default for InspectLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 6,
+            max_length: 20,
+            max_string: 256,
+        }
+    }
+}
+
+impl InspectLimits {
+    /// Sets the limit called `name`, `depth`, `length` or `string`, to
+    /// `value`, for the `set` commands of the debugger and the REPL.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let value = value
+            .parse::<usize>()
+            .ok()
+            .filter(|value| *value > 0)
+            .ok_or_else(|| format!("\"{value}\" is not a positive number"))?;
+
+        match name {
+            "depth" => self.max_depth = value,
+            "length" => self.max_length = value,
+            "string" => self.max_string = value,
+            _ => {
+                return Err(format!(
+                    "unknown limit \"{name}\", use depth, length or string"
+                ))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for InspectLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "depth {}, length {}, string {}",
+            self.max_depth, self.max_length, self.max_string
+        )
+    }
+}
+
+/// A value as the [`Display`] of [`Value`] writes it, cut with `...` past
+/// its [`InspectLimits`], so that a huge list doesn't flood the debugger,
+/// the REPL or an error message. See [`Value::inspect`].
+#[derive(Debug, Clone, Copy)]
+pub struct Inspect<'a> {
+    value: &'a Value,
+    limits: InspectLimits,
+}
+
+impl Value {
+    /// The value cut past the default [`InspectLimits`].
+    pub fn inspect(&self) -> Inspect<'_> {
+        Inspect {
+            value: self,
+            limits: InspectLimits::default(),
+        }
+    }
+}
+
+impl Inspect<'_> {
+    pub fn with_limits(mut self, limits: InspectLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, value: &Value, depth: usize) -> fmt::Result {
+        match value {
+            Value::Tuple(_) if depth >= self.limits.max_depth => f.write_str("(...)"),
+            Value::Tuple(tuple) => {
+                // The tuples of a list are shown nested as they are, but
+                // count for its length rather than for the depth.
+                let mut tuple = tuple;
+                let mut opened = 0;
+
+                loop {
+                    f.write_str("(")?;
+                    opened += 1;
+                    self.write(f, &tuple.first, depth + 1)?;
+                    f.write_str(", ")?;
+
+                    match &*tuple.second {
+                        Value::Tuple(_) if opened >= self.limits.max_length => {
+                            f.write_str("...")?;
+                            break;
+                        }
+                        Value::Tuple(next) => tuple = next,
+                        second => {
+                            self.write(f, second, depth + 1)?;
+                            break;
+                        }
+                    }
+                }
+
+                for _ in 0..opened {
+                    f.write_str(")")?;
+                }
+
+                Ok(())
+            }
+            Value::Str(str) => match str.char_indices().nth(self.limits.max_string) {
+                Some((end, _)) => write!(f, "{}...", &str[..end]),
+                None => f.write_str(str),
+            },
+            value => write!(f, "{value}"),
+        }
+    }
+}
+
+impl Display for Inspect<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, self.value, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{evaluate, Interpreter, Value, IO};
+
+    use super::InspectLimits;
+
+    fn value(source: &str) -> Value {
+        evaluate(&mut Interpreter::new(IO), source).unwrap()
+    }
+
+    static LIST: &str = "\
+let range = fn (from, to) => { if (from == to) { 0 } else { (from, range(from + 1, to)) } };";
+
+    #[test]
+    fn cuts_long_lists() {
+        let list = value(&format!("{LIST} range(0, 1000)"));
+        let limits = InspectLimits {
+            max_length: 3,
+            ..InspectLimits::default()
+        };
+
+        assert_eq!(
+            list.inspect().with_limits(limits).to_string(),
+            "(0, (1, (2, ...)))"
+        );
+        assert_eq!(
+            value(&format!("{LIST} range(0, 3)"))
+                .inspect()
+                .with_limits(limits)
+                .to_string(),
+            "(0, (1, (2, 0)))"
+        );
+        assert!(list.inspect().to_string().len() < 200);
+    }
+
+    #[test]
+    fn cuts_deep_tuples_and_long_strings() {
+        let limits = InspectLimits {
+            max_depth: 2,
+            max_string: 4,
+            ..InspectLimits::default()
+        };
+        let deep = value("((((1, 2), 3), 4), \"abcdefgh\")");
+
+        assert_eq!(
+            deep.inspect().with_limits(limits).to_string(),
+            "(((...), 4), abcd...)"
+        );
+        assert_eq!(deep.inspect().to_string(), "((((1, 2), 3), 4), abcdefgh)");
+    }
+
+    #[test]
+    fn sets_limits() {
+        let mut limits = InspectLimits::default();
+
+        limits.set("depth", "3").unwrap();
+        limits.set("length", "4").unwrap();
+        assert_eq!(limits.to_string(), "depth 3, length 4, string 256");
+        assert!(limits.set("depth", "0").is_err());
+        assert!(limits.set("width", "3").is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use std::{collections::HashMap, mem::size_of};
+
+use crate::{ast::Location, messages::ErrorCode};
+
+use super::{Interpreter, Printer, RuntimeError, Value};
+
+/// How many bytes can be held when no maximum is given, which leaves room
+/// for the syntax tree and the allocator within the 2 GB of the containers of
+/// the competition, so programs fail with a [`RuntimeError`] instead of being
+/// killed by the system.
+static DEFAULT_MAX_MEMORY: usize = 1536 << 20;
+
+/// Approximate number of bytes held by an interpreter, see
+/// [`Interpreter::memory_stats`].
+///
+/// Closures share the bindings they captured, which are not counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Bindings of the calls being evaluated.
+    pub contexts: usize,
+    /// Results of the memoized calls.
+    pub cache: usize,
+    /// Most bytes held at once since the interpreter was created.
+    pub peak: usize,
+}
+
+impl MemoryStats {
+    /// Bytes held now.
+    pub fn total(&self) -> usize {
+        self.contexts + self.cache
+    }
+}
+
+impl Value {
+    /// Approximate number of bytes used by the value. Lists are walked in a
+    /// loop, they can be longer than the stack allows recursing.
+    pub(super) fn size(&self) -> usize {
+        let mut size = 0;
+        let mut value = self;
+
+        loop {
+            size += size_of::<Value>();
+
+            match value {
+                Value::Str(str) => size += str.len(),
+                Value::Tuple(tuple) => {
+                    size += tuple.first.size();
+                    value = &tuple.second;
+                    continue;
+                }
+                _ => (),
+            }
+
+            return size;
+        }
+    }
+}
+
+/// Approximate number of bytes used by the name of a binding, or the key of
+/// a cache entry.
+pub(super) fn name_size(name: &str) -> usize {
+    size_of::<String>() + name.len()
+}
+
+pub(super) fn context_size<K: AsRef<str>>(context: &HashMap<K, Value>) -> usize {
+    context
+        .iter()
+        .map(|(name, value)| name_size(name.as_ref()) + value.size())
+        .sum()
+}
+
+impl<I: Printer> Interpreter<I> {
+    /// Approximate memory held by the evaluation in progress and by the
+    /// memoization cache.
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.memory
+    }
+
+    /// Aborts the evaluation once it holds more than about `bytes` bytes,
+    /// counting the memoization cache, instead of 1.5 GB.
+    pub fn with_max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = Some(bytes);
+
+        self
+    }
+
+    /// Starts counting the memory of a new evaluation. The cache is counted
+    /// again, since the host may have changed it since the last one.
+    pub(super) fn reset_memory(&mut self) {
+        self.memory.contexts = 0;
+        self.memory.cache = context_size(&self.cache);
+        self.memory.peak = self.memory.peak.max(self.memory.total());
+    }
+
+    pub(super) fn allocate(&mut self, bytes: usize) {
+        self.stats.allocations += 1;
+        self.memory.contexts += bytes;
+        self.memory.peak = self.memory.peak.max(self.memory.total());
+    }
+
+    pub(super) fn free(&mut self, bytes: usize) {
+        self.memory.contexts = self.memory.contexts.saturating_sub(bytes);
+    }
+
+    /// Keeps the result of a call, failing once the cache holds more than
+    /// the limit, since results are memoized when calls return, after the
+    /// limit was checked when they started.
+    pub(super) fn memoize(
+        &mut self,
+        key: String,
+        value: Value,
+        location: &Location,
+    ) -> Result<(), RuntimeError> {
+        let key_size = name_size(&key);
+        self.memory.cache += key_size + value.size();
+        self.memory.peak = self.memory.peak.max(self.memory.total());
+
+        if let Some(old) = self.cache.insert(key, value) {
+            self.memory.cache = self.memory.cache.saturating_sub(key_size + old.size());
+        }
+
+        self.check_memory(location)
+    }
+
+    pub(super) fn check_memory(&self, location: &Location) -> Result<(), RuntimeError> {
+        let max_memory = self.max_memory.unwrap_or(DEFAULT_MAX_MEMORY);
+
+        if self.memory.total() > max_memory {
+            return Err(RuntimeError::new(
+                ErrorCode::MemoryLimit,
+                vec![max_memory.to_string()],
+                location.clone(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{evaluate, Collect, Interpreter},
+        messages::ErrorCode,
+    };
+
+    /// Builds a string of `n` characters, one call per character.
+    static REPEAT: &str = r#"
+        let repeat = fn (n) => {
+            if (n == 0) { "" } else { "x" + repeat(n - 1) }
+        };
+    "#;
+
+    #[test]
+    fn stats() {
+        let source = format!("{REPEAT} repeat(1000)");
+        let mut interpreter = Interpreter::new(Collect::default()).with_memoization(false);
+
+        evaluate(&mut interpreter, &source).unwrap();
+
+        let stats = interpreter.memory_stats();
+        assert_eq!(stats.contexts, 0);
+        assert_eq!(stats.cache, 0);
+        assert!(stats.peak > 1000 * 64, "{stats:?}");
+    }
+
+    #[cfg(feature = "memo")]
+    #[test]
+    fn cache() {
+        let source = format!("{REPEAT} repeat(10)");
+        let mut interpreter = Interpreter::new(Collect::default());
+
+        evaluate(&mut interpreter, &source).unwrap();
+
+        assert!(interpreter.memory_stats().cache > 0);
+    }
+
+    #[test]
+    fn max_memory() {
+        let source = format!("{REPEAT} repeat(100000)");
+        let mut interpreter = Interpreter::new(Collect::default()).with_max_memory(1 << 20);
+
+        let error = evaluate(&mut interpreter, &source).unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::MemoryLimit);
+        assert_eq!(interpreter.memory_stats().contexts, 0);
+    }
+
+    #[cfg(feature = "memo")]
+    #[test]
+    fn max_memory_of_the_cache() {
+        // No call is made while the lists are memoized, one per call, as the
+        // calls return.
+        let source = "let range = fn (n) => { if (n == 0) { 0 } else { (n, range(n - 1)) } };
+            range(4000)";
+        let mut interpreter = Interpreter::new(Collect::default()).with_max_memory(1 << 20);
+
+        let error = evaluate(&mut interpreter, source).unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::MemoryLimit);
+        assert!(interpreter.memory_stats().peak < 2 << 20);
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt::{self, Display};
+
+use super::Value;
+
+/// A value as `print` writes it, in the exact forms of the specification,
+/// see [`Value::printed`].
+///
+/// The [`Display`] of [`Value`] is for diagnostics and tells natives and
+/// continuations apart, while every function is printed as `<#closure>`,
+/// so the output of a program is the one of the reference interpreter.
+#[derive(Debug, Clone, Copy)]
+pub struct Printed<'a>(&'a Value);
+
+impl Value {
+    /// The value as `print` writes it.
+    pub fn printed(&self) -> Printed<'_> {
+        Printed(self)
+    }
+}
+
+impl Display for Printed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Value::Int(int) => write!(f, "{int}"),
+            Value::Str(str) => f.write_str(str),
+            Value::Bool(true) => f.write_str("true"),
+            Value::Bool(false) => f.write_str("false"),
+            Value::Tuple(tuple) => {
+                write!(f, "({}, {})", tuple.first.printed(), tuple.second.printed())
+            }
+            Value::Closure(_) | Value::Native(_) => f.write_str("<#closure>"),
+            #[cfg(feature = "callcc")]
+            Value::Callcc(_) => f.write_str("<#closure>"),
+            #[cfg(feature = "generators")]
+            Value::Generator(_) => f.write_str("<#closure>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{run_with, Collect, Interpreter};
+
+    fn printed(source: &str) -> Vec<String> {
+        let mut interpreter = Interpreter::new(Collect::default());
+        interpreter.register_native("id", |arguments| Ok(arguments[0].clone()));
+        let (result, printed) = run_with(interpreter, source);
+        result.unwrap();
+
+        printed
+    }
+
+    #[test]
+    fn nested_tuples() {
+        assert_eq!(
+            printed("print(((1, \"a b\"), (true, (false, 0 - 2))))"),
+            vec!["((1, a b), (true, (false, -2)))"]
+        );
+    }
+
+    #[test]
+    fn functions_are_closures() {
+        assert_eq!(
+            printed("let _ = print((1, fn (x) => { x })); print(id)"),
+            vec!["(1, <#closure>)", "<#closure>"]
+        );
+    }
+}
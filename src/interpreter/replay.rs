@@ -0,0 +1,327 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use super::{Interpreter, NativeFn, Printer, Tuple, Value};
+
+/// Bytes every trace file starts with, followed by its version.
+static MAGIC: &[u8; 8] = b"RINHATRC";
+static VERSION: u8 = 1;
+
+/// A call to a native function and what it answered.
+#[derive(Debug, Clone)]
+pub(super) struct Entry {
+    native: String,
+    result: Result<Value, String>,
+}
+
+/// The answers of the native functions called by a run, like the lines read
+/// by `read_line`, which are the only inputs of a program that can change
+/// from one run to the other.
+///
+/// A run recorded with [`Interpreter::with_recording`] is reproduced by
+/// [`Interpreter::with_replay`], which answers the same calls the same way
+/// without calling the natives.
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    entries: Vec<Entry>,
+}
+
+/// What the interpreter does with the calls to native functions.
+pub(super) enum Tape {
+    Record(Trace),
+    Replay(std::vec::IntoIter<Entry>),
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_str(writer: &mut impl Write, str: &str) -> io::Result<()> {
+    write_u64(writer, str.len() as u64)?;
+    writer.write_all(str.as_bytes())
+}
+
+fn write_value(writer: &mut impl Write, value: &Value) -> io::Result<()> {
+    match value {
+        Value::Int(int) => {
+            writer.write_all(&[0])?;
+            writer.write_all(&int.to_le_bytes())
+        }
+        Value::Str(str) => {
+            writer.write_all(&[1])?;
+            write_str(writer, str)
+        }
+        Value::Bool(bool) => writer.write_all(&[2, u8::from(*bool)]),
+        Value::Tuple(tuple) => {
+            writer.write_all(&[3])?;
+            write_value(writer, &tuple.first)?;
+            write_value(writer, &tuple.second)
+        }
+        Value::Closure(_) | Value::Native(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "functions answered by native functions can't be recorded",
+        )),
+        #[cfg(feature = "callcc")]
+        Value::Callcc(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "functions answered by native functions can't be recorded",
+        )),
+        #[cfg(feature = "generators")]
+        Value::Generator(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "generators answered by native functions can't be recorded",
+        )),
+    }
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+
+    Ok(byte[0])
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_str(reader: &mut impl Read) -> io::Result<String> {
+    let length = read_u64(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(length).read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 != length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid("string of the trace is not UTF-8"))
+}
+
+fn read_value(reader: &mut impl Read) -> io::Result<Value> {
+    Ok(match read_u8(reader)? {
+        0 => Value::Int(read_u64(reader)? as i64),
+        1 => Value::Str(read_str(reader)?.into()),
+        2 => Value::Bool(read_u8(reader)? != 0),
+        3 => Value::Tuple(Tuple {
+            first: Box::new(read_value(reader)?),
+            second: Box::new(read_value(reader)?),
+        }),
+        _ => return Err(invalid("unknown kind of value in the trace")),
+    })
+}
+
+impl Trace {
+    /// How many native calls were recorded.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        write_u64(writer, self.entries.len() as u64)?;
+
+        for entry in &self.entries {
+            write_str(writer, &entry.native)?;
+
+            match &entry.result {
+                Ok(value) => {
+                    writer.write_all(&[0])?;
+                    write_value(writer, value)?;
+                }
+                Err(message) => {
+                    writer.write_all(&[1])?;
+                    write_str(writer, message)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(reader: &mut impl Read) -> io::Result<Trace> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(invalid("not a trace file"));
+        }
+
+        if read_u8(reader)? != VERSION {
+            return Err(invalid("unsupported version of trace file"));
+        }
+
+        let count = read_u64(reader)?;
+        let mut entries = Vec::new();
+
+        for _ in 0..count {
+            let native = read_str(reader)?;
+            let result = match read_u8(reader)? {
+                0 => Ok(read_value(reader)?),
+                1 => Err(read_str(reader)?),
+                _ => return Err(invalid("unknown kind of answer in the trace")),
+            };
+
+            entries.push(Entry { native, result });
+        }
+
+        Ok(Trace { entries })
+    }
+
+    /// Writes the trace to `path`, in a binary format. Fails when a native
+    /// function answered with a function, which can't be recorded.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+
+        writer.flush()
+    }
+
+    /// Reads a trace written by [`Trace::save`].
+    pub fn load(path: &Path) -> io::Result<Trace> {
+        Trace::read(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+impl<I: Printer> Interpreter<I> {
+    /// Records the answers of the native functions called from now on, see
+    /// [`Interpreter::recording`].
+    pub fn with_recording(mut self) -> Self {
+        self.tape = Some(Tape::Record(Trace::default()));
+
+        self
+    }
+
+    /// Answers the calls to native functions with the ones of `trace`, in
+    /// order, without calling them. Calls that differ from the recorded
+    /// ones fail.
+    pub fn with_replay(mut self, trace: Trace) -> Self {
+        self.tape = Some(Tape::Replay(trace.entries.into_iter()));
+
+        self
+    }
+
+    /// The calls recorded since [`Interpreter::with_recording`].
+    pub fn recording(&self) -> Option<&Trace> {
+        match &self.tape {
+            Some(Tape::Record(trace)) => Some(trace),
+            _ => None,
+        }
+    }
+
+    pub(super) fn call_native(
+        &mut self,
+        name: &str,
+        function: &NativeFn,
+        arguments: &[Value],
+    ) -> Result<Value, String> {
+        match &mut self.tape {
+            None => function(arguments),
+            Some(Tape::Record(trace)) => {
+                let result = function(arguments);
+
+                trace.entries.push(Entry {
+                    native: name.to_string(),
+                    result: result.clone(),
+                });
+
+                result
+            }
+            Some(Tape::Replay(entries)) => match entries.next() {
+                Some(entry) if entry.native == name => entry.result,
+                Some(entry) => Err(format!(
+                    "the replayed run called \"{}\" here instead",
+                    entry.native
+                )),
+                None => Err(String::from("the replayed run made no more native calls")),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        interpreter::{Collect, Context, Interpreter},
+        messages::ErrorCode,
+        parser::parse,
+    };
+
+    use super::Trace;
+
+    static SOURCE: &str = r#"
+        let name = read_line();
+        let greeting = "hello " + name;
+        print(greeting)
+    "#;
+
+    fn run(interpreter: Interpreter<Collect>) -> Interpreter<Collect> {
+        let mut interpreter = interpreter;
+        let file = parse(SOURCE, "tests").unwrap();
+
+        interpreter
+            .eval(file.expression, &mut Context::new())
+            .unwrap();
+
+        interpreter
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let recorded = run(Interpreter::new(Collect::default())
+            .with_input(Cursor::new("world\n"))
+            .with_recording());
+
+        let trace = recorded.recording().unwrap();
+        assert_eq!(trace.len(), 1);
+
+        let mut bytes = Vec::new();
+        trace.write(&mut bytes).unwrap();
+        let trace = Trace::read(&mut bytes.as_slice()).unwrap();
+
+        // The replay doesn't read its input.
+        let replayed = run(Interpreter::new(Collect::default())
+            .with_input(Cursor::new("other\n"))
+            .with_replay(trace));
+
+        assert_eq!(replayed.io.0, vec!["hello world"]);
+        assert_eq!(replayed.io.0, recorded.io.0);
+    }
+
+    #[test]
+    fn diverging_replays() {
+        let file = parse(r#"read_line() + read_line()"#, "tests").unwrap();
+        let mut interpreter = Interpreter::new(Collect::default()).with_replay(Trace::default());
+
+        let error = interpreter
+            .eval(file.expression, &mut Context::new())
+            .unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::NativeFailed);
+    }
+
+    #[test]
+    fn invalid_files() {
+        let error = Trace::read(&mut &b"RINHATRX\x01"[..]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        let error = Trace::read(&mut &b"RINHATRC\x01\x05"[..]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
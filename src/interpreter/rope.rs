@@ -0,0 +1,282 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    rc::Rc,
+    sync::Arc,
+};
+
+/// Concatenations shorter than this are copied right away, since a node
+/// costs more than copying a few bytes.
+static FLAT: usize = 64;
+
+/// Fingerprints are polynomials in `BASE` modulo the prime `MODULUS`, so the
+/// one of a concatenation is computed from the ones of its pieces.
+static MODULUS: u64 = (1 << 61) - 1;
+static BASE: u64 = 1_000_003;
+
+/// Text of string values. Concatenating two ropes only links them, so
+/// building a string with `+` in a loop is linear instead of quadratic.
+/// The pieces are copied into a single string the first time the text is
+/// read, like when it is printed or compared, and the result is kept.
+#[derive(Clone)]
+pub struct Rope(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    /// Shared with the literal it comes from, if any, so evaluating string
+    /// literals doesn't copy them.
+    Flat(Arc<str>),
+    Concat(Rc<Node>),
+}
+
+struct Node {
+    len: usize,
+    fingerprint: u64,
+    flat: OnceCell<Arc<str>>,
+    /// Released once the node is flattened.
+    pieces: RefCell<Option<(Rope, Rope)>>,
+}
+
+impl Rope {
+    /// `left` followed by `right`.
+    pub fn concat(left: Rope, right: Rope) -> Rope {
+        let len = left.len() + right.len();
+
+        if right.is_empty() {
+            return left;
+        }
+        if left.is_empty() {
+            return right;
+        }
+        if len < FLAT {
+            return Rope(Repr::Flat(format!("{}{}", &*left, &*right).into()));
+        }
+
+        let fingerprint =
+            (multiply(left.fingerprint(), power(right.len())) + right.fingerprint()) % MODULUS;
+
+        Rope(Repr::Concat(Rc::new(Node {
+            len,
+            fingerprint,
+            flat: OnceCell::new(),
+            pieces: RefCell::new(Some((left, right))),
+        })))
+    }
+
+    /// Length in bytes, without flattening the rope.
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Repr::Flat(str) => str.len(),
+            Repr::Concat(node) => node.len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Hash of the text, without flattening the rope.
+    fn fingerprint(&self) -> u64 {
+        match &self.0 {
+            Repr::Flat(str) => str.bytes().fold(0, |fingerprint, byte| {
+                (multiply(fingerprint, BASE) + u64::from(byte) + 1) % MODULUS
+            }),
+            Repr::Concat(node) => node.fingerprint,
+        }
+    }
+
+    /// The text as a single shared string.
+    pub fn flatten(&self) -> Arc<str> {
+        match &self.0 {
+            Repr::Flat(str) => str.clone(),
+            Repr::Concat(node) => node.flatten().clone(),
+        }
+    }
+}
+
+fn multiply(lhs: u64, rhs: u64) -> u64 {
+    (u128::from(lhs) * u128::from(rhs) % u128::from(MODULUS)) as u64
+}
+
+/// `BASE` to the power of `exponent`.
+fn power(mut exponent: usize) -> u64 {
+    let (mut power, mut base) = (1, BASE);
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            power = multiply(power, base);
+        }
+        base = multiply(base, base);
+        exponent >>= 1;
+    }
+
+    power
+}
+
+impl Node {
+    fn flatten(&self) -> &Arc<str> {
+        self.flat.get_or_init(|| {
+            let mut text = String::with_capacity(self.len);
+            let mut pending = match self.pieces.borrow_mut().take() {
+                Some((left, right)) => vec![right, left],
+                None => vec![],
+            };
+
+            // Ropes built in loops are as deep as the loop is long, so they
+            // are walked without recursion.
+            while let Some(rope) = pending.pop() {
+                match &rope.0 {
+                    Repr::Flat(str) => text.push_str(str),
+                    Repr::Concat(node) => match (node.flat.get(), &*node.pieces.borrow()) {
+                        (Some(str), _) => text.push_str(str),
+                        (None, Some((left, right))) => {
+                            pending.push(right.clone());
+                            pending.push(left.clone());
+                        }
+                        (None, None) => unreachable!("nodes keep their pieces until flattened"),
+                    },
+                }
+            }
+
+            text.into()
+        })
+    }
+}
+
+impl Drop for Node {
+    /// Drops the nodes only held by this one without recursion, which would
+    /// overflow the stack for deep ropes.
+    fn drop(&mut self) {
+        let mut pending = match self.pieces.get_mut().take() {
+            Some((left, right)) => vec![left, right],
+            None => return,
+        };
+
+        while let Some(rope) = pending.pop() {
+            if let Repr::Concat(node) = rope.0 {
+                if let Ok(mut node) = Rc::try_unwrap(node) {
+                    if let Some((left, right)) = node.pieces.get_mut().take() {
+                        pending.push(left);
+                        pending.push(right);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Deref for Rope {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match &self.0 {
+            Repr::Flat(str) => str,
+            Repr::Concat(node) => node.flatten(),
+        }
+    }
+}
+
+impl From<Arc<str>> for Rope {
+    fn from(str: Arc<str>) -> Self {
+        Rope(Repr::Flat(str))
+    }
+}
+
+impl From<String> for Rope {
+    fn from(str: String) -> Self {
+        Rope(Repr::Flat(str.into()))
+    }
+}
+
+impl From<&str> for Rope {
+    fn from(str: &str) -> Self {
+        Rope(Repr::Flat(str.into()))
+    }
+}
+
+impl PartialEq for Rope {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && **self == **other
+    }
+}
+
+impl Eq for Rope {}
+
+impl PartialOrd for Rope {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rope {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+/// Ropes are hashed by their fingerprint, so hashing the arguments of calls,
+/// which happens on every call, doesn't flatten them.
+impl Hash for Rope {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        self.fingerprint().hash(state);
+    }
+}
+
+impl Display for Rope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl Debug for Rope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "json")]
+impl serde::Serialize for Rope {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rope, FLAT};
+
+    #[test]
+    fn concat() {
+        let mut rope = Rope::from("");
+        let piece = "x".repeat(100);
+
+        for _ in 0..10 {
+            rope = Rope::concat(rope, Rope::from(piece.as_str()));
+        }
+
+        assert_eq!(rope.len(), 1000);
+        assert_eq!(&*rope, piece.repeat(10));
+        assert_eq!(rope, Rope::from(piece.repeat(10)));
+        assert_eq!(
+            rope.fingerprint(),
+            Rope::from(piece.repeat(10)).fingerprint()
+        );
+    }
+
+    #[test]
+    fn deep() {
+        // Long enough for every concatenation to make a node.
+        let piece = Rope::from("x".repeat(FLAT));
+        let rope = (0..200_000).fold(Rope::from(""), |rope, _| Rope::concat(rope, piece.clone()));
+
+        assert_eq!(rope.len(), 200_000 * FLAT);
+        assert!(rope.chars().all(|char| char == 'x'));
+        drop(rope);
+
+        let rope = (0..200_000).fold(Rope::from(""), |rope, _| Rope::concat(piece.clone(), rope));
+        drop(rope);
+    }
+}
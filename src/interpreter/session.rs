@@ -0,0 +1,1346 @@
+use std::{
+    cell::RefCell,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    rc::Rc,
+    sync::Arc,
+};
+
+use crate::{
+    ast::{Binary, Element, Location, Node, Term, Var},
+    effects,
+    messages::{ErrorCode, Lang},
+};
+
+use super::{
+    cache_key, memory, Closure, Context, Frame, HigherOrderFn, Interpreter, NativeFunction,
+    Printer, RuntimeError, Tuple, Value,
+};
+
+#[cfg(feature = "callcc")]
+mod callcc;
+mod fused;
+#[cfg(feature = "generators")]
+mod generator;
+
+#[cfg(feature = "callcc")]
+pub use callcc::{Callcc, Captured};
+#[cfg(feature = "generators")]
+pub use generator::{Generator, Routine};
+
+/// How many bounces [`Session::step`] runs by default.
+static DEFAULT_BOUNCES: usize = 10_000;
+
+/// How many contexts of returned calls are kept for the next calls to reuse.
+static FRAME_POOL: usize = 256;
+
+/// What is left to do with the value of the term being evaluated.
+#[cfg_attr(feature = "callcc", derive(Clone))]
+enum Continuation {
+    /// Binds the value and evaluates the rest of the `let`, binding it in
+    /// the closure too when its value is a function, so it can recurse.
+    Let {
+        name: Arc<str>,
+        next: Box<Term>,
+        recursive: bool,
+    },
+    /// Ends the scope of a `let` nested in another term, restoring the
+    /// binding it shadowed, so `((let a = 1; a), a)` doesn't see `a` twice.
+    Unbind {
+        name: Arc<str>,
+        shadowed: Option<Value>,
+    },
+    /// Picks a branch, `location` is the one of the condition.
+    If {
+        then: Box<Term>,
+        otherwise: Box<Term>,
+        location: Location,
+    },
+    /// Evaluates the right hand side of the operation.
+    Lhs(Binary),
+    /// Applies the operation, the value is the left hand side.
+    Rhs(Binary, Value),
+    /// Evaluates the arguments given to the callee.
+    Callee {
+        frame: Frame,
+        arguments: Vec<Term>,
+    },
+    /// Collects one more argument, calling the function after the last one.
+    Argument {
+        function: Value,
+        frame: Frame,
+        values: Vec<Value>,
+        rest: std::vec::IntoIter<Term>,
+    },
+    /// Leaves the body of a closure, memoizing its result with the key when
+    /// no native function was called since the count was taken.
+    Return {
+        frame: Frame,
+        memo: Option<(String, u64)>,
+        /// Key of the call among the active ones, and the count of native
+        /// calls of the call with the same key it hides, if any, when
+        /// memoization is disabled.
+        active: Option<(u64, Option<u64>)>,
+        /// State the call started in, when looking for infinite loops.
+        state: Option<u64>,
+        /// Bookkeeping of the calls that made this one as their tail call,
+        /// which return its value, outermost first.
+        replaced: Vec<Bookkeeping>,
+        /// Span of the call, left when the continuation is dropped.
+        #[cfg(feature = "tracing")]
+        _span: CallSpan,
+    },
+    TupleFirst(Box<Term>),
+    TupleSecond(Value),
+    First(Location),
+    Second(Location),
+    Print,
+    /// Tells the observers that the term at the location was evaluated.
+    Exit(Location),
+    /// Hands what the generator being resumed returns to the calls that
+    /// resumed it, `yield` suspends the generator up to here.
+    #[cfg(feature = "generators")]
+    Resumed {
+        routine: Rc<RefCell<generator::Routine>>,
+        caller: generator::Caller,
+    },
+}
+
+/// Span of a call, left when the continuation is dropped.
+#[cfg(feature = "tracing")]
+struct CallSpan(tracing::span::EnteredSpan);
+
+/// Copies of a call resumed by a continuation enter its span again.
+#[cfg(all(feature = "tracing", feature = "callcc"))]
+impl Clone for CallSpan {
+    fn clone(&self) -> Self {
+        CallSpan(tracing::Span::clone(&self.0).entered())
+    }
+}
+
+/// What is undone, and memoized, when a call returns. Calls replaced by
+/// their tail call keep only this, see [`Session::replace_frame`].
+#[cfg_attr(feature = "callcc", derive(Clone))]
+struct Bookkeeping {
+    memo: Option<(String, u64)>,
+    active: Option<(u64, Option<u64>)>,
+    state: Option<u64>,
+}
+
+impl Bookkeeping {
+    /// Approximate number of bytes held while the call is replaced, counted
+    /// so that endless tail calls still run out of memory.
+    fn size(&self) -> usize {
+        std::mem::size_of::<Bookkeeping>()
+            + self
+                .memo
+                .as_ref()
+                .map_or(0, |(key, _)| memory::name_size(key))
+    }
+}
+
+enum Control {
+    Eval(Term),
+    Value(Value),
+    Error(RuntimeError),
+    Done(Result<Value, RuntimeError>),
+}
+
+fn cancelled(control: &Control) -> RuntimeError {
+    let location = match control {
+        Control::Eval(term) => term.location().clone(),
+        _ => Location::default(),
+    };
+
+    RuntimeError::new(ErrorCode::Cancelled, vec![], location)
+}
+
+/// Outcome of a call to [`Session::step`].
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// The evaluation ran out of bounces, step again to continue it.
+    Pending,
+    Done(Value),
+    Failed(RuntimeError),
+}
+
+impl From<Result<Value, RuntimeError>> for Step {
+    fn from(result: Result<Value, RuntimeError>) -> Self {
+        match result {
+            Ok(value) => Step::Done(value),
+            Err(error) => Step::Failed(error),
+        }
+    }
+}
+
+/// An evaluation that runs a bounded amount of work at a time, so the host
+/// can interleave it with its own, like redrawing a UI or answering requests.
+///
+/// Every bounce is a small step of the evaluation, like reading a variable or
+/// entering a call, and the continuations are kept on the heap, so how deep a
+/// program nests is only bounded by [`Interpreter::with_max_depth`].
+pub struct Session<'a, I: Printer> {
+    interpreter: &'a mut Interpreter<I>,
+    control: Option<Control>,
+    continuations: Vec<Continuation>,
+    /// Bindings of every active call, the innermost last. The first one is
+    /// the context the session was started with.
+    contexts: Vec<Context>,
+    /// Count of native calls when each active call started, by closure and
+    /// arguments, see [`Session::repeated`].
+    active: HashMap<u64, u64>,
+    /// How many active calls started in each state, see
+    /// [`Interpreter::with_loop_detection`].
+    states: HashMap<u64, u32>,
+    /// Inline cache of every call site that memoized a call, by its span.
+    call_sites: HashMap<(usize, usize), CallSite>,
+    /// Emptied contexts of returned calls, whose storage is reused by the
+    /// next calls instead of allocating new ones.
+    pool: Vec<Context>,
+    bounces: usize,
+}
+
+/// The closure last called by a call site, remembered so the next calls to
+/// the same closure, the common case, don't hash its whole body again to
+/// compute their key in the memoization cache.
+struct CallSite {
+    /// Bindings of the closure, which are shared by its copies only. Holding
+    /// them keeps another closure from reusing their address.
+    closure: Rc<RefCell<Context>>,
+    /// Hasher that hashed the body, to hash the arguments with.
+    hasher: DefaultHasher,
+}
+
+/// Hashes `value` like its [`Hash`] implementation does, without
+/// formatting it, since it runs on every call.
+fn hash_value(value: &Value, hasher: &mut DefaultHasher) {
+    std::mem::discriminant(value).hash(hasher);
+
+    match value {
+        Value::Closure(closure) => Rc::as_ptr(&closure.context).hash(hasher),
+        Value::Native(native) => native.name.hash(hasher),
+        Value::Int(int) => int.hash(hasher),
+        Value::Str(str) => str.hash(hasher),
+        Value::Bool(bool) => bool.hash(hasher),
+        Value::Tuple(tuple) => {
+            hash_value(&tuple.first, hasher);
+            hash_value(&tuple.second, hasher);
+        }
+        #[cfg(feature = "callcc")]
+        Value::Callcc(callcc) => callcc.hash(hasher),
+        #[cfg(feature = "generators")]
+        Value::Generator(generator) => generator.hash(hasher),
+    }
+}
+
+/// Identifies a call by the closure, and the bindings it captured, and its
+/// arguments.
+fn call_key(closure: &Closure, arguments: &[Value]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    Rc::as_ptr(&closure.context).hash(&mut hasher);
+
+    for argument in arguments {
+        hash_value(argument, &mut hasher);
+    }
+
+    hasher.finish()
+}
+
+/// Hash of the bindings of `context`, whatever their order.
+fn bindings_key(context: &Context) -> u64 {
+    context.iter().fold(0u64, |sum, (name, value)| {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        hash_value(value, &mut hasher);
+
+        sum.wrapping_add(hasher.finish())
+    })
+}
+
+/// Identifies the state a call starts in by the body of the closure and the
+/// bindings it sees, whatever their order.
+fn state_key(closure: &Closure, context: &Context) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    closure.body.location().hash(&mut hasher);
+    bindings_key(context).hash(&mut hasher);
+
+    hasher.finish()
+}
+
+impl<'a, I: Printer> Session<'a, I> {
+    /// Starts evaluating `term` with the bindings of `context`, nothing is
+    /// evaluated until the session is stepped.
+    pub fn new(interpreter: &'a mut Interpreter<I>, term: Term, context: Context) -> Self {
+        interpreter.reset_memory();
+
+        Self {
+            interpreter,
+            control: Some(Control::Eval(term)),
+            continuations: Vec::new(),
+            contexts: vec![context],
+            active: HashMap::new(),
+            states: HashMap::new(),
+            call_sites: HashMap::new(),
+            pool: Vec::new(),
+            bounces: DEFAULT_BOUNCES,
+        }
+    }
+
+    /// Starts calling `function` with `arguments`.
+    pub(super) fn call(
+        interpreter: &'a mut Interpreter<I>,
+        function: Value,
+        arguments: Vec<Value>,
+        frame: Frame,
+    ) -> Self {
+        interpreter.reset_memory();
+
+        Self::inner(interpreter, function, arguments, frame)
+    }
+
+    /// Starts calling `function` with `arguments` on behalf of a session
+    /// already running, like a higher order native function does, whose
+    /// bindings still hold their memory.
+    fn inner(
+        interpreter: &'a mut Interpreter<I>,
+        function: Value,
+        arguments: Vec<Value>,
+        frame: Frame,
+    ) -> Self {
+        let mut session = Self {
+            interpreter,
+            control: None,
+            continuations: Vec::new(),
+            contexts: vec![Context::new()],
+            active: HashMap::new(),
+            states: HashMap::new(),
+            call_sites: HashMap::new(),
+            pool: Vec::new(),
+            bounces: DEFAULT_BOUNCES,
+        };
+
+        let control = match session.interpreter.check_limits(&frame.location) {
+            Ok(()) => session.apply(function, arguments, frame),
+            Err(error) => Control::Error(error),
+        };
+        session.control = Some(control);
+
+        session
+    }
+
+    /// Sets how many bounces each call to [`Session::step`] runs at most.
+    pub fn with_bounces(mut self, bounces: usize) -> Self {
+        self.bounces = bounces.max(1);
+
+        self
+    }
+
+    /// Advances the evaluation, returning [`Step::Pending`] when it is not
+    /// over yet. Once it is, every call returns its result again.
+    pub fn step(&mut self) -> Step {
+        self.run(self.bounces);
+
+        match &self.control {
+            Some(Control::Done(result)) => result.clone().into(),
+            _ => Step::Pending,
+        }
+    }
+
+    /// Whether the evaluation is over.
+    pub fn is_done(&self) -> bool {
+        matches!(self.control, Some(Control::Done(_)))
+    }
+
+    /// The term evaluated by the next bounce, if the next bounce evaluates a
+    /// term rather than using a value.
+    pub fn term(&self) -> Option<&Term> {
+        match &self.control {
+            Some(Control::Eval(term)) => Some(term),
+            _ => None,
+        }
+    }
+
+    /// Bindings of the innermost call being evaluated, or the ones the
+    /// session was started with outside of calls.
+    pub fn bindings(&self) -> &Context {
+        self.contexts
+            .last()
+            .expect("the session always has a context")
+    }
+
+    /// Calls being evaluated, innermost first.
+    pub fn stack(&self) -> impl Iterator<Item = &Frame> {
+        self.continuations
+            .iter()
+            .rev()
+            .filter_map(|continuation| match continuation {
+                Continuation::Return { frame, .. } => Some(frame),
+                _ => None,
+            })
+    }
+
+    /// The interpreter running the session, to look at its printer for
+    /// example.
+    pub fn interpreter(&self) -> &Interpreter<I> {
+        self.interpreter
+    }
+
+    /// Runs the evaluation to its end, returning its result along with the
+    /// context the session was started with, holding the bindings made by
+    /// the outermost `let`s.
+    pub fn finish(mut self) -> (Result<Value, RuntimeError>, Context) {
+        self.run(usize::MAX);
+
+        match self.control.take() {
+            Some(Control::Done(result)) => (result, self.contexts.swap_remove(0)),
+            _ => unreachable!("the evaluation is over"),
+        }
+    }
+
+    fn context(&mut self) -> &mut Context {
+        self.contexts
+            .last_mut()
+            .expect("the session always has a context")
+    }
+
+    /// The value bound to `var` in the innermost call, or among the globals
+    /// and the native functions.
+    fn lookup(&self, var: &Var) -> Result<Value, RuntimeError> {
+        self.contexts
+            .last()
+            .and_then(|context| context.get(&var.text))
+            .or_else(|| self.interpreter.globals.get(&var.text))
+            .or_else(|| self.interpreter.natives.get(&var.text))
+            .cloned()
+            .ok_or_else(|| {
+                RuntimeError::new(
+                    ErrorCode::UnboundVariable,
+                    vec![var.text.to_string()],
+                    var.location.clone(),
+                )
+            })
+    }
+
+    /// Runs at most `bounces` bounces, stopping early when the evaluation is
+    /// over.
+    fn run(&mut self, bounces: usize) {
+        let mut control = self.control.take().expect("the control is put back");
+
+        for _ in 0..bounces {
+            if let Some(token) = &self.interpreter.cancel {
+                if token.is_cancelled() && !matches!(control, Control::Done(_)) {
+                    control = Control::Error(cancelled(&control));
+                }
+            }
+
+            control = match control {
+                Control::Eval(term) => self.eval(term),
+                Control::Value(value) => match self.continuations.pop() {
+                    Some(continuation) => self.resume(continuation, value),
+                    None => Control::Done(Ok(value)),
+                },
+                Control::Error(error) => Control::Done(self.unwind(error)),
+                Control::Done(_) => break,
+            };
+        }
+
+        self.control = Some(control);
+    }
+
+    fn eval(&mut self, term: Term) -> Control {
+        self.interpreter.stats.terms += 1;
+
+        if !self.interpreter.observers.is_empty() {
+            for observer in &mut self.interpreter.observers {
+                observer.enter(&term);
+            }
+
+            self.continuations
+                .push(Continuation::Exit(term.location().clone()));
+        } else if self.interpreter.superinstructions {
+            return self.fused(term);
+        }
+
+        self.eval_term(term)
+    }
+
+    /// Evaluates `term` one bounce at a time, with a continuation for each
+    /// of its parts.
+    fn eval_term(&mut self, term: Term) -> Control {
+        match term.into_node() {
+            Node::Let(let_) => {
+                self.continuations.push(Continuation::Let {
+                    name: let_.name.text,
+                    next: let_.next,
+                    recursive: matches!(*let_.value, Term::Function(_)),
+                });
+
+                Control::Eval(*let_.value)
+            }
+            Node::Int(int) => Control::Value(Value::Int(int.value)),
+            Node::Str(str) => Control::Value(Value::Str(str.value.into())),
+            Node::Bool(bool) => Control::Value(Value::Bool(bool.value)),
+            Node::Function(function) => {
+                #[cfg(feature = "heap-profile")]
+                let _phase = crate::heap::enter(crate::heap::Phase::Context);
+
+                let context = self.context().clone();
+                let effects =
+                    effects::latent(&function, &|name| context.get(name).map(Value::effects));
+
+                Control::Value(Value::Closure(Closure {
+                    parameters: function.parameters.into(),
+                    body: Rc::new(*function.value),
+                    context: Rc::new(RefCell::new(context)),
+                    effects,
+                }))
+            }
+            Node::Call(call) => {
+                if let Err(error) = self.interpreter.check_limits(&call.location) {
+                    return Control::Error(error);
+                }
+
+                let frame = Frame {
+                    name: match call.callee.as_ref() {
+                        Term::Var(var) => var.text.to_string(),
+                        _ => String::from("<anonymous>"),
+                    },
+                    location: call.location,
+                };
+
+                self.continuations.push(Continuation::Callee {
+                    frame,
+                    arguments: call.arguments,
+                });
+
+                Control::Eval(*call.callee)
+            }
+            Node::If(if_) => {
+                self.continuations.push(Continuation::If {
+                    then: if_.then,
+                    otherwise: if_.otherwise,
+                    location: if_.condition.location().clone(),
+                });
+
+                Control::Eval(*if_.condition)
+            }
+            Node::Binary(binary) => {
+                let lhs = *binary.lhs.clone();
+                self.continuations.push(Continuation::Lhs(binary));
+
+                Control::Eval(lhs)
+            }
+            Node::Var(var) => match self.lookup(&var) {
+                Ok(value) => Control::Value(value),
+                Err(error) => Control::Error(error),
+            },
+            Node::Tuple(tuple) => {
+                self.continuations
+                    .push(Continuation::TupleFirst(tuple.second));
+
+                Control::Eval(*tuple.first)
+            }
+            Node::First(first) => {
+                self.continuations.push(Continuation::First(first.location));
+
+                Control::Eval(*first.value)
+            }
+            Node::Second(second) => {
+                self.continuations
+                    .push(Continuation::Second(second.location));
+
+                Control::Eval(*second.value)
+            }
+            Node::Print(print) => {
+                self.continuations.push(Continuation::Print);
+
+                Control::Eval(*print.value)
+            }
+        }
+    }
+
+    fn resume(&mut self, continuation: Continuation, value: Value) -> Control {
+        match continuation {
+            Continuation::Let {
+                name,
+                next,
+                recursive,
+            } => {
+                #[cfg(feature = "heap-profile")]
+                let _phase = crate::heap::enter(crate::heap::Phase::Context);
+
+                // Only the closure made by the `let` itself sees its name,
+                // others keep the bindings of where they were made.
+                if let (true, Value::Closure(closure)) = (recursive, &value) {
+                    closure
+                        .context
+                        .borrow_mut()
+                        .insert(name.clone(), value.clone());
+                }
+
+                let (name_size, value_size) = (memory::name_size(&name), value.size());
+                let shadowed = self.context().insert(name.clone(), value);
+
+                // The bindings of the context the session started with belong
+                // to the host, only the ones of calls are counted.
+                if self.contexts.len() > 1 {
+                    self.interpreter.allocate(name_size + value_size);
+
+                    if let Some(old) = &shadowed {
+                        self.interpreter.free(name_size + old.size());
+                    }
+                }
+
+                if self.nested() {
+                    self.continuations
+                        .push(Continuation::Unbind { name, shadowed });
+                }
+
+                Control::Eval(*next)
+            }
+            Continuation::Unbind { name, shadowed } => {
+                let name_size = memory::name_size(&name);
+                let restored = shadowed.as_ref().map(Value::size);
+                let bound = match shadowed {
+                    Some(old) => self.context().insert(name, old),
+                    None => self.context().remove(&name),
+                };
+
+                if self.contexts.len() > 1 {
+                    if let Some(bound) = bound {
+                        self.interpreter.free(name_size + bound.size());
+                    }
+
+                    if let Some(restored) = restored {
+                        self.interpreter.allocate(name_size + restored);
+                    }
+                }
+
+                Control::Value(value)
+            }
+            Continuation::If {
+                then,
+                otherwise,
+                location,
+            } => match value {
+                Value::Bool(true) => Control::Eval(*then),
+                Value::Bool(false) => Control::Eval(*otherwise),
+                value => Control::Error(RuntimeError::new(
+                    ErrorCode::InvalidIfCondition,
+                    vec![value.inspect().to_string()],
+                    location,
+                )),
+            },
+            Continuation::Lhs(binary) => {
+                let rhs = *binary.rhs.clone();
+                self.continuations.push(Continuation::Rhs(binary, value));
+
+                Control::Eval(rhs)
+            }
+            Continuation::Rhs(binary, lhs) => {
+                #[cfg(feature = "heap-profile")]
+                let _phase = crate::heap::enter(crate::heap::Phase::Values);
+
+                let location = binary.lhs.location();
+
+                match self.interpreter.operate(&lhs, &binary.op, &value, location) {
+                    Ok(value) => Control::Value(value),
+                    Err(error) => Control::Error(error),
+                }
+            }
+            Continuation::Callee {
+                frame,
+                mut arguments,
+            } => {
+                // Closures ignore the arguments given after their parameters.
+                let count = match &value {
+                    Value::Closure(closure) => closure.parameters.len(),
+                    Value::Native(_) => arguments.len(),
+                    #[cfg(feature = "callcc")]
+                    Value::Callcc(_) => 1,
+                    #[cfg(feature = "generators")]
+                    Value::Generator(_) => 1,
+                    _ => 0,
+                };
+
+                arguments.truncate(count);
+
+                #[cfg(feature = "heap-profile")]
+                let _phase = crate::heap::enter(crate::heap::Phase::Arguments);
+
+                let values = Vec::with_capacity(arguments.len());
+
+                self.next_argument(value, frame, values, arguments.into_iter())
+            }
+            Continuation::Argument {
+                function,
+                frame,
+                mut values,
+                rest,
+            } => {
+                #[cfg(feature = "heap-profile")]
+                let _phase = crate::heap::enter(crate::heap::Phase::Arguments);
+
+                values.push(value);
+
+                self.next_argument(function, frame, values, rest)
+            }
+            Continuation::Return {
+                frame,
+                memo,
+                active,
+                state,
+                replaced,
+                ..
+            } => {
+                self.leave();
+                let mut result = self.returned(
+                    Bookkeeping {
+                        memo,
+                        active,
+                        state,
+                    },
+                    &value,
+                    &frame.location,
+                );
+
+                for call in replaced.into_iter().rev() {
+                    self.interpreter.free(call.size());
+                    result = result.and(self.returned(call, &value, &frame.location));
+                }
+
+                match result {
+                    Ok(()) => Control::Value(value),
+                    Err(error) => Control::Error(error),
+                }
+            }
+            Continuation::TupleFirst(second) => {
+                self.continuations.push(Continuation::TupleSecond(value));
+
+                Control::Eval(*second)
+            }
+            Continuation::TupleSecond(first) => Control::Value(Value::Tuple(Tuple {
+                first: Box::new(first),
+                second: Box::new(value),
+            })),
+            Continuation::First(location) => match value {
+                Value::Tuple(tuple) => Control::Value(*tuple.first),
+                _value => {
+                    Control::Error(RuntimeError::new(ErrorCode::InvalidFirst, vec![], location))
+                }
+            },
+            Continuation::Second(location) => match value {
+                Value::Tuple(tuple) => Control::Value(*tuple.second),
+                _value => Control::Error(RuntimeError::new(
+                    ErrorCode::InvalidSecond,
+                    vec![],
+                    location,
+                )),
+            },
+            Continuation::Print => {
+                for observer in &mut self.interpreter.observers {
+                    observer.print(&value);
+                }
+
+                Control::Value(self.interpreter.io.print(value))
+            }
+            Continuation::Exit(location) => {
+                let result = Ok(value);
+
+                for observer in &mut self.interpreter.observers {
+                    observer.exit(&location, &result);
+                }
+
+                match result {
+                    Ok(value) => Control::Value(value),
+                    Err(error) => Control::Error(error),
+                }
+            }
+            #[cfg(feature = "generators")]
+            Continuation::Resumed { routine, caller } => self.returned_from(routine, caller, value),
+        }
+    }
+
+    fn next_argument(
+        &mut self,
+        function: Value,
+        frame: Frame,
+        values: Vec<Value>,
+        mut rest: std::vec::IntoIter<Term>,
+    ) -> Control {
+        match rest.next() {
+            Some(argument) => {
+                self.continuations.push(Continuation::Argument {
+                    function,
+                    frame,
+                    values,
+                    rest,
+                });
+
+                Control::Eval(argument)
+            }
+            None => self.apply(function, values, frame),
+        }
+    }
+
+    fn apply(&mut self, function: Value, arguments: Vec<Value>, frame: Frame) -> Control {
+        for observer in &mut self.interpreter.observers {
+            observer.call(&frame, &arguments);
+        }
+
+        match function {
+            Value::Closure(closure) => {
+                #[cfg(feature = "heap-profile")]
+                let phase = crate::heap::enter(crate::heap::Phase::Context);
+
+                let mut context = self.pool.pop().unwrap_or_default();
+                context.clone_from(&closure.context.borrow());
+
+                for (parameter, argument) in closure.parameters.iter().zip(&arguments) {
+                    context.insert(parameter.text.clone(), argument.clone());
+                }
+
+                #[cfg(feature = "heap-profile")]
+                drop(phase);
+
+                // Repeated calls are only looked for without memoization,
+                // sparing the calls hashing their arguments twice.
+                let memoization = cfg!(feature = "memo") && self.interpreter.memoization;
+                let active = (!memoization).then(|| call_key(&closure, &arguments));
+                let key = match self.interpreter.memoization && closure.effects.is_pure() {
+                    true => {
+                        #[cfg(feature = "heap-profile")]
+                        let _phase = crate::heap::enter(crate::heap::Phase::Cache);
+
+                        cache_key(self.body_hasher(&frame.location, &closure), arguments)
+                    }
+                    false => None,
+                };
+
+                let cached = key.as_ref().and_then(|key| {
+                    #[cfg(feature = "tracing")]
+                    let _span = tracing::trace_span!("memo", key).entered();
+
+                    self.interpreter.cache.get(key)
+                });
+
+                if let Some(value) = cached {
+                    self.interpreter.stats.memo_hits += 1;
+                    return Control::Value(value.clone());
+                }
+
+                if key.is_some() {
+                    self.interpreter.stats.memo_misses += 1;
+                }
+
+                if active.is_some_and(|active| self.repeated(active)) {
+                    return Control::Error(RuntimeError::new(
+                        ErrorCode::InfiniteRecursion,
+                        vec![frame.name],
+                        frame.location,
+                    ));
+                }
+
+                let state = match self.interpreter.loop_repeats() {
+                    Some(repeats) => {
+                        let state = state_key(&closure, &context);
+                        let count = self.states.entry(state).or_default();
+                        *count += 1;
+
+                        if *count >= repeats {
+                            return Control::Error(RuntimeError::new(
+                                ErrorCode::ProbableInfiniteLoop,
+                                vec![frame.name, repeats.to_string()],
+                                frame.location,
+                            ));
+                        }
+
+                        Some(state)
+                    }
+                    None => None,
+                };
+
+                let replaced = self.replace_frame();
+                let active = active.map(|active| {
+                    let hidden = self.active.insert(active, self.interpreter.native_calls);
+                    (active, hidden)
+                });
+
+                self.interpreter.depth += 1;
+                self.interpreter.stats.max_depth =
+                    self.interpreter.stats.max_depth.max(self.interpreter.depth);
+                self.interpreter.allocate(memory::context_size(&context));
+                self.contexts.push(context);
+                self.continuations.push(Continuation::Return {
+                    active,
+                    state,
+                    replaced,
+                    #[cfg(feature = "tracing")]
+                    _span: CallSpan(tracing::debug_span!("call", name = frame.name).entered()),
+                    frame,
+                    memo: key.map(|key| (key, self.interpreter.native_calls)),
+                });
+
+                Control::Eval(Term::clone(&closure.body))
+            }
+            Value::Native(native) => {
+                self.interpreter.native_calls += 1;
+
+                let result = match &native.function {
+                    NativeFunction::Plain(function) => {
+                        self.interpreter
+                            .call_native(&native.name, function.as_ref(), &arguments)
+                    }
+                    NativeFunction::HigherOrder(function) => {
+                        match self.call_higher_order(function.as_ref(), &arguments, &frame) {
+                            Ok(result) => result,
+                            Err(error) => return Control::Error(error),
+                        }
+                    }
+                };
+
+                match result {
+                    Ok(value) => Control::Value(value),
+                    Err(message) => Control::Error(RuntimeError::new(
+                        ErrorCode::NativeFailed,
+                        vec![native.name, message],
+                        frame.location,
+                    )),
+                }
+            }
+            #[cfg(feature = "callcc")]
+            Value::Callcc(callcc) => self.callcc(callcc, arguments, frame),
+            #[cfg(feature = "generators")]
+            Value::Generator(generator) => self.generator(generator, arguments, frame),
+            value => Control::Error(RuntimeError::new(
+                ErrorCode::InvalidCall,
+                vec![value.inspect().to_string()],
+                frame.location,
+            )),
+        }
+    }
+
+    /// Calls a higher order native function, evaluating the calls it makes
+    /// in sessions of their own. When one of them fails and the function
+    /// passes the failure on, the error of the call is the one returned.
+    fn call_higher_order(
+        &mut self,
+        function: &HigherOrderFn,
+        arguments: &[Value],
+        frame: &Frame,
+    ) -> Result<Result<Value, String>, RuntimeError> {
+        let interpreter = &mut *self.interpreter;
+        let mut failure = None;
+
+        let result = function(arguments, &mut |callee, arguments| {
+            let session = Session::inner(interpreter, callee.clone(), arguments, frame.clone());
+
+            session.finish().0.map_err(|error| {
+                let message = error.message(Lang::En);
+                failure = Some(error);
+
+                message
+            })
+        });
+
+        match (result, failure) {
+            (Err(_), Some(error)) => Err(error),
+            (result, _) => Ok(result),
+        }
+    }
+
+    /// Leaves the call being evaluated when the call being made is its last
+    /// term, so that tail calls, even to other functions, run in constant
+    /// stack. Returns the calls the new one replaces that still have to be
+    /// undone when it returns.
+    ///
+    /// The calls with no result to memoize, and no state to count, are undone
+    /// right away, so loops of tail calls also run in constant memory.
+    ///
+    /// Observers see every call return, since they wait for the terms that
+    /// make calls to be evaluated, so nothing is replaced while they watch.
+    fn replace_frame(&mut self) -> Vec<Bookkeeping> {
+        if !matches!(self.continuations.last(), Some(Continuation::Return { .. })) {
+            return Vec::new();
+        }
+
+        let Some(Continuation::Return {
+            memo,
+            active,
+            state,
+            mut replaced,
+            ..
+        }) = self.continuations.pop()
+        else {
+            unreachable!("the last continuation is a return");
+        };
+
+        self.leave();
+
+        let call = Bookkeeping {
+            // The calls replaced return the same result, memoized for the
+            // outermost one only, which is the one its caller made.
+            memo: memo.filter(|_| replaced.is_empty()),
+            active,
+            state,
+        };
+
+        if call.memo.is_none() && call.state.is_none() {
+            self.release(call);
+        } else {
+            self.interpreter.allocate(call.size());
+            replaced.push(call);
+        }
+
+        replaced
+    }
+
+    /// Undoes the bookkeeping of a call returning `value`, memoizing it when
+    /// the call was pure. Fails when the cache grows past the memory limit.
+    fn returned(
+        &mut self,
+        mut call: Bookkeeping,
+        value: &Value,
+        location: &Location,
+    ) -> Result<(), RuntimeError> {
+        let memo = call.memo.take();
+        self.release(call);
+
+        if let Some((key, native_calls)) = memo {
+            #[cfg(feature = "heap-profile")]
+            let _phase = crate::heap::enter(crate::heap::Phase::Cache);
+
+            if self.interpreter.native_calls == native_calls {
+                return self.interpreter.memoize(key, value.clone(), location);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Takes the call out of the active ones, and out of the count of its
+    /// state.
+    fn release(&mut self, call: Bookkeeping) {
+        if let Some((key, hidden)) = call.active {
+            match hidden {
+                Some(native_calls) => self.active.insert(key, native_calls),
+                None => self.active.remove(&key),
+            };
+        }
+
+        if let Some(state) = call.state {
+            if let Some(count) = self.states.get_mut(&state) {
+                *count -= 1;
+            }
+        }
+    }
+
+    /// Whether the context is used after the term being evaluated, so its
+    /// bindings must not outlive it. The bindings of the `let`s that end a
+    /// call go away with its context, and the ones that end the program are
+    /// kept for the host, like the ones of a prelude.
+    fn nested(&self) -> bool {
+        self.continuations
+            .iter()
+            .rev()
+            .find(|continuation| !matches!(continuation, Continuation::Exit(_)))
+            .is_some_and(|continuation| !matches!(continuation, Continuation::Return { .. }))
+    }
+
+    /// Whether a call with the same closure and arguments is still running,
+    /// and no native function was called since it started. Evaluation is
+    /// deterministic otherwise, so the new call would start it over again
+    /// forever.
+    fn repeated(&self, key: u64) -> bool {
+        self.active.get(&key) == Some(&self.interpreter.native_calls)
+    }
+
+    /// A hasher that hashed the body of `closure` and the bindings it
+    /// captured, since closures with the same body may return different
+    /// results, taken from the inline cache of the call site at `location`
+    /// when it called the same closure last time.
+    fn body_hasher(&mut self, location: &Location, closure: &Closure) -> &DefaultHasher {
+        let site = (location.start, location.end);
+        let hit = self
+            .call_sites
+            .get(&site)
+            .is_some_and(|cached| Rc::ptr_eq(&cached.closure, &closure.context));
+
+        if !hit {
+            let mut hasher = DefaultHasher::new();
+            closure.body.hash(&mut hasher);
+            bindings_key(&closure.context.borrow()).hash(&mut hasher);
+
+            self.call_sites.insert(
+                site,
+                CallSite {
+                    closure: closure.context.clone(),
+                    hasher,
+                },
+            );
+        }
+
+        &self.call_sites[&site].hasher
+    }
+
+    /// Pops the context of the call being left, keeping its storage for
+    /// the next call.
+    fn leave(&mut self) {
+        let mut context = self.contexts.pop().expect("calls push a context");
+        self.interpreter.free(memory::context_size(&context));
+        self.interpreter.depth -= 1;
+
+        if self.pool.len() < FRAME_POOL {
+            context.clear();
+            self.pool.push(context);
+        }
+    }
+
+    /// Drops every continuation, leaving the calls the error went through
+    /// on its stack.
+    fn unwind(&mut self, error: RuntimeError) -> Result<Value, RuntimeError> {
+        let context = self
+            .contexts
+            .last()
+            .expect("the session always has a context");
+        let mut result = Err(error.with_context(context));
+
+        while let Some(continuation) = self.continuations.pop() {
+            match continuation {
+                Continuation::Return {
+                    frame, replaced, ..
+                } => {
+                    self.leave();
+
+                    for call in replaced {
+                        self.interpreter.free(call.size());
+                    }
+
+                    result = result.map_err(|error| error.with_frame(frame));
+                }
+                Continuation::Exit(location) => {
+                    for observer in &mut self.interpreter.observers {
+                        observer.exit(&location, &result);
+                    }
+                }
+                #[cfg(feature = "generators")]
+                Continuation::Resumed { routine, .. } => generator::fail(&routine),
+                _continuation => (),
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{Collect, Context, Interpreter},
+        messages::ErrorCode,
+        parser::parse,
+    };
+
+    use super::{Session, Step};
+
+    /// A session of `source` with `interpreter`.
+    pub(super) fn session<'a>(
+        interpreter: &'a mut Interpreter<Collect>,
+        source: &str,
+    ) -> Session<'a, Collect> {
+        let file = parse(source, "tests").unwrap();
+
+        Session::new(interpreter, file.expression, Context::new())
+    }
+
+    #[test]
+    fn step_until_done() {
+        let mut interpreter = Interpreter::new(Collect::default());
+        let mut session =
+            session(&mut interpreter, "let x = print(1); print(x + 1)").with_bounces(3);
+
+        let mut steps = 1;
+        let value = loop {
+            match session.step() {
+                Step::Pending => steps += 1,
+                Step::Done(value) => break value,
+                Step::Failed(error) => panic!("{error:?}"),
+            }
+        };
+
+        assert!(steps > 1);
+        assert!(session.is_done());
+        assert_eq!(value.to_string(), "2");
+        assert!(matches!(session.step(), Step::Done(_)));
+        assert_eq!(interpreter.io.0, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn deep_recursion() {
+        let mut interpreter = Interpreter::new(Collect::default()).with_memoization(false);
+        let source =
+            "let sum = fn (n) => { if (n == 0) { 0 } else { n + sum(n - 1) } }; sum(50000)";
+        let (result, context) = session(&mut interpreter, source).finish();
+
+        assert_eq!(result.unwrap().to_string(), "1250025000");
+        assert!(context.contains_key("sum"));
+    }
+
+    #[test]
+    fn deep_bodies() {
+        let depth = 100_000;
+        let source = format!(
+            "let f = fn (n) => {{ {}x + n }}; f(1) + f(2)",
+            "let x = 1;\n".repeat(depth)
+        );
+
+        for memoization in [true, false] {
+            let mut interpreter =
+                Interpreter::new(Collect::default()).with_memoization(memoization);
+            let (result, _) = session(&mut interpreter, &source).finish();
+
+            assert_eq!(result.unwrap().to_string(), "5");
+        }
+    }
+
+    #[test]
+    fn mutual_tail_calls() {
+        let mut interpreter = Interpreter::new(Collect::default()).with_max_depth(10);
+        let source = "let odd = fn (n, even) => { if (n == 0) { false } else { even(n - 1) } };
+            let even = fn (n) => { if (n == 0) { true } else { odd(n - 1, even) } };
+            even(1001)";
+        let (result, _) = session(&mut interpreter, source).finish();
+
+        assert_eq!(result.unwrap().to_string(), "false");
+        assert_eq!(interpreter.eval_stats().max_depth, 1);
+
+        // Replaced calls still count as running while they are kept, like
+        // when looking for loops.
+        let mut interpreter = Interpreter::new(Collect::default())
+            .with_memoization(false)
+            .with_loop_detection(3);
+        let source = "let f = fn (n) => { let g = fn (m) => { f(m) }; g(n) }; f(1)";
+        let (result, _) = session(&mut interpreter, source).finish();
+
+        assert_eq!(result.unwrap_err().code, ErrorCode::InfiniteRecursion);
+    }
+
+    #[test]
+    fn tail_calls_in_constant_memory() {
+        let mut interpreter = Interpreter::new(Collect::default())
+            .with_memoization(false)
+            .with_max_memory(1 << 20);
+        let source =
+            "let loop = fn (n, acc) => { if (n == 0) { acc } else { loop(n - 1, acc + 1) } };
+            loop(200000, 0)";
+        let (result, _) = session(&mut interpreter, source).finish();
+
+        // Keeping anything for each replaced call would take megabytes.
+        assert_eq!(result.unwrap().to_string(), "200000");
+        assert!(interpreter.memory_stats().peak < 1 << 12);
+    }
+
+    #[cfg(feature = "memo")]
+    #[test]
+    fn memoized_tail_calls() {
+        let mut interpreter = Interpreter::new(Collect::default()).with_max_memory(1 << 20);
+        let source =
+            "let loop = fn (n, acc) => { if (n == 0) { acc } else { loop(n - 1, acc + 1) } };
+            loop(200000, 0)";
+        let (result, _) = session(&mut interpreter, source).finish();
+
+        // Only the call made by the program and the last one are memoized.
+        assert_eq!(result.unwrap().to_string(), "200000");
+        assert_eq!(interpreter.cache.len(), 2);
+    }
+
+    #[test]
+    fn pooled_contexts() {
+        let mut interpreter = Interpreter::new(Collect::default()).with_memoization(false);
+        let source = "let sum = fn (n) => { if (n == 0) { 0 } else { n + sum(n - 1) } }; sum(1000)";
+        let mut session = session(&mut interpreter, source).with_bounces(usize::MAX);
+
+        assert!(matches!(session.step(), Step::Done(_)));
+        assert_eq!(session.pool.len(), super::FRAME_POOL);
+        assert!(session.pool.iter().all(|context| context.is_empty()));
+    }
+
+    #[test]
+    fn failed() {
+        let mut interpreter = Interpreter::new(Collect::default());
+        let mut session = session(&mut interpreter, "let f = fn (n) => { n / 0 }; f(1)");
+
+        match session.step() {
+            Step::Failed(error) => assert_eq!(error.stack[0].name, "f"),
+            step => panic!("{step:?}"),
+        }
+    }
+
+    #[cfg(feature = "memo")]
+    #[test]
+    fn call_sites_calling_many_closures() {
+        let mut interpreter = Interpreter::new(Collect::default());
+        let source = "let double = fn (n) => { n * 2 };
+            let square = fn (n) => { n * n };
+            let apply = fn (f, n) => { f(n) };
+            (apply(double, 3), (apply(square, 3), apply(double, 3)))";
+
+        match session(&mut interpreter, source).step() {
+            Step::Done(value) => assert_eq!(value.to_string(), "(6, (9, 6))"),
+            step => panic!("{step:?}"),
+        }
+
+        assert_eq!(interpreter.eval_stats().memo_hits, 1);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_spans() {
+        use std::sync::{Arc, Mutex};
+
+        use tracing::{span, subscriber, Event, Metadata, Subscriber};
+
+        /// Keeps the names of the spans created.
+        #[derive(Clone, Default)]
+        struct Names(Arc<Mutex<Vec<&'static str>>>);
+
+        impl Subscriber for Names {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+                let mut names = self.0.lock().unwrap();
+                names.push(span.metadata().name());
+
+                span::Id::from_u64(names.len() as u64)
+            }
+
+            fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+            fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+            fn event(&self, _event: &Event<'_>) {}
+
+            fn enter(&self, _span: &span::Id) {}
+
+            fn exit(&self, _span: &span::Id) {}
+        }
+
+        let names = Names::default();
+
+        subscriber::with_default(names.clone(), || {
+            let file = parse("let f = fn (n) => { n + 1 }; f(1) + f(1)", "tests").unwrap();
+            let mut interpreter = Interpreter::new(Collect::default());
+
+            interpreter
+                .eval(file.expression, &mut Context::new())
+                .unwrap();
+        });
+
+        let names = names.0.lock().unwrap();
+        assert_eq!(names[..2], ["parse", "eval"]);
+        assert_eq!(names.iter().filter(|name| **name == "call").count(), 1);
+        assert_eq!(names.iter().filter(|name| **name == "memo").count(), 2);
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use crate::messages::ErrorCode;
+
+use super::{Context, Continuation, Control, Frame, Printer, RuntimeError, Session, Value};
+
+/// The `callcc` builtin, or a continuation it captured.
+#[derive(Debug, Clone)]
+pub enum Callcc {
+    /// Calls its argument with the continuation of the call to `callcc`.
+    Builtin,
+    /// Resumes the evaluation where `callcc` was called, with the value it
+    /// is called with as the value of the call.
+    Continuation(Rc<Captured>),
+}
+
+/// What was left to evaluate when `callcc` was called. It is copied every
+/// time it is resumed, so a continuation can be resumed many times, even
+/// after the call to `callcc` returned.
+pub struct Captured {
+    /// Continuations of the call, without the ones waiting to tell the
+    /// observers that a term was evaluated, which they are told when the
+    /// evaluation jumps away from it instead.
+    continuations: Vec<Continuation>,
+    /// Bindings of the active calls, without the context the session was
+    /// started with, which is the one of the session resuming it.
+    contexts: Vec<Context>,
+    active: HashMap<u64, u64>,
+    states: HashMap<u64, u32>,
+    depth: usize,
+    /// Bytes held by the bindings of the calls.
+    memory: usize,
+}
+
+/// Continuations are only equal to themselves, like closures.
+impl Hash for Callcc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Callcc::Builtin => "callcc".hash(state),
+            Callcc::Continuation(captured) => Rc::as_ptr(captured).hash(state),
+        }
+    }
+}
+
+impl std::fmt::Debug for Captured {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Captured")
+            .field("depth", &self.depth)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, I: Printer> Session<'a, I> {
+    /// Calls `callcc`, or resumes a continuation it captured.
+    ///
+    /// Both count as calls to native functions: the calls that capture a
+    /// continuation may return more than once, and the ones a continuation
+    /// jumps into or out of return values that don't only depend on their
+    /// arguments, so none of them are memoized.
+    pub(super) fn callcc(
+        &mut self,
+        callcc: Callcc,
+        arguments: Vec<Value>,
+        frame: Frame,
+    ) -> Control {
+        self.interpreter.native_calls += 1;
+
+        let Some(argument) = arguments.into_iter().next() else {
+            let message = match callcc {
+                Callcc::Builtin => "expects the function to call with the continuation",
+                Callcc::Continuation(_) => "expects the value to resume the continuation with",
+            };
+
+            return Control::Error(RuntimeError::new(
+                ErrorCode::NativeFailed,
+                vec![frame.name, message.to_string()],
+                frame.location,
+            ));
+        };
+
+        match callcc {
+            Callcc::Builtin => {
+                let continuation = Callcc::Continuation(Rc::new(self.capture()));
+
+                self.apply(argument, vec![Value::Callcc(continuation)], frame)
+            }
+            Callcc::Continuation(captured) => {
+                self.jump(&captured, &argument);
+
+                Control::Value(argument)
+            }
+        }
+    }
+
+    fn capture(&self) -> Captured {
+        Captured {
+            continuations: self
+                .continuations
+                .iter()
+                .filter(|continuation| !matches!(continuation, Continuation::Exit(_)))
+                .cloned()
+                .collect(),
+            contexts: self.contexts[1..].to_vec(),
+            active: self.active.clone(),
+            states: self.states.clone(),
+            depth: self.interpreter.depth,
+            memory: self.interpreter.memory.contexts,
+        }
+    }
+
+    /// Drops every continuation for a copy of the captured ones, telling the
+    /// observers that the terms being evaluated were evaluated to `value`.
+    fn jump(&mut self, captured: &Captured, value: &Value) {
+        let result = Ok(value.clone());
+
+        for continuation in self.continuations.drain(..).rev() {
+            if let Continuation::Exit(location) = continuation {
+                for observer in &mut self.interpreter.observers {
+                    observer.exit(&location, &result);
+                }
+            }
+        }
+
+        self.continuations.clone_from(&captured.continuations);
+        self.contexts.truncate(1);
+        self.contexts.extend(captured.contexts.iter().cloned());
+        self.active.clone_from(&captured.active);
+        self.states.clone_from(&captured.states);
+        self.interpreter.depth = captured.depth;
+        self.interpreter.memory.contexts = captured.memory;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{evaluate, run, Collect, Interpreter},
+        messages::ErrorCode,
+    };
+
+    #[test]
+    fn early_exit() {
+        let (result, printed) = run("let find = fn (n, k) => {
+                if (n == 3) { k(n) } else { let _ = print(n); find(n + 1, k) }
+            };
+            1 + callcc(fn (k) => { find(0, k) })");
+
+        assert_eq!(result, Ok(String::from("4")));
+        assert_eq!(printed, vec!["0", "1", "2"]);
+    }
+
+    #[test]
+    fn resume_many_times() {
+        // The continuation is resumed after `callcc` returned, going back to
+        // the `let` that bound its result.
+        let (result, printed) = run("let pair = callcc(fn (k) => { (k, 0) });
+            let k = first(pair);
+            let n = second(pair);
+            let _ = print(n);
+            if (n < 3) { k((k, n + 1)) } else { n }");
+
+        assert_eq!(result, Ok(String::from("3")));
+        assert_eq!(printed, vec!["0", "1", "2", "3"]);
+    }
+
+    #[test]
+    fn capturing_calls_are_not_memoized() {
+        let source = "let f = fn (n) => { callcc(fn (k) => { n + 1 }) }; f(1) + f(1)";
+        let mut interpreter = Interpreter::new(Collect::default());
+        let value = evaluate(&mut interpreter, source).unwrap();
+
+        assert_eq!(value.to_string(), "4");
+        assert_eq!(interpreter.eval_stats().memo_hits, 0);
+        assert!(interpreter.cache.is_empty());
+
+        let (result, _) = run("callcc()");
+        assert_eq!(result, Err(ErrorCode::NativeFailed));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{
+    ast::{Binary, Call, Element, If, Node, Term},
+    messages::ErrorCode,
+};
+
+use super::{Control, Frame, Printer, RuntimeError, Session, Value};
+
+impl<'a, I: Printer> Session<'a, I> {
+    /// Evaluates the common patterns whose operands are variables or
+    /// literals in a single bounce, instead of one bounce and one
+    /// continuation per term: comparisons that pick a branch, operations
+    /// on variables, and calls with such arguments. Other terms are
+    /// evaluated as usual.
+    pub(super) fn fused(&mut self, term: Term) -> Control {
+        match term.into_node() {
+            Node::Binary(binary) => match self.operands(&binary) {
+                Some(Ok(value)) => Control::Value(value),
+                Some(Err(error)) => Control::Error(error),
+                None => self.eval_term(Term::Binary(binary)),
+            },
+            Node::If(if_) => match self.condition(&if_) {
+                Some(Ok(true)) => Control::Eval(*if_.then),
+                Some(Ok(false)) => Control::Eval(*if_.otherwise),
+                Some(Err(error)) => Control::Error(error),
+                None => self.eval_term(Term::If(if_)),
+            },
+            Node::Call(call) => match self.call_with_operands(&call) {
+                Some(control) => control,
+                None => self.eval_term(Term::Call(call)),
+            },
+            node => self.eval_term(Term::from(node)),
+        }
+    }
+
+    /// The value of `term` when it takes no bounce of its own.
+    fn operand(&self, term: &Term) -> Option<Result<Value, RuntimeError>> {
+        let value = match term {
+            Term::Int(int) => Ok(Value::Int(int.value)),
+            Term::Str(str) => Ok(Value::Str(str.value.clone().into())),
+            Term::Bool(bool) => Ok(Value::Bool(bool.value)),
+            Term::Var(var) => self.lookup(var),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    fn operands(&mut self, binary: &Binary) -> Option<Result<Value, RuntimeError>> {
+        if !is_operand(&binary.lhs) || !is_operand(&binary.rhs) {
+            return None;
+        }
+
+        self.interpreter.stats.terms += 2;
+
+        let lhs = match self.operand(&binary.lhs)? {
+            Ok(lhs) => lhs,
+            Err(error) => return Some(Err(error)),
+        };
+        let rhs = match self.operand(&binary.rhs)? {
+            Ok(rhs) => rhs,
+            Err(error) => return Some(Err(error)),
+        };
+
+        #[cfg(feature = "heap-profile")]
+        let _phase = crate::heap::enter(crate::heap::Phase::Values);
+
+        Some(
+            self.interpreter
+                .operate(&lhs, &binary.op, &rhs, binary.lhs.location()),
+        )
+    }
+
+    /// Which branch to take, when the condition is an operation on
+    /// operands.
+    fn condition(&mut self, if_: &If) -> Option<Result<bool, RuntimeError>> {
+        let Term::Binary(condition) = if_.condition.as_ref() else {
+            return None;
+        };
+
+        let condition = self.operands(condition)?;
+        self.interpreter.stats.terms += 1;
+
+        Some(match condition {
+            Ok(Value::Bool(bool)) => Ok(bool),
+            Ok(value) => Err(RuntimeError::new(
+                ErrorCode::InvalidIfCondition,
+                vec![value.inspect().to_string()],
+                if_.condition.location().clone(),
+            )),
+            Err(error) => Err(error),
+        })
+    }
+
+    fn call_with_operands(&mut self, call: &Call) -> Option<Control> {
+        let Term::Var(callee) = call.callee.as_ref() else {
+            return None;
+        };
+
+        if !call.arguments.iter().all(is_operand) {
+            return None;
+        }
+
+        if let Err(error) = self.interpreter.check_limits(&call.location) {
+            return Some(Control::Error(error));
+        }
+
+        let function = match self.lookup(callee) {
+            Ok(function) => function,
+            Err(error) => return Some(Control::Error(error)),
+        };
+
+        // Closures ignore the arguments given after their parameters.
+        let count = match &function {
+            Value::Closure(closure) => closure.parameters.len(),
+            Value::Native(_) => call.arguments.len(),
+            #[cfg(feature = "callcc")]
+            Value::Callcc(_) => 1,
+            #[cfg(feature = "generators")]
+            Value::Generator(_) => 1,
+            _ => 0,
+        };
+
+        #[cfg(feature = "heap-profile")]
+        let phase = crate::heap::enter(crate::heap::Phase::Arguments);
+
+        let mut values = Vec::with_capacity(count);
+
+        for argument in call.arguments.iter().take(count) {
+            match self.operand(argument)? {
+                Ok(value) => values.push(value),
+                Err(error) => return Some(Control::Error(error)),
+            }
+        }
+
+        #[cfg(feature = "heap-profile")]
+        drop(phase);
+
+        self.interpreter.stats.terms += 1 + values.len() as u64;
+
+        let frame = Frame {
+            name: callee.text.to_string(),
+            location: call.location.clone(),
+        };
+
+        Some(self.apply(function, values, frame))
+    }
+}
+
+fn is_operand(term: &Term) -> bool {
+    matches!(
+        term,
+        Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{session::tests::session, Collect, Interpreter, Step};
+
+    /// The outcome of `source` and how many bounces it took.
+    fn bounces(source: &str, superinstructions: bool) -> (String, usize) {
+        let mut interpreter = Interpreter::new(Collect::default())
+            .with_memoization(false)
+            .with_superinstructions(superinstructions);
+        let mut session = session(&mut interpreter, source).with_bounces(1);
+
+        let mut bounces = 1;
+        loop {
+            match session.step() {
+                Step::Pending => bounces += 1,
+                Step::Done(value) => return (value.to_string(), bounces),
+                Step::Failed(error) => return (format!("{:?}", error.code), bounces),
+            }
+        }
+    }
+
+    #[test]
+    fn same_outcomes_in_fewer_bounces() {
+        let sources = [
+            "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(10)",
+            "let f = fn (a, b) => { a + b }; f(1, \"a\")",
+            "let f = fn (a) => { a }; f(1, x)",
+            "let n = 1; if (n + 1) { 1 } else { 2 }",
+            "let n = 1; n / 0",
+            "x < 1",
+        ];
+
+        for source in sources {
+            let (terms, slow) = bounces(source, false);
+            let (fused, fast) = bounces(source, true);
+
+            assert_eq!(terms, fused, "{source}");
+            assert!(fast < slow, "{source}: {fast} bounces, {slow} without");
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use crate::messages::ErrorCode;
+
+use super::{Context, Continuation, Control, Frame, Printer, RuntimeError, Session, Tuple, Value};
+
+/// The builtins of generators, or a generator one of them made.
+#[derive(Debug, Clone)]
+pub enum Generator {
+    /// Makes a generator of the function it is given, which is called
+    /// without arguments the first time the generator is resumed.
+    New,
+    /// Resumes a generator until it yields a value, or its function returns
+    /// one.
+    Next,
+    /// Suspends the generator being resumed, handing the value to `next`.
+    Yield,
+    /// A generator made by `generator`, resumed by `next`.
+    Made(Rc<RefCell<Routine>>),
+}
+
+/// Where a generator is at.
+pub struct Routine(State);
+
+enum State {
+    /// Not resumed yet, holding the function to call.
+    Start(Value),
+    /// Suspended by `yield`.
+    Suspended(Suspended),
+    /// Being resumed.
+    Running,
+    /// Its function returned the value.
+    Done(Value),
+    /// It failed, stopping the evaluation that resumed it.
+    Failed,
+}
+
+/// What was left to evaluate of a generator when it yielded, taken from
+/// the session until it is resumed. Only the part of the evaluation since
+/// `next` was called is taken, like the calls the generator made.
+struct Suspended {
+    continuations: Vec<Continuation>,
+    contexts: Vec<Context>,
+    active: HashMap<u64, u64>,
+    states: HashMap<u64, u32>,
+    depth: usize,
+    /// Bytes held by the bindings of the calls.
+    memory: usize,
+    /// The value yielded, which is also the value of the call to `yield`
+    /// once the generator is resumed.
+    value: Value,
+}
+
+/// The bookkeeping of the calls that resumed a generator, put aside while
+/// it runs and given back when it yields or returns.
+#[cfg_attr(feature = "callcc", derive(Clone))]
+pub(super) struct Caller {
+    active: HashMap<u64, u64>,
+    states: HashMap<u64, u32>,
+    /// How many contexts the calls have, the ones above belong to the
+    /// generator.
+    contexts: usize,
+    depth: usize,
+    memory: usize,
+}
+
+/// Generators are only equal to themselves, like closures.
+impl Hash for Generator {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Generator::New => "generator".hash(state),
+            Generator::Next => "next".hash(state),
+            Generator::Yield => "yield".hash(state),
+            Generator::Made(routine) => Rc::as_ptr(routine).hash(state),
+        }
+    }
+}
+
+impl Generator {
+    /// Name of the builtin, `None` for the generators it made.
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Generator::New => Some("generator"),
+            Generator::Next => Some("next"),
+            Generator::Yield => Some("yield"),
+            Generator::Made(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for Routine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self.0 {
+            State::Start(_) => "Start",
+            State::Suspended(_) => "Suspended",
+            State::Running => "Running",
+            State::Done(_) => "Done",
+            State::Failed => "Failed",
+        };
+
+        f.debug_tuple("Routine").field(&state).finish()
+    }
+}
+
+/// The `(resumable, value)` pair `next` returns.
+fn pair(resumable: bool, value: Value) -> Value {
+    Value::Tuple(Tuple {
+        first: Box::new(Value::Bool(resumable)),
+        second: Box::new(value),
+    })
+}
+
+impl<'a, I: Printer> Session<'a, I> {
+    /// Calls `generator`, `next` or `yield`.
+    ///
+    /// All of them count as calls to native functions: the calls that resume
+    /// a generator return values that don't only depend on their arguments,
+    /// and the ones of the generator are suspended in the middle, so none of
+    /// them are memoized.
+    pub(super) fn generator(
+        &mut self,
+        generator: Generator,
+        arguments: Vec<Value>,
+        frame: Frame,
+    ) -> Control {
+        self.interpreter.native_calls += 1;
+
+        let message = match (generator, arguments.into_iter().next()) {
+            (Generator::New, Some(function @ (Value::Closure(_) | Value::Native(_)))) => {
+                let routine = Routine(State::Start(function));
+
+                return Control::Value(Value::Generator(Generator::Made(Rc::new(RefCell::new(
+                    routine,
+                )))));
+            }
+            (Generator::Next, Some(Value::Generator(Generator::Made(routine)))) => {
+                return self.next(routine, frame)
+            }
+            (Generator::Yield, Some(value)) => return self.suspend(value, frame),
+            (Generator::New, _) => "expects the function of the generator",
+            (Generator::Next, _) => "expects a generator",
+            (Generator::Yield, None) => "expects the value to yield",
+            (Generator::Made(routine), _) => {
+                return Control::Error(RuntimeError::new(
+                    ErrorCode::InvalidCall,
+                    vec![Value::Generator(Generator::Made(routine)).to_string()],
+                    frame.location,
+                ))
+            }
+        };
+
+        failed(frame, message)
+    }
+
+    /// Resumes `routine` where it yielded, or calls its function the first
+    /// time, until it yields or returns.
+    fn next(&mut self, routine: Rc<RefCell<Routine>>, frame: Frame) -> Control {
+        let state = std::mem::replace(&mut routine.borrow_mut().0, State::Running);
+
+        match state {
+            State::Start(function) => {
+                self.enter(routine);
+                self.apply(function, Vec::new(), frame)
+            }
+            State::Suspended(suspended) => {
+                self.enter(routine);
+
+                self.continuations.extend(suspended.continuations);
+                self.contexts.extend(suspended.contexts);
+                self.active = suspended.active;
+                self.states = suspended.states;
+                self.interpreter.depth += suspended.depth;
+                self.interpreter.allocate(suspended.memory);
+
+                Control::Value(suspended.value)
+            }
+            State::Done(value) => {
+                routine.borrow_mut().0 = State::Done(value.clone());
+
+                Control::Value(pair(false, value))
+            }
+            state => {
+                let message = match state {
+                    State::Running => "the generator is already running",
+                    _ => "the generator failed",
+                };
+                routine.borrow_mut().0 = state;
+
+                failed(frame, message)
+            }
+        }
+    }
+
+    /// Puts the bookkeeping of the calls resuming `routine` aside, under
+    /// the continuation that hands them what it yields or returns.
+    fn enter(&mut self, routine: Rc<RefCell<Routine>>) {
+        let caller = Caller {
+            active: std::mem::take(&mut self.active),
+            states: std::mem::take(&mut self.states),
+            contexts: self.contexts.len(),
+            depth: self.interpreter.depth,
+            memory: self.interpreter.memory.contexts,
+        };
+
+        self.continuations
+            .push(Continuation::Resumed { routine, caller });
+    }
+
+    /// Takes what is left to evaluate of the innermost generator being
+    /// resumed, handing `value` to the call to `next` that resumed it.
+    fn suspend(&mut self, value: Value, frame: Frame) -> Control {
+        let Some(index) = self
+            .continuations
+            .iter()
+            .rposition(|continuation| matches!(continuation, Continuation::Resumed { .. }))
+        else {
+            return failed(frame, "can only be called by a generator");
+        };
+
+        let continuations = self.continuations.split_off(index + 1);
+        let Some(Continuation::Resumed { routine, caller }) = self.continuations.pop() else {
+            unreachable!("the continuation resumed a generator");
+        };
+
+        let suspended = Suspended {
+            continuations,
+            contexts: self.contexts.split_off(caller.contexts),
+            active: std::mem::replace(&mut self.active, caller.active),
+            states: std::mem::replace(&mut self.states, caller.states),
+            depth: self.interpreter.depth - caller.depth,
+            memory: self
+                .interpreter
+                .memory
+                .contexts
+                .saturating_sub(caller.memory),
+            value: value.clone(),
+        };
+        self.interpreter.depth = caller.depth;
+        self.interpreter.free(suspended.memory);
+        routine.borrow_mut().0 = State::Suspended(suspended);
+
+        Control::Value(pair(true, value))
+    }
+
+    /// Hands the value the function of `routine` returned to the calls that
+    /// resumed it.
+    pub(super) fn returned_from(
+        &mut self,
+        routine: Rc<RefCell<Routine>>,
+        caller: Caller,
+        value: Value,
+    ) -> Control {
+        self.active = caller.active;
+        self.states = caller.states;
+        routine.borrow_mut().0 = State::Done(value.clone());
+
+        Control::Value(pair(false, value))
+    }
+}
+
+/// Marks a generator that was being resumed when the evaluation failed.
+pub(super) fn fail(routine: &RefCell<Routine>) {
+    routine.borrow_mut().0 = State::Failed;
+}
+
+fn failed(frame: Frame, message: &str) -> Control {
+    Control::Error(RuntimeError::new(
+        ErrorCode::NativeFailed,
+        vec![frame.name, message.to_string()],
+        frame.location,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::run, messages::ErrorCode};
+
+    static NATURALS: &str = "
+        let from = fn (n) => { let _ = yield(n); from(n + 1) };
+        let naturals = generator(fn () => { from(0) });";
+
+    #[test]
+    fn infinite_sequences() {
+        let (result, _) = run(&format!(
+            "{NATURALS}
+            let take = fn (g, n) => {{
+                if (n == 0) {{ 0 }} else {{ (second(next(g)), take(g, n - 1)) }}
+            }};
+            take(naturals, 4)"
+        ));
+
+        assert_eq!(result, Ok(String::from("(0, (1, (2, (3, 0))))")));
+    }
+
+    #[test]
+    fn finite_generators() {
+        let (result, printed) = run("let g = generator(fn () => {
+                let _ = print(yield(1));
+                let _ = yield(2);
+                3
+            });
+            let a = next(g);
+            let b = next(g);
+            let c = next(g);
+            (a, (b, (c, next(g))))");
+
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "((true, 1), ((true, 2), ((false, 3), (false, 3))))"
+            ))
+        );
+        assert_eq!(printed, vec!["1"]);
+    }
+
+    #[test]
+    fn nested_generators() {
+        // Every generator yields to the `next` that resumed it, even when
+        // another generator resumes it.
+        let (result, _) = run(&format!(
+            "{NATURALS}
+            let doubles = generator(fn () => {{
+                let loop = fn () => {{ let _ = yield(second(next(naturals)) * 2); loop() }};
+                loop()
+            }});
+            let a = second(next(doubles));
+            let b = second(next(naturals));
+            (a, (b, second(next(doubles))))"
+        ));
+
+        assert_eq!(result, Ok(String::from("(0, (1, 4))")));
+    }
+
+    #[test]
+    fn deep_suspended_calls() {
+        // The calls the generator made survive the yields in the middle of
+        // them, and each yield hands back the calls of the caller.
+        let (result, _) = run("let count = fn (n) => {
+                if (n == 0) { 0 } else { let _ = yield(n); 1 + count(n - 1) }
+            };
+            let g = generator(fn () => { count(3) });
+            let sum = fn (total) => {
+                let step = next(g);
+                if (first(step)) { sum(total + second(step)) } else { (total, second(step)) }
+            };
+            sum(0)");
+
+        assert_eq!(result, Ok(String::from("(6, 3)")));
+    }
+
+    #[test]
+    fn misuse() {
+        assert_eq!(run("yield(1)").0, Err(ErrorCode::NativeFailed));
+        assert_eq!(run("next(1)").0, Err(ErrorCode::NativeFailed));
+        assert_eq!(run("generator(1)").0, Err(ErrorCode::NativeFailed));
+        assert_eq!(
+            run("let g = generator(fn () => { 1 }); g()").0,
+            Err(ErrorCode::InvalidCall)
+        );
+    }
+}
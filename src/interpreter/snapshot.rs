@@ -0,0 +1,383 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Display,
+    fs::File,
+    hash::Hash,
+    io::{self, BufReader, BufWriter},
+    path::Path,
+    rc::Rc,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ast::{Term, Var},
+    effects::Effects,
+};
+
+use super::{Cache, Closure, Context, Interpreter, Printer, Tuple, Value};
+
+/// A value as stored in a snapshot. Closures refer to the bindings they
+/// captured by their index in [`Snapshot::contexts`], since recursive
+/// functions capture themselves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+enum Encoded {
+    Closure {
+        parameters: Vec<Var>,
+        body: Term,
+        context: usize,
+        /// Snapshots taken before effects were tracked hold closures that
+        /// are taken as effectful, so they are never memoized.
+        #[serde(default = "unknown")]
+        effects: Effects,
+    },
+    Native {
+        name: String,
+    },
+    Int {
+        value: i64,
+    },
+    Str {
+        value: String,
+    },
+    Bool {
+        value: bool,
+    },
+    Tuple {
+        first: Box<Encoded>,
+        second: Box<Encoded>,
+    },
+    /// A continuation captured by `callcc`, whose calls can't be stored.
+    Continuation,
+    /// A generator, whose calls can't be stored either.
+    Generator,
+}
+
+fn unknown() -> Effects {
+    Effects::UNKNOWN
+}
+
+/// The bindings of a program and the memoization cache of its interpreter,
+/// which can be written to disk and restored later, to resume long sessions
+/// or to start services with precomputed state.
+///
+/// Native functions are stored by name, so the interpreter that restores the
+/// snapshot must register them too.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    context: HashMap<Arc<str>, Encoded>,
+    cache: HashMap<String, Encoded>,
+    /// Bindings captured by the closures of the snapshot.
+    contexts: Vec<HashMap<Arc<str>, Encoded>>,
+}
+
+/// Why a [`Snapshot`] could not be restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The snapshot refers to a native function that is not registered.
+    UnknownNative(String),
+    /// A closure refers to captured bindings that are not in the snapshot.
+    UnknownContext(usize),
+    /// The snapshot holds a continuation captured by `callcc`, which only
+    /// lives as long as the process that captured it.
+    Continuation,
+    /// The snapshot holds a generator, which only lives as long as the
+    /// process that made it.
+    Generator,
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownNative(name) => write!(f, "native function \"{name}\" is not registered"),
+            Self::UnknownContext(index) => write!(f, "captured bindings {index} are missing"),
+            Self::Continuation => f.write_str("continuations can't be restored"),
+            Self::Generator => f.write_str("generators can't be restored"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+#[derive(Default)]
+struct Encoder {
+    /// Index of the captured bindings already seen, by address.
+    seen: HashMap<*const RefCell<Context>, usize>,
+    contexts: Vec<HashMap<Arc<str>, Encoded>>,
+}
+
+impl Encoder {
+    fn encode(&mut self, value: &Value) -> Encoded {
+        match value {
+            Value::Closure(closure) => Encoded::Closure {
+                parameters: closure.parameters.to_vec(),
+                body: Term::clone(&closure.body),
+                context: self.capture(&closure.context),
+                effects: closure.effects,
+            },
+            Value::Native(native) => Encoded::Native {
+                name: native.name.clone(),
+            },
+            Value::Int(value) => Encoded::Int { value: *value },
+            Value::Str(value) => Encoded::Str {
+                value: value.to_string(),
+            },
+            Value::Bool(value) => Encoded::Bool { value: *value },
+            Value::Tuple(tuple) => Encoded::Tuple {
+                first: Box::new(self.encode(&tuple.first)),
+                second: Box::new(self.encode(&tuple.second)),
+            },
+            #[cfg(feature = "callcc")]
+            Value::Callcc(super::Callcc::Builtin) => Encoded::Native {
+                name: String::from("callcc"),
+            },
+            #[cfg(feature = "callcc")]
+            Value::Callcc(super::Callcc::Continuation(_)) => Encoded::Continuation,
+            #[cfg(feature = "generators")]
+            Value::Generator(generator) => match generator.name() {
+                Some(name) => Encoded::Native {
+                    name: String::from(name),
+                },
+                None => Encoded::Generator,
+            },
+        }
+    }
+
+    fn encode_all<'a, K: Clone + Eq + Hash + 'a>(
+        &mut self,
+        bindings: impl Iterator<Item = (&'a K, &'a Value)>,
+    ) -> HashMap<K, Encoded> {
+        bindings
+            .map(|(name, value)| (name.clone(), self.encode(value)))
+            .collect()
+    }
+
+    fn capture(&mut self, context: &Rc<RefCell<Context>>) -> usize {
+        if let Some(index) = self.seen.get(&Rc::as_ptr(context)) {
+            return *index;
+        }
+
+        // The index is taken before encoding the bindings, so a closure that
+        // captured itself refers to them instead of encoding them again.
+        let index = self.contexts.len();
+        self.seen.insert(Rc::as_ptr(context), index);
+        self.contexts.push(HashMap::new());
+
+        let bindings = self.encode_all(context.borrow().iter());
+        self.contexts[index] = bindings;
+
+        index
+    }
+}
+
+struct Decoder<'a> {
+    natives: &'a Context,
+    contexts: Vec<Rc<RefCell<Context>>>,
+}
+
+impl Decoder<'_> {
+    fn decode(&self, value: &Encoded) -> Result<Value, SnapshotError> {
+        Ok(match value {
+            Encoded::Closure {
+                parameters,
+                body,
+                context,
+                effects,
+            } => Value::Closure(Closure {
+                parameters: parameters.as_slice().into(),
+                body: Rc::new(body.clone()),
+                context: self
+                    .contexts
+                    .get(*context)
+                    .ok_or(SnapshotError::UnknownContext(*context))?
+                    .clone(),
+                effects: *effects,
+            }),
+            Encoded::Native { name } => match self.natives.get(name.as_str()) {
+                Some(native) => native.clone(),
+                None => return Err(SnapshotError::UnknownNative(name.clone())),
+            },
+            Encoded::Int { value } => Value::Int(*value),
+            Encoded::Str { value } => Value::Str(value.as_str().into()),
+            Encoded::Bool { value } => Value::Bool(*value),
+            Encoded::Tuple { first, second } => Value::Tuple(Tuple {
+                first: Box::new(self.decode(first)?),
+                second: Box::new(self.decode(second)?),
+            }),
+            Encoded::Continuation => return Err(SnapshotError::Continuation),
+            Encoded::Generator => return Err(SnapshotError::Generator),
+        })
+    }
+
+    fn decode_all<K: Clone + Eq + Hash>(
+        &self,
+        bindings: &HashMap<K, Encoded>,
+    ) -> Result<HashMap<K, Value>, SnapshotError> {
+        bindings
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), self.decode(value)?)))
+            .collect()
+    }
+}
+
+impl Snapshot {
+    /// Writes the snapshot to `path` as JSON.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+
+        serde_json::to_writer(writer, self).map_err(io::Error::from)
+    }
+
+    /// Reads a snapshot written by [`Snapshot::save`].
+    pub fn load(path: &Path) -> io::Result<Snapshot> {
+        let reader = BufReader::new(File::open(path)?);
+
+        serde_json::from_reader(reader).map_err(io::Error::from)
+    }
+}
+
+impl<I: Printer> Interpreter<I> {
+    /// Takes a snapshot of `context` and of the memoization cache.
+    pub fn snapshot(&self, context: &Context) -> Snapshot {
+        let mut encoder = Encoder::default();
+        let context = encoder.encode_all(context.iter());
+        let cache = encoder.encode_all(self.cache.iter());
+
+        Snapshot {
+            context,
+            cache,
+            contexts: encoder.contexts,
+        }
+    }
+
+    /// Replaces the memoization cache by the one of `snapshot` and returns
+    /// its bindings, to evaluate the next programs in. The snapshot can be
+    /// restored again, by other interpreters too, and the names of the
+    /// bindings are shared with it.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<Context, SnapshotError> {
+        // Captured bindings are created empty first, since they can refer to
+        // each other.
+        let decoder = Decoder {
+            natives: &self.natives,
+            contexts: (0..snapshot.contexts.len())
+                .map(|_| Rc::new(RefCell::new(Context::new())))
+                .collect(),
+        };
+
+        for (index, bindings) in snapshot.contexts.iter().enumerate() {
+            let bindings = decoder.decode_all(bindings)?;
+            *decoder.contexts[index].borrow_mut() = bindings;
+        }
+
+        let context = decoder.decode_all(&snapshot.context)?;
+        let cache: Cache = decoder.decode_all(&snapshot.cache)?;
+
+        self.cache = cache;
+
+        Ok(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{build::*, BinaryOp},
+        interpreter::{Collect, Context, Interpreter, Value},
+    };
+
+    use super::{Snapshot, SnapshotError};
+
+    fn with_double() -> Interpreter<Collect> {
+        let mut interpreter = Interpreter::new(Collect::default());
+        interpreter.register_native("double", |arguments| match arguments {
+            [Value::Int(int)] => Ok(Value::Int(int * 2)),
+            _ => Err(String::from("expected an integer")),
+        });
+
+        interpreter
+    }
+
+    #[test]
+    fn save_and_restore() {
+        let mut interpreter = with_double();
+        let mut context = Context::new();
+
+        // let sum = fn (n) => if (n == 0) { 0 } else { n + sum(n - 1) }
+        let sum = function(
+            &["n"],
+            if_(
+                binary(BinaryOp::Eq, var("n"), int(0)),
+                int(0),
+                binary(
+                    BinaryOp::Add,
+                    var("n"),
+                    call(var("sum"), vec![binary(BinaryOp::Sub, var("n"), int(1))]),
+                ),
+            ),
+        );
+        let program = let_(
+            "sum",
+            sum,
+            let_("pair", tuple(var("double"), str("text")), var("sum")),
+        );
+
+        interpreter.eval(program, &mut context).unwrap();
+        let snapshot = interpreter.snapshot(&context);
+
+        let path = std::env::temp_dir().join(format!("snapshot-{}.json", std::process::id()));
+        snapshot.save(&path).unwrap();
+        let snapshot = Snapshot::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut restored = with_double();
+        let mut context = restored.restore(&snapshot).unwrap();
+
+        let program = print(tuple(
+            call(var("sum"), vec![int(10)]),
+            call(first(var("pair")), vec![second(tuple(int(0), int(21)))]),
+        ));
+        restored.eval(program, &mut context).unwrap();
+
+        assert_eq!(restored.io.0, vec!["(55, 42)"]);
+    }
+
+    #[cfg(feature = "memo")]
+    #[test]
+    fn cache() {
+        let mut interpreter = with_double();
+        let mut context = Context::new();
+
+        let program = let_(
+            "inc",
+            function(&["n"], binary(BinaryOp::Add, var("n"), int(1))),
+            call(var("inc"), vec![int(1)]),
+        );
+        interpreter.eval(program, &mut context).unwrap();
+
+        let snapshot = interpreter.snapshot(&context);
+        let mut restored = with_double();
+        restored.restore(&snapshot).unwrap();
+
+        assert_eq!(restored.cache.len(), interpreter.cache.len());
+        assert!(!restored.cache.is_empty());
+    }
+
+    #[test]
+    fn unknown_native() {
+        let mut interpreter = with_double();
+        let mut context = Context::new();
+        interpreter
+            .eval(let_("f", var("double"), var("f")), &mut context)
+            .unwrap();
+
+        let snapshot = interpreter.snapshot(&context);
+        let error = Interpreter::new(Collect::default())
+            .restore(&snapshot)
+            .unwrap_err();
+
+        assert_eq!(error, SnapshotError::UnknownNative(String::from("double")));
+    }
+}
@@ -0,0 +1,63 @@
+use super::{Interpreter, Printer};
+
+/// What an interpreter did since it was created, see
+/// [`Interpreter::eval_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct EvalStats {
+    /// Terms evaluated.
+    pub terms: u64,
+    /// Function calls, counting the ones answered by the cache.
+    pub calls: u64,
+    /// Most calls nested at once.
+    pub max_depth: usize,
+    /// Calls to pure functions answered by the memoization cache.
+    pub memo_hits: u64,
+    /// Calls to pure functions that were evaluated and memoized.
+    pub memo_misses: u64,
+    /// Contexts created for calls and `let` bindings.
+    pub allocations: u64,
+}
+
+impl EvalStats {
+    /// Share of the calls to pure functions answered by the cache, if any
+    /// such call was made.
+    pub fn memo_hit_rate(&self) -> Option<f64> {
+        let lookups = self.memo_hits + self.memo_misses;
+        (lookups > 0).then(|| self.memo_hits as f64 / lookups as f64)
+    }
+}
+
+impl<I: Printer> Interpreter<I> {
+    /// Counts of what the interpreter did, see also
+    /// [`Interpreter::memory_stats`].
+    pub fn eval_stats(&self) -> EvalStats {
+        EvalStats {
+            calls: self.calls,
+            ..self.stats
+        }
+    }
+}
+
+#[cfg(all(test, feature = "memo"))]
+mod tests {
+    use crate::interpreter::{evaluate, Collect, Interpreter};
+
+    #[test]
+    fn counts() {
+        let source = "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+            fib(10)";
+        let mut interpreter = Interpreter::new(Collect::default());
+
+        evaluate(&mut interpreter, source).unwrap();
+
+        let stats = interpreter.eval_stats();
+        assert_eq!(stats.memo_misses, 11);
+        assert_eq!(stats.memo_hits, 8);
+        assert_eq!(stats.calls, 19);
+        assert_eq!(stats.max_depth, 10);
+        assert!(stats.terms > stats.calls, "{stats:?}");
+        assert!(stats.allocations >= 11, "{stats:?}");
+        assert_eq!(stats.memo_hit_rate(), Some(8.0 / 19.0));
+    }
+}
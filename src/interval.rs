@@ -0,0 +1,496 @@
+//! Interval analysis of the integers of a program, finding the operations
+//! whose result can exceed what an integer holds.
+//!
+//! The program is evaluated with the range of values each integer can take
+//! instead of the value itself. Calls are analyzed with the ranges of their
+//! arguments and remembered, so recursive functions called with known
+//! arguments, like `fib(100)`, are followed down to their base cases.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    sync::Arc,
+};
+
+use crate::{
+    ast::{deeper, Binary, BinaryOp, Function, Location, Term},
+    lint::Warning,
+    messages::ErrorCode,
+};
+
+/// Values an integer can take, from `lo` to `hi`. Bounds are kept wider
+/// than integers, so operations that exceed them can be told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Interval {
+    lo: i128,
+    hi: i128,
+}
+
+impl Interval {
+    fn new(lo: i128, hi: i128) -> Self {
+        Self { lo, hi }
+    }
+
+    fn single(value: i128) -> Self {
+        Self::new(value, value)
+    }
+
+    fn fits(&self) -> bool {
+        self.lo >= i128::from(i64::MIN) && self.hi <= i128::from(i64::MAX)
+    }
+
+    fn contains(&self, value: i128) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    fn join(&self, other: &Interval) -> Interval {
+        Interval::new(self.lo.min(other.lo), self.hi.max(other.hi))
+    }
+
+    /// The smallest interval holding every result of `op` between the
+    /// corners of both intervals.
+    fn corners(&self, other: &Interval, op: fn(i128, i128) -> i128) -> Interval {
+        let results = [
+            op(self.lo, other.lo),
+            op(self.lo, other.hi),
+            op(self.hi, other.lo),
+            op(self.hi, other.hi),
+        ];
+
+        Interval::new(
+            results.into_iter().min().unwrap_or_default(),
+            results.into_iter().max().unwrap_or_default(),
+        )
+    }
+
+    fn div(&self, other: &Interval) -> Option<Interval> {
+        // Dividing by the values closest to zero gives the results farthest
+        // from it.
+        let divisors = [other.lo, other.hi, -1, 1]
+            .into_iter()
+            .filter(|divisor| *divisor != 0 && other.contains(*divisor))
+            .collect::<Vec<_>>();
+
+        let results = divisors
+            .iter()
+            .flat_map(|divisor| [self.lo / divisor, self.hi / divisor]);
+
+        Some(Interval::new(results.clone().min()?, results.max()?))
+    }
+
+    fn rem(&self, other: &Interval) -> Interval {
+        let largest = other.lo.unsigned_abs().max(other.hi.unsigned_abs()) as i128;
+        let bound = (largest - 1).max(0);
+
+        match (self.lo >= 0, self.hi <= 0) {
+            (true, _) => Interval::new(0, bound.min(self.hi)),
+            (_, true) => Interval::new((-bound).max(self.lo), 0),
+            _ => Interval::new(-bound, bound),
+        }
+    }
+
+    fn compare(&self, op: &BinaryOp, other: &Interval) -> Option<bool> {
+        let (always, never) = match op {
+            BinaryOp::Eq => (
+                self.lo == self.hi && other.lo == other.hi && self.lo == other.lo,
+                self.hi < other.lo || other.hi < self.lo,
+            ),
+            BinaryOp::Neq => (
+                self.hi < other.lo || other.hi < self.lo,
+                self.lo == self.hi && other.lo == other.hi && self.lo == other.lo,
+            ),
+            BinaryOp::Lt => (self.hi < other.lo, self.lo >= other.hi),
+            BinaryOp::Lte => (self.hi <= other.lo, self.lo > other.hi),
+            BinaryOp::Gt => (self.lo > other.hi, self.hi <= other.lo),
+            BinaryOp::Gte => (self.lo >= other.hi, self.hi < other.lo),
+            _ => return None,
+        };
+
+        match (always, never) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+struct Lambda<'a> {
+    function: &'a Function,
+    scope: RefCell<Scope<'a>>,
+}
+
+/// Bindings visible to a term, innermost first.
+type Scope<'a> = Option<Rc<Binding<'a>>>;
+
+struct Binding<'a> {
+    name: Arc<str>,
+    value: Abstract<'a>,
+    next: Scope<'a>,
+}
+
+/// Scopes can be as long as the `let` chains of the program, so they are
+/// dropped one binding at a time rather than recursively.
+impl Drop for Binding<'_> {
+    fn drop(&mut self) {
+        let mut next = self.next.take();
+
+        while let Some(binding) = next {
+            next = Rc::try_unwrap(binding)
+                .ok()
+                .and_then(|mut binding| binding.next.take());
+        }
+    }
+}
+
+fn bind<'a>(scope: &Scope<'a>, name: &Arc<str>, value: Abstract<'a>) -> Scope<'a> {
+    Some(Rc::new(Binding {
+        name: name.clone(),
+        value,
+        next: scope.clone(),
+    }))
+}
+
+fn lookup<'a>(mut scope: &Scope<'a>, name: &str) -> Option<Abstract<'a>> {
+    while let Some(binding) = scope {
+        if &*binding.name == name {
+            return Some(binding.value.clone());
+        }
+
+        scope = &binding.next;
+    }
+
+    None
+}
+
+#[derive(Clone)]
+enum Abstract<'a> {
+    /// An integer in the interval, or any integer.
+    Int(Option<Interval>),
+    /// A boolean, when it is known.
+    Bool(Option<bool>),
+    Str,
+    Tuple(Rc<(Abstract<'a>, Abstract<'a>)>),
+    Closure(Rc<Lambda<'a>>),
+    /// Any value.
+    Unknown,
+}
+
+impl Abstract<'_> {
+    /// The values of both `self` and `other`.
+    fn join(self, other: Self) -> Self {
+        match (self, other) {
+            (Abstract::Int(Some(lhs)), Abstract::Int(Some(rhs))) => {
+                Abstract::Int(Some(lhs.join(&rhs)))
+            }
+            (Abstract::Int(_), Abstract::Int(_)) => Abstract::Int(None),
+            (Abstract::Bool(lhs), Abstract::Bool(rhs)) if lhs == rhs => Abstract::Bool(lhs),
+            (Abstract::Bool(_), Abstract::Bool(_)) => Abstract::Bool(None),
+            (Abstract::Str, Abstract::Str) => Abstract::Str,
+            _ => Abstract::Unknown,
+        }
+    }
+
+    /// Part of the key of the calls taking the value as argument.
+    fn key(&self) -> Key {
+        match self {
+            Abstract::Int(interval) => Key::Int(*interval),
+            Abstract::Bool(bool) => Key::Bool(*bool),
+            Abstract::Closure(lambda) => Key::Closure(Rc::as_ptr(lambda) as usize),
+            _ => Key::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Int(Option<Interval>),
+    Bool(Option<bool>),
+    Closure(usize),
+    Other,
+}
+
+/// A closure, by address, and the arguments it was called with.
+type Call = (usize, Vec<Key>);
+
+struct Analysis<'a> {
+    /// Results of the calls analyzed so far.
+    calls: HashMap<Call, Abstract<'a>>,
+    /// Calls being analyzed, whose results are not known yet.
+    active: HashSet<Call>,
+    warnings: Vec<Warning>,
+    steps: u64,
+    depth: usize,
+}
+
+/// How many terms are analyzed before giving up on the rest of the program.
+static MAX_STEPS: u64 = 200_000;
+
+/// How deeply calls are followed, the deeper ones are taken as returning
+/// any value.
+static MAX_DEPTH: usize = 200;
+
+impl<'a> Analysis<'a> {
+    fn eval(&mut self, term: &'a Term, scope: &Scope<'a>) -> Abstract<'a> {
+        deeper(|| {
+            self.steps += 1;
+
+            if self.steps > MAX_STEPS {
+                return Abstract::Unknown;
+            }
+
+            match term {
+                Term::Int(int) => Abstract::Int(Some(Interval::single(i128::from(int.value)))),
+                Term::Str(_) => Abstract::Str,
+                Term::Bool(bool) => Abstract::Bool(Some(bool.value)),
+                Term::Var(var) => lookup(scope, &var.text).unwrap_or(Abstract::Unknown),
+                Term::Function(function) => Abstract::Closure(Rc::new(Lambda {
+                    function,
+                    scope: RefCell::new(scope.clone()),
+                })),
+                Term::Let(let_) => {
+                    let value = self.eval(&let_.value, scope);
+
+                    // Functions can see their own name, so they can call
+                    // themselves.
+                    if let Abstract::Closure(lambda) = &value {
+                        let recursive =
+                            bind(&lambda.scope.borrow(), &let_.name.text, value.clone());
+                        *lambda.scope.borrow_mut() = recursive;
+                    }
+
+                    let inner = bind(scope, &let_.name.text, value);
+                    self.eval(&let_.next, &inner)
+                }
+                Term::If(if_) => match self.eval(&if_.condition, scope) {
+                    Abstract::Bool(Some(true)) => self.eval(&if_.then, scope),
+                    Abstract::Bool(Some(false)) => self.eval(&if_.otherwise, scope),
+                    _ => {
+                        let then = self.eval(&if_.then, scope);
+                        let otherwise = self.eval(&if_.otherwise, scope);
+
+                        then.join(otherwise)
+                    }
+                },
+                Term::Binary(binary) => {
+                    let lhs = self.eval(&binary.lhs, scope);
+                    let rhs = self.eval(&binary.rhs, scope);
+
+                    self.binary(binary, lhs, rhs)
+                }
+                Term::Call(call) => {
+                    let callee = self.eval(&call.callee, scope);
+                    let arguments = call
+                        .arguments
+                        .iter()
+                        .map(|argument| self.eval(argument, scope))
+                        .collect::<Vec<_>>();
+
+                    match callee {
+                        Abstract::Closure(lambda) => self.call(lambda, arguments),
+                        _ => Abstract::Unknown,
+                    }
+                }
+                Term::Print(print) => self.eval(&print.value, scope),
+                Term::Tuple(tuple) => {
+                    let first = self.eval(&tuple.first, scope);
+                    let second = self.eval(&tuple.second, scope);
+
+                    Abstract::Tuple(Rc::new((first, second)))
+                }
+                Term::First(first) => match self.eval(&first.value, scope) {
+                    Abstract::Tuple(tuple) => tuple.0.clone(),
+                    _ => Abstract::Unknown,
+                },
+                Term::Second(second) => match self.eval(&second.value, scope) {
+                    Abstract::Tuple(tuple) => tuple.1.clone(),
+                    _ => Abstract::Unknown,
+                },
+            }
+        })
+    }
+
+    fn call(&mut self, lambda: Rc<Lambda<'a>>, arguments: Vec<Abstract<'a>>) -> Abstract<'a> {
+        let key = (
+            Rc::as_ptr(&lambda) as usize,
+            arguments.iter().map(Abstract::key).collect::<Vec<_>>(),
+        );
+
+        if let Some(result) = self.calls.get(&key) {
+            return result.clone();
+        }
+
+        // Calls that go on forever, or too deep, are taken as returning
+        // any value.
+        if self.depth >= MAX_DEPTH || !self.active.insert(key.clone()) {
+            return Abstract::Unknown;
+        }
+
+        let mut scope = lambda.scope.borrow().clone();
+
+        for (parameter, argument) in lambda.function.parameters.iter().zip(arguments) {
+            scope = bind(&scope, &parameter.text, argument);
+        }
+
+        self.depth += 1;
+        let result = self.eval(&lambda.function.value, &scope);
+        self.depth -= 1;
+
+        self.active.remove(&key);
+
+        if self.steps <= MAX_STEPS {
+            self.calls.insert(key, result.clone());
+        }
+
+        result
+    }
+
+    fn binary(&mut self, binary: &Binary, lhs: Abstract<'a>, rhs: Abstract<'a>) -> Abstract<'a> {
+        let (lhs, rhs) = match (lhs, rhs) {
+            (Abstract::Int(Some(lhs)), Abstract::Int(Some(rhs))) => (lhs, rhs),
+            (Abstract::Int(_), Abstract::Int(_)) => {
+                return match binary.op {
+                    BinaryOp::Add
+                    | BinaryOp::Sub
+                    | BinaryOp::Mul
+                    | BinaryOp::Div
+                    | BinaryOp::Rem => Abstract::Int(None),
+                    _ => Abstract::Bool(None),
+                }
+            }
+            (Abstract::Bool(Some(lhs)), Abstract::Bool(Some(rhs))) => {
+                return match binary.op {
+                    BinaryOp::And => Abstract::Bool(Some(lhs && rhs)),
+                    BinaryOp::Or => Abstract::Bool(Some(lhs || rhs)),
+                    BinaryOp::Eq => Abstract::Bool(Some(lhs == rhs)),
+                    BinaryOp::Neq => Abstract::Bool(Some(lhs != rhs)),
+                    _ => Abstract::Bool(None),
+                }
+            }
+            (Abstract::Str, _) | (_, Abstract::Str) if binary.op == BinaryOp::Add => {
+                return Abstract::Str
+            }
+            (_, _) => {
+                return match binary.op {
+                    BinaryOp::Add
+                    | BinaryOp::Sub
+                    | BinaryOp::Mul
+                    | BinaryOp::Div
+                    | BinaryOp::Rem => Abstract::Unknown,
+                    _ => Abstract::Bool(None),
+                }
+            }
+        };
+
+        let result = match binary.op {
+            BinaryOp::Add => lhs.corners(&rhs, i128::saturating_add),
+            BinaryOp::Sub => lhs.corners(&rhs, i128::saturating_sub),
+            BinaryOp::Mul => lhs.corners(&rhs, i128::saturating_mul),
+            BinaryOp::Div => match lhs.div(&rhs) {
+                Some(result) => result,
+                None => return Abstract::Int(None),
+            },
+            BinaryOp::Rem => lhs.rem(&rhs),
+            ref op => return Abstract::Bool(lhs.compare(op, &rhs)),
+        };
+
+        if result.fits() {
+            return Abstract::Int(Some(result));
+        }
+
+        self.overflow(&result, &binary.location);
+
+        Abstract::Int(None)
+    }
+
+    fn overflow(&mut self, result: &Interval, location: &Location) {
+        if self
+            .warnings
+            .iter()
+            .any(|warning| &warning.location == location)
+        {
+            return;
+        }
+
+        let reached = match result.hi > i128::from(i64::MAX) {
+            true => result.hi,
+            false => result.lo,
+        };
+
+        self.warnings.push(Warning::new(
+            ErrorCode::PossibleOverflow,
+            vec![reached.to_string()],
+            location.clone(),
+        ));
+    }
+}
+
+/// Warnings about the operations of `term` whose result can exceed what an
+/// integer holds, in the order they were found.
+pub fn overflows(term: &Term) -> Vec<Warning> {
+    let mut analysis = Analysis {
+        calls: HashMap::new(),
+        active: HashSet::new(),
+        warnings: Vec::new(),
+        steps: 0,
+        depth: 0,
+    };
+
+    analysis.eval(term, &None);
+
+    analysis.warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+
+    use super::overflows;
+
+    /// What each overflow warning says the result can reach.
+    fn reached(source: &str) -> Vec<String> {
+        overflows(&parse(source, "tests").unwrap().expression)
+            .into_iter()
+            .map(|warning| warning.args[0].clone())
+            .collect()
+    }
+
+    static FIB: &str = "let fib = fn (n) => if (n < 2) { n } else { fib(n - 1) + fib(n - 2) };";
+
+    #[test]
+    fn recursion() {
+        assert!(reached(&format!("{FIB} fib(90)")).is_empty());
+        assert_eq!(
+            reached(&format!("{FIB} fib(100)")),
+            vec!["12200160415121876738"]
+        );
+
+        let factorial = "let f = fn (n) => if (n == 0) { 1 } else { n * f(n - 1) };";
+        assert!(reached(&format!("{factorial} f(20)")).is_empty());
+        assert_eq!(
+            reached(&format!("{factorial} f(21)")),
+            vec!["51090942171709440000"]
+        );
+    }
+
+    #[test]
+    fn branches() {
+        let source = "let x = if (true) { 9223372036854775807 } else { 0 }; x - 1";
+        assert!(reached(source).is_empty());
+
+        let source =
+            "let f = fn (b) => if (b) { 9223372036854775807 } else { 0 }; f(true) + f(false) + 1";
+        assert_eq!(reached(source), vec!["9223372036854775808"]);
+    }
+
+    #[test]
+    fn unknown_values() {
+        // Parameters that are never given known values can be anything,
+        // which is not reported.
+        let source = "let f = fn (n) => n * 2; f";
+        assert!(reached(source).is_empty());
+
+        let source = "let loop = fn (n, acc) => if (n == 0) { acc } else { loop(n - 1, acc + n) }; loop(1000000, 0)";
+        assert!(reached(source).is_empty());
+    }
+}
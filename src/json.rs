@@ -0,0 +1,781 @@
+//! Reading of JSON syntax trees written by other generators, which do not
+//! always follow the specification to the letter: locations may be missing,
+//! keys may be in camelCase or snake_case and kinds in other casings. Those
+//! are fixed, leaving a [`JsonWarning`] behind, before reading the tree.
+
+mod reader;
+mod writer;
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value as Json};
+
+use crate::{
+    ast::{deeper, File},
+    intern::Interner,
+    messages::{self, ErrorCode, Lang},
+};
+
+/// Every key used by the JSON syntax tree.
+static KEYS: &[&str] = &[
+    "annotation",
+    "arguments",
+    "callee",
+    "condition",
+    "end",
+    "expression",
+    "filename",
+    "first",
+    "kind",
+    "lhs",
+    "location",
+    "name",
+    "next",
+    "op",
+    "otherwise",
+    "parameters",
+    "rhs",
+    "second",
+    "start",
+    "text",
+    "then",
+    "value",
+];
+
+pub(crate) static KINDS: &[&str] = &[
+    "Int", "Str", "Call", "Binary", "Function", "Let", "If", "Print", "First", "Second", "Bool",
+    "Tuple", "Var",
+];
+
+pub(crate) static OPS: &[&str] = &[
+    "Add", "Sub", "Mul", "Div", "Rem", "Eq", "Neq", "Lt", "Gt", "Lte", "Gte", "And", "Or",
+];
+
+/// Something in a JSON syntax tree that did not follow the specification
+/// and was fixed. `path` is the JSON pointer of the offending value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonWarning {
+    pub code: ErrorCode,
+    pub args: Vec<String>,
+    pub path: String,
+}
+
+impl JsonWarning {
+    /// Short description of the warning, rendered in `lang`.
+    pub fn message(&self, lang: Lang) -> String {
+        messages::message(self.code, lang, &self.args)
+    }
+
+    /// Full explanation of the warning, rendered in `lang`.
+    pub fn full_text(&self, lang: Lang) -> String {
+        messages::full_text(self.code, lang, &self.args)
+    }
+}
+
+/// Appends `key` to the JSON pointer `path`.
+pub(crate) fn pointer(path: &str, key: &str) -> String {
+    format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// The name in `names` that `name` is spelled as, ignoring the casing and
+/// underscores.
+fn canonical(name: &str, names: &[&'static str]) -> Option<&'static str> {
+    let name = name.replace('_', "").to_lowercase();
+
+    names
+        .iter()
+        .find(|candidate| candidate.to_lowercase() == name)
+        .copied()
+}
+
+struct Normalizer {
+    filename: String,
+    warnings: Vec<JsonWarning>,
+    /// Keys from the root to the value being normalized, only joined into a
+    /// JSON pointer for the warnings.
+    path: Vec<String>,
+}
+
+impl Normalizer {
+    /// Warns about the value being normalized, or the one at its `key`.
+    fn warn(&mut self, code: ErrorCode, args: Vec<String>, key: Option<&str>) {
+        let path =
+            self.path
+                .iter()
+                .map(String::as_str)
+                .chain(key)
+                .fold(String::new(), |mut path, key| {
+                    path.push_str(&pointer("", key));
+                    path
+                });
+
+        self.warnings.push(JsonWarning { code, args, path });
+    }
+
+    fn rename_keys(&mut self, object: &mut Map<String, Json>) {
+        let renamed = object
+            .keys()
+            .filter(|key| !KEYS.contains(&key.as_str()))
+            .filter_map(|key| Some((key.clone(), canonical(key, KEYS)?)))
+            .collect::<Vec<_>>();
+
+        for (key, name) in renamed {
+            if object.contains_key(name) {
+                continue;
+            }
+
+            if let Some(value) = object.remove(&key) {
+                self.warn(
+                    ErrorCode::RenamedKey,
+                    vec![key.clone(), name.to_string()],
+                    Some(&key),
+                );
+                object.insert(name.to_string(), value);
+            }
+        }
+    }
+
+    /// Fixes the casing of the string at `key`, when it is one of `names`.
+    fn fix_case(&mut self, object: &mut Map<String, Json>, key: &str, names: &[&'static str]) {
+        let Some(Json::String(value)) = object.get_mut(key) else {
+            return;
+        };
+
+        if names.contains(&value.as_str()) {
+            return;
+        }
+
+        if let Some(name) = canonical(value, names) {
+            self.warn(
+                ErrorCode::MiscasedName,
+                vec![value.clone(), name.to_string()],
+                Some(key),
+            );
+            *value = name.to_string();
+        }
+    }
+
+    fn normalize(&mut self, json: &mut Json) {
+        deeper(|| match json {
+            Json::Object(object) => {
+                self.rename_keys(object);
+                self.fix_case(object, "kind", KINDS);
+                self.fix_case(object, "op", OPS);
+
+                // Terms and names are the ones with locations.
+                let located = object.contains_key("kind") || object.contains_key("text");
+
+                if located && !object.contains_key("location") {
+                    self.warn(ErrorCode::MissingLocation, vec![], None);
+                    object.insert(
+                        String::from("location"),
+                        serde_json::json!({ "start": 0, "end": 0, "filename": self.filename }),
+                    );
+                }
+
+                // Type annotations have kinds too, but no locations.
+                for (key, value) in object.iter_mut() {
+                    if key != "annotation" {
+                        self.path.push(key.clone());
+                        self.normalize(value);
+                        self.path.pop();
+                    }
+                }
+            }
+            Json::Array(array) => {
+                for (index, value) in array.iter_mut().enumerate() {
+                    self.path.push(index.to_string());
+                    self.normalize(value);
+                    self.path.pop();
+                }
+            }
+            _ => (),
+        })
+    }
+}
+
+/// Fixes the deviations from the specification in a JSON syntax tree, in
+/// place, returning what was fixed. Missing locations point to `filename`.
+pub fn normalize(json: &mut Json, filename: &str) -> Vec<JsonWarning> {
+    let mut normalizer = Normalizer {
+        filename: filename.to_string(),
+        warnings: Vec::new(),
+        path: Vec::new(),
+    };
+
+    if let Json::Object(file) = json {
+        normalizer.rename_keys(file);
+
+        if !file.contains_key("name") {
+            file.insert(String::from("name"), Json::from(filename));
+        }
+
+        if !file.contains_key("location") {
+            normalizer.warn(ErrorCode::MissingLocation, vec![], None);
+            file.insert(
+                String::from("location"),
+                serde_json::json!({ "start": 0, "end": 0, "filename": filename }),
+            );
+        }
+    }
+
+    normalizer.normalize(json);
+    normalizer.warnings
+}
+
+/// A place where a JSON syntax tree breaks the specification. `path` is the
+/// JSON pointer of the offending value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub code: ErrorCode,
+    pub args: Vec<String>,
+    pub path: String,
+}
+
+impl Violation {
+    /// Short description of the violation, rendered in `lang`.
+    pub fn message(&self, lang: Lang) -> String {
+        messages::message(self.code, lang, &self.args)
+    }
+
+    /// Full explanation of the violation, rendered in `lang`.
+    pub fn full_text(&self, lang: Lang) -> String {
+        messages::full_text(self.code, lang, &self.args)
+    }
+}
+
+/// Name of the JSON type of `json`, as used by the messages.
+fn json_type(json: &Json) -> &'static str {
+    match json {
+        Json::Null => "null",
+        Json::Bool(_) => "boolean",
+        Json::Number(_) => "number",
+        Json::String(_) => "string",
+        Json::Array(_) => "array",
+        Json::Object(_) => "object",
+    }
+}
+
+/// Checks the value at the path of the validator.
+type Check = fn(&mut Validator, &Json);
+
+#[derive(Default)]
+struct Validator {
+    violations: Vec<Violation>,
+    /// Keys from the root to the value being checked, only joined into a
+    /// JSON pointer for the violations.
+    path: Vec<String>,
+}
+
+impl Validator {
+    fn violate(&mut self, code: ErrorCode, args: Vec<String>) {
+        let path = self.path.iter().fold(String::new(), |mut path, key| {
+            path.push_str(&pointer("", key));
+            path
+        });
+
+        self.violations.push(Violation { code, args, path });
+    }
+
+    /// Checks that `json` has the JSON type `expected`.
+    fn expect<'a>(&mut self, json: &'a Json, expected: &str) -> Option<&'a Json> {
+        if json_type(json) == expected {
+            return Some(json);
+        }
+
+        self.violate(
+            ErrorCode::InvalidField,
+            vec![
+                self.path.last().cloned().unwrap_or_default(),
+                expected.to_string(),
+                json_type(json).to_string(),
+            ],
+        );
+
+        None
+    }
+
+    /// The value at `key` of `object`, when it is there.
+    fn field<'a>(&mut self, object: &'a Map<String, Json>, key: &str) -> Option<&'a Json> {
+        let field = object.get(key);
+
+        if field.is_none() {
+            self.violate(ErrorCode::MissingField, vec![key.to_string()]);
+        }
+
+        field
+    }
+
+    /// Checks `json`, at `key` of the value being checked, with `check`.
+    fn at(&mut self, key: String, json: &Json, check: Check) {
+        self.path.push(key);
+        check(self, json);
+        self.path.pop();
+    }
+
+    /// Checks the value at `key` of `object` with `check`.
+    fn check(&mut self, object: &Map<String, Json>, key: &str, check: Check) {
+        if let Some(json) = self.field(object, key) {
+            self.at(key.to_string(), json, check);
+        }
+    }
+
+    fn object<'a>(&mut self, json: &'a Json) -> Option<&'a Map<String, Json>> {
+        self.expect(json, "object").and_then(Json::as_object)
+    }
+
+    fn string(&mut self, json: &Json) {
+        self.expect(json, "string");
+    }
+
+    fn boolean(&mut self, json: &Json) {
+        self.expect(json, "boolean");
+    }
+
+    fn integer(&mut self, json: &Json) {
+        if self.expect(json, "number").is_some() && json.as_i64().is_none() {
+            self.violate(ErrorCode::InvalidInteger, vec![json.to_string()]);
+        }
+    }
+
+    fn offset(&mut self, json: &Json) {
+        if self.expect(json, "number").is_some() && json.as_u64().is_none() {
+            self.violate(ErrorCode::InvalidInteger, vec![json.to_string()]);
+        }
+    }
+
+    fn location(&mut self, json: &Json) {
+        let Some(object) = self.object(json) else {
+            return;
+        };
+
+        self.check(object, "start", Self::offset);
+        self.check(object, "end", Self::offset);
+        self.check(object, "filename", Self::string);
+    }
+
+    fn var(&mut self, json: &Json) {
+        let Some(object) = self.object(json) else {
+            return;
+        };
+
+        self.check(object, "text", Self::string);
+        self.check(object, "location", Self::location);
+    }
+
+    fn list(&mut self, json: &Json, check: Check) {
+        if let Some(Json::Array(array)) = self.expect(json, "array") {
+            for (index, json) in array.iter().enumerate() {
+                self.at(index.to_string(), json, check);
+            }
+        }
+    }
+
+    fn op(&mut self, json: &Json) {
+        if let Some(Json::String(op)) = self.expect(json, "string") {
+            if !OPS.contains(&op.as_str()) {
+                self.violate(ErrorCode::UnknownOperator, vec![op.clone()]);
+            }
+        }
+    }
+
+    fn kind(&mut self, json: &Json) {
+        if let Some(Json::String(kind)) = self.expect(json, "string") {
+            if !KINDS.contains(&kind.as_str()) {
+                self.violate(ErrorCode::UnknownKind, vec![kind.clone()]);
+            }
+        }
+    }
+
+    fn term(&mut self, json: &Json) {
+        deeper(|| {
+            let Some(object) = self.object(json) else {
+                return;
+            };
+
+            self.check(object, "location", Self::location);
+            self.check(object, "kind", Self::kind);
+
+            let Some(Json::String(kind)) = object.get("kind") else {
+                return;
+            };
+
+            let fields: &[(&str, Check)] = match kind.as_str() {
+                "Int" => &[("value", Self::integer)],
+                "Str" => &[("value", Self::string)],
+                "Bool" => &[("value", Self::boolean)],
+                "Var" => &[("text", Self::string)],
+                "Call" => &[
+                    ("callee", Self::term),
+                    ("arguments", |validator, json| {
+                        validator.list(json, Self::term)
+                    }),
+                ],
+                "Binary" => &[("lhs", Self::term), ("op", Self::op), ("rhs", Self::term)],
+                "Function" => &[
+                    ("parameters", |validator, json| {
+                        validator.list(json, Self::var)
+                    }),
+                    ("value", Self::term),
+                ],
+                "Let" => &[
+                    ("name", Self::var),
+                    ("value", Self::term),
+                    ("next", Self::term),
+                ],
+                "If" => &[
+                    ("condition", Self::term),
+                    ("then", Self::term),
+                    ("otherwise", Self::term),
+                ],
+                "Print" | "First" | "Second" => &[("value", Self::term)],
+                "Tuple" => &[("first", Self::term), ("second", Self::term)],
+                _ => return,
+            };
+
+            for (key, check) in fields {
+                self.check(object, key, *check);
+            }
+        })
+    }
+
+    fn file(&mut self, json: &Json) {
+        let Some(object) = self.object(json) else {
+            return;
+        };
+
+        self.check(object, "name", Self::string);
+        self.check(object, "expression", Self::term);
+        self.check(object, "location", Self::location);
+    }
+}
+
+/// Checks a JSON syntax tree against the specification, returning every
+/// place that breaks it.
+pub fn validate(json: &Json) -> Vec<Violation> {
+    let mut validator = Validator::default();
+    validator.file(json);
+
+    validator.violations
+}
+
+/// Checks the JSON syntax tree in `text`, nested at any depth, against the
+/// specification, see [`validate`].
+pub fn validate_text(text: &str) -> serde_json::Result<Vec<Violation>> {
+    let json = from_str(text)?;
+    let violations = validate(&json);
+    drop_deep(json);
+
+    Ok(violations)
+}
+
+/// Parses JSON nested at any depth, without the recursion limit of
+/// `serde_json`, growing the stack on the heap as it nests.
+pub(crate) fn from_str<T: DeserializeOwned>(text: &str) -> serde_json::Result<T> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    deserializer.disable_recursion_limit();
+
+    let value = T::deserialize(serde_stacker::Deserializer::new(&mut deserializer))?;
+    deserializer.end()?;
+
+    Ok(value)
+}
+
+/// Writes `json` as text, like its `Display` does, without recursing, so it
+/// can be nested at any depth.
+fn to_text(json: &Json) -> String {
+    enum Item<'a> {
+        Value(&'a Json),
+        Key(&'a str),
+        Text(&'static str),
+    }
+
+    let mut text = String::new();
+    let mut items = vec![Item::Value(json)];
+
+    while let Some(item) = items.pop() {
+        match item {
+            Item::Value(Json::Array(array)) => {
+                text.push('[');
+                items.push(Item::Text("]"));
+
+                for (index, value) in array.iter().enumerate().rev() {
+                    items.push(Item::Value(value));
+
+                    if index > 0 {
+                        items.push(Item::Text(","));
+                    }
+                }
+            }
+            Item::Value(Json::Object(object)) => {
+                text.push('{');
+                items.push(Item::Text("}"));
+
+                for (index, (key, value)) in object.iter().enumerate().rev() {
+                    items.push(Item::Value(value));
+                    items.push(Item::Key(key));
+
+                    if index > 0 {
+                        items.push(Item::Text(","));
+                    }
+                }
+            }
+            Item::Value(value) => text.push_str(&value.to_string()),
+            Item::Key(key) => {
+                text.push_str(&Json::from(key).to_string());
+                text.push(':');
+            }
+            Item::Text(item) => text.push_str(item),
+        }
+    }
+
+    text
+}
+
+/// Drops `json` one value at a time, since dropping a value drops the ones
+/// nested in it recursively.
+pub(crate) fn drop_deep(json: Json) {
+    let mut values = vec![json];
+
+    while let Some(json) = values.pop() {
+        match json {
+            Json::Array(array) => values.extend(array),
+            Json::Object(object) => values.extend(object.into_iter().map(|(_, value)| value)),
+            _ => (),
+        }
+    }
+}
+
+/// Reads a JSON syntax tree, tolerating the deviations fixed by
+/// [`normalize`]. Trees that follow the specification, like the ones of the
+/// official generator, are read straight from the text, which is much faster
+/// for large programs. Both handle any depth of nesting.
+pub fn read_file(text: &str, filename: &str) -> serde_json::Result<(File, Vec<JsonWarning>)> {
+    if let Some(file) = reader::read(text) {
+        return Ok((file, Vec::new()));
+    }
+
+    let mut json: Json = from_str(text)?;
+    let warnings = normalize(&mut json, filename);
+    let text = to_text(&json);
+    drop_deep(json);
+
+    // Once fixed, the tree follows the specification unless it is invalid,
+    // which serde describes best, or nested too deeply for it.
+    let file = match reader::read(&text) {
+        Some(file) => file,
+        None => {
+            let mut file: File = serde_json::from_str(&text)?;
+            file.expression = Interner::new().intern_term(file.expression);
+            file
+        }
+    };
+
+    Ok((file, warnings))
+}
+
+/// Writes a JSON syntax tree like its `Serialize` implementation, but
+/// handling any depth of nesting.
+pub fn write_file(file: &File) -> String {
+    writer::write(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            build::*,
+            visit::{fold_children, TermFolder},
+            BinaryOp, Location, Term,
+        },
+        messages::ErrorCode,
+        parser::parse,
+    };
+
+    use super::{read_file, validate, validate_text, write_file};
+
+    struct StripLocations;
+
+    impl TermFolder for StripLocations {
+        fn fold_term(&mut self, term: Term) -> Term {
+            let mut term = fold_children(self, term);
+            *term.location_mut() = Location::default();
+            term
+        }
+    }
+
+    #[test]
+    fn strict_trees_are_untouched() {
+        let text = std::fs::read_to_string("examples/fib.json").unwrap();
+        let (file, warnings) = read_file(&text, "fib.json").unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(file.name, "files/fib.rinha");
+    }
+
+    #[test]
+    fn tail_calls() {
+        let file = parse("fn (n) => { become f(n) }", "a.rinha").unwrap();
+        let text = serde_json::to_string(&file).unwrap();
+        let (read, _) = read_file(&text, "a.json").unwrap();
+
+        assert_eq!(read.expression, file.expression);
+        assert!(!serde_json::to_string(&call(var("f"), vec![]))
+            .unwrap()
+            .contains("tail"));
+    }
+
+    #[test]
+    fn written_as_serde_does() {
+        let source =
+            "let f = fn (n: int, p: (int, str)): bool => { become f(n - 1, (first(p), \"a\")) };\n\
+                      if (f(1, (2, \"b\")) == true) { print(f) } else { second((1, 2)) }";
+        let mut files = vec![parse(source, "a.rinha").unwrap()];
+        for example in ["combination", "fib", "hello-world", "sum"] {
+            let text = std::fs::read_to_string(format!("examples/{example}.json")).unwrap();
+            files.push(read_file(&text, example).unwrap().0);
+        }
+
+        for file in files {
+            assert_eq!(write_file(&file), serde_json::to_string(&file).unwrap());
+        }
+    }
+
+    #[test]
+    fn tolerant() {
+        let text = r#"{
+            "expression": {
+                "kind": "binary",
+                "LHS": { "kind": "Int", "value": 1 },
+                "op": "ADD",
+                "rhs": {
+                    "kind": "Var",
+                    "text": "x",
+                    "location": { "start": 4, "end": 5, "file_name": "a.rinha" }
+                }
+            }
+        }"#;
+
+        let (file, warnings) = read_file(text, "a.json").unwrap();
+        let codes = warnings
+            .iter()
+            .map(|warning| (warning.code, warning.path.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(file.name, "a.json");
+        assert_eq!(
+            StripLocations.fold_term(file.expression),
+            binary(BinaryOp::Add, int(1), var("x"))
+        );
+        assert_eq!(
+            codes,
+            vec![
+                (ErrorCode::MissingLocation, ""),
+                (ErrorCode::RenamedKey, "/expression/LHS"),
+                (ErrorCode::MiscasedName, "/expression/kind"),
+                (ErrorCode::MiscasedName, "/expression/op"),
+                (ErrorCode::MissingLocation, "/expression"),
+                (ErrorCode::MissingLocation, "/expression/lhs"),
+                (ErrorCode::RenamedKey, "/expression/rhs/location/file_name"),
+            ]
+        );
+    }
+
+    static LOCATION: &str = r#"{ "start": 0, "end": 1, "filename": "deep" }"#;
+
+    /// A term of `depth` nested lets, ending in `last`.
+    fn lets(depth: usize, last: &str) -> String {
+        let binding = format!(
+            r#"{{ "kind": "Let", "name": {{ "text": "x", "location": {LOCATION} }}, "value": {{ "kind": "Int", "value": 0, "location": {LOCATION} }}, "location": {LOCATION}, "next": "#
+        );
+
+        format!("{}{last}{}", binding.repeat(depth), " }".repeat(depth))
+    }
+
+    #[test]
+    fn deep_deviating_trees() {
+        let depth = 20_000;
+        let text = format!(
+            r#"{{ "expression": {} }}"#,
+            lets(depth, r#"{ "kind": "Var", "text": "x" }"#)
+        );
+
+        let (file, warnings) = read_file(&text, "deep.json").unwrap();
+        let mut term = &file.expression;
+        let mut lets = 0;
+
+        while let Term::Let(let_) = term {
+            term = &let_.next;
+            lets += 1;
+        }
+
+        assert_eq!(lets, depth);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[1].path,
+            format!("/expression{}", "/next".repeat(depth))
+        );
+    }
+
+    #[test]
+    fn validation() {
+        let text = std::fs::read_to_string("examples/combination.json").unwrap();
+        assert!(validate(&serde_json::from_str(&text).unwrap()).is_empty());
+
+        let json = serde_json::json!({
+            "name": "a.rinha",
+            "location": { "start": 0, "end": 1, "filename": "a.rinha" },
+            "expression": {
+                "kind": "Binary",
+                "lhs": { "kind": "Loop", "location": { "start": 0, "end": 1, "filename": "a" } },
+                "op": "Pow",
+                "rhs": { "kind": "Int", "value": "1" },
+                "location": { "start": -1, "end": 1, "filename": "a" }
+            }
+        });
+
+        let violations = validate(&json)
+            .into_iter()
+            .map(|violation| (violation.code, violation.path))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            violations,
+            vec![
+                (
+                    ErrorCode::InvalidInteger,
+                    String::from("/expression/location/start")
+                ),
+                (ErrorCode::UnknownKind, String::from("/expression/lhs/kind")),
+                (ErrorCode::UnknownOperator, String::from("/expression/op")),
+                (ErrorCode::MissingField, String::from("/expression/rhs")),
+                (
+                    ErrorCode::InvalidField,
+                    String::from("/expression/rhs/value")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn deep_validation() {
+        let depth = 20_000;
+        let last = format!(r#"{{ "kind": "Var", "text": 1, "location": {LOCATION} }}"#);
+        let text = format!(
+            r#"{{ "name": "deep", "location": {LOCATION}, "expression": {} }}"#,
+            lets(depth, &last)
+        );
+
+        let violations = validate_text(&text).unwrap();
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            violations[0].path,
+            format!("/expression{}/text", "/next".repeat(depth))
+        );
+    }
+
+    #[test]
+    fn unknown_kinds_still_fail() {
+        assert!(read_file(r#"{ "expression": { "kind": "Loop" } }"#, "a.json").is_err());
+    }
+}
@@ -0,0 +1,664 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::ast::{
+    Annotation, Binary, BinaryOp, Bool, Call, File, First, Function, If, Int, Let, Location, Print,
+    Second, Str, Term, Tuple, Var,
+};
+
+/// What an object, or each item of an array, is read as, which is known from
+/// the key it is the value of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    File,
+    Term,
+    Var,
+    Location,
+    Annotation,
+    /// Values of unknown keys, which are read and thrown away.
+    Skip,
+}
+
+impl Shape {
+    /// Shape of the value of `key` in an object of this shape.
+    fn child(self, key: &str) -> Shape {
+        match (self, key) {
+            (Shape::File, "expression") => Shape::Term,
+            (Shape::File | Shape::Term | Shape::Var, "location") => Shape::Location,
+            (Shape::Term | Shape::Var, "annotation") => Shape::Annotation,
+            (Shape::Term, "name" | "parameters") => Shape::Var,
+            (
+                Shape::Term,
+                "value" | "callee" | "arguments" | "lhs" | "rhs" | "condition" | "then"
+                | "otherwise" | "first" | "second" | "next",
+            ) => Shape::Term,
+            (Shape::Annotation, "first" | "second" | "parameters" | "result") => Shape::Annotation,
+            _ => Shape::Skip,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    Term(Term),
+    Var(Var),
+    Location(Location),
+    Annotation(Annotation),
+    List(Vec<Value>),
+}
+
+/// An object or an array whose values are being read.
+struct Frame<'a> {
+    shape: Shape,
+    array: bool,
+    /// Values read so far, along with their keys when the frame is an
+    /// object.
+    values: Vec<(&'a str, Value)>,
+    /// Number of values read, including the ones thrown away.
+    count: usize,
+    /// Key of the value being read, when the frame is an object.
+    key: &'a str,
+}
+
+impl<'a> Frame<'a> {
+    fn new(shape: Shape, array: bool) -> Self {
+        Frame {
+            shape,
+            array,
+            values: Vec::new(),
+            count: 0,
+            key: "",
+        }
+    }
+
+    fn push(&mut self, value: Value) {
+        self.count += 1;
+
+        if self.shape != Shape::Skip {
+            self.values.push((self.key, value));
+        }
+    }
+
+    fn take(&mut self, key: &str) -> Option<Value> {
+        let index = self.values.iter().position(|(other, _)| *other == key)?;
+        Some(self.values.swap_remove(index).1)
+    }
+
+    fn int(&mut self, key: &str) -> Option<i64> {
+        match self.take(key)? {
+            Value::Int(int) => Some(int),
+            _ => None,
+        }
+    }
+
+    fn offset(&mut self, key: &str) -> Option<usize> {
+        usize::try_from(self.int(key)?).ok()
+    }
+
+    fn str(&mut self, key: &str) -> Option<String> {
+        match self.take(key)? {
+            Value::Str(str) => Some(str),
+            _ => None,
+        }
+    }
+
+    fn term(&mut self, key: &str) -> Option<Box<Term>> {
+        match self.take(key)? {
+            Value::Term(term) => Some(Box::new(term)),
+            _ => None,
+        }
+    }
+
+    fn location(&mut self) -> Option<Location> {
+        match self.take("location")? {
+            Value::Location(location) => Some(location),
+            _ => None,
+        }
+    }
+
+    fn annotation(&mut self, key: &str) -> Option<Annotation> {
+        match self.take(key)? {
+            Value::Annotation(annotation) => Some(annotation),
+            _ => None,
+        }
+    }
+
+    /// The annotation of a binding, which can be missing or null.
+    fn optional_annotation(&mut self) -> Option<Option<Annotation>> {
+        match self.take("annotation") {
+            None | Some(Value::Null) => Some(None),
+            Some(Value::Annotation(annotation)) => Some(Some(annotation)),
+            Some(_) => None,
+        }
+    }
+
+    fn list(&mut self, key: &str) -> Option<Vec<Value>> {
+        match self.take(key)? {
+            Value::List(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+fn binary_op(op: &str) -> Option<BinaryOp> {
+    let op = match op {
+        "Add" => BinaryOp::Add,
+        "Sub" => BinaryOp::Sub,
+        "Mul" => BinaryOp::Mul,
+        "Div" => BinaryOp::Div,
+        "Rem" => BinaryOp::Rem,
+        "Eq" => BinaryOp::Eq,
+        "Neq" => BinaryOp::Neq,
+        "Lt" => BinaryOp::Lt,
+        "Gt" => BinaryOp::Gt,
+        "Lte" => BinaryOp::Lte,
+        "Gte" => BinaryOp::Gte,
+        "And" => BinaryOp::And,
+        "Or" => BinaryOp::Or,
+        _ => return None,
+    };
+
+    Some(op)
+}
+
+struct Reader<'a> {
+    text: &'a str,
+    at: usize,
+    /// Names and string literals read so far, shared by every occurrence,
+    /// like an [`Interner`](crate::intern::Interner) does.
+    names: HashSet<Arc<str>>,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.at).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(b' ' | b'\n' | b'\r' | b'\t') = self.peek() {
+            self.at += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> Option<()> {
+        self.whitespace();
+
+        if self.peek()? != byte {
+            return None;
+        }
+
+        self.at += 1;
+        Some(())
+    }
+
+    fn literal(&mut self, literal: &str, value: Value) -> Option<Value> {
+        if !self.text[self.at..].starts_with(literal) {
+            return None;
+        }
+
+        self.at += literal.len();
+        Some(value)
+    }
+
+    /// Integers, the only numbers of the syntax tree.
+    fn int(&mut self) -> Option<i64> {
+        let start = self.at;
+
+        if self.peek() == Some(b'-') {
+            self.at += 1;
+        }
+
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.at += 1;
+        }
+
+        if let Some(b'.' | b'e' | b'E') = self.peek() {
+            return None;
+        }
+
+        self.text[start..self.at].parse().ok()
+    }
+
+    /// A string without escapes, borrowed from the text, like the keys.
+    fn raw(&mut self) -> Option<&'a str> {
+        self.eat(b'"')?;
+
+        let length = self.text[self.at..].find(['"', '\\'])?;
+        let raw = &self.text[self.at..self.at + length];
+        self.at += length;
+        self.eat(b'"')?;
+
+        Some(raw)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.eat(b'"')?;
+        let mut string = String::new();
+
+        loop {
+            let length = self.text[self.at..].find(['"', '\\'])?;
+            let raw = &self.text[self.at..self.at + length];
+
+            // Control characters must be escaped.
+            if raw.bytes().any(|byte| byte < b' ') {
+                return None;
+            }
+
+            string.push_str(raw);
+            self.at += length + 1;
+
+            if self.text.as_bytes()[self.at - 1] == b'"' {
+                break;
+            }
+
+            let escape = self.peek()?;
+            self.at += 1;
+
+            match escape {
+                b'"' => string.push('"'),
+                b'\\' => string.push('\\'),
+                b'/' => string.push('/'),
+                b'b' => string.push('\u{8}'),
+                b'f' => string.push('\u{c}'),
+                b'n' => string.push('\n'),
+                b'r' => string.push('\r'),
+                b't' => string.push('\t'),
+                b'u' => string.push(self.unicode()?),
+                _ => return None,
+            }
+        }
+
+        Some(string)
+    }
+
+    fn hex(&mut self) -> Option<u32> {
+        let hex = self.text.get(self.at..self.at + 4)?;
+        self.at += 4;
+
+        u32::from_str_radix(hex, 16).ok()
+    }
+
+    /// `\uXXXX`, which may be the first half of a surrogate pair.
+    fn unicode(&mut self) -> Option<char> {
+        let high = self.hex()?;
+
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high);
+        }
+
+        if !self.text[self.at..].starts_with("\\u") {
+            return None;
+        }
+
+        self.at += 2;
+        let low = self.hex()?;
+
+        if !(0xDC00..0xE000).contains(&low) {
+            return None;
+        }
+
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+    }
+
+    fn name(&mut self, name: String) -> Arc<str> {
+        match self.names.get(name.as_str()) {
+            Some(name) => name.clone(),
+            None => {
+                let name: Arc<str> = name.into();
+                self.names.insert(name.clone());
+                name
+            }
+        }
+    }
+
+    /// Builds what `frame` is read as, once all of its values are read.
+    fn finish(&mut self, mut frame: Frame) -> Option<Value> {
+        if frame.array {
+            let values = frame.values.into_iter().map(|(_, value)| value).collect();
+            return Some(Value::List(values));
+        }
+
+        let value = match frame.shape {
+            Shape::Skip => Value::Null,
+            Shape::Location => Value::Location(Location {
+                start: frame.offset("start")?,
+                end: frame.offset("end")?,
+                filename: frame.str("filename")?,
+            }),
+            Shape::Var => Value::Var(Var {
+                text: {
+                    let text = frame.str("text")?;
+                    self.name(text)
+                },
+                annotation: frame.optional_annotation()?,
+                location: frame.location()?,
+            }),
+            Shape::Annotation => Value::Annotation(match frame.str("kind")?.as_str() {
+                "Int" => Annotation::Int,
+                "Str" => Annotation::Str,
+                "Bool" => Annotation::Bool,
+                "Tuple" => Annotation::Tuple {
+                    first: Box::new(frame.annotation("first")?),
+                    second: Box::new(frame.annotation("second")?),
+                },
+                "Function" => Annotation::Function {
+                    parameters: frame
+                        .list("parameters")?
+                        .into_iter()
+                        .map(|value| match value {
+                            Value::Annotation(annotation) => Some(annotation),
+                            _ => None,
+                        })
+                        .collect::<Option<_>>()?,
+                    result: Box::new(frame.annotation("result")?),
+                },
+                _ => return None,
+            }),
+            Shape::Term => Value::Term(self.term(&mut frame)?),
+            Shape::File => return None,
+        };
+
+        Some(value)
+    }
+
+    fn term(&mut self, frame: &mut Frame) -> Option<Term> {
+        let term = match frame.str("kind")?.as_str() {
+            "Int" => Term::Int(Int {
+                value: frame.int("value")?,
+                location: frame.location()?,
+            }),
+            "Str" => Term::Str(Str {
+                value: self.name(frame.str("value")?),
+                location: frame.location()?,
+            }),
+            "Bool" => Term::Bool(Bool {
+                value: match frame.take("value")? {
+                    Value::Bool(value) => value,
+                    _ => return None,
+                },
+                location: frame.location()?,
+            }),
+            "Var" => Term::Var(Var {
+                text: {
+                    let text = frame.str("text")?;
+                    self.name(text)
+                },
+                annotation: frame.optional_annotation()?,
+                location: frame.location()?,
+            }),
+            "Call" => Term::Call(Call {
+                callee: frame.term("callee")?,
+                arguments: frame
+                    .list("arguments")?
+                    .into_iter()
+                    .map(|value| match value {
+                        Value::Term(term) => Some(term),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+                tail: match frame.take("tail") {
+                    None | Some(Value::Null) => false,
+                    Some(Value::Bool(tail)) => tail,
+                    Some(_) => return None,
+                },
+                location: frame.location()?,
+            }),
+            "Binary" => Term::Binary(Binary {
+                lhs: frame.term("lhs")?,
+                op: binary_op(&frame.str("op")?)?,
+                rhs: frame.term("rhs")?,
+                location: frame.location()?,
+            }),
+            "Function" => Term::Function(Function {
+                parameters: frame
+                    .list("parameters")?
+                    .into_iter()
+                    .map(|value| match value {
+                        Value::Var(var) => Some(var),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+                annotation: frame.optional_annotation()?,
+                value: frame.term("value")?,
+                location: frame.location()?,
+            }),
+            "Let" => Term::Let(Let {
+                name: match frame.take("name")? {
+                    Value::Var(var) => var,
+                    _ => return None,
+                },
+                value: frame.term("value")?,
+                next: frame.term("next")?,
+                location: frame.location()?,
+            }),
+            "If" => Term::If(If {
+                condition: frame.term("condition")?,
+                then: frame.term("then")?,
+                otherwise: frame.term("otherwise")?,
+                location: frame.location()?,
+            }),
+            "Print" => Term::Print(Print {
+                value: frame.term("value")?,
+                location: frame.location()?,
+            }),
+            "First" => Term::First(First {
+                value: frame.term("value")?,
+                location: frame.location()?,
+            }),
+            "Second" => Term::Second(Second {
+                value: frame.term("value")?,
+                location: frame.location()?,
+            }),
+            "Tuple" => Term::Tuple(Tuple {
+                first: frame.term("first")?,
+                second: frame.term("second")?,
+                location: frame.location()?,
+            }),
+            _ => return None,
+        };
+
+        Some(term)
+    }
+
+    fn file(&mut self) -> Option<File> {
+        self.eat(b'{')?;
+
+        let mut stack = vec![Frame::new(Shape::File, false)];
+
+        loop {
+            self.whitespace();
+            let frame = stack.last_mut()?;
+            let end = if frame.array { b']' } else { b'}' };
+
+            if self.peek()? == end {
+                self.at += 1;
+                let mut frame = stack.pop()?;
+
+                let Some(parent) = stack.last_mut() else {
+                    self.whitespace();
+
+                    if self.at != self.text.len() {
+                        return None;
+                    }
+
+                    let Value::Term(expression) = frame.take("expression")? else {
+                        return None;
+                    };
+
+                    return Some(File {
+                        name: frame.str("name")?,
+                        expression,
+                        location: frame.location()?,
+                    });
+                };
+
+                let shape = parent.shape;
+                let value = match shape {
+                    Shape::Skip => Value::Null,
+                    _ => self.finish(frame)?,
+                };
+
+                stack.last_mut()?.push(value);
+                continue;
+            }
+
+            if frame.count > 0 {
+                self.eat(b',')?;
+            }
+
+            let shape = if frame.array {
+                frame.shape
+            } else {
+                frame.key = self.raw()?;
+                self.eat(b':')?;
+                frame.shape.child(frame.key)
+            };
+
+            self.whitespace();
+
+            let value = match self.peek()? {
+                b'{' => {
+                    self.at += 1;
+                    stack.push(Frame::new(shape, false));
+                    continue;
+                }
+                b'[' => {
+                    self.at += 1;
+                    stack.push(Frame::new(shape, true));
+                    continue;
+                }
+                b'"' => Value::Str(self.string()?),
+                b't' => self.literal("true", Value::Bool(true))?,
+                b'f' => self.literal("false", Value::Bool(false))?,
+                b'n' => self.literal("null", Value::Null)?,
+                _ => Value::Int(self.int()?),
+            };
+
+            stack.last_mut()?.push(value);
+        }
+    }
+}
+
+/// Reads a JSON syntax tree that follows the specification in a single pass
+/// over the text, without building a JSON value first nor recursing into
+/// the nested terms, so deeply nested trees of large programs don't overflow
+/// the stack. Names are shared between the variables that use them.
+///
+/// Returns `None` for anything it doesn't expect, like numbers with
+/// fractions, escaped keys or missing fields, which are left to the tolerant
+/// reader, along with the syntax errors.
+pub(crate) fn read(text: &str) -> Option<File> {
+    Reader {
+        text,
+        at: 0,
+        names: HashSet::new(),
+    }
+    .file()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{File, Term};
+
+    use super::read;
+
+    fn check(text: &str) {
+        let expected: File = serde_json::from_str(text).unwrap();
+        let file = read(text).unwrap();
+
+        assert_eq!(file.name, expected.name);
+        assert_eq!(file.expression, expected.expression);
+        assert_eq!(file.location, expected.location);
+    }
+
+    #[test]
+    fn same_trees_as_serde() {
+        for name in ["combination", "fib", "hello-world", "sum"] {
+            check(&std::fs::read_to_string(format!("examples/{name}.json")).unwrap());
+        }
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let location = r#"{ "start": 0, "end": 1, "filename": "deep" }"#;
+        let binding = format!(
+            r#"{{ "kind": "Let", "name": {{ "text": "x", "location": {location} }}, "value": {{ "kind": "Int", "value": 0, "location": {location} }}, "location": {location}, "next": "#
+        );
+
+        let depth = 5000;
+        let text = format!(
+            r#"{{ "name": "deep", "location": {location}, "expression": {}{{ "kind": "Var", "text": "x", "location": {location} }}{} }}"#,
+            binding.repeat(depth),
+            " }".repeat(depth),
+        );
+
+        let file = read(&text).unwrap();
+        let mut term = &file.expression;
+        let mut lets = 0;
+
+        while let Term::Let(let_) = term {
+            term = &let_.next;
+            lets += 1;
+        }
+
+        assert_eq!(lets, depth);
+        assert!(matches!(term, Term::Var(var) if &*var.text == "x"));
+    }
+
+    #[test]
+    fn deep_trees_drop() {
+        let location = r#"{ "start": 0, "end": 1, "filename": "deep" }"#;
+        let one = format!(r#"{{ "kind": "Int", "value": 1, "location": {location} }}"#);
+
+        let depth = 200_000;
+        let text = format!(
+            r#"{{ "name": "deep", "location": {location}, "expression": {}{one}{} }}"#,
+            r#"{ "kind": "Binary", "op": "Add", "lhs": "#.repeat(depth),
+            format!(r#", "rhs": {one}, "location": {location} }}"#).repeat(depth),
+        );
+
+        let file = read(&text).unwrap();
+        let mut term = &file.expression;
+        let mut binaries = 0;
+
+        while let Term::Binary(binary) = term {
+            term = &binary.lhs;
+            binaries += 1;
+        }
+
+        assert_eq!(binaries, depth);
+        drop(file);
+    }
+
+    #[test]
+    fn keys_in_any_order_and_escapes() {
+        let text = r#"{
+            "location": { "filename": "a", "end": 1, "start": 0 },
+            "expression": {
+                "location": { "start": 0, "end": 1, "filename": "a" },
+                "value": "\"tab\t\" é 😀",
+                "kind": "Str"
+            },
+            "extra": [1, { "nested": [true, null] }],
+            "name": "a"
+        }"#;
+
+        check(text);
+    }
+
+    #[test]
+    fn leaves_deviations_behind() {
+        for text in [
+            r#"{ "name": "a", "expression": { "kind": "int", "value": 1, "location": { "start": 0, "end": 1, "filename": "a" } }, "location": { "start": 0, "end": 1, "filename": "a" } }"#,
+            r#"{ "name": "a", "expression": { "kind": "Int", "value": 1.5, "location": { "start": 0, "end": 1, "filename": "a" } }, "location": { "start": 0, "end": 1, "filename": "a" } }"#,
+            r#"{ "name": "a", "expression": { "kind": "Int", "value": 1 }, "location": { "start": 0, "end": 1, "filename": "a" } }"#,
+            r#"{ "name": "a", "expression": { "kind": "Int", "value": 1, "location": { "start": 0, "end": 1, "filename": "a" } }, "location": { "start": 0, "end": 1, "filename": "a" } } x"#,
+            r#"{ "name": "a" }"#,
+        ] {
+            assert!(read(text).is_none(), "{text}");
+        }
+    }
+}
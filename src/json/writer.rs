@@ -0,0 +1,132 @@
+use serde::Serialize;
+
+use crate::ast::{File, Location, Term};
+
+/// What is left to write, the next piece last.
+enum Piece<'a> {
+    Text(String),
+    Term(&'a Term),
+}
+
+/// JSON of `value`, which is never nested deeply.
+fn json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).expect("syntax trees serialize to JSON")
+}
+
+/// Opening of a term of `kind` up to the value of its first `key`.
+fn open(kind: &str, key: &str) -> Piece<'static> {
+    Piece::Text(format!("{{\"kind\":\"{kind}\",\"{key}\":"))
+}
+
+/// Value of the next `key` of an object.
+fn key(key: &str) -> Piece<'static> {
+    Piece::Text(format!(",\"{key}\":"))
+}
+
+/// Closing of a term at `location`.
+fn close(location: &Location) -> Piece<'static> {
+    Piece::Text(format!(",\"location\":{}}}", json(location)))
+}
+
+/// Writes `file` as serde does, but with an explicit stack of what is left to
+/// write instead of recursion, so that it handles any depth of nesting.
+pub(crate) fn write(file: &File) -> String {
+    let mut text = format!("{{\"name\":{},\"expression\":", json(&file.name));
+    let mut pieces = vec![close(&file.location), Piece::Term(&file.expression)];
+
+    while let Some(piece) = pieces.pop() {
+        match piece {
+            Piece::Text(piece) => text.push_str(&piece),
+            Piece::Term(term) => pieces.extend(term_pieces(term).into_iter().rev()),
+        }
+    }
+
+    text
+}
+
+/// Pieces of `term`, in order.
+fn term_pieces(term: &Term) -> Vec<Piece<'_>> {
+    match term {
+        Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_) => vec![Piece::Text(json(term))],
+        Term::Let(term) => vec![
+            Piece::Text(format!(
+                "{{\"kind\":\"Let\",\"name\":{},\"value\":",
+                json(&term.name)
+            )),
+            Piece::Term(&term.value),
+            key("next"),
+            Piece::Term(&term.next),
+            close(&term.location),
+        ],
+        Term::If(term) => vec![
+            open("If", "condition"),
+            Piece::Term(&term.condition),
+            key("then"),
+            Piece::Term(&term.then),
+            key("otherwise"),
+            Piece::Term(&term.otherwise),
+            close(&term.location),
+        ],
+        Term::Binary(term) => vec![
+            open("Binary", "lhs"),
+            Piece::Term(&term.lhs),
+            Piece::Text(format!(",\"op\":{},\"rhs\":", json(&term.op))),
+            Piece::Term(&term.rhs),
+            close(&term.location),
+        ],
+        Term::Call(term) => {
+            let mut pieces = vec![open("Call", "callee"), Piece::Term(&term.callee)];
+            pieces.push(Piece::Text(String::from(",\"arguments\":[")));
+
+            for (index, argument) in term.arguments.iter().enumerate() {
+                if index > 0 {
+                    pieces.push(Piece::Text(String::from(",")));
+                }
+                pieces.push(Piece::Term(argument));
+            }
+
+            let tail = if term.tail { "],\"tail\":true" } else { "]" };
+            pieces.push(Piece::Text(String::from(tail)));
+            pieces.push(close(&term.location));
+            pieces
+        }
+        Term::Function(term) => {
+            let mut opening = format!(
+                "{{\"kind\":\"Function\",\"parameters\":{}",
+                json(&term.parameters)
+            );
+            if let Some(annotation) = &term.annotation {
+                opening.push_str(&format!(",\"annotation\":{}", json(annotation)));
+            }
+            opening.push_str(",\"value\":");
+
+            vec![
+                Piece::Text(opening),
+                Piece::Term(&term.value),
+                close(&term.location),
+            ]
+        }
+        Term::Print(term) => vec![
+            open("Print", "value"),
+            Piece::Term(&term.value),
+            close(&term.location),
+        ],
+        Term::First(term) => vec![
+            open("First", "value"),
+            Piece::Term(&term.value),
+            close(&term.location),
+        ],
+        Term::Second(term) => vec![
+            open("Second", "value"),
+            Piece::Term(&term.value),
+            close(&term.location),
+        ],
+        Term::Tuple(term) => vec![
+            open("Tuple", "first"),
+            Piece::Term(&term.first),
+            key("second"),
+            Piece::Term(&term.second),
+            close(&term.location),
+        ],
+    }
+}
@@ -0,0 +1,604 @@
+use std::{fmt::Display, iter::Peekable, ops::Range, str::CharIndices};
+
+use crate::{
+    ast::Location,
+    cst::{Trivia, TriviaKind},
+    messages::{self, ErrorCode, Lang},
+};
+
+/// Words the syntax reserves, with their tokens. They can't name bindings.
+pub static KEYWORDS: [(&str, Token); 10] = [
+    ("let", Token::Let),
+    ("fn", Token::Fn),
+    ("if", Token::If),
+    ("else", Token::Else),
+    ("true", Token::True),
+    ("false", Token::False),
+    ("print", Token::Print),
+    ("first", Token::First),
+    ("second", Token::Second),
+    ("become", Token::Become),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Token {
+    /// Magnitude of an integer literal, up to the one of [`i64::MIN`], which
+    /// only fits in an `i64` after a minus.
+    Int(u64),
+    Str(String),
+    Ident(String),
+
+    // Keywords
+    Let,
+    Fn,
+    If,
+    Else,
+    True,
+    False,
+    Print,
+    First,
+    Second,
+    Become,
+
+    // Punctuation
+    LParen,    // (
+    RParen,    // )
+    LBrace,    // {
+    RBrace,    // }
+    Comma,     // ,
+    Semicolon, // ;
+    Colon,     // :
+    Arrow,     // =>
+    Assign,    // =
+
+    // Operators
+    Plus,    // +
+    Minus,   // -
+    Star,    // *
+    Slash,   // /
+    Percent, // %
+    EqEq,    // ==
+    Neq,     // !=
+    Lt,      // <
+    Gt,      // >
+    Lte,     // <=
+    Gte,     // >=
+    And,     // &&
+    Or,      // ||
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Token::Int(int) => return write!(f, "{int}"),
+            Token::Str(str) => return write!(f, "{str:?}"),
+            Token::Ident(ident) => return f.write_str(ident),
+            Token::Let => "let",
+            Token::Fn => "fn",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Print => "print",
+            Token::First => "first",
+            Token::Second => "second",
+            Token::Become => "become",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::Comma => ",",
+            Token::Semicolon => ";",
+            Token::Colon => ":",
+            Token::Arrow => "=>",
+            Token::Assign => "=",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::EqEq => "==",
+            Token::Neq => "!=",
+            Token::Lt => "<",
+            Token::Gt => ">",
+            Token::Lte => "<=",
+            Token::Gte => ">=",
+            Token::And => "&&",
+            Token::Or => "||",
+        };
+
+        f.write_str(text)
+    }
+}
+
+/// A token and where it is in the source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned {
+    pub token: Token,
+    pub location: Location,
+}
+
+/// An error found while reading the source code, before evaluation.
+#[derive(Debug, Clone)]
+pub struct SyntaxError {
+    pub code: ErrorCode,
+    pub args: Vec<String>,
+    pub location: Location,
+}
+
+impl SyntaxError {
+    pub fn new(code: ErrorCode, args: Vec<String>, location: Location) -> Self {
+        Self {
+            code,
+            args,
+            location,
+        }
+    }
+
+    /// Short description of the error, rendered in `lang`.
+    pub fn message(&self, lang: Lang) -> String {
+        messages::message(self.code, lang, &self.args)
+    }
+
+    /// Full explanation of the error, rendered in `lang`.
+    pub fn full_text(&self, lang: Lang) -> String {
+        messages::full_text(self.code, lang, &self.args)
+    }
+
+    /// Byte range of the source file where the error was found.
+    pub fn span(&self) -> Range<usize> {
+        self.location.span()
+    }
+}
+
+/// Renders the error in English, use [`SyntaxError::message`] for other
+/// languages.
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} at {}",
+            self.code,
+            self.message(Lang::En),
+            self.location
+        )
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+struct Lexer<'a> {
+    source: &'a str,
+    filename: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    /// The trivia skipped since the last token, when they are kept.
+    trivia: Option<Vec<Trivia>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn location(&self, start: usize, end: usize) -> Location {
+        Location::new(start, end, self.filename)
+    }
+
+    /// Byte offset of the next character.
+    fn offset(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map(|(offset, _)| *offset)
+            .unwrap_or(self.source.len())
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.chars.next_if(|(_, char)| *char == expected).is_some()
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), SyntaxError> {
+        loop {
+            let start = self.offset();
+
+            let kind = match self.chars.peek() {
+                Some((_, char)) if char.is_whitespace() => {
+                    while self
+                        .chars
+                        .next_if(|(_, char)| char.is_whitespace())
+                        .is_some()
+                    {}
+                    TriviaKind::Whitespace
+                }
+                Some((_, '/')) => {
+                    let mut lookahead = self.chars.clone();
+                    lookahead.next();
+
+                    match lookahead.next() {
+                        Some((_, '/')) => {
+                            while self.chars.next_if(|(_, char)| *char != '\n').is_some() {}
+                            TriviaKind::LineComment
+                        }
+                        Some((_, '*')) => {
+                            self.chars.next();
+                            self.chars.next();
+                            self.skip_block_comment(start)?;
+                            TriviaKind::BlockComment
+                        }
+                        _ => return Ok(()),
+                    }
+                }
+                _ => return Ok(()),
+            };
+
+            let end = self.offset();
+
+            if let Some(trivia) = &mut self.trivia {
+                trivia.push(Trivia {
+                    kind,
+                    text: self.source[start..end].to_string(),
+                    location: Location::new(start, end, self.filename),
+                });
+            }
+        }
+    }
+
+    fn skip_block_comment(&mut self, start: usize) -> Result<(), SyntaxError> {
+        while let Some((_, char)) = self.chars.next() {
+            if char == '*' && self.eat('/') {
+                return Ok(());
+            }
+        }
+
+        Err(SyntaxError::new(
+            ErrorCode::UnterminatedComment,
+            vec![],
+            self.location(start, self.source.len()),
+        ))
+    }
+
+    /// `1_000`, `0xFF` or `0b1010`, whose underscores are ignored.
+    fn int(&mut self, start: usize, first: char) -> Result<Token, SyntaxError> {
+        let radix = match (first, self.chars.peek()) {
+            ('0', Some((_, 'x' | 'X'))) => 16,
+            ('0', Some((_, 'b' | 'B'))) => 2,
+            _ => 10,
+        };
+
+        let prefix = if radix == 10 {
+            0
+        } else {
+            self.chars.next();
+            2
+        };
+
+        // Letters are read too, so that `0b12` or `1st` are reported whole.
+        while self
+            .chars
+            .next_if(|(_, char)| char.is_ascii_alphanumeric() || *char == '_')
+            .is_some()
+        {}
+
+        let end = self.offset();
+        let text = &self.source[start..end];
+        let digits = text[prefix..].replace('_', "");
+
+        if digits.is_empty() || !digits.chars().all(|char| char.is_digit(radix)) {
+            return Err(SyntaxError::new(
+                ErrorCode::InvalidDigit,
+                vec![text.to_string(), radix.to_string()],
+                self.location(start, end),
+            ));
+        }
+
+        match u64::from_str_radix(&digits, radix) {
+            Ok(int) if int <= i64::MIN.unsigned_abs() => Ok(Token::Int(int)),
+            _ => Err(SyntaxError::new(
+                ErrorCode::IntegerOverflow,
+                vec![text.to_string()],
+                self.location(start, end),
+            )),
+        }
+    }
+
+    fn str(&mut self, start: usize) -> Result<Token, SyntaxError> {
+        let mut str = String::new();
+
+        while let Some((offset, char)) = self.chars.next() {
+            match char {
+                '"' => return Ok(Token::Str(str)),
+                '\\' => match self.chars.next() {
+                    Some((_, 'n')) => str.push('\n'),
+                    Some((_, 't')) => str.push('\t'),
+                    Some((_, 'r')) => str.push('\r'),
+                    Some((_, '0')) => str.push('\0'),
+                    Some((_, '"')) => str.push('"'),
+                    Some((_, '\\')) => str.push('\\'),
+                    Some((end, escape)) => {
+                        return Err(SyntaxError::new(
+                            ErrorCode::InvalidEscape,
+                            vec![escape.to_string()],
+                            self.location(offset, end + escape.len_utf8()),
+                        ))
+                    }
+                    None => break,
+                },
+                char => str.push(char),
+            }
+        }
+
+        Err(SyntaxError::new(
+            ErrorCode::UnterminatedString,
+            vec![],
+            self.location(start, self.source.len()),
+        ))
+    }
+
+    fn ident(&mut self, start: usize) -> Token {
+        while self
+            .chars
+            .next_if(|(_, char)| unicode_ident::is_xid_continue(*char))
+            .is_some()
+        {}
+
+        let ident = &self.source[start..self.offset()];
+
+        match KEYWORDS.iter().find(|(keyword, _)| *keyword == ident) {
+            Some((_, token)) => token.clone(),
+            None => Token::Ident(ident.to_string()),
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Result<Spanned, SyntaxError>> {
+        if let Err(error) = self.skip_trivia() {
+            return Some(Err(error));
+        }
+
+        let (start, char) = self.chars.next()?;
+
+        let token = match char {
+            '(' => Ok(Token::LParen),
+            ')' => Ok(Token::RParen),
+            '{' => Ok(Token::LBrace),
+            '}' => Ok(Token::RBrace),
+            ',' => Ok(Token::Comma),
+            ';' => Ok(Token::Semicolon),
+            ':' => Ok(Token::Colon),
+            '+' => Ok(Token::Plus),
+            '-' => Ok(Token::Minus),
+            '*' => Ok(Token::Star),
+            '/' => Ok(Token::Slash),
+            '%' => Ok(Token::Percent),
+            '=' if self.eat('>') => Ok(Token::Arrow),
+            '=' if self.eat('=') => Ok(Token::EqEq),
+            '=' => Ok(Token::Assign),
+            '!' if self.eat('=') => Ok(Token::Neq),
+            '<' if self.eat('=') => Ok(Token::Lte),
+            '<' => Ok(Token::Lt),
+            '>' if self.eat('=') => Ok(Token::Gte),
+            '>' => Ok(Token::Gt),
+            '&' if self.eat('&') => Ok(Token::And),
+            '|' if self.eat('|') => Ok(Token::Or),
+            '"' => self.str(start),
+            char if char.is_ascii_digit() => self.int(start, char),
+            // Identifiers follow UAX #31, like `soma_até`, and can also start
+            // with `_`.
+            char if unicode_ident::is_xid_start(char) || char == '_' => Ok(self.ident(start)),
+            char => Err(SyntaxError::new(
+                ErrorCode::UnexpectedCharacter,
+                vec![char.to_string()],
+                self.location(start, start + char.len_utf8()),
+            )),
+        };
+
+        let end = self.offset();
+        let location = self.location(start, end);
+
+        Some(token.map(|token| Spanned { token, location }))
+    }
+}
+
+/// Splits `source` into tokens, skipping whitespace and comments.
+pub fn lex(source: &str, filename: &str) -> Result<Vec<Spanned>, SyntaxError> {
+    let mut lexer = Lexer {
+        source,
+        filename,
+        chars: source.char_indices().peekable(),
+        trivia: None,
+    };
+
+    std::iter::from_fn(|| lexer.next_token()).collect()
+}
+
+/// Tokens of a source along with the whitespace and comments around them.
+#[derive(Debug, Clone)]
+pub(crate) struct Lossless {
+    pub tokens: Vec<Spanned>,
+    /// The trivia before each token.
+    pub leading: Vec<Vec<Trivia>>,
+    /// The trivia after the last token.
+    pub trailing: Vec<Trivia>,
+}
+
+/// Splits `source` into tokens like [`lex`], keeping the whitespace and
+/// comments before each token.
+pub(crate) fn lex_lossless(source: &str, filename: &str) -> Result<Lossless, SyntaxError> {
+    let mut lexer = Lexer {
+        source,
+        filename,
+        chars: source.char_indices().peekable(),
+        trivia: Some(Vec::new()),
+    };
+
+    let mut lossless = Lossless {
+        tokens: Vec::new(),
+        leading: Vec::new(),
+        trailing: Vec::new(),
+    };
+
+    while let Some(spanned) = lexer.next_token() {
+        lossless.tokens.push(spanned?);
+        lossless
+            .leading
+            .push(lexer.trivia.replace(Vec::new()).unwrap_or_default());
+    }
+
+    lossless.trailing = lexer.trivia.unwrap_or_default();
+
+    Ok(lossless)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::ErrorCode;
+
+    use crate::cst::TriviaKind;
+
+    use super::{lex, lex_lossless, Token};
+
+    fn tokens(source: &str) -> Vec<Token> {
+        lex(source, "tests")
+            .unwrap()
+            .into_iter()
+            .map(|spanned| spanned.token)
+            .collect()
+    }
+
+    #[test]
+    fn let_binding() {
+        assert_eq!(
+            tokens("let x = fn (n) => { n >= 10 };"),
+            vec![
+                Token::Let,
+                Token::Ident("x".to_string()),
+                Token::Assign,
+                Token::Fn,
+                Token::LParen,
+                Token::Ident("n".to_string()),
+                Token::RParen,
+                Token::Arrow,
+                Token::LBrace,
+                Token::Ident("n".to_string()),
+                Token::Gte,
+                Token::Int(10),
+                Token::RBrace,
+                Token::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_comments() {
+        assert_eq!(
+            tokens("// line\n1 /* block\n */ + 2"),
+            vec![Token::Int(1), Token::Plus, Token::Int(2)]
+        );
+    }
+
+    #[test]
+    fn keep_comments() {
+        let lossless = lex_lossless("// line\n1 /* block */ +2 ", "tests").unwrap();
+
+        let kinds = |trivia: &[crate::cst::Trivia]| {
+            trivia.iter().map(|trivia| trivia.kind).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            kinds(&lossless.leading[0]),
+            vec![TriviaKind::LineComment, TriviaKind::Whitespace]
+        );
+        assert_eq!(
+            kinds(&lossless.leading[1]),
+            vec![
+                TriviaKind::Whitespace,
+                TriviaKind::BlockComment,
+                TriviaKind::Whitespace
+            ]
+        );
+        assert!(lossless.leading[2].is_empty());
+        assert_eq!(lossless.trailing[0].text, " ");
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(
+            tokens(r#""a\"b\n""#),
+            vec![Token::Str("a\"b\n".to_string())]
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let spanned = lex("print (\"hi\")", "tests").unwrap();
+
+        assert_eq!(
+            (spanned[2].location.start, spanned[2].location.end),
+            (7, 11)
+        );
+    }
+
+    #[test]
+    fn unicode_identifiers() {
+        let spanned = lex("let soma_até = ação1 + _π;", "tests").unwrap();
+
+        assert_eq!(spanned[1].token, Token::Ident("soma_até".to_string()));
+        assert_eq!(
+            (spanned[1].location.start, spanned[1].location.end),
+            (4, 13)
+        );
+        assert_eq!(spanned[3].token, Token::Ident("ação1".to_string()));
+        assert_eq!(spanned[5].token, Token::Ident("_π".to_string()));
+
+        let source = "let é = 1;\nlet x = é € 2";
+        let error = lex(source, "tests").unwrap_err();
+        assert_eq!(error.code, ErrorCode::UnexpectedCharacter);
+        assert_eq!((error.location.start, error.location.end), (23, 26));
+        assert_eq!(error.location.line_column(source), (2, 11));
+    }
+
+    #[test]
+    fn unterminated_string() {
+        let error = lex("\"abc", "tests").unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::UnterminatedString);
+    }
+
+    #[test]
+    fn integer_overflow() {
+        let error = lex("99999999999999999999", "tests").unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::IntegerOverflow);
+
+        let error = lex("9223372036854775809", "tests").unwrap_err();
+        assert_eq!(error.code, ErrorCode::IntegerOverflow);
+
+        let error = lex("1 + 0x1_0000_0000_0000_0000", "tests").unwrap_err();
+        assert_eq!(error.code, ErrorCode::IntegerOverflow);
+        assert_eq!((error.location.start, error.location.end), (4, 27));
+    }
+
+    #[test]
+    fn integer_literals() {
+        assert_eq!(
+            tokens("1_000_000 0xFF 0Xff 0b1010 0b_1 007"),
+            vec![
+                Token::Int(1_000_000),
+                Token::Int(255),
+                Token::Int(255),
+                Token::Int(10),
+                Token::Int(1),
+                Token::Int(7),
+            ]
+        );
+        assert_eq!(
+            tokens("0x7FFF_FFFF_FFFF_FFFF"),
+            vec![Token::Int(i64::MAX as u64)]
+        );
+        assert_eq!(
+            tokens("9223372036854775808"),
+            vec![Token::Int(i64::MIN.unsigned_abs())]
+        );
+
+        for source in ["0b102", "0x", "0xG", "12ab"] {
+            let error = lex(source, "tests").unwrap_err();
+
+            assert_eq!(error.code, ErrorCode::InvalidDigit, "{source}");
+            assert_eq!(error.location.end, source.len());
+        }
+    }
+}
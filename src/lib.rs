@@ -1,3 +1,88 @@
+//! An interpreter for the rinha language, which can be embedded in other Rust
+//! programs.
+//!
+//! ```
+//! use lipsum::{parse, Collect, Context, Interpreter};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let file = parse("let x = 20; print(x + 1)", "example.rinha")?;
+//! let mut interpreter = Interpreter::new(Collect::default());
+//! let value = interpreter.eval(file.expression, &mut Context::new())?;
+//!
+//! assert_eq!(value.to_string(), "21");
+//! assert_eq!(interpreter.io.0, vec!["21"]);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Both [`SyntaxError`] and [`RuntimeError`] implement
+//! [`std::error::Error`], and their `span` is the byte range of the source
+//! where they happened.
+//!
+//! # Stability
+//!
+//! The items re-exported at the root of the crate and the [`ast`] module
+//! follow semantic versioning: they only change in incompatible ways in a
+//! new major version. The other modules back the command line tool and may
+//! change in any release.
+
 pub mod ast;
 pub mod binary;
+#[cfg(feature = "json")]
+pub mod bundle;
+#[cfg(feature = "json")]
+pub mod cache;
+pub mod callgraph;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod convert;
+pub mod cst;
+pub mod debugger;
+pub mod diff;
+pub mod doc;
+pub mod effects;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "heap-profile")]
+pub mod heap;
+pub mod highlight;
+pub mod incremental;
+pub mod intern;
 pub mod interpreter;
+pub mod interval;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod lexer;
+pub mod lint;
+pub mod literate;
+pub mod messages;
+pub mod minify;
+pub mod observer;
+pub mod parser;
+pub mod pass;
+pub mod profile;
+#[cfg(feature = "config")]
+pub mod protocol;
+pub mod repl;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod spec;
+pub mod stats;
+pub mod symbolic;
+#[cfg(feature = "config")]
+pub mod sync;
+pub mod typecheck;
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use convert::ConversionError;
+pub use intern::Interner;
+pub use interpreter::{
+    capture, Callback, CancelToken, Channel, Collect, Context, EvalError, EvalStats, Interpreter,
+    MemoryStats, Printer, RuntimeError, Session, Sink, Step, Trace, Value, IO,
+};
+pub use lexer::SyntaxError;
+pub use messages::{ErrorCode, Lang};
+pub use observer::EvalObserver;
+pub use parser::parse;
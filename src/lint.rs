@@ -0,0 +1,804 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{
+        deeper,
+        visit::{walk_term, TermVisitor},
+        Binary, BinaryOp, Call, Element, Function, If, Let, Location, Term, Var,
+    },
+    interpreter::Value,
+    interval,
+    messages::{self, ErrorCode, Lang},
+};
+
+/// A binding whose value is known without running the program, one of the
+/// reasons why a term is constant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub name: String,
+    /// The value bound, as the program would print it.
+    pub value: String,
+    pub location: Location,
+}
+
+/// Something suspicious found in a program that does not prevent it from
+/// running.
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub code: ErrorCode,
+    pub args: Vec<String>,
+    pub location: Location,
+    /// Another place of the program the warning is about, like the
+    /// definition of the function being called.
+    pub related: Option<Location>,
+    /// The bindings that make the term constant, for the warnings about
+    /// constant terms, outermost first.
+    pub chain: Vec<Step>,
+}
+
+impl Warning {
+    pub fn new(code: ErrorCode, args: Vec<String>, location: Location) -> Self {
+        Self {
+            code,
+            args,
+            location,
+            related: None,
+            chain: Vec::new(),
+        }
+    }
+
+    pub fn with_related(mut self, location: Location) -> Self {
+        self.related = Some(location);
+
+        self
+    }
+
+    pub fn with_chain(mut self, chain: Vec<Step>) -> Self {
+        self.chain = chain;
+
+        self
+    }
+
+    /// Short description of the warning, rendered in `lang`.
+    pub fn message(&self, lang: Lang) -> String {
+        messages::message(self.code, lang, &self.args)
+    }
+
+    /// Full explanation of the warning, rendered in `lang`.
+    pub fn full_text(&self, lang: Lang) -> String {
+        messages::full_text(self.code, lang, &self.args)
+    }
+}
+
+/// Name of each lint, which can be used in place of its code to set its
+/// [`Level`].
+pub static LINTS: &[(ErrorCode, &str)] = &[
+    (ErrorCode::UnusedBinding, "unused-binding"),
+    (ErrorCode::ShadowedName, "shadowed-name"),
+    (ErrorCode::ConstantCondition, "constant-condition"),
+    (ErrorCode::MismatchedComparison, "mismatched-comparison"),
+    (ErrorCode::NoBaseCase, "no-base-case"),
+    (ErrorCode::WrongArgumentCount, "wrong-argument-count"),
+    (ErrorCode::UnusedParameter, "unused-parameter"),
+    (ErrorCode::ConstantOverflow, "constant-overflow"),
+    (
+        ErrorCode::ConstantDivisionByZero,
+        "constant-division-by-zero",
+    ),
+    (ErrorCode::PossibleOverflow, "possible-overflow"),
+];
+
+/// The lint named `lint`, by its code, like `W0002`, or by its name, like
+/// `shadowed-name`.
+pub fn find_lint(lint: &str) -> Option<ErrorCode> {
+    LINTS
+        .iter()
+        .find(|(code, name)| *name == lint || code.to_string().eq_ignore_ascii_case(lint))
+        .map(|(code, _)| *code)
+}
+
+/// How the warnings of a lint are reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Level {
+    /// Not reported.
+    Allow,
+    /// Reported, without failing.
+    #[default]
+    Warn,
+    /// Reported as an error.
+    Deny,
+}
+
+/// The level of each lint. Levels set for a lint win over the one set for
+/// every lint, with the `warnings` group.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Levels {
+    warnings: Option<Level>,
+    lints: HashMap<ErrorCode, Level>,
+}
+
+impl Levels {
+    /// Sets the level of `lint`, a lint code or name or `warnings` for every
+    /// lint, replacing the level it had.
+    pub fn set(&mut self, lint: &str, level: Level) -> Result<(), String> {
+        if lint == "warnings" {
+            self.warnings = Some(level);
+            return Ok(());
+        }
+
+        let code = find_lint(lint).ok_or_else(|| format!("unknown lint `{lint}`"))?;
+        self.lints.insert(code, level);
+
+        Ok(())
+    }
+
+    pub fn level(&self, code: ErrorCode) -> Level {
+        self.lints
+            .get(&code)
+            .copied()
+            .or(self.warnings)
+            .unwrap_or_default()
+    }
+}
+
+struct Binding {
+    name: String,
+    location: Location,
+    used: bool,
+    /// Number of parameters, when the binding is a function.
+    arity: Option<usize>,
+    /// The value, when it is known without running the program.
+    value: Option<Constant>,
+    /// Index of the binding of the same name it shadows.
+    shadowed: Option<usize>,
+}
+
+/// A value known without running the program, along with the bindings it
+/// was computed from.
+#[derive(Clone)]
+struct Constant {
+    value: Value,
+    chain: Vec<Step>,
+}
+
+impl Constant {
+    /// The bindings `self` and `other` were computed from, each one once.
+    fn merge(mut self, other: Constant) -> Vec<Step> {
+        for step in other.chain {
+            if !self.chain.contains(&step) {
+                self.chain.push(step);
+            }
+        }
+
+        self.chain
+    }
+}
+
+#[derive(Default)]
+struct Linter {
+    scopes: Vec<Binding>,
+    /// Index of the innermost binding of each name in `scopes`.
+    innermost: HashMap<String, usize>,
+    warnings: Vec<Warning>,
+}
+
+/// Names starting with `_` are meant to be ignored, like `let _ = print(1)`.
+fn is_ignored(name: &str) -> bool {
+    name.starts_with('_')
+}
+
+/// Kind of the value of a literal term, if the term is a literal.
+fn literal_kind(term: &Term) -> Option<&'static str> {
+    match term {
+        Term::Int(_) => Some("Int"),
+        Term::Str(_) => Some("Str"),
+        Term::Bool(_) => Some("Bool"),
+        Term::Tuple(_) => Some("Tuple"),
+        Term::Function(_) => Some("Closure"),
+        _ => None,
+    }
+}
+
+/// Whether every way of evaluating `term` ends up calling `name`.
+fn always_calls(term: &Term, name: &str) -> bool {
+    match term {
+        Term::Call(call) => {
+            matches!(call.callee.as_ref(), Term::Var(var) if &*var.text == name)
+                || always_calls(&call.callee, name)
+                || call
+                    .arguments
+                    .iter()
+                    .any(|argument| always_calls(argument, name))
+        }
+        Term::If(if_) => {
+            always_calls(&if_.condition, name)
+                || (always_calls(&if_.then, name) && always_calls(&if_.otherwise, name))
+        }
+        Term::Let(let_) => {
+            always_calls(&let_.value, name)
+                || (&*let_.name.text != name && always_calls(&let_.next, name))
+        }
+        Term::Binary(binary) => always_calls(&binary.lhs, name) || always_calls(&binary.rhs, name),
+        Term::Tuple(tuple) => always_calls(&tuple.first, name) || always_calls(&tuple.second, name),
+        Term::Print(print) => always_calls(&print.value, name),
+        Term::First(first) => always_calls(&first.value, name),
+        Term::Second(second) => always_calls(&second.value, name),
+        Term::Function(_) | Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_) => false,
+    }
+}
+
+impl Linter {
+    fn warn(&mut self, code: ErrorCode, args: Vec<String>, location: &Location) {
+        self.warnings
+            .push(Warning::new(code, args, location.clone()));
+    }
+
+    /// The innermost binding of `name`.
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        let index = self.innermost.get(name)?;
+
+        self.scopes.get(*index)
+    }
+
+    fn bind(&mut self, name: &Var) {
+        let shadowed = self
+            .innermost
+            .insert(name.text.to_string(), self.scopes.len());
+
+        self.scopes.push(Binding {
+            name: name.text.to_string(),
+            location: name.location.clone(),
+            used: false,
+            arity: None,
+            value: None,
+            shadowed,
+        });
+    }
+
+    fn bind_constant(&mut self, name: &Var, value: Option<Constant>) {
+        self.bind(name);
+
+        if let Some(binding) = self.scopes.last_mut() {
+            binding.value = value;
+        }
+    }
+
+    /// The value of `term`, when it is known without running the program.
+    fn constant(&self, term: &Term) -> Option<Constant> {
+        let value = match term {
+            Term::Int(int) => Value::Int(int.value),
+            Term::Str(str) => Value::Str(str.value.clone().into()),
+            Term::Bool(bool) => Value::Bool(bool.value),
+            Term::Var(var) => {
+                let binding = self.binding(&var.text)?;
+                let mut constant = binding.value.clone()?;
+
+                constant.chain.push(Step {
+                    name: binding.name.clone(),
+                    value: constant.value.to_string(),
+                    location: binding.location.clone(),
+                });
+
+                return Some(constant);
+            }
+            Term::Binary(binary) => {
+                let lhs = self.constant(&binary.lhs)?;
+                let rhs = self.constant(&binary.rhs)?;
+                let value = lhs
+                    .value
+                    .operate(&binary.op, &rhs.value, &binary.location)
+                    .ok()?;
+
+                return Some(Constant {
+                    value,
+                    chain: lhs.merge(rhs),
+                });
+            }
+            Term::If(if_) => {
+                let condition = self.constant(&if_.condition)?;
+                let branch = match condition.value {
+                    Value::Bool(true) => self.constant(&if_.then)?,
+                    Value::Bool(false) => self.constant(&if_.otherwise)?,
+                    _ => return None,
+                };
+
+                return Some(Constant {
+                    value: branch.value.clone(),
+                    chain: condition.merge(branch),
+                });
+            }
+            _ => return None,
+        };
+
+        Some(Constant {
+            value,
+            chain: Vec::new(),
+        })
+    }
+
+    fn bind_function(&mut self, name: &Var, function: &Function) {
+        self.bind(name);
+
+        if let Some(binding) = self.scopes.last_mut() {
+            binding.arity = Some(function.parameters.len());
+        }
+    }
+
+    /// Removes the innermost binding, telling whether it was used.
+    fn unbind(&mut self) -> bool {
+        let Some(binding) = self.scopes.pop() else {
+            return true;
+        };
+
+        match binding.shadowed {
+            Some(index) => self.innermost.insert(binding.name, index),
+            None => self.innermost.remove(&binding.name),
+        };
+
+        binding.used
+    }
+
+    fn use_var(&mut self, name: &str) {
+        if let Some(&index) = self.innermost.get(name) {
+            self.scopes[index].used = true;
+        }
+    }
+
+    fn lint_let(&mut self, let_: &Let) {
+        let name = &let_.name.text;
+
+        if let Some(shadowed) = self.binding(name).filter(|_| !is_ignored(name)) {
+            self.warnings.push(
+                Warning::new(
+                    ErrorCode::ShadowedName,
+                    vec![name.to_string()],
+                    let_.name.location.clone(),
+                )
+                .with_related(shadowed.location.clone()),
+            );
+        }
+
+        match let_.value.as_ref() {
+            // Functions can see their own name, so they can call themselves.
+            Term::Function(function) => {
+                if always_calls(&function.value, name) {
+                    self.warn(
+                        ErrorCode::NoBaseCase,
+                        vec![name.to_string()],
+                        &let_.name.location,
+                    );
+                }
+
+                self.bind_function(&let_.name, function);
+                self.lint_function(function);
+                self.unbind();
+                self.bind_function(&let_.name, function);
+            }
+            value => {
+                self.visit_term(value);
+                let constant = self.constant(value);
+                self.bind_constant(&let_.name, constant);
+            }
+        }
+
+        self.visit_term(&let_.next);
+
+        if !self.unbind() && !is_ignored(name) {
+            self.warn(
+                ErrorCode::UnusedBinding,
+                vec![name.to_string()],
+                &let_.name.location,
+            );
+        }
+    }
+
+    fn lint_function(&mut self, function: &Function) {
+        for parameter in &function.parameters {
+            self.bind(parameter);
+        }
+
+        self.visit_term(&function.value);
+
+        for parameter in function.parameters.iter().rev() {
+            if !self.unbind() && !is_ignored(&parameter.text) {
+                self.warn(
+                    ErrorCode::UnusedParameter,
+                    vec![parameter.text.to_string()],
+                    &parameter.location,
+                );
+            }
+        }
+    }
+
+    fn lint_call(&mut self, call: &Call) {
+        if let Term::Var(var) = call.callee.as_ref() {
+            let function = self
+                .binding(&var.text)
+                .and_then(|binding| Some((binding.arity?, binding.location.clone())));
+
+            match function {
+                Some((parameters, definition)) if parameters != call.arguments.len() => {
+                    self.warnings.push(
+                        Warning::new(
+                            ErrorCode::WrongArgumentCount,
+                            vec![
+                                var.text.to_string(),
+                                parameters.to_string(),
+                                call.arguments.len().to_string(),
+                            ],
+                            call.location.clone(),
+                        )
+                        .with_related(definition),
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        self.visit_term(&call.callee);
+
+        for argument in &call.arguments {
+            self.visit_term(argument);
+        }
+    }
+
+    fn lint_if(&mut self, if_: &If) {
+        if let Some(Constant {
+            value: Value::Bool(bool),
+            chain,
+        }) = self.constant(&if_.condition)
+        {
+            let unreachable = match bool {
+                true => "else",
+                false => "then",
+            };
+
+            self.warnings.push(
+                Warning::new(
+                    ErrorCode::ConstantCondition,
+                    vec![bool.to_string(), unreachable.to_string()],
+                    if_.condition.location().clone(),
+                )
+                .with_chain(chain),
+            );
+        }
+
+        self.visit_term(&if_.condition);
+        self.visit_term(&if_.then);
+        self.visit_term(&if_.otherwise);
+    }
+
+    fn lint_binary(&mut self, binary: &Binary) {
+        let comparison = matches!(
+            binary.op,
+            BinaryOp::Eq
+                | BinaryOp::Neq
+                | BinaryOp::Lt
+                | BinaryOp::Gt
+                | BinaryOp::Lte
+                | BinaryOp::Gte
+        );
+
+        match (literal_kind(&binary.lhs), literal_kind(&binary.rhs)) {
+            (Some(lhs), Some(rhs)) if comparison && lhs != rhs => self.warn(
+                ErrorCode::MismatchedComparison,
+                vec![lhs.to_string(), rhs.to_string()],
+                &binary.location,
+            ),
+            _ => (),
+        }
+
+        if let (Some(lhs), Some(rhs)) = (self.constant(&binary.lhs), self.constant(&binary.rhs)) {
+            self.lint_arithmetic(binary, lhs, rhs);
+        }
+
+        self.visit_term(&binary.lhs);
+        self.visit_term(&binary.rhs);
+    }
+
+    /// Looks for arithmetic between constants that fails or overflows.
+    fn lint_arithmetic(&mut self, binary: &Binary, lhs: Constant, rhs: Constant) {
+        let (&Value::Int(l_int), &Value::Int(r_int)) = (&lhs.value, &rhs.value) else {
+            return;
+        };
+
+        let (l_int, r_int) = (i128::from(l_int), i128::from(r_int));
+        let exact = match binary.op {
+            BinaryOp::Div | BinaryOp::Rem if r_int == 0 => {
+                self.warnings.push(
+                    Warning::new(
+                        ErrorCode::ConstantDivisionByZero,
+                        vec![],
+                        binary.location.clone(),
+                    )
+                    .with_chain(lhs.merge(rhs)),
+                );
+                return;
+            }
+            BinaryOp::Add => l_int + r_int,
+            BinaryOp::Sub => l_int - r_int,
+            BinaryOp::Mul => l_int * r_int,
+            BinaryOp::Div => l_int / r_int,
+            _ => return,
+        };
+
+        if i64::try_from(exact).is_ok() {
+            return;
+        }
+
+        let Ok(wrapped) = lhs.value.operate(&binary.op, &rhs.value, &binary.location) else {
+            return;
+        };
+
+        self.warnings.push(
+            Warning::new(
+                ErrorCode::ConstantOverflow,
+                vec![exact.to_string(), wrapped.to_string()],
+                binary.location.clone(),
+            )
+            .with_chain(lhs.merge(rhs)),
+        );
+    }
+}
+
+impl TermVisitor for Linter {
+    fn visit_term(&mut self, term: &Term) {
+        deeper(|| match term {
+            Term::Let(let_) => self.lint_let(let_),
+            Term::Function(function) => self.lint_function(function),
+            Term::If(if_) => self.lint_if(if_),
+            Term::Binary(binary) => self.lint_binary(binary),
+            Term::Call(call) => self.lint_call(call),
+            Term::Var(var) => self.use_var(&var.text),
+            term => walk_term(self, term),
+        })
+    }
+}
+
+/// Looks for suspicious code in `term`, returning the warnings in the order
+/// they appear in the program.
+pub fn lint(term: &Term) -> Vec<Warning> {
+    let mut linter = Linter::default();
+    linter.visit_term(term);
+
+    let mut warnings = linter.warnings;
+
+    // Operations that always overflow are already reported as such.
+    for overflow in interval::overflows(term) {
+        let reported = warnings.iter().any(|warning| {
+            warning.code == ErrorCode::ConstantOverflow && warning.location == overflow.location
+        });
+
+        if !reported {
+            warnings.push(overflow);
+        }
+    }
+
+    warnings.sort_by_key(|warning| (warning.location.start, warning.location.end));
+
+    warnings
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use crate::{
+        ast::{Location, Term},
+        messages::ErrorCode,
+        parser::parse,
+    };
+
+    use super::{lint, Level, Levels, Warning};
+
+    /// Warnings with `code` about the program in `source`.
+    fn warnings(source: &str, code: ErrorCode) -> Vec<Warning> {
+        lint(&parse(source, "tests").unwrap().expression)
+            .into_iter()
+            .filter(|warning| warning.code == code)
+            .collect()
+    }
+
+    /// Names in the chain of bindings of each warning.
+    fn chains(warnings: &[Warning]) -> Vec<Vec<&str>> {
+        warnings
+            .iter()
+            .map(|warning| warning.chain.iter().map(|step| &*step.name).collect())
+            .collect()
+    }
+
+    fn codes(json: &str) -> Vec<ErrorCode> {
+        let term: Term = serde_json::from_str(json).unwrap();
+
+        lint(&term)
+            .into_iter()
+            .map(|warning| warning.code)
+            .collect()
+    }
+
+    fn location() -> String {
+        serde_json::to_string(&Location::new(0, 0, "tests")).unwrap()
+    }
+
+    fn int(value: i64) -> String {
+        format!(
+            r#"{{"kind": "Int", "value": {value}, "location": {}}}"#,
+            location()
+        )
+    }
+
+    fn var(text: &str) -> String {
+        format!(
+            r#"{{"kind": "Var", "text": "{text}", "location": {}}}"#,
+            location()
+        )
+    }
+
+    fn let_(name: &str, value: &str, next: &str) -> String {
+        format!(
+            r#"{{"kind": "Let", "name": {{"text": "{name}", "location": {0}}}, "value": {value}, "next": {next}, "location": {0}}}"#,
+            location()
+        )
+    }
+
+    fn function(parameter: &str, value: &str) -> String {
+        format!(
+            r#"{{"kind": "Function", "parameters": [{{"text": "{parameter}", "location": {0}}}], "value": {value}, "location": {0}}}"#,
+            location()
+        )
+    }
+
+    fn call(callee: &str, argument: &str) -> String {
+        format!(
+            r#"{{"kind": "Call", "callee": {}, "arguments": [{argument}], "location": {}}}"#,
+            var(callee),
+            location()
+        )
+    }
+
+    #[test]
+    fn unused_binding() {
+        assert_eq!(
+            codes(&let_("x", &int(1), &int(2))),
+            vec![ErrorCode::UnusedBinding]
+        );
+        assert_eq!(codes(&let_("_", &int(1), &int(2))), vec![]);
+        assert_eq!(codes(&let_("x", &int(1), &var("x"))), vec![]);
+    }
+
+    #[test]
+    fn shadowed_name() {
+        let program = let_("x", &int(1), &let_("x", &var("x"), &var("x")));
+
+        assert_eq!(codes(&program), vec![ErrorCode::ShadowedName]);
+    }
+
+    #[test]
+    fn long_let_chains() {
+        let source = format!("{}x", "let x = 1;\n".repeat(100_000));
+
+        assert_eq!(
+            warnings(&source, ErrorCode::ShadowedName).len(),
+            100_000 - 1
+        );
+    }
+
+    #[test]
+    fn unused_parameter() {
+        let program = let_("f", &function("n", &int(1)), &call("f", &int(1)));
+        assert_eq!(codes(&program), vec![ErrorCode::UnusedParameter]);
+
+        let program = let_("f", &function("_n", &int(1)), &call("f", &int(1)));
+        assert_eq!(codes(&program), vec![]);
+    }
+
+    #[test]
+    fn no_base_case() {
+        let program = let_(
+            "f",
+            &function("n", &call("f", &var("n"))),
+            &call("f", &int(1)),
+        );
+
+        assert_eq!(codes(&program), vec![ErrorCode::NoBaseCase]);
+    }
+
+    #[test]
+    fn wrong_argument_count() {
+        let call_twice = format!(
+            r#"{{"kind": "Call", "callee": {}, "arguments": [{}, {}], "location": {}}}"#,
+            var("f"),
+            int(1),
+            int(2),
+            location()
+        );
+        let program = let_("f", &function("n", &var("n")), &call_twice);
+
+        let warnings = lint(&serde_json::from_str(&program).unwrap());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, ErrorCode::WrongArgumentCount);
+        assert_eq!(warnings[0].args, vec!["f", "1", "2"]);
+        assert!(warnings[0].related.is_some());
+
+        let program = let_("f", &function("n", &var("n")), &call("f", &int(1)));
+        assert_eq!(codes(&program), vec![]);
+    }
+
+    #[test]
+    fn constant_condition() {
+        let program = format!(
+            r#"{{"kind": "If", "condition": {{"kind": "Bool", "value": true, "location": {0}}}, "then": {1}, "otherwise": {1}, "location": {0}}}"#,
+            location(),
+            int(1)
+        );
+
+        assert_eq!(codes(&program), vec![ErrorCode::ConstantCondition]);
+    }
+
+    #[test]
+    fn propagated_constants() {
+        let source = "let x = 1; let y = x + 1; if (y == 2) { 1 } else { 2 }";
+        let found = warnings(source, ErrorCode::ConstantCondition);
+        assert_eq!(chains(&found), vec![vec!["x", "y"]]);
+        assert_eq!(found[0].args, vec!["true", "else"]);
+        assert_eq!(found[0].chain[1].value, "2");
+
+        let source = "let x = 1; let f = fn (x) => if (x == 1) { 1 } else { 2 }; f(x)";
+        assert!(warnings(source, ErrorCode::ConstantCondition).is_empty());
+    }
+
+    #[test]
+    fn constant_arithmetic() {
+        let source = "let zero = 1 - 1; let n = 10; n / zero";
+        let found = warnings(source, ErrorCode::ConstantDivisionByZero);
+        assert_eq!(chains(&found), vec![vec!["n", "zero"]]);
+
+        let source = "let max = 9223372036854775807; max + 1";
+        let found = warnings(source, ErrorCode::ConstantOverflow);
+        assert_eq!(
+            found[0].args,
+            vec!["9223372036854775808", "-9223372036854775808"]
+        );
+
+        assert!(warnings("let n = 2; n * 3 % n", ErrorCode::ConstantOverflow).is_empty());
+    }
+
+    #[test]
+    fn possible_overflow() {
+        let source = "let f = fn (n) => if (n == 0) { 1 } else { n * f(n - 1) }; f(25)";
+        let found = warnings(source, ErrorCode::PossibleOverflow);
+        assert_eq!(found.len(), 1);
+
+        let source = "let max = 9223372036854775807; max + 1";
+        assert!(warnings(source, ErrorCode::PossibleOverflow).is_empty());
+    }
+
+    #[test]
+    fn mismatched_comparison() {
+        let program = format!(
+            r#"{{"kind": "Binary", "op": "Eq", "lhs": {}, "rhs": {{"kind": "Str", "value": "1", "location": {1}}}, "location": {1}}}"#,
+            int(1),
+            location()
+        );
+
+        assert_eq!(codes(&program), vec![ErrorCode::MismatchedComparison]);
+    }
+
+    #[test]
+    fn levels() {
+        let mut levels = Levels::default();
+        assert_eq!(levels.level(ErrorCode::ShadowedName), Level::Warn);
+
+        levels.set("warnings", Level::Deny).unwrap();
+        levels.set("shadowed-name", Level::Allow).unwrap();
+        levels.set("w0001", Level::Warn).unwrap();
+        assert_eq!(levels.level(ErrorCode::ShadowedName), Level::Allow);
+        assert_eq!(levels.level(ErrorCode::UnusedBinding), Level::Warn);
+        assert_eq!(levels.level(ErrorCode::NoBaseCase), Level::Deny);
+
+        assert!(levels.set("shadowing", Level::Deny).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//! Literate programs: Markdown documents whose ```` ```rinha ```` code blocks
+//! are run in order by `lipsum notes.md`, with what each block prints written
+//! after it.
+//!
+//! The blocks share one context, so the names bound by the outermost `let`s
+//! of a block can be used by the blocks after it, like the lines of the
+//! [REPL](crate::repl). The locations of the errors are those of the
+//! document.
+
+use std::ops::Range;
+
+use crate::{
+    ast::File,
+    interpreter::{Collect, Context, EvalError, Interpreter, Value},
+    lexer::SyntaxError,
+    parser::parse,
+};
+
+/// A fenced code block of rinha code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    /// Byte range of the code, between the fences.
+    pub code: Range<usize>,
+    /// Where the line after the closing fence starts.
+    pub end: usize,
+}
+
+/// The ```` ```rinha ```` code blocks of `markdown`, in order. Blocks fenced
+/// with `~~~` are read the same way, and the code of the other languages is
+/// skipped.
+pub fn blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    // The fence of the block being read, with where its code starts when it
+    // is rinha code.
+    let mut open: Option<(&str, Option<usize>)> = None;
+    let mut offset = 0;
+
+    for line in markdown.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+
+        let trimmed = line.trim_start_matches(' ');
+        let Some(fence) = fence(trimmed) else {
+            continue;
+        };
+        let info = trimmed[fence.len()..].trim();
+
+        match open {
+            None => {
+                let rinha = info.split_whitespace().next() == Some("rinha");
+                open = Some((fence, rinha.then_some(offset)));
+            }
+            // A closing fence is at least as long as the opening one, and
+            // made of the same character.
+            Some((opening, code))
+                if info.is_empty()
+                    && fence.len() >= opening.len()
+                    && fence.as_bytes()[0] == opening.as_bytes()[0] =>
+            {
+                if let Some(code) = code {
+                    blocks.push(Block {
+                        code: code..start,
+                        end: offset,
+                    });
+                }
+
+                open = None;
+            }
+            Some(_) => (),
+        }
+    }
+
+    // A block left open ends with the document.
+    if let Some((_, Some(code))) = open {
+        blocks.push(Block {
+            code: code..offset,
+            end: offset,
+        });
+    }
+
+    blocks
+}
+
+/// The fence starting `line`, three backticks or tildes or more.
+fn fence(line: &str) -> Option<&str> {
+    let char = line
+        .chars()
+        .next()
+        .filter(|char| matches!(char, '`' | '~'))?;
+    let length = line.len() - line.trim_start_matches(char).len();
+
+    (length >= 3).then(|| &line[..length])
+}
+
+/// Parses the code of `block`. Everything else in `markdown` is blanked,
+/// keeping the locations of the program those of the document.
+pub fn parse_block(markdown: &str, block: &Block, filename: &str) -> Result<File, SyntaxError> {
+    let mut source = String::with_capacity(markdown.len());
+
+    for (index, char) in markdown.char_indices() {
+        if block.code.contains(&index) || char == '\n' {
+            source.push(char);
+        } else {
+            source.extend(std::iter::repeat_n(' ', char.len_utf8()));
+        }
+    }
+
+    parse(&source, filename)
+}
+
+/// A document whose blocks were run, see [`weave`].
+#[derive(Debug)]
+pub struct Weave {
+    /// The document, with what each block printed in a ```` ```text ````
+    /// block after it. It ends at the block that failed, if any.
+    pub text: String,
+    /// The value of the last block, `None` when there are no blocks, or the
+    /// error that stopped them.
+    pub result: Result<Option<Value>, EvalError>,
+}
+
+/// Runs the rinha code blocks of `markdown` in order with `interpreter`,
+/// in `context`, until one of them fails.
+pub fn weave(
+    markdown: &str,
+    filename: &str,
+    interpreter: &mut Interpreter<Collect>,
+    context: &mut Context,
+) -> Weave {
+    let mut text = String::with_capacity(markdown.len());
+    let mut written = 0;
+    let mut value = None;
+
+    for block in blocks(markdown) {
+        if markdown[block.code.clone()].trim().is_empty() {
+            continue;
+        }
+
+        text.push_str(&markdown[written..block.end]);
+        written = block.end;
+
+        let result = parse_block(markdown, &block, filename)
+            .map_err(EvalError::from)
+            .and_then(|file| Ok(interpreter.eval(file.expression, context)?));
+        let prints = std::mem::take(&mut interpreter.io.0);
+
+        if !prints.is_empty() {
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+
+            text.push_str("\n```text\n");
+
+            for printed in prints {
+                text.push_str(&printed);
+                text.push('\n');
+            }
+
+            text.push_str("```\n");
+        }
+
+        match result {
+            Ok(last) => value = Some(last),
+            Err(error) => {
+                return Weave {
+                    text,
+                    result: Err(error),
+                }
+            }
+        }
+    }
+
+    text.push_str(&markdown[written..]);
+
+    Weave {
+        text,
+        result: Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Element,
+        interpreter::{Collect, Context, EvalError, Interpreter},
+    };
+
+    use super::{blocks, parse_block, weave};
+
+    static NOTES: &str = "\
+# Notes
+
+```rinha
+let double = fn (n) => { n * 2 };
+print(double(1))
+```
+
+Not run:
+
+````text
+```rinha
+print(0)
+```
+````
+
+~~~ rinha
+print(double(21))
+~~~
+";
+
+    #[test]
+    fn finds_blocks() {
+        let blocks = blocks(NOTES);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            &NOTES[blocks[0].code.clone()],
+            "let double = fn (n) => { n * 2 };\nprint(double(1))\n"
+        );
+        assert_eq!(&NOTES[blocks[1].code.clone()], "print(double(21))\n");
+        assert_eq!(blocks[1].end, NOTES.len());
+
+        let file = parse_block(NOTES, &blocks[1], "notes.md").unwrap();
+        assert_eq!(
+            &NOTES[file.expression.location().span()],
+            "print(double(21))"
+        );
+    }
+
+    #[test]
+    fn weaves_the_output() {
+        let mut interpreter = Interpreter::new(Collect::default());
+        let weave = weave(NOTES, "notes.md", &mut interpreter, &mut Context::new());
+
+        assert_eq!(weave.result.unwrap().unwrap().to_string(), "42");
+        assert!(weave
+            .text
+            .starts_with("# Notes\n\n```rinha\nlet double = fn (n) => { n * 2 };\nprint(double(1))\n```\n\n```text\n2\n```\n\nNot run:\n"));
+        assert!(weave
+            .text
+            .ends_with("~~~ rinha\nprint(double(21))\n~~~\n\n```text\n42\n```\n"));
+    }
+
+    #[test]
+    fn stops_at_the_first_error() {
+        let notes = "```rinha\nprint(1)\n```\n\n```rinha\n1 / 0\n```\n\n```rinha\nprint(2)\n```\n";
+        let mut interpreter = Interpreter::new(Collect::default());
+        let weave = weave(notes, "notes.md", &mut interpreter, &mut Context::new());
+
+        let Err(EvalError::Runtime(error)) = weave.result else {
+            panic!("the division by zero should fail");
+        };
+        assert_eq!(error.span().start, notes.find("1 / 0").unwrap());
+        assert!(weave.text.ends_with("1 / 0\n```\n"));
+        assert!(!weave.text.contains("print(2)"));
+    }
+}
@@ -1,36 +1,2138 @@
-use clap::Parser;
+use std::{
+    collections::BTreeMap,
+    io::{IsTerminal, Write},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock, PoisonError},
+    time::{Duration, Instant},
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+#[cfg(unix)]
+use lipsum::repl::{History, Terminal};
+#[cfg(feature = "serve")]
+use lipsum::serve::Server;
 use lipsum::{
-    ast::File,
-    interpreter::{eval, Cache, Context, IO},
+    ast::{File, Location},
+    bundle::{bundle, payload},
+    cache,
+    callgraph::callgraph,
+    config::{Config, Limits},
+    debugger::{post_mortem, remote, time_travel},
+    diff::unified,
+    doc::{docs, html, markdown},
+    highlight,
+    interpreter::{
+        CancelToken, Collect, Context, EvalError, Interpreter, Printer, RuntimeError, Sink, Trace,
+        Value, IO,
+    },
+    json::{read_file, validate_text, write_file, JsonWarning},
+    lexer::{lex, Spanned},
+    lint::{lint, Level, Levels},
+    literate::weave,
+    messages::{ErrorCode, Lang, LANG_ENV},
+    minify::minify,
+    parser::{check_tail_calls, parse},
+    pass::{Pipeline, Timing},
+    profile::Profiler,
+    protocol,
+    repl::{Lines, Repl},
+    stats::stats,
+    symbolic::explore,
+    sync::SyncInterpreter,
+    typecheck::{Checker, Type, TypeError},
+    verify::verify,
 };
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Command {
+    /// Program to run, can be repeated to run many programs in sequence
+    #[arg(short, long)]
+    file: Vec<String>,
+
+    /// More programs to run, after the ones given with --file
+    files: Vec<String>,
+
+    /// Language of the error messages, "en" or "pt-BR"
+    #[arg(long, env = LANG_ENV, default_value_t = Lang::En, global = true)]
+    lang: Lang,
+
+    /// Open an interactive prompt to inspect the program state when it fails
+    #[arg(long)]
+    debug_on_error: bool,
+
+    /// Configuration file, by default rinha.toml is looked up from the
+    /// current directory upwards
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Abort the program when it runs for longer than the given seconds
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+
+    /// Abort the program when it makes more than the given number of calls
+    #[arg(long, value_name = "CALLS")]
+    fuel: Option<u64>,
+
+    /// Abort the program when it nests more than the given number of calls
+    /// [default: 100000]
+    #[arg(long, value_name = "CALLS")]
+    max_depth: Option<usize>,
+
+    /// Abort the program when it holds more than about the given megabytes
+    /// in bindings and memoized results [default: 1536]
+    #[arg(long, value_name = "MEGABYTES")]
+    max_memory: Option<usize>,
+
+    /// Abort the program as a probable infinite loop when a function is
+    /// running the given number of times in the same state, unless
+    /// `--timeout` or `--fuel` is given [default: 3]
+    #[arg(
+        long,
+        value_name = "REPEATS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "3"
+    )]
+    detect_loops: Option<u32>,
+
+    /// Don't memoize calls to pure functions
+    #[arg(long)]
+    no_memo: bool,
+
+    /// Follow the rinha specification to the letter: integers have 32 bits
+    /// and only integers can be compared with `<`, `>`, `<=` and `>=`
+    #[arg(long)]
+    spec: bool,
+
+    /// Program to evaluate before each program, whose bindings are visible to it
+    #[arg(long)]
+    prelude: Option<PathBuf>,
+
+    /// Infer the types of each program before running it, and don't run the
+    /// programs that would use a value of the wrong type
+    #[arg(long)]
+    typecheck: bool,
+
+    /// Run each program with memoization on and off, reporting whether the
+    /// results or the printed values differ, and exit
+    #[arg(long)]
+    verify: bool,
+
+    /// Print the tokens of the given rinha source files and exit
+    #[arg(long)]
+    dump_tokens: bool,
+
+    /// Print the JSON AST of each program as it is after the given pass, and
+    /// exit
+    #[arg(long, value_name = "PASS")]
+    emit_after: Option<String>,
+
+    /// Run only the given passes, in their usual order, like
+    /// `--passes peephole`
+    #[arg(long, value_name = "PASS", value_delimiter = ',')]
+    passes: Option<Vec<String>>,
+
+    /// Don't read nor write the programs cached in .rinhac files next to
+    /// them, always parsing them and running the passes
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Write how long loading the program and each pass took to stderr
+    #[arg(long, visible_alias = "time")]
+    time_passes: bool,
+
+    /// Write a summary of the evaluation to stderr once the program ends, or
+    /// add it to the document of `--output json`
+    #[arg(long)]
+    stats: bool,
+
+    /// Write a line with the time, memory and steps each program took to
+    /// stderr once it ends, for harnesses to collect
+    #[arg(long)]
+    usage: bool,
+
+    /// Write the answers of the native functions, like the lines read from
+    /// stdin, to the given file, to reproduce the run with `--replay`
+    #[arg(long, value_name = "PATH", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Answer the native functions as in the run recorded in the given file
+    /// by `--record`, without reading stdin
+    #[arg(long, value_name = "PATH")]
+    replay: Option<PathBuf>,
+
+    /// Measure how long each call takes and write it to the given file once
+    /// the program ends
+    #[arg(long, value_name = "PATH")]
+    profile: Option<PathBuf>,
+
+    /// Write the functions called the most, with their number of calls and
+    /// the time spent in them, to stderr once the program ends [default: 10]
+    #[arg(
+        long,
+        value_name = "COUNT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "10"
+    )]
+    hot: Option<usize>,
+
+    /// Format of the file written by `--profile`
+    #[arg(long, value_enum, default_value_t = ProfileFormat::Chrome)]
+    profile_format: ProfileFormat,
+
+    /// Write how many allocations each phase of the evaluation made to
+    /// stderr once the program ends
+    #[cfg(feature = "heap-profile")]
+    #[arg(long)]
+    heap_profile: bool,
+
+    /// Compare what the program writes to stdout with the given file instead
+    /// of writing it, and fail with a diff when they differ. The final value
+    /// is part of the output with `--result-to stdout`
+    #[arg(long, value_name = "PATH")]
+    expect: Option<PathBuf>,
+
+    /// Format of the program output
+    #[arg(long, value_enum, default_value_t = Output::Text)]
+    output: Output,
+
+    /// Stream where the values given to `print` are written
+    #[arg(long, value_enum, default_value_t = Stream::Stdout)]
+    prints_to: Stream,
+
+    /// Also write the final value of the program to the given stream
+    #[arg(long, value_enum)]
+    result_to: Option<Stream>,
+
+    /// Never write the final value of the program, even with --result-to
     #[arg(short, long)]
-    file: Option<String>,
+    quiet: bool,
+
+    #[command(subcommand)]
+    action: Option<Action>,
 }
 
-static DEFAULT_PATH: &'static str = "/var/rinha/source.rinha.json";
+#[derive(Subcommand, Debug)]
+enum Action {
+    /// Look for suspicious code in programs, without running them
+    Lint {
+        files: Vec<String>,
 
-fn main() -> Result<(), String> {
-    let command = Command::parse();
-    let path = match command.file {
-        Some(path) => path,
-        None => DEFAULT_PATH.to_string(),
+        /// Report the warnings of a lint, by code or name, as errors that
+        /// make the command fail, or of every lint with `warnings`
+        #[arg(long, value_name = "LINT")]
+        deny: Vec<String>,
+
+        /// Report the warnings of a lint without failing
+        #[arg(long, value_name = "LINT")]
+        warn: Vec<String>,
+
+        /// Don't report the warnings of a lint
+        #[arg(long, value_name = "LINT")]
+        allow: Vec<String>,
+
+        /// Fail when any warning is found, like `--deny warnings`
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+    /// Infer the types of programs, without running them
+    #[command(alias = "check")]
+    Typecheck { files: Vec<String> },
+    /// Check JSON ASTs against the rinha specification, without running them
+    Validate { files: Vec<String> },
+    /// Write the smallest JSON AST that behaves like the given program
+    Minify {
+        file: PathBuf,
+
+        /// Where to write the minified program, by default the standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Run every program in a directory, each with the limits of the flags
+    /// and of rinha.toml, and write a summary of how each one ended
+    RunAll {
+        dir: PathBuf,
+
+        /// Also write the summary as JSON to the given file, with the hash of
+        /// the output of each program
+        #[arg(long, value_name = "PATH")]
+        report: Option<PathBuf>,
+    },
+    /// Write an executable that runs the given program when it is given no
+    /// files, with the same flags as this one
+    Bundle {
+        file: PathBuf,
+
+        /// Where to write the executable, by default the name of the program
+        /// without its extensions
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write which functions of the program call which, without running it
+    Callgraph {
+        file: PathBuf,
+
+        /// Format of the graph
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+    },
+    /// Write the documentation of the functions of a rinha source file,
+    /// from the `///` comments before them
+    Doc {
+        file: PathBuf,
+
+        /// Format of the documentation
+        #[arg(long, value_enum, default_value_t = DocFormat::Markdown)]
+        format: DocFormat,
+
+        /// Where to write the documentation [default: stdout]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write a rinha source file with its syntax highlighted, with ANSI
+    /// colors or as an HTML page
+    Highlight {
+        file: PathBuf,
+
+        /// Write a standalone HTML page instead of ANSI colors
+        #[arg(long)]
+        html: bool,
+
+        /// Where to write the highlighted source [default: stdout]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Look for the inputs that make programs fail, exploring the branches
+    /// that depend on them
+    Explore { files: Vec<String> },
+    /// Measure programs without running them, to tell why they may be slow
+    Stats {
+        files: Vec<String>,
+
+        /// Write the measures as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run a program one term at a time in an interactive prompt, going
+    /// back and forth in its evaluation
+    Debug {
+        file: PathBuf,
+
+        /// Answer the native functions as in the run recorded in the given
+        /// file by `--record`
+        #[arg(long, value_name = "PATH")]
+        trace: Option<PathBuf>,
+
+        /// Wait for the debugger to be used over TCP at the given address,
+        /// by a client sending the token written to stderr first
+        #[arg(long, value_name = "ADDRESS")]
+        listen: Option<String>,
+    },
+    /// Evaluate expressions typed at an interactive prompt, keeping the
+    /// names bound by each one
+    Repl {
+        /// File keeping the lines typed across sessions [default:
+        /// ~/.rinha_history]
+        #[arg(long, value_name = "PATH")]
+        history: Option<PathBuf>,
+    },
+    /// Evaluate the programs of the JSON requests read from stdin, one per
+    /// line, answering each one with a line of JSON on stdout
+    Stdio,
+    /// Evaluate the programs POSTed to /eval over HTTP, answering with what
+    /// they printed, their result and how long they took as JSON
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Address to listen on
+        #[arg(default_value = "127.0.0.1:8080")]
+        address: String,
+
+        /// How many programs can run at the same time [default: the number
+        /// of CPUs]
+        #[arg(long)]
+        workers: Option<usize>,
+    },
+    /// Serve a page to write programs in and run them from a browser, with
+    /// the limits of a sandbox
+    #[cfg(feature = "serve")]
+    Playground {
+        /// Address to listen on
+        #[arg(default_value = "127.0.0.1:8000")]
+        address: String,
+
+        /// How many programs can run at the same time [default: the number
+        /// of CPUs]
+        #[arg(long)]
+        workers: Option<usize>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ProfileFormat {
+    /// The JSON trace event format of chrome://tracing, Perfetto and
+    /// speedscope
+    Chrome,
+    /// Folded stacks, read by flamegraph tools like inferno and speedscope
+    Folded,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DocFormat {
+    Markdown,
+    /// A standalone HTML page
+    Html,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum GraphFormat {
+    /// The DOT language of Graphviz
+    Dot,
+    /// A JSON document with the functions and the calls between them
+    Json,
+}
+
+/// Counts the allocations for `--heap-profile`.
+#[cfg(feature = "heap-profile")]
+#[global_allocator]
+static ALLOCATOR: lipsum::heap::Counting = lipsum::heap::Counting;
+
+static DEFAULT_PATH: &str = "/var/rinha/source.rinha.json";
+
+/// Path standing for the program embedded by `lipsum bundle`, which runs
+/// instead of [`DEFAULT_PATH`] when there is one.
+static BUNDLED_PATH: &str = "<bundled>";
+
+/// JSON syntax tree of the program embedded in this executable, once
+/// [`bundled`] is called. It is read again by every [`load`], since cloning
+/// a deep program would overflow the stack.
+static BUNDLED: OnceLock<Result<Option<String>, String>> = OnceLock::new();
+
+/// Sources of the programs loaded by [`load`], by the file name of their
+/// locations, so that errors are written with lines and columns.
+static SOURCES: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// Exit code used when the program is aborted by `--timeout`, the same one
+/// used by coreutils `timeout`.
+static TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Exit code used when the program is stopped by Ctrl-C, the one shells give
+/// to processes killed by SIGINT.
+static INTERRUPT_EXIT_CODE: i32 = 130;
+
+/// Seconds the programs of `serve` can run for when the configuration has no
+/// timeout, so that no request holds a worker forever.
+#[cfg(feature = "serve")]
+static SERVE_TIMEOUT: u64 = 10;
+
+/// Seconds the programs of `playground` can run for when the configuration
+/// has no timeout.
+#[cfg(feature = "serve")]
+static PLAYGROUND_TIMEOUT: u64 = 5;
+
+/// Megabytes the programs of `playground` can hold when the configuration
+/// has no memory limit.
+#[cfg(feature = "serve")]
+static PLAYGROUND_MAX_MEMORY: usize = 256;
+
+/// History of the REPL, in the home directory, unless given with
+/// `--history`.
+#[cfg(unix)]
+static HISTORY_FILE: &str = ".rinha_history";
+
+/// Cancelled by Ctrl-C, once [`handle_interrupts`] is called.
+static INTERRUPT: OnceLock<CancelToken> = OnceLock::new();
+
+/// Makes Ctrl-C stop the running program at its next step, so that it is
+/// reported like an error instead of being killed in the middle of a print.
+/// Pressing it again kills the process right away.
+fn handle_interrupts() {
+    let token = INTERRUPT.get_or_init(CancelToken::new).clone();
+
+    let _ = ctrlc::set_handler(move || {
+        if token.is_cancelled() {
+            std::process::exit(INTERRUPT_EXIT_CODE);
+        }
+
+        token.cancel();
+    });
+}
+
+fn interrupted() -> bool {
+    INTERRUPT.get().is_some_and(CancelToken::is_cancelled)
+}
+
+/// Creates an interpreter with the settings of `config`, stopped by Ctrl-C,
+/// recording or replaying the run as asked by `command`.
+fn interpreter<I: Printer>(
+    config: &Config,
+    command: &Command,
+    profiler: &Profiler,
+    io: I,
+) -> Result<Interpreter<I>, String> {
+    let mut interpreter = config.interpreter(io);
+
+    if command.profile.is_some() || command.hot.is_some() {
+        interpreter = interpreter.with_observer(profiler.clone());
+    }
+
+    if let Some(token) = INTERRUPT.get() {
+        interpreter = interpreter.with_cancel(token.clone());
+    }
+
+    if command.record.is_some() {
+        interpreter = interpreter.with_recording();
+    }
+
+    if let Some(path) = &command.replay {
+        let trace = Trace::load(path)
+            .map_err(|error| format!("failed to read trace at {}: {error}", path.display()))?;
+        interpreter = interpreter.with_replay(trace);
+    }
+
+    Ok(interpreter)
+}
+
+/// Writes the measures of `profiler` to the file given to `--profile`.
+fn write_profile(profiler: &Profiler, command: &Command) -> Result<(), String> {
+    let Some(path) = &command.profile else {
+        return Ok(());
+    };
+
+    let profile = match command.profile_format {
+        ProfileFormat::Chrome => profiler.chrome_trace().to_string(),
+        ProfileFormat::Folded => profiler.folded(),
     };
 
-    let file = std::fs::read_to_string(&path).expect(&format!("failed to read file at {}", &path));
+    std::fs::write(path, profile)
+        .map_err(|error| format!("failed to write profile at {}: {error}", path.display()))
+}
+
+/// Writes the run recorded by `interpreter` to the file given to `--record`.
+fn save_recording<I: Printer>(
+    interpreter: &Interpreter<I>,
+    command: &Command,
+) -> Result<(), String> {
+    match (&command.record, interpreter.recording()) {
+        (Some(path), Some(trace)) => trace
+            .save(path)
+            .map_err(|error| format!("failed to write trace at {}: {error}", path.display())),
+        _ => Ok(()),
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Output {
+    /// Only the printed values, as they are printed
+    Text,
+    /// A JSON document with the printed values and the result of the program
+    Json,
+}
 
-    let parsed_file: File = serde_json::from_str(&file).unwrap();
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
 
-    let entrypoint = Box::new(parsed_file.expression);
+impl Stream {
+    fn write(&self, value: &Value) {
+        match self {
+            Stream::Stdout => println!("{}", value.printed()),
+            Stream::Stderr => eprintln!("{}", value.printed()),
+        }
+    }
 
+    fn writer(&self) -> Box<dyn Write> {
+        match self {
+            Stream::Stdout => Box::new(std::io::stdout()),
+            Stream::Stderr => Box::new(std::io::stderr()),
+        }
+    }
+}
+
+impl Command {
+    /// Settings given by the flags, which override the configuration file.
+    fn config(&self) -> Config {
+        Config {
+            memoization: self.no_memo.then_some(false),
+            spec: self.spec.then_some(true),
+            prelude: self.prelude.clone(),
+            limits: Limits {
+                fuel: self.fuel,
+                max_depth: self.max_depth,
+                timeout: self.timeout,
+                max_memory: self.max_memory,
+                detect_loops: self.detect_loops,
+            },
+            lints: BTreeMap::new(),
+        }
+    }
+}
+
+fn config(command: &Command) -> Result<Config, String> {
+    let path = match &command.config {
+        Some(path) => Some(path.clone()),
+        None => std::env::current_dir()
+            .ok()
+            .and_then(|dir| Config::find(&dir)),
+    };
+
+    let file = match path {
+        Some(path) => Config::load(&path)?,
+        None => Config::default(),
+    };
+
+    Ok(file.merge(command.config()))
+}
+
+/// Reads a program, from its source code when the file ends with `.rinha` or
+/// from its JSON syntax tree otherwise.
+fn load(path: &Path, lang: Lang) -> Result<File, String> {
+    if path == Path::new(BUNDLED_PATH) {
+        if let Some(json) = bundled()? {
+            return read_file(json, BUNDLED_PATH)
+                .map(|(program, _)| program)
+                .map_err(|error| format!("failed to read the embedded program: {error}"));
+        }
+    }
+
+    let file = std::fs::read_to_string(path)
+        .map_err(|error| format!("failed to read file at {}: {error}", path.display()))?;
+
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "rinha")
+    {
+        let name = path.display().to_string();
+        let program = parse(&file, &name).map_err(|error| {
+            write_error(
+                error.code,
+                &error.message(lang),
+                &error.location.position(&file),
+                &error.full_text(lang),
+            );
+
+            format!("failed to parse file at {}", path.display())
+        })?;
+
+        SOURCES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(name, file);
+
+        return Ok(program);
+    }
+
+    let (file, warnings) = read_file(&file, &path.display().to_string())
+        .map_err(|error| format!("failed to parse file at {}: {error}", path.display()))?;
+
+    write_json_warnings(path, &warnings, lang);
+
+    check_tail_calls(&file.expression).map_err(|error| {
+        write_error(
+            error.code,
+            &error.message(lang),
+            &position(&error.location),
+            &error.full_text(lang),
+        );
+
+        format!("failed to parse file at {}", path.display())
+    })?;
+
+    Ok(file)
+}
+
+/// Writes the deviations from the specification found in a JSON syntax tree,
+/// once for each kind, with the first place it was found.
+fn write_json_warnings(path: &Path, warnings: &[JsonWarning], lang: Lang) {
+    let mut seen: Vec<(&JsonWarning, usize)> = Vec::new();
+
+    for warning in warnings {
+        let same = |(other, _): &&mut (&JsonWarning, usize)| {
+            other.code == warning.code && other.args == warning.args
+        };
+
+        match seen.iter_mut().find(same) {
+            Some((_, count)) => *count += 1,
+            None => seen.push((warning, 1)),
+        }
+    }
+
+    for (warning, count) in seen {
+        let more = match count {
+            1 => String::new(),
+            count => format!(" (and {} more)", count - 1),
+        };
+
+        eprintln!(
+            "{}: {}\n  --> {}#{}{more}\n  {}",
+            paint(&format!("warning[{}]", warning.code), "1;33"),
+            warning.message(lang),
+            path.display(),
+            warning.path,
+            warning.full_text(lang)
+        );
+    }
+}
+
+/// Evaluates the prelude, if any, and then the program.
+fn eval<I: Printer>(
+    interpreter: &mut Interpreter<I>,
+    prelude: Option<&File>,
+    program: File,
+) -> Result<Value, RuntimeError> {
     let mut context = Context::new();
-    let mut cache = Cache::new();
-    let mut io = IO {};
-    let _ = eval(entrypoint, &mut context, &mut cache, &mut io).unwrap();
+
+    if let Some(prelude) = prelude {
+        interpreter.eval(prelude.expression.clone(), &mut context)?;
+    }
+
+    interpreter.eval(program.expression, &mut context)
+}
+
+fn report(error: &RuntimeError, command: &Command) -> serde_json::Value {
+    serde_json::json!({
+        "code": error.code.as_str(),
+        "message": error.message(command.lang),
+        "full_text": error.full_text(command.lang),
+        "location": error.location,
+    })
+}
+
+fn debug(error: &RuntimeError, command: &Command) -> Result<(), String> {
+    if command.debug_on_error {
+        let stdin = std::io::stdin();
+        post_mortem(error, command.lang, stdin.lock(), std::io::stdout())
+            .map_err(|error| error.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn exit_code(error: &RuntimeError) -> i32 {
+    match error.code {
+        ErrorCode::Timeout => TIMEOUT_EXIT_CODE,
+        ErrorCode::Cancelled => INTERRUPT_EXIT_CODE,
+        _ => 1,
+    }
+}
+
+/// Wraps `text` in the ANSI escape sequence of `style` when the `color`
+/// feature is enabled and stderr is a terminal.
+fn paint(text: &str, style: &str) -> String {
+    if cfg!(feature = "color") && std::io::stderr().is_terminal() {
+        format!("\x1b[{style}m{text}\x1b[0m")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Where `location` is, by line and column when its source was loaded.
+fn position(location: &Location) -> String {
+    let sources = SOURCES.lock().unwrap_or_else(PoisonError::into_inner);
+
+    match sources.get(&location.filename) {
+        Some(source) => location.position(source),
+        None => location.to_string(),
+    }
+}
+
+fn write_error(code: ErrorCode, message: &str, location: &dyn std::fmt::Display, full_text: &str) {
+    let header = paint(&format!("error[{code}]"), "1;31");
+    eprintln!("{header}: {message}\n  --> {location}\n  {full_text}");
+}
+
+/// How many calls [`write_stack`] writes, deep recursions having millions.
+static STACK_FRAMES: usize = 16;
+
+/// Writes the calls that were running when `error` happened, innermost first.
+fn write_stack(error: &RuntimeError) {
+    for (depth, frame) in error.stack.iter().take(STACK_FRAMES).enumerate() {
+        eprintln!("  #{depth} {} at {}", frame.name, position(&frame.location));
+    }
+
+    if error.stack.len() > STACK_FRAMES {
+        eprintln!("  ... {} more calls", error.stack.len() - STACK_FRAMES);
+    }
+}
+
+/// Lints every file, reporting each warning at its level in `levels` and
+/// telling whether no denied warnings were found.
+fn lint_files(files: &[String], command: &Command, levels: &Levels) -> Result<bool, String> {
+    let mut count = 0;
+    let mut denied = 0;
+
+    for path in files {
+        let program = load(Path::new(path), command.lang)?;
+
+        let warnings = lint(&program.expression)
+            .into_iter()
+            .filter(|warning| levels.level(warning.code) != Level::Allow);
+
+        for warning in warnings {
+            count += 1;
+
+            let header = if levels.level(warning.code) == Level::Deny {
+                denied += 1;
+                paint(&format!("error[{}]", warning.code), "1;31")
+            } else {
+                paint(&format!("warning[{}]", warning.code), "1;33")
+            };
+
+            let mut related = match &warning.related {
+                Some(location) => format!("\n  ::: {location}"),
+                None => String::new(),
+            };
+
+            for step in &warning.chain {
+                related.push_str(&format!(
+                    "\n  ::: {} ({} = {})",
+                    step.location, step.name, step.value
+                ));
+            }
+
+            eprintln!(
+                "{header}: {}\n  --> {}{related}\n  {}",
+                warning.message(command.lang),
+                warning.location,
+                warning.full_text(command.lang)
+            );
+        }
+    }
+
+    if denied > 0 {
+        eprintln!("{count} warning(s) found, {denied} denied");
+    } else if count > 0 {
+        eprintln!("{count} warning(s) found");
+    }
+
+    Ok(denied == 0)
+}
+
+/// Validates every JSON file, telling whether all of them follow the
+/// specification.
+fn validate_files(files: &[String], lang: Lang) -> Result<bool, String> {
+    let mut count = 0;
+
+    for path in files {
+        let text = std::fs::read_to_string(path).map_err(|error| format!("{path}: {error}"))?;
+        let violations = validate_text(&text).map_err(|error| format!("{path}: {error}"))?;
+
+        for violation in violations {
+            count += 1;
+            eprintln!(
+                "{}: {}\n  --> {path}#{}\n  {}",
+                paint(&format!("error[{}]", violation.code), "1;31"),
+                violation.message(lang),
+                violation.path,
+                violation.full_text(lang)
+            );
+        }
+    }
+
+    if count > 0 {
+        eprintln!("{count} violation(s) found");
+    }
+
+    Ok(count == 0)
+}
+
+/// Infers the type of `program`, after the bindings of the prelude.
+fn typecheck(program: &File, prelude: Option<&File>, lang: Lang) -> Result<Type, ()> {
+    let mut checker = Checker::new();
+
+    prelude
+        .map_or(Ok(()), |prelude| checker.prelude(&prelude.expression))
+        .and_then(|()| checker.check(&program.expression))
+        .map_err(|errors: Vec<TypeError>| {
+            for error in &errors {
+                write_error(
+                    error.code,
+                    &error.message(lang),
+                    &position(&error.location),
+                    &error.full_text(lang),
+                );
+            }
+
+            eprintln!("{} error(s) found", errors.len());
+        })
+}
+
+/// Typechecks every file, writing their types and telling whether all of
+/// them typecheck.
+fn typecheck_files(files: &[String], command: &Command, config: &Config) -> Result<bool, String> {
+    let prelude = config
+        .prelude
+        .as_deref()
+        .map(|prelude| load(prelude, command.lang))
+        .transpose()?;
+    let mut ok = true;
+
+    for path in files {
+        let program = load(Path::new(path), command.lang)?;
+
+        match typecheck(&program, prelude.as_ref(), command.lang) {
+            Ok(ty) => println!("{path}: {ty}"),
+            Err(()) => ok = false,
+        }
+    }
+
+    Ok(ok)
+}
+
+/// Explores every file, telling whether no input makes them fail.
+fn explore_files(files: &[String], lang: Lang) -> Result<bool, String> {
+    let mut ok = true;
+
+    for path in files {
+        let exploration = explore(&load(Path::new(path), lang)?.expression);
+
+        for finding in &exploration.findings {
+            ok = false;
+
+            let error = &finding.error;
+            write_error(
+                error.code,
+                &error.message(lang),
+                &position(&error.location),
+                &error.full_text(lang),
+            );
+            eprintln!("  input: {:?}", finding.input);
+        }
+
+        if !exploration.complete {
+            eprintln!(
+                "{path}: explored {} paths, others were left out",
+                exploration.paths
+            );
+        }
+    }
+
+    Ok(ok)
+}
+
+/// Runs the program at `path` with every engine, telling whether they agree.
+fn verify_file(path: &str, command: &Command, config: &Config) -> Result<bool, String> {
+    let program = load(Path::new(path), command.lang)?;
+    let prelude = config
+        .prelude
+        .as_deref()
+        .map(|prelude| load(prelude, command.lang))
+        .transpose()?;
+    let prelude = prelude.as_ref().map(|prelude| &prelude.expression);
+
+    match verify(
+        || config.interpreter(Collect::default()),
+        prelude,
+        &program.expression,
+    ) {
+        Ok(outcome) => {
+            eprintln!("{path}: engines agree, {outcome}");
+            Ok(true)
+        }
+        Err(divergence) => {
+            eprintln!("{path}: engines diverge\n{divergence}");
+            Ok(false)
+        }
+    }
+}
+
+/// Writes the minified program at `path` to `output`, or to stdout.
+fn minify_file(path: &Path, output: Option<&Path>, lang: Lang) -> Result<(), String> {
+    let program = minify(load(path, lang)?);
+    let json = write_file(&program);
+
+    match output {
+        Some(output) => std::fs::write(output, json)
+            .map_err(|error| format!("failed to write file at {}: {error}", output.display())),
+        None => {
+            println!("{json}");
+            Ok(())
+        }
+    }
+}
+
+/// The programs in `dir` and its subdirectories, sorted by path.
+fn programs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|error| format!("failed to read directory at {}: {error}", dir.display()))?;
+    let mut found = Vec::new();
+
+    for entry in entries {
+        let path = entry.map_err(|error| error.to_string())?.path();
+
+        if path.is_dir() {
+            found.extend(programs(&path)?);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "rinha" || extension == "json")
+        {
+            found.push(path);
+        }
+    }
+
+    found.sort();
+
+    Ok(found)
+}
+
+/// Runs every program in `dir`, each in a fresh interpreter, writing how
+/// they ended to stderr and to `report`. Fails when any program fails.
+fn run_all(
+    dir: &Path,
+    report: Option<&Path>,
+    command: &Command,
+    config: &Config,
+) -> Result<bool, String> {
+    let prelude = config
+        .prelude
+        .as_deref()
+        .map(|prelude| load(prelude, command.lang))
+        .transpose()?;
+
+    handle_interrupts();
+
+    let mut summaries = Vec::new();
+    let mut entries = Vec::new();
+
+    for path in programs(dir)? {
+        let path = path.display().to_string();
+        let start = Instant::now();
+
+        let (status, result, prints, peak) = match transform(&path, None, command, config) {
+            Ok(program) => {
+                let profiler = Profiler::counting();
+                let mut interpreter = interpreter(config, command, &profiler, Collect::default())?;
+                let result = eval(&mut interpreter, prelude.as_ref(), program);
+                let peak = interpreter.memory_stats().peak;
+                let prints = std::mem::take(&mut interpreter.io.0);
+
+                match result {
+                    Ok(value) => (
+                        String::from("ok"),
+                        value.inspect().to_string(),
+                        prints,
+                        peak,
+                    ),
+                    Err(error) => (
+                        error.code.to_string(),
+                        error.message(command.lang),
+                        prints,
+                        peak,
+                    ),
+                }
+            }
+            Err(error) => (String::from("invalid"), error, Vec::new(), 0),
+        };
+
+        let time = start.elapsed();
+
+        entries.push(serde_json::json!({
+            "path": path,
+            "status": status,
+            "time": time.as_secs_f64(),
+            "peak_memory": peak,
+            "output_hash": output_hash(&prints),
+            "result": result,
+        }));
+
+        summaries.push(Summary {
+            path,
+            status,
+            time,
+            result,
+        });
+
+        if interrupted() {
+            break;
+        }
+    }
+
+    write_summaries(&summaries);
+
+    let failed = summaries
+        .iter()
+        .filter(|summary| summary.status != "ok")
+        .count();
+
+    if let Some(report) = report {
+        let json = serde_json::json!({
+            "passed": summaries.len() - failed,
+            "failed": failed,
+            "programs": entries,
+        });
+
+        std::fs::write(report, json.to_string())
+            .map_err(|error| format!("failed to write file at {}: {error}", report.display()))?;
+    }
+
+    Ok(failed == 0)
+}
+
+/// The 64-bit FNV-1a hash of what the program wrote to stdout, each printed
+/// value on its own line, in hexadecimal.
+fn output_hash(prints: &[String]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for byte in prints.iter().flat_map(|line| line.bytes().chain([b'\n'])) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    format!("{hash:016x}")
+}
+
+/// JSON syntax tree of the program embedded in this executable by `lipsum
+/// bundle`, if any, which is an error when it is there but cannot be read.
+fn bundled() -> Result<Option<&'static str>, String> {
+    BUNDLED
+        .get_or_init(|| {
+            let Ok(path) = std::env::current_exe() else {
+                return Ok(None);
+            };
+
+            payload(&path).map_err(|error| {
+                format!(
+                    "failed to read the program embedded in {}: {error}",
+                    path.display()
+                )
+            })
+        })
+        .as_ref()
+        .map(Option::as_deref)
+        .map_err(Clone::clone)
+}
+
+/// Writes a copy of this executable with the program at `path` embedded.
+fn bundle_file(path: &Path, output: Option<&Path>, lang: Lang) -> Result<(), String> {
+    let program = load(path, lang)?;
+    let executable = std::env::current_exe()
+        .map_err(|error| format!("failed to find the lipsum executable: {error}"))?;
+    let bytes = std::fs::read(&executable)
+        .map_err(|error| format!("failed to read file at {}: {error}", executable.display()))?;
+    let bundle = bundle(&bytes, &program);
+
+    let output = match output {
+        Some(output) => output.to_path_buf(),
+        None => {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let stem = name.split('.').next().unwrap_or_default();
+            path.with_file_name(if stem.is_empty() { "bundle" } else { stem })
+        }
+    };
+
+    if output == path {
+        return Err(format!("the bundle would overwrite {}", path.display()));
+    }
+
+    std::fs::write(&output, bundle)
+        .and_then(|()| {
+            let permissions = std::fs::metadata(&executable)?.permissions();
+            std::fs::set_permissions(&output, permissions)
+        })
+        .map_err(|error| format!("failed to write file at {}: {error}", output.display()))
+}
+
+fn callgraph_file(path: &Path, format: GraphFormat, lang: Lang) -> Result<(), String> {
+    let graph = callgraph(&load(path, lang)?.expression);
+
+    match format {
+        GraphFormat::Dot => print!("{}", graph.to_dot()),
+        GraphFormat::Json => println!(
+            "{}",
+            serde_json::to_string(&graph).map_err(|error| error.to_string())?
+        ),
+    }
+
+    Ok(())
+}
+
+fn doc_file(
+    path: &Path,
+    format: DocFormat,
+    output: Option<&Path>,
+    lang: Lang,
+) -> Result<(), String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|error| format!("failed to read file at {}: {error}", path.display()))?;
+    let name = path.display().to_string();
+
+    let docs = docs(&source, &name).map_err(|error| {
+        write_error(
+            error.code,
+            &error.message(lang),
+            &error.location.position(&source),
+            &error.full_text(lang),
+        );
+
+        format!("failed to parse file at {name}")
+    })?;
+
+    let title = path
+        .file_name()
+        .map_or(name.clone(), |name| name.to_string_lossy().into_owned());
+    let text = match format {
+        DocFormat::Markdown => markdown(&title, &docs),
+        DocFormat::Html => html(&title, &docs),
+    };
+
+    match output {
+        Some(output) => std::fs::write(output, text)
+            .map_err(|error| format!("failed to write file at {}: {error}", output.display())),
+        None => {
+            print!("{text}");
+            Ok(())
+        }
+    }
+}
+
+fn highlight_file(
+    path: &Path,
+    html: bool,
+    output: Option<&Path>,
+    lang: Lang,
+) -> Result<(), String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|error| format!("failed to read file at {}: {error}", path.display()))?;
+    let name = path.display().to_string();
+
+    let highlighted = match html {
+        true => highlight::html(&source, &name),
+        false => highlight::ansi(&source, &name),
+    };
+
+    let text = highlighted.map_err(|error| {
+        write_error(
+            error.code,
+            &error.message(lang),
+            &error.location.position(&source),
+            &error.full_text(lang),
+        );
+
+        format!("failed to lex file at {name}")
+    })?;
+
+    match output {
+        Some(output) => std::fs::write(output, text)
+            .map_err(|error| format!("failed to write file at {}: {error}", output.display())),
+        None => {
+            print!("{text}");
+            Ok(())
+        }
+    }
+}
+
+fn stats_files(files: &[String], json: bool, lang: Lang) -> Result<(), String> {
+    for path in files {
+        let stats = stats(&load(Path::new(path), lang)?.expression);
+
+        if json {
+            let json = serde_json::to_string(&stats).map_err(|error| error.to_string())?;
+            println!("{json}");
+        } else {
+            println!("{path}:\n{stats}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the tokens of the source file at `path`, one per line.
+fn dump_tokens(path: &str, command: &Command) -> Result<bool, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|error| format!("failed to read file at {path}: {error}"))?;
+
+    match lex(&source, path) {
+        Ok(tokens) => {
+            for Spanned { token, location } in tokens {
+                println!("{:>6}..{:<6} {token:?}", location.start, location.end);
+            }
+
+            Ok(true)
+        }
+        Err(error) => {
+            write_error(
+                error.code,
+                &error.message(command.lang),
+                &error.location.position(&source),
+                &error.full_text(command.lang),
+            );
+
+            Ok(false)
+        }
+    }
+}
+
+fn write_timings(path: &str, timings: &[Timing]) {
+    for timing in timings {
+        eprintln!("{path}: {} took {:.3?}", timing.pass, timing.time);
+    }
+}
+
+/// The passes selected by `--passes`, or all of them.
+fn pipeline(command: &Command, config: &Config) -> Result<Pipeline, String> {
+    let pipeline = match config.spec {
+        Some(true) => Pipeline::spec(),
+        _ => Pipeline::default(),
+    };
+
+    match &command.passes {
+        Some(passes) => {
+            let names = passes.iter().map(String::as_str).collect::<Vec<_>>();
+            pipeline.only(&names)
+        }
+        None => Ok(pipeline),
+    }
+}
+
+/// Loads the program at `path` and runs the passes over it, up to `last`.
+fn transform(
+    path: &str,
+    last: Option<&str>,
+    command: &Command,
+    config: &Config,
+) -> Result<File, String> {
+    let start = Instant::now();
+    let mut pipeline = pipeline(command, config)?;
+
+    // Constants are folded with other semantics with `--spec`.
+    let mut passes = pipeline.names();
+
+    if config.spec == Some(true) {
+        passes.push("spec");
+    }
+
+    // Programs stopped before the last pass are not worth caching.
+    let cache = match last {
+        None if !command.no_cache && path != BUNDLED_PATH => std::fs::read(path)
+            .ok()
+            .map(|source| (cache::path(Path::new(path)), cache::key(&source, &passes))),
+        _ => None,
+    };
+
+    if let Some((cached, key)) = &cache {
+        if let Some(program) = cache::read(cached, key) {
+            if command.time_passes {
+                let cache = Timing {
+                    pass: "cache",
+                    time: start.elapsed(),
+                };
+                write_timings(path, &[cache]);
+            }
+
+            return Ok(program);
+        }
+    }
+
+    let mut program = load(Path::new(path), command.lang)?;
+    let load = Timing {
+        pass: "load",
+        time: start.elapsed(),
+    };
+
+    let (expression, timings) = pipeline.run(program.expression, last);
+    program.expression = expression;
+
+    if command.time_passes {
+        write_timings(path, &[load]);
+        write_timings(path, &timings);
+    }
+
+    // The directory may be read-only, the program then runs uncached.
+    if let Some((cached, key)) = &cache {
+        let _ = cache::write(cached, key, &program);
+    }
+
+    Ok(program)
+}
+
+/// Writes the JSON AST of the program at `path` after the pass `last`.
+fn emit_after(path: &str, last: &str, command: &Command, config: &Config) -> Result<(), String> {
+    let names = pipeline(command, config)?.names();
+
+    if !names.contains(&last) {
+        return Err(format!(
+            "unknown pass {last}, expected one of: {}",
+            names.join(", ")
+        ));
+    }
+
+    let program = transform(path, Some(last), command, config)?;
+    let json = write_file(&program);
+    println!("{json}");
 
     Ok(())
 }
+
+/// Runs the program at `path` in a fresh interpreter, writing its output and
+/// errors. Fails only when the program cannot be loaded.
+fn run(
+    path: &str,
+    command: &Command,
+    config: &Config,
+) -> Result<Result<Value, RuntimeError>, String> {
+    if Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension == "md")
+    {
+        return run_markdown(path, command, config);
+    }
+
+    let start = Instant::now();
+    let program = transform(path, None, command, config)?;
+    let prelude = config
+        .prelude
+        .as_deref()
+        .map(|prelude| load(prelude, command.lang))
+        .transpose()?;
+
+    if command.typecheck && typecheck(&program, prelude.as_ref(), command.lang).is_err() {
+        return Err(format!("program at {path} does not typecheck"));
+    }
+
+    if let Some(expected) = &command.expect {
+        return expect_output(expected, path, program, prelude.as_ref(), command, config);
+    }
+
+    // `--hot` alone only needs the totals of each function.
+    let profiler = match command.profile {
+        Some(_) => Profiler::new(),
+        None => Profiler::counting(),
+    };
+
+    let result = match command.output {
+        Output::Text => {
+            let mut interpreter =
+                interpreter(config, command, &profiler, Sink(command.prints_to.writer()))?;
+            let result = eval(&mut interpreter, prelude.as_ref(), program);
+            let _ = interpreter.io.0.flush();
+            save_recording(&interpreter, command)?;
+
+            if command.stats {
+                write_stats(path, &interpreter);
+            }
+
+            if command.usage {
+                write_usage(path, start, &interpreter);
+            }
+
+            match &result {
+                Ok(value) => match command.result_to {
+                    Some(stream) if !command.quiet => stream.write(value),
+                    _ => (),
+                },
+                Err(error) => {
+                    write_error(
+                        error.code,
+                        &error.message(command.lang),
+                        &position(&error.location),
+                        &error.full_text(command.lang),
+                    );
+
+                    if error.code == ErrorCode::Cancelled {
+                        write_stack(error);
+                    }
+                }
+            }
+
+            result
+        }
+        Output::Json => {
+            let mut interpreter = interpreter(config, command, &profiler, Collect::default())?;
+            let result = eval(&mut interpreter, prelude.as_ref(), program);
+            save_recording(&interpreter, command)?;
+            let prints = &interpreter.io.0;
+
+            let mut json = match &result {
+                Ok(value) => serde_json::json!({ "result": value, "prints": prints }),
+                Err(error) => {
+                    serde_json::json!({ "error": report(error, command), "prints": prints })
+                }
+            };
+
+            if command.stats {
+                json["stats"] = stats_json(&interpreter);
+            }
+
+            if command.usage {
+                write_usage(path, start, &interpreter);
+            }
+
+            println!("{json}");
+
+            result
+        }
+    };
+
+    write_profile(&profiler, command)?;
+
+    if let Some(count) = command.hot {
+        write_hot(path, &profiler, count);
+    }
+
+    #[cfg(feature = "heap-profile")]
+    if command.heap_profile {
+        eprintln!("{}", lipsum::heap::report());
+    }
+
+    if let Err(error) = &result {
+        debug(error, command)?;
+    }
+
+    Ok(result)
+}
+
+/// Runs the rinha code blocks of the Markdown document at `path` in order,
+/// writing the document with what each block printed after it.
+fn run_markdown(
+    path: &str,
+    command: &Command,
+    config: &Config,
+) -> Result<Result<Value, RuntimeError>, String> {
+    let markdown = std::fs::read_to_string(path)
+        .map_err(|error| format!("failed to read file at {path}: {error}"))?;
+    let prelude = config
+        .prelude
+        .as_deref()
+        .map(|prelude| load(prelude, command.lang))
+        .transpose()?;
+
+    let mut interpreter = interpreter(config, command, &Profiler::counting(), Collect::default())?;
+    let mut context = Context::new();
+
+    if let Some(prelude) = prelude {
+        if let Err(error) = interpreter.eval(prelude.expression, &mut context) {
+            write_error(
+                error.code,
+                &error.message(command.lang),
+                &position(&error.location),
+                &error.full_text(command.lang),
+            );
+
+            return Ok(Err(error));
+        }
+    }
+
+    let weave = weave(&markdown, path, &mut interpreter, &mut context);
+    print!("{}", weave.text);
+
+    match weave.result {
+        Ok(Some(value)) => Ok(Ok(value)),
+        Ok(None) => Err(format!("no rinha code blocks in {path}")),
+        Err(error) => {
+            let span = error.span();
+            let location = Location::new(span.start, span.end, path);
+
+            write_error(
+                error.code(),
+                &error.message(command.lang),
+                &location.position(&markdown),
+                &error.full_text(command.lang),
+            );
+
+            match error {
+                EvalError::Runtime(error) => Ok(Err(error)),
+                EvalError::Syntax(_) => Err(format!("failed to parse file at {path}")),
+            }
+        }
+    }
+}
+
+/// Opens the time travel debugger on the program at `path`, on stdin or on
+/// the first connection to `listen` sending the token.
+fn debug_file(
+    path: &Path,
+    trace: Option<&Path>,
+    listen: Option<&str>,
+    lang: Lang,
+) -> Result<(), String> {
+    let program = load(path, lang)?;
+    // Breakpoints are set by line, which JSON ASTs don't have.
+    let source = match path
+        .extension()
+        .is_some_and(|extension| extension == "rinha")
+    {
+        true => std::fs::read_to_string(path).ok(),
+        false => None,
+    };
+    let trace = match trace {
+        Some(trace) => Trace::load(trace)
+            .map_err(|error| format!("failed to read trace at {}: {error}", trace.display()))?,
+        None => Trace::default(),
+    };
+
+    if let Some(address) = listen {
+        let listener = TcpListener::bind(address)
+            .map_err(|error| format!("failed to listen on {address}: {error}"))?;
+        let token = remote::token();
+
+        eprintln!("debugger listening on {address}, send the token {token} to connect");
+
+        let (input, output) = remote::accept(&listener, &token)
+            .map_err(|error| format!("failed to accept a connection: {error}"))?;
+
+        return time_travel(
+            &program.expression,
+            source.as_deref(),
+            &trace,
+            lang,
+            input,
+            output,
+        )
+        .map_err(|error| error.to_string());
+    }
+
+    let stdin = std::io::stdin();
+    time_travel(
+        &program.expression,
+        source.as_deref(),
+        &trace,
+        lang,
+        stdin.lock(),
+        std::io::stdout(),
+    )
+    .map_err(|error| error.to_string())
+}
+
+/// Formats a number of bytes with the largest unit that keeps it above one.
+fn format_bytes(bytes: usize) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit + 1 < units.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{size:.1} {}", units[unit]),
+    }
+}
+
+fn write_stats<I: Printer>(path: &str, interpreter: &Interpreter<I>) {
+    let stats = interpreter.eval_stats();
+    let memo = match stats.memo_hit_rate() {
+        Some(rate) => format!("{:.1}%", rate * 100.0),
+        None => String::from("-"),
+    };
+
+    eprintln!("{path}: {} terms evaluated", stats.terms);
+    eprintln!(
+        "{path}: {} calls, {} nested at most",
+        stats.calls, stats.max_depth
+    );
+    eprintln!(
+        "{path}: {} of {} calls to pure functions answered by the cache ({memo})",
+        stats.memo_hits,
+        stats.memo_hits + stats.memo_misses
+    );
+    eprintln!("{path}: {} contexts allocated", stats.allocations);
+    eprintln!(
+        "{path}: {} held at most",
+        format_bytes(interpreter.memory_stats().peak)
+    );
+}
+
+/// Writes the resources used to run the program at `path` as a single line
+/// of `key=value` fields. The times and memory of the process are counted
+/// since it started, so they include the programs run before.
+fn write_usage<I: Printer>(path: &str, start: Instant, interpreter: &Interpreter<I>) {
+    let stats = interpreter.eval_stats();
+    let mut line = format!(
+        "usage path={path:?} wall_ms={:.3}",
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+
+    if let Some(process) = ProcessUsage::current() {
+        line.push_str(&format!(
+            " user_ms={:.3} sys_ms={:.3} max_rss_kb={}",
+            process.user.as_secs_f64() * 1000.0,
+            process.system.as_secs_f64() * 1000.0,
+            process.max_rss_kb
+        ));
+    }
+
+    line.push_str(&format!(
+        " steps={} calls={} max_depth={} memo_hits={} memo_misses={} peak_memory={}",
+        stats.terms,
+        stats.calls,
+        stats.max_depth,
+        stats.memo_hits,
+        stats.memo_misses,
+        interpreter.memory_stats().peak
+    ));
+
+    eprintln!("{line}");
+}
+
+/// Resources used by the process so far.
+struct ProcessUsage {
+    user: Duration,
+    system: Duration,
+    max_rss_kb: u64,
+}
+
+impl ProcessUsage {
+    #[cfg(unix)]
+    fn current() -> Option<Self> {
+        // SAFETY: `getrusage` only writes to the struct it is given.
+        let usage = unsafe {
+            let mut usage = std::mem::zeroed::<libc::rusage>();
+            if libc::getrusage(libc::RUSAGE_SELF, &mut usage) != 0 {
+                return None;
+            }
+            usage
+        };
+
+        let time = |time: libc::timeval| {
+            Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+        };
+
+        // Linux counts the peak in kilobytes, macOS in bytes.
+        let max_rss = usage.ru_maxrss as u64;
+        let max_rss_kb = if cfg!(target_os = "macos") {
+            max_rss / 1024
+        } else {
+            max_rss
+        };
+
+        Some(Self {
+            user: time(usage.ru_utime),
+            system: time(usage.ru_stime),
+            max_rss_kb,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn current() -> Option<Self> {
+        None
+    }
+}
+
+fn write_hot(path: &str, profiler: &Profiler, count: usize) {
+    let hottest = profiler.hottest(count);
+    let width = hottest
+        .iter()
+        .map(|hot| hot.calls.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    for hot in hottest {
+        eprintln!(
+            "{path}: {:>width$} calls, {:>10.3?} in {}",
+            hot.calls, hot.time, hot.name
+        );
+    }
+}
+
+/// Interpreter for the programs sent by other processes, with the prelude of
+/// `config` evaluated once for all of them.
+fn sync_interpreter(config: Config, command: &Command) -> Result<SyncInterpreter, String> {
+    let prelude = config
+        .prelude
+        .as_ref()
+        .map(|prelude| load(prelude, command.lang))
+        .transpose()?;
+
+    let interpreter = SyncInterpreter::new(config);
+
+    match prelude {
+        Some(prelude) => interpreter
+            .with_prelude(prelude.expression)
+            .map_err(|error| format!("failed to evaluate the prelude: {error}")),
+        None => Ok(interpreter),
+    }
+}
+
+/// Opens the REPL, editing lines in place when stdin is a terminal.
+fn repl(history: Option<PathBuf>, command: &Command, config: &Config) -> Result<(), String> {
+    handle_interrupts();
+
+    let interpreter = interpreter(config, command, &Profiler::counting(), IO)?;
+    let mut repl = Repl::new(interpreter, command.lang);
+
+    if let Some(token) = INTERRUPT.get() {
+        repl = repl.with_cancel(token.clone());
+    }
+
+    if let Some(prelude) = &config.prelude {
+        repl = repl
+            .with_prelude(load(prelude, command.lang)?.expression)
+            .map_err(|error| error.message(command.lang))?;
+    }
+
+    #[cfg(unix)]
+    if std::io::stdin().is_terminal() {
+        let history = history
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(HISTORY_FILE)))
+            .map(History::load)
+            .unwrap_or_default();
+
+        return repl
+            .run(&mut Terminal::new(history), std::io::stdout())
+            .map_err(|error| error.to_string());
+    }
+
+    // Prompts are only noise when the lines are piped in.
+    let mut lines = Lines {
+        input: std::io::stdin().lock(),
+        output: std::io::sink(),
+    };
+
+    repl.run(&mut lines, std::io::stdout())
+        .map_err(|error| error.to_string())
+}
+
+/// Answers the requests read from stdin, one JSON document per line, with
+/// the documents of their evaluation on stdout, until stdin ends.
+fn stdio(command: &Command, config: Config) -> Result<(), String> {
+    let interpreter = sync_interpreter(config, command)?;
+
+    protocol::stdio(
+        &interpreter,
+        command.lang,
+        std::io::stdin().lock(),
+        std::io::stdout().lock(),
+    )
+    .map_err(|error| error.to_string())
+}
+
+/// Answers the programs POSTed to `address`. Requests can only lower the
+/// limits of the configuration, and the programs are stopped after
+/// [`SERVE_TIMEOUT`] seconds unless it has a timeout. They have no input.
+#[cfg(feature = "serve")]
+fn serve(
+    address: &str,
+    workers: Option<usize>,
+    command: &Command,
+    mut config: Config,
+) -> Result<(), String> {
+    config.limits.timeout = config.limits.timeout.or(Some(SERVE_TIMEOUT));
+
+    let interpreter = sync_interpreter(config, command)?;
+    let workers = workers
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |workers| workers.get()));
+
+    eprintln!("listening on http://{address}/eval");
+
+    Server::new(interpreter, command.lang).run(address, workers)
+}
+
+/// Serves the playground at `address`. The programs are stopped after
+/// [`PLAYGROUND_TIMEOUT`] seconds or [`PLAYGROUND_MAX_MEMORY`] megabytes
+/// unless the configuration has its own limits, and they have no input.
+#[cfg(feature = "serve")]
+fn playground(
+    address: &str,
+    workers: Option<usize>,
+    command: &Command,
+    mut config: Config,
+) -> Result<(), String> {
+    config.limits.timeout = config.limits.timeout.or(Some(PLAYGROUND_TIMEOUT));
+    config.limits.max_memory = config.limits.max_memory.or(Some(PLAYGROUND_MAX_MEMORY));
+
+    let interpreter = sync_interpreter(config, command)?;
+    let workers = workers
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |workers| workers.get()));
+
+    eprintln!("playground on http://{address}/");
+
+    Server::new(interpreter, command.lang)
+        .with_playground()
+        .run(address, workers)
+}
+
+/// Runs `program` comparing what it writes to stdout with the file at
+/// `expected`, like the judge does, instead of writing it. Fails with a
+/// unified diff of the two when they differ.
+fn expect_output(
+    expected: &Path,
+    path: &str,
+    program: File,
+    prelude: Option<&File>,
+    command: &Command,
+    config: &Config,
+) -> Result<Result<Value, RuntimeError>, String> {
+    let text = std::fs::read_to_string(expected)
+        .map_err(|error| format!("failed to read file at {}: {error}", expected.display()))?;
+
+    let profiler = Profiler::counting();
+    let mut interpreter = interpreter(config, command, &profiler, Collect::default())?;
+    let result = eval(&mut interpreter, prelude, program);
+    save_recording(&interpreter, command)?;
+
+    let mut stdout = String::new();
+
+    if command.prints_to == Stream::Stdout {
+        for line in &interpreter.io.0 {
+            stdout.push_str(line);
+            stdout.push('\n');
+        }
+    }
+
+    match &result {
+        Ok(value) if command.result_to == Some(Stream::Stdout) && !command.quiet => {
+            stdout.push_str(&format!("{value}\n"));
+        }
+        Ok(_) => (),
+        Err(error) => write_error(
+            error.code,
+            &error.message(command.lang),
+            &position(&error.location),
+            &error.full_text(command.lang),
+        ),
+    }
+
+    let Some(diff) = unified(&text, &stdout, &expected.display().to_string(), path) else {
+        return Ok(result);
+    };
+
+    for line in diff.lines() {
+        let style = match line.chars().next() {
+            _ if line.starts_with("---") || line.starts_with("+++") => "1",
+            Some('-') => "31",
+            Some('+') => "32",
+            Some('@') => "36",
+            _ => "0",
+        };
+
+        eprintln!("{}", paint(line, style));
+    }
+
+    Err(format!(
+        "the output of {path} differs from {}",
+        expected.display()
+    ))
+}
+
+fn stats_json<I: Printer>(interpreter: &Interpreter<I>) -> serde_json::Value {
+    let stats = interpreter.eval_stats();
+    let mut json = serde_json::to_value(stats).unwrap_or_default();
+    json["memo_hit_rate"] = stats.memo_hit_rate().into();
+    json["peak_memory"] = interpreter.memory_stats().peak.into();
+
+    json
+}
+
+/// A line of the table written after running many programs.
+struct Summary {
+    path: String,
+    status: String,
+    time: Duration,
+    result: String,
+}
+
+fn write_summaries(summaries: &[Summary]) {
+    let path_width = summaries
+        .iter()
+        .map(|summary| summary.path.len())
+        .chain(std::iter::once("file".len()))
+        .max()
+        .unwrap_or_default();
+
+    eprintln!(
+        "{:path_width$}  {:8}  {:>12}  result",
+        "file", "status", "time"
+    );
+
+    for summary in summaries {
+        eprintln!(
+            "{:path_width$}  {:8}  {:>12}  {}",
+            summary.path,
+            summary.status,
+            format!("{:.3?}", summary.time),
+            summary.result
+        );
+    }
+}
+
+fn main() -> Result<(), String> {
+    let command = Command::parse();
+    let config = config(&command)?;
+    let mut paths = command
+        .file
+        .iter()
+        .chain(command.files.iter())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if paths.is_empty() {
+        let path = match bundled()? {
+            Some(_) => BUNDLED_PATH,
+            None => DEFAULT_PATH,
+        };
+
+        paths.push(path.to_string());
+    }
+
+    match &command.action {
+        Some(Action::Lint {
+            files,
+            deny,
+            warn,
+            allow,
+            deny_warnings,
+        }) => {
+            let mut levels = config.levels()?;
+
+            if *deny_warnings {
+                levels.set("warnings", Level::Deny)?;
+            }
+
+            let flags = [
+                (allow, Level::Allow),
+                (warn, Level::Warn),
+                (deny, Level::Deny),
+            ];
+
+            for (lints, level) in flags {
+                for name in lints {
+                    levels.set(name, level)?;
+                }
+            }
+
+            let ok = lint_files(files, &command, &levels)?;
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Some(Action::Typecheck { files }) => {
+            let ok = typecheck_files(files, &command, &config)?;
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Some(Action::Validate { files }) => {
+            let valid = validate_files(files, command.lang)?;
+            std::process::exit(if valid { 0 } else { 1 });
+        }
+        Some(Action::Minify { file, output }) => {
+            return minify_file(file, output.as_deref(), command.lang)
+        }
+        Some(Action::RunAll { dir, report }) => {
+            let ok = run_all(dir, report.as_deref(), &command, &config)?;
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Some(Action::Bundle { file, output }) => {
+            return bundle_file(file, output.as_deref(), command.lang)
+        }
+        Some(Action::Callgraph { file, format }) => {
+            return callgraph_file(file, *format, command.lang)
+        }
+        Some(Action::Doc {
+            file,
+            format,
+            output,
+        }) => return doc_file(file, *format, output.as_deref(), command.lang),
+        Some(Action::Highlight { file, html, output }) => {
+            return highlight_file(file, *html, output.as_deref(), command.lang)
+        }
+        Some(Action::Explore { files }) => {
+            let ok = explore_files(files, command.lang)?;
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Some(Action::Stats { files, json }) => return stats_files(files, *json, command.lang),
+        Some(Action::Debug {
+            file,
+            trace,
+            listen,
+        }) => return debug_file(file, trace.as_deref(), listen.as_deref(), command.lang),
+        Some(Action::Repl { history }) => return repl(history.clone(), &command, &config),
+        Some(Action::Stdio) => return stdio(&command, config),
+        #[cfg(feature = "serve")]
+        Some(Action::Serve { address, workers }) => {
+            return serve(address, *workers, &command, config)
+        }
+        #[cfg(feature = "serve")]
+        Some(Action::Playground { address, workers }) => {
+            return playground(address, *workers, &command, config)
+        }
+        None => (),
+    }
+
+    if command.verify {
+        let mut ok = true;
+
+        for path in &paths {
+            ok &= verify_file(path, &command, &config)?;
+        }
+
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
+    if command.dump_tokens {
+        let mut ok = true;
+
+        for path in &paths {
+            ok &= dump_tokens(path, &command)?;
+        }
+
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
+    if let Some(last) = &command.emit_after {
+        for path in &paths {
+            emit_after(path, last, &command, &config)?;
+        }
+
+        return Ok(());
+    }
+
+    if paths.len() > 1
+        && (command.record.is_some()
+            || command.replay.is_some()
+            || command.profile.is_some()
+            || command.expect.is_some())
+    {
+        return Err(String::from(
+            "--record, --replay, --profile and --expect take a single program",
+        ));
+    }
+
+    handle_interrupts();
+
+    if let [path] = paths.as_slice() {
+        return match run(path, &command, &config)? {
+            Ok(_value) => Ok(()),
+            Err(error) => std::process::exit(exit_code(&error)),
+        };
+    }
+
+    let mut summaries = Vec::new();
+    let mut code = 0;
+
+    for path in paths {
+        let start = Instant::now();
+        let result = run(&path, &command, &config);
+        let time = start.elapsed();
+
+        let (status, result) = match result {
+            Ok(Ok(value)) => (String::from("ok"), value.inspect().to_string()),
+            Ok(Err(error)) => {
+                code = exit_code(&error);
+                (error.code.to_string(), error.message(command.lang))
+            }
+            Err(error) => {
+                code = 1;
+                (String::from("invalid"), error)
+            }
+        };
+
+        summaries.push(Summary {
+            path,
+            status,
+            time,
+            result,
+        });
+
+        if interrupted() {
+            break;
+        }
+    }
+
+    write_summaries(&summaries);
+
+    std::process::exit(code)
+}
@@ -0,0 +1,637 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Environment variable used to pick the language of diagnostics when
+/// `--lang` is not given.
+pub static LANG_ENV: &str = "RINHA_LANG";
+
+/// Language in which diagnostics are rendered.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lang {
+    #[default]
+    En,
+    PtBr,
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(lang: &str) -> Result<Self, Self::Err> {
+        match lang.to_lowercase().replace('_', "-").as_str() {
+            "en" | "en-us" | "en-gb" => Ok(Lang::En),
+            "pt" | "pt-br" => Ok(Lang::PtBr),
+            _ => Err(format!(
+                "unknown language \"{lang}\", use \"en\" or \"pt-BR\""
+            )),
+        }
+    }
+}
+
+impl Display for Lang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lang::En => f.write_str("en"),
+            Lang::PtBr => f.write_str("pt-BR"),
+        }
+    }
+}
+
+/// Stable identifier of every diagnostic the interpreter can emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    InvalidComparison,
+    InvalidAnd,
+    InvalidOr,
+    InvalidAddition,
+    InvalidSubtraction,
+    InvalidMultiplication,
+    DivisionByZero,
+    InvalidDivision,
+    RemainderByZero,
+    InvalidRemainder,
+    InvalidCall,
+    InvalidIfCondition,
+    UnboundVariable,
+    InvalidFirst,
+    InvalidSecond,
+    Timeout,
+    OutOfFuel,
+    StackOverflow,
+    NativeFailed,
+    Cancelled,
+    MemoryLimit,
+    InfiniteRecursion,
+    ProbableInfiniteLoop,
+
+    // Syntax errors
+    UnexpectedCharacter,
+    UnterminatedString,
+    UnterminatedComment,
+    InvalidEscape,
+    IntegerOverflow,
+    UnexpectedToken,
+    ExpectedTerm,
+    ExpectedName,
+    ExpectedType,
+    TrailingInput,
+    UnexpectedEnd,
+    InvalidDigit,
+    ExpectedCall,
+    MisplacedBecome,
+    TooDeep,
+
+    // Violations of the specification by JSON syntax trees
+    UnknownKind,
+    UnknownOperator,
+    MissingField,
+    InvalidField,
+    InvalidInteger,
+
+    // Type errors
+    TypeMismatch,
+
+    // Warnings
+    UnusedBinding,
+    UnusedParameter,
+    ShadowedName,
+    ConstantCondition,
+    MismatchedComparison,
+    NoBaseCase,
+    WrongArgumentCount,
+    ConstantOverflow,
+    ConstantDivisionByZero,
+    PossibleOverflow,
+
+    // Deviations from the specification in JSON syntax trees
+    MissingLocation,
+    RenamedKey,
+    MiscasedName,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidComparison => "E0001",
+            ErrorCode::InvalidAnd => "E0002",
+            ErrorCode::InvalidOr => "E0003",
+            ErrorCode::InvalidAddition => "E0004",
+            ErrorCode::InvalidSubtraction => "E0005",
+            ErrorCode::InvalidMultiplication => "E0006",
+            ErrorCode::DivisionByZero => "E0007",
+            ErrorCode::InvalidDivision => "E0008",
+            ErrorCode::RemainderByZero => "E0009",
+            ErrorCode::InvalidRemainder => "E0010",
+            ErrorCode::InvalidCall => "E0011",
+            ErrorCode::InvalidIfCondition => "E0012",
+            ErrorCode::UnboundVariable => "E0013",
+            ErrorCode::InvalidFirst => "E0014",
+            ErrorCode::InvalidSecond => "E0015",
+            ErrorCode::Timeout => "E0016",
+            ErrorCode::OutOfFuel => "E0017",
+            ErrorCode::StackOverflow => "E0018",
+            ErrorCode::NativeFailed => "E0019",
+            ErrorCode::Cancelled => "E0020",
+            ErrorCode::MemoryLimit => "E0021",
+            ErrorCode::InfiniteRecursion => "E0022",
+            ErrorCode::ProbableInfiniteLoop => "E0023",
+            ErrorCode::UnexpectedCharacter => "E0101",
+            ErrorCode::UnterminatedString => "E0102",
+            ErrorCode::UnterminatedComment => "E0103",
+            ErrorCode::InvalidEscape => "E0104",
+            ErrorCode::IntegerOverflow => "E0105",
+            ErrorCode::UnexpectedToken => "E0106",
+            ErrorCode::ExpectedTerm => "E0107",
+            ErrorCode::ExpectedName => "E0108",
+            ErrorCode::TrailingInput => "E0109",
+            ErrorCode::UnexpectedEnd => "E0110",
+            ErrorCode::ExpectedType => "E0111",
+            ErrorCode::InvalidDigit => "E0112",
+            ErrorCode::ExpectedCall => "E0113",
+            ErrorCode::MisplacedBecome => "E0114",
+            ErrorCode::TooDeep => "E0115",
+            ErrorCode::UnknownKind => "E0201",
+            ErrorCode::UnknownOperator => "E0202",
+            ErrorCode::MissingField => "E0203",
+            ErrorCode::InvalidField => "E0204",
+            ErrorCode::InvalidInteger => "E0205",
+            ErrorCode::TypeMismatch => "E0301",
+            ErrorCode::UnusedBinding => "W0001",
+            ErrorCode::ShadowedName => "W0002",
+            ErrorCode::ConstantCondition => "W0003",
+            ErrorCode::MismatchedComparison => "W0004",
+            ErrorCode::NoBaseCase => "W0005",
+            ErrorCode::WrongArgumentCount => "W0006",
+            ErrorCode::UnusedParameter => "W0007",
+            ErrorCode::ConstantOverflow => "W0008",
+            ErrorCode::ConstantDivisionByZero => "W0009",
+            ErrorCode::PossibleOverflow => "W0010",
+            ErrorCode::MissingLocation => "W0101",
+            ErrorCode::RenamedKey => "W0102",
+            ErrorCode::MiscasedName => "W0103",
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A catalog entry: the short message and the full explanation. Both may
+/// reference arguments positionally with `{0}`, `{1}`, etc.
+struct Entry {
+    message: &'static str,
+    full_text: &'static str,
+}
+
+fn entry(code: ErrorCode, lang: Lang) -> Entry {
+    use ErrorCode::*;
+
+    let (message, full_text) = match (code, lang) {
+        (InvalidComparison, Lang::En) => ("invalid comparison", "{0} and {1} cannot be compared"),
+        (InvalidComparison, Lang::PtBr) => {
+            ("comparação inválida", "{0} e {1} não podem ser comparados")
+        }
+        (InvalidAnd, Lang::En) => (
+            "invalid AND operation",
+            "only booleans can be used on short-circuit operations",
+        ),
+        (InvalidAnd, Lang::PtBr) => (
+            "operação AND inválida",
+            "apenas booleanos podem ser usados em operações de curto-circuito",
+        ),
+        (InvalidOr, Lang::En) => (
+            "invalid OR operation",
+            "only booleans can be used on short-circuit operations",
+        ),
+        (InvalidOr, Lang::PtBr) => (
+            "operação OR inválida",
+            "apenas booleanos podem ser usados em operações de curto-circuito",
+        ),
+        (InvalidAddition, Lang::En) => ("invalid addition", "{0} cannot be added to {1}"),
+        (InvalidAddition, Lang::PtBr) => ("adição inválida", "{0} não pode ser somado a {1}"),
+        (InvalidSubtraction, Lang::En) => {
+            ("invalid subtraction", "{0} cannot be subtracted by {1}")
+        }
+        (InvalidSubtraction, Lang::PtBr) => {
+            ("subtração inválida", "{0} não pode ser subtraído por {1}")
+        }
+        (InvalidMultiplication, Lang::En) => {
+            ("invalid multiplication", "{0} cannot be multiplied by {1}")
+        }
+        (InvalidMultiplication, Lang::PtBr) => (
+            "multiplicação inválida",
+            "{0} não pode ser multiplicado por {1}",
+        ),
+        (DivisionByZero, Lang::En) => ("division by zero", "zero cannot be divised"),
+        (DivisionByZero, Lang::PtBr) => ("divisão por zero", "não é possível dividir por zero"),
+        (InvalidDivision, Lang::En) => ("invalid division", "{0} cannot be divised by {1}"),
+        (InvalidDivision, Lang::PtBr) => ("divisão inválida", "{0} não pode ser dividido por {1}"),
+        (RemainderByZero, Lang::En) => (
+            "division by zero",
+            "cannot get remainder from a zero division",
+        ),
+        (RemainderByZero, Lang::PtBr) => (
+            "divisão por zero",
+            "não é possível obter o resto de uma divisão por zero",
+        ),
+        (InvalidRemainder, Lang::En) => (
+            "invalid remainder operation",
+            "cannot get remainder from {0} and {1} division",
+        ),
+        (InvalidRemainder, Lang::PtBr) => (
+            "operação de resto inválida",
+            "não é possível obter o resto da divisão entre {0} e {1}",
+        ),
+        (InvalidCall, Lang::En) => (
+            "invalid function call",
+            "{0} cannot be called as a function",
+        ),
+        (InvalidCall, Lang::PtBr) => (
+            "chamada de função inválida",
+            "{0} não pode ser chamado como uma função",
+        ),
+        (InvalidIfCondition, Lang::En) => (
+            "invalid if condition",
+            "{0} can't be used as an if condition. use a boolean instead",
+        ),
+        (InvalidIfCondition, Lang::PtBr) => (
+            "condição de if inválida",
+            "{0} não pode ser usado como condição de if. use um booleano",
+        ),
+        (UnboundVariable, Lang::En) => (
+            "unbound variable \"{0}\"",
+            "variable \"{0}\" was not defined in the current scope",
+        ),
+        (UnboundVariable, Lang::PtBr) => (
+            "variável \"{0}\" não definida",
+            "a variável \"{0}\" não foi definida no escopo atual",
+        ),
+        (InvalidFirst, Lang::En) => (
+            "invalid expression",
+            "cannot use first operation from anything but a tuple",
+        ),
+        (InvalidFirst, Lang::PtBr) => (
+            "expressão inválida",
+            "a operação first só pode ser usada em tuplas",
+        ),
+        (InvalidSecond, Lang::En) => (
+            "invalid expression",
+            "cannot use second operation from anything but a tuple",
+        ),
+        (InvalidSecond, Lang::PtBr) => (
+            "expressão inválida",
+            "a operação second só pode ser usada em tuplas",
+        ),
+        (Timeout, Lang::En) => ("timeout", "execution exceeded {0} seconds"),
+        (Timeout, Lang::PtBr) => ("tempo esgotado", "a execução excedeu {0} segundos"),
+        (OutOfFuel, Lang::En) => ("out of fuel", "the program made more than {0} calls"),
+        (OutOfFuel, Lang::PtBr) => (
+            "combustível esgotado",
+            "o programa fez mais de {0} chamadas",
+        ),
+        (StackOverflow, Lang::En) => (
+            "maximum recursion depth exceeded",
+            "the program nested more than {0} calls",
+        ),
+        (StackOverflow, Lang::PtBr) => (
+            "profundidade máxima de recursão excedida",
+            "o programa aninhou mais de {0} chamadas",
+        ),
+        (NativeFailed, Lang::En) => ("native function \"{0}\" failed", "{1}"),
+        (NativeFailed, Lang::PtBr) => ("a função nativa \"{0}\" falhou", "{1}"),
+        (Cancelled, Lang::En) => ("cancelled", "the evaluation was cancelled by the host"),
+        (Cancelled, Lang::PtBr) => ("cancelado", "a avaliação foi cancelada pelo hospedeiro"),
+        (MemoryLimit, Lang::En) => (
+            "memory limit exceeded",
+            "the program used more than {0} bytes",
+        ),
+        (MemoryLimit, Lang::PtBr) => (
+            "limite de memória excedido",
+            "o programa usou mais de {0} bytes",
+        ),
+        (UnexpectedCharacter, Lang::En) => ("unexpected character", "\"{0}\" is not valid here"),
+        (UnexpectedCharacter, Lang::PtBr) => ("caractere inesperado", "\"{0}\" não é válido aqui"),
+        (UnterminatedString, Lang::En) => (
+            "unterminated string",
+            "the string is missing its closing quote",
+        ),
+        (UnterminatedString, Lang::PtBr) => {
+            ("string não terminada", "falta fechar as aspas da string")
+        }
+        (UnterminatedComment, Lang::En) => (
+            "unterminated comment",
+            "the comment is missing its closing \"*/\"",
+        ),
+        (UnterminatedComment, Lang::PtBr) => (
+            "comentário não terminado",
+            "falta o \"*/\" que fecha o comentário",
+        ),
+        (InvalidEscape, Lang::En) => (
+            "invalid escape sequence",
+            "\"\\{0}\" is not a valid escape sequence",
+        ),
+        (InvalidEscape, Lang::PtBr) => (
+            "sequência de escape inválida",
+            "\"\\{0}\" não é uma sequência de escape válida",
+        ),
+        (IntegerOverflow, Lang::En) => (
+            "integer literal is too large",
+            "{0} does not fit in a 64 bits integer",
+        ),
+        (IntegerOverflow, Lang::PtBr) => (
+            "literal inteiro grande demais",
+            "{0} não cabe em um inteiro de 64 bits",
+        ),
+        (UnexpectedToken, Lang::En) => ("unexpected \"{0}\"", "expected \"{1}\", found \"{0}\""),
+        (UnexpectedToken, Lang::PtBr) => {
+            ("\"{0}\" inesperado", "esperava \"{1}\", encontrou \"{0}\"")
+        }
+        (ExpectedTerm, Lang::En) => (
+            "expected an expression",
+            "expected an expression, found \"{0}\"",
+        ),
+        (ExpectedTerm, Lang::PtBr) => (
+            "esperava uma expressão",
+            "esperava uma expressão, encontrou \"{0}\"",
+        ),
+        (ExpectedName, Lang::En) => ("expected a name", "expected a name, found \"{0}\""),
+        (ExpectedName, Lang::PtBr) => ("esperava um nome", "esperava um nome, encontrou \"{0}\""),
+        (ExpectedType, Lang::En) => (
+            "expected a type",
+            "expected int, str, bool, a tuple or a function type, found \"{0}\"",
+        ),
+        (ExpectedType, Lang::PtBr) => (
+            "esperava um tipo",
+            "esperava int, str, bool, uma tupla ou um tipo de função, encontrou \"{0}\"",
+        ),
+        (TrailingInput, Lang::En) => (
+            "unexpected \"{0}\" after the program",
+            "the program is already complete, remove \"{0}\" and what follows it",
+        ),
+        (TrailingInput, Lang::PtBr) => (
+            "\"{0}\" inesperado depois do programa",
+            "o programa já está completo, remova \"{0}\" e o que vem depois",
+        ),
+        (UnexpectedEnd, Lang::En) => (
+            "unexpected end of file",
+            "the file ended before the program was complete",
+        ),
+        (UnexpectedEnd, Lang::PtBr) => (
+            "fim de arquivo inesperado",
+            "o arquivo terminou antes do programa estar completo",
+        ),
+        (InvalidDigit, Lang::En) => (
+            "invalid integer literal",
+            "\"{0}\" is not an integer in base {1}",
+        ),
+        (InvalidDigit, Lang::PtBr) => (
+            "literal inteiro inválido",
+            "\"{0}\" não é um inteiro na base {1}",
+        ),
+        (ExpectedCall, Lang::En) => (
+            "expected a call after \"become\"",
+            "\"become\" is followed by the call it makes, like \"become f(x)\"",
+        ),
+        (ExpectedCall, Lang::PtBr) => (
+            "esperava uma chamada depois de \"become\"",
+            "\"become\" é seguido da chamada que faz, como \"become f(x)\"",
+        ),
+        (MisplacedBecome, Lang::En) => (
+            "\"become\" is not the last term of a function",
+            "the value of the call is still used by the function, so it can't replace it, call it without \"become\"",
+        ),
+        (MisplacedBecome, Lang::PtBr) => (
+            "\"become\" não é o último termo de uma função",
+            "o valor da chamada ainda é usado pela função, que não pode ser substituída por ela, chame sem \"become\"",
+        ),
+        (TooDeep, Lang::En) => (
+            "expression nested too deeply",
+            "expressions can be nested at most {0} levels deep, give names to some of its parts with \"let\"",
+        ),
+        (TooDeep, Lang::PtBr) => (
+            "expressão aninhada demais",
+            "expressões podem ser aninhadas em no máximo {0} níveis, dê nomes a algumas das suas partes com \"let\"",
+        ),
+        (UnusedBinding, Lang::En) => (
+            "unused binding \"{0}\"",
+            "\"{0}\" is never used, name it \"_\" if it is only evaluated for its effects",
+        ),
+        (UnusedBinding, Lang::PtBr) => (
+            "\"{0}\" não é usado",
+            "\"{0}\" nunca é usado, chame-o de \"_\" se ele só é avaliado pelos seus efeitos",
+        ),
+        (UnusedParameter, Lang::En) => (
+            "unused parameter \"{0}\"",
+            "\"{0}\" is never used, start its name with \"_\" if the argument is ignored on purpose",
+        ),
+        (UnusedParameter, Lang::PtBr) => (
+            "parâmetro \"{0}\" não é usado",
+            "\"{0}\" nunca é usado, comece o nome com \"_\" se o argumento é ignorado de propósito",
+        ),
+        (ShadowedName, Lang::En) => (
+            "\"{0}\" shadows an existing binding",
+            "the previous \"{0}\" can't be used after this point",
+        ),
+        (ShadowedName, Lang::PtBr) => (
+            "\"{0}\" esconde uma definição existente",
+            "o \"{0}\" anterior não pode ser usado a partir daqui",
+        ),
+        (ConstantCondition, Lang::En) => (
+            "condition is always {0}",
+            "the {1} branch is never evaluated",
+        ),
+        (ConstantCondition, Lang::PtBr) => {
+            ("a condição é sempre {0}", "o ramo {1} nunca é avaliado")
+        }
+        (MismatchedComparison, Lang::En) => (
+            "comparison between {0} and {1}",
+            "values of different kinds can't be compared, this fails when evaluated",
+        ),
+        (MismatchedComparison, Lang::PtBr) => (
+            "comparação entre {0} e {1}",
+            "valores de tipos diferentes não podem ser comparados, isso falha ao ser avaliado",
+        ),
+        (NoBaseCase, Lang::En) => (
+            "\"{0}\" has no base case",
+            "every call to \"{0}\" calls it again, so it never returns",
+        ),
+        (NoBaseCase, Lang::PtBr) => (
+            "\"{0}\" não tem caso base",
+            "toda chamada a \"{0}\" chama a função de novo, então ela nunca retorna",
+        ),
+        (WrongArgumentCount, Lang::En) => (
+            "wrong number of arguments to \"{0}\"",
+            "\"{0}\" takes {1} argument(s) but is called with {2}",
+        ),
+        (WrongArgumentCount, Lang::PtBr) => (
+            "número errado de argumentos para \"{0}\"",
+            "\"{0}\" recebe {1} argumento(s), mas é chamada com {2}",
+        ),
+        (ConstantOverflow, Lang::En) => (
+            "arithmetic always overflows",
+            "the result, {0}, doesn't fit in an integer and wraps around to {1}",
+        ),
+        (ConstantOverflow, Lang::PtBr) => (
+            "a operação sempre transborda",
+            "o resultado, {0}, não cabe em um inteiro e dá a volta para {1}",
+        ),
+        (ConstantDivisionByZero, Lang::En) => (
+            "division by zero",
+            "the divisor is always zero, this fails when evaluated",
+        ),
+        (ConstantDivisionByZero, Lang::PtBr) => (
+            "divisão por zero",
+            "o divisor é sempre zero, isso falha ao ser avaliado",
+        ),
+        (PossibleOverflow, Lang::En) => (
+            "arithmetic may overflow",
+            "the result can reach {0}, which doesn't fit in an integer and wraps around",
+        ),
+        (PossibleOverflow, Lang::PtBr) => (
+            "a operação pode transbordar",
+            "o resultado pode chegar a {0}, que não cabe em um inteiro e dá a volta",
+        ),
+        (InfiniteRecursion, Lang::En) => (
+            "infinite recursion in \"{0}\"",
+            "\"{0}\" was called again with the same arguments before returning, so it would never return",
+        ),
+        (InfiniteRecursion, Lang::PtBr) => (
+            "recursão infinita em \"{0}\"",
+            "\"{0}\" foi chamada de novo com os mesmos argumentos antes de retornar, então ela nunca retornaria",
+        ),
+        (ProbableInfiniteLoop, Lang::En) => (
+            "probable infinite loop in \"{0}\"",
+            "\"{0}\" was called {1} times in the same state without returning, so it is probably looping forever",
+        ),
+        (ProbableInfiniteLoop, Lang::PtBr) => (
+            "provável laço infinito em \"{0}\"",
+            "\"{0}\" foi chamada {1} vezes no mesmo estado sem retornar, então provavelmente está em um laço sem fim",
+        ),
+        (TypeMismatch, Lang::En) => ("mismatched types", "expected {0}, found {1}"),
+        (TypeMismatch, Lang::PtBr) => {
+            ("tipos incompatíveis", "era esperado {0}, mas foi encontrado {1}")
+        }
+        (UnknownKind, Lang::En) => (
+            "unknown kind \"{0}\"",
+            "terms are one of Int, Str, Bool, Var, Call, Binary, Function, Let, If, Print, First, Second and Tuple",
+        ),
+        (UnknownKind, Lang::PtBr) => (
+            "tipo de termo \"{0}\" desconhecido",
+            "os termos são Int, Str, Bool, Var, Call, Binary, Function, Let, If, Print, First, Second ou Tuple",
+        ),
+        (UnknownOperator, Lang::En) => (
+            "unknown operator \"{0}\"",
+            "operators are one of Add, Sub, Mul, Div, Rem, Eq, Neq, Lt, Gt, Lte, Gte, And and Or",
+        ),
+        (UnknownOperator, Lang::PtBr) => (
+            "operador \"{0}\" desconhecido",
+            "os operadores são Add, Sub, Mul, Div, Rem, Eq, Neq, Lt, Gt, Lte, Gte, And ou Or",
+        ),
+        (MissingField, Lang::En) => (
+            "missing \"{0}\"",
+            "the specification requires \"{0}\" here",
+        ),
+        (MissingField, Lang::PtBr) => (
+            "\"{0}\" ausente",
+            "a especificação exige \"{0}\" aqui",
+        ),
+        (InvalidField, Lang::En) => (
+            "\"{0}\" must be of type {1}",
+            "found a value of type {2}",
+        ),
+        (InvalidField, Lang::PtBr) => (
+            "\"{0}\" deve ser do tipo {1}",
+            "foi encontrado um valor do tipo {2}",
+        ),
+        (InvalidInteger, Lang::En) => (
+            "invalid integer {0}",
+            "integers are whole numbers that fit in 64 bits, and offsets can't be negative",
+        ),
+        (InvalidInteger, Lang::PtBr) => (
+            "inteiro inválido {0}",
+            "inteiros são números sem parte decimal que cabem em 64 bits, e posições não podem ser negativas",
+        ),
+        (MissingLocation, Lang::En) => (
+            "missing location",
+            "the location is required, the start of the file was used instead",
+        ),
+        (MissingLocation, Lang::PtBr) => (
+            "localização ausente",
+            "a localização é obrigatória, o início do arquivo foi usado no lugar",
+        ),
+        (RenamedKey, Lang::En) => (
+            "\"{0}\" should be \"{1}\"",
+            "the specification names the key \"{1}\", it was read as such",
+        ),
+        (RenamedKey, Lang::PtBr) => (
+            "\"{0}\" deveria ser \"{1}\"",
+            "a especificação chama a chave de \"{1}\", ela foi lida assim",
+        ),
+        (MiscasedName, Lang::En) => (
+            "\"{0}\" should be \"{1}\"",
+            "the specification spells it \"{1}\", it was read as such",
+        ),
+        (MiscasedName, Lang::PtBr) => (
+            "\"{0}\" deveria ser \"{1}\"",
+            "a especificação escreve \"{1}\", e foi lido assim",
+        ),
+    };
+
+    Entry { message, full_text }
+}
+
+fn render(template: &str, args: &[String]) -> String {
+    args.iter()
+        .enumerate()
+        .fold(template.to_string(), |text, (index, arg)| {
+            text.replace(&format!("{{{index}}}"), arg)
+        })
+}
+
+/// Renders the short message of `code` in `lang`.
+pub fn message(code: ErrorCode, lang: Lang, args: &[String]) -> String {
+    render(entry(code, lang).message, args)
+}
+
+/// Renders the full explanation of `code` in `lang`.
+pub fn full_text(code: ErrorCode, lang: Lang, args: &[String]) -> String {
+    render(entry(code, lang).full_text, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{full_text, message, ErrorCode, Lang};
+
+    #[test]
+    fn parse_lang() {
+        assert_eq!("en".parse::<Lang>().unwrap(), Lang::En);
+        assert_eq!("pt-BR".parse::<Lang>().unwrap(), Lang::PtBr);
+        assert_eq!("pt_br".parse::<Lang>().unwrap(), Lang::PtBr);
+        assert!("fr".parse::<Lang>().is_err());
+    }
+
+    #[test]
+    fn render_arguments() {
+        let args = vec!["1".to_string(), "true".to_string()];
+
+        assert_eq!(
+            full_text(ErrorCode::InvalidAddition, Lang::En, &args),
+            "1 cannot be added to true"
+        );
+        assert_eq!(
+            full_text(ErrorCode::InvalidAddition, Lang::PtBr, &args),
+            "1 não pode ser somado a true"
+        );
+    }
+
+    #[test]
+    fn render_message() {
+        let args = vec!["x".to_string()];
+
+        assert_eq!(
+            message(ErrorCode::UnboundVariable, Lang::PtBr, &args),
+            "variável \"x\" não definida"
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    ast::{
+        visit::{fold_children, walk_term, TermFolder, TermVisitor},
+        File, Location, Node, Term, Var,
+    },
+    lexer::KEYWORDS,
+};
+
+static ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Name number `index` in the sequence `a`, `b`, ..., `Z`, `aa`, `ba`, ...
+fn short_name(mut index: usize) -> String {
+    let mut name = String::new();
+
+    loop {
+        name.push(ALPHABET[index % ALPHABET.len()] as char);
+        index /= ALPHABET.len();
+
+        if index == 0 {
+            return name;
+        }
+
+        index -= 1;
+    }
+}
+
+/// Collects the names used by a term that are not bound by it. Those can be
+/// bound by a prelude, so they are never renamed.
+#[derive(Default)]
+struct FreeNames {
+    bound: Vec<Arc<str>>,
+    free: HashSet<Arc<str>>,
+}
+
+impl TermVisitor for FreeNames {
+    fn visit_term(&mut self, term: &Term) {
+        match term {
+            Term::Var(var) => {
+                if !self.bound.contains(&var.text) {
+                    self.free.insert(var.text.clone());
+                }
+            }
+            Term::Let(let_) => {
+                self.bound.push(let_.name.text.clone());
+                walk_term(self, term);
+                self.bound.pop();
+            }
+            Term::Function(function) => {
+                let parameters = function.parameters.iter().map(|var| var.text.clone());
+                self.bound.extend(parameters);
+                walk_term(self, term);
+                self.bound
+                    .truncate(self.bound.len() - function.parameters.len());
+            }
+            term => walk_term(self, term),
+        }
+    }
+}
+
+struct Minifier {
+    /// Names that must be kept as they are.
+    free: HashSet<Arc<str>>,
+    /// New names of the bindings in scope, innermost last.
+    scopes: Vec<(Arc<str>, Arc<str>)>,
+    /// Name given to every original name, reused across scopes.
+    names: HashMap<Arc<str>, Arc<str>>,
+    next: usize,
+}
+
+impl Minifier {
+    fn fresh(&mut self) -> Arc<str> {
+        loop {
+            let name = short_name(self.next);
+            self.next += 1;
+
+            // Keywords are never generated, so that minified programs can
+            // still be printed back as source.
+            let keyword = KEYWORDS.iter().any(|(keyword, _)| *keyword == name);
+
+            if !self.free.contains(name.as_str()) && !keyword {
+                return name.into();
+            }
+        }
+    }
+
+    /// Renames a binding, bringing it into scope.
+    fn bind(&mut self, var: &mut Var) {
+        var.location = Location::default();
+
+        if self.free.contains(&var.text) {
+            self.scopes.push((var.text.clone(), var.text.clone()));
+            return;
+        }
+
+        let name = match self.names.get(&var.text) {
+            Some(name) => name.clone(),
+            None => {
+                let name = self.fresh();
+                self.names.insert(var.text.clone(), name.clone());
+                name
+            }
+        };
+
+        let original = std::mem::replace(&mut var.text, name.clone());
+        self.scopes.push((original, name));
+    }
+
+    fn unbind(&mut self, count: usize) {
+        self.scopes.truncate(self.scopes.len() - count);
+    }
+
+    fn rename(&self, var: &mut Var) {
+        var.location = Location::default();
+
+        if let Some((_, name)) = self
+            .scopes
+            .iter()
+            .rev()
+            .find(|(original, _)| *original == var.text)
+        {
+            var.text = name.clone();
+        }
+    }
+}
+
+impl TermFolder for Minifier {
+    fn fold_term(&mut self, term: Term) -> Term {
+        let mut term = match term.into_node() {
+            Node::Var(mut var) => {
+                self.rename(&mut var);
+                Term::Var(var)
+            }
+            Node::Let(mut let_) => {
+                self.bind(&mut let_.name);
+                let term = fold_children(self, Term::Let(let_));
+                self.unbind(1);
+                term
+            }
+            Node::Function(mut function) => {
+                for parameter in &mut function.parameters {
+                    self.bind(parameter);
+                }
+
+                let count = function.parameters.len();
+                let term = fold_children(self, Term::Function(function));
+                self.unbind(count);
+                term
+            }
+            node => fold_children(self, Term::from(node)),
+        };
+
+        *term.location_mut() = Location::default();
+        term
+    }
+}
+
+/// Shrinks a program without changing what it does: every location is
+/// emptied and bound names are replaced by the shortest names available.
+/// Names that the program uses without binding are kept.
+pub fn minify(mut file: File) -> File {
+    let mut free_names = FreeNames::default();
+    free_names.visit_term(&file.expression);
+
+    let mut minifier = Minifier {
+        free: free_names.free,
+        scopes: Vec::new(),
+        names: HashMap::new(),
+        next: 0,
+    };
+    file.expression = minifier.fold_term(file.expression);
+
+    file.location = Location::default();
+    file
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use crate::{
+        ast::{File, Term},
+        interpreter::{Collect, Context, Interpreter},
+        lexer::KEYWORDS,
+        parser::parse,
+    };
+
+    use super::{minify, short_name};
+
+    fn file(path: &str) -> File {
+        let text = std::fs::read_to_string(path).unwrap();
+        serde_json::from_str(&text).unwrap()
+    }
+
+    fn run(file: File) -> (String, Vec<String>) {
+        let mut interpreter = Interpreter::new(Collect::default());
+        let value = interpreter
+            .eval(file.expression, &mut Context::new())
+            .unwrap();
+
+        (value.to_string(), interpreter.io.0)
+    }
+
+    #[test]
+    fn short_names() {
+        assert_eq!(short_name(0), "a");
+        assert_eq!(short_name(51), "Z");
+        assert_eq!(short_name(52), "aa");
+        assert_eq!(short_name(53), "ba");
+    }
+
+    #[test]
+    fn keep_behaviour() {
+        for path in ["examples/fib.json", "examples/combination.json"] {
+            let original = file(path);
+            let minified = minify(original.clone());
+
+            assert_eq!(run(original), run(minified));
+        }
+    }
+
+    #[test]
+    fn shrink_output() {
+        let original = std::fs::read_to_string("examples/fib.json").unwrap();
+        let minified = serde_json::to_string(&minify(file("examples/fib.json"))).unwrap();
+
+        assert!(minified.len() < original.len() / 2);
+        assert!(!minified.contains("\"fib\""));
+    }
+
+    #[test]
+    fn skip_keywords() {
+        let mut source = (0..800)
+            .map(|index| format!("let v{index} = {index}; "))
+            .collect::<String>();
+        source.push_str("v799");
+
+        let original = parse(&source, "tests").unwrap();
+        let minified = minify(original.clone());
+
+        let mut names = Vec::new();
+        let mut term = &minified.expression;
+        while let Term::Let(let_) = term {
+            names.push(let_.name.text.to_string());
+            term = &let_.next;
+        }
+
+        assert_eq!(names.len(), 800);
+        assert!(names.contains(&String::from("aa")));
+        assert!(names.contains(&String::from("Zn")));
+        assert!(KEYWORDS
+            .iter()
+            .all(|(keyword, _)| !names.contains(&keyword.to_string())));
+        assert_eq!(run(original), run(minified));
+    }
+
+    #[test]
+    fn keep_free_names() {
+        let program: File = serde_json::from_str(
+            r#"{"name": "p", "location": {"start": 0, "end": 0, "filename": "p"},
+                "expression": {"kind": "Let", "name": {"text": "a", "location": {"start": 0, "end": 0, "filename": "p"}},
+                    "value": {"kind": "Int", "value": 1, "location": {"start": 0, "end": 0, "filename": "p"}},
+                    "next": {"kind": "Var", "text": "lib", "location": {"start": 0, "end": 0, "filename": "p"}},
+                    "location": {"start": 0, "end": 0, "filename": "p"}}}"#,
+        )
+        .unwrap();
+
+        let minified = serde_json::to_string(&minify(program)).unwrap();
+
+        assert!(minified.contains("\"lib\""));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{
+    ast::{Location, Term},
+    interpreter::{Frame, RuntimeError, Value},
+};
+
+/// Receives the events of an evaluation, for tracing, profiling or coverage,
+/// see [`Interpreter::with_observer`](crate::interpreter::Interpreter::with_observer).
+///
+/// Every event does nothing by default, so observers only implement the ones
+/// they need.
+pub trait EvalObserver {
+    /// Called before `term` is evaluated.
+    fn enter(&mut self, _term: &Term) {}
+
+    /// Called after the term at `location` is evaluated.
+    fn exit(&mut self, _location: &Location, _result: &Result<Value, RuntimeError>) {}
+
+    /// Called before a function is applied to its arguments.
+    fn call(&mut self, _frame: &Frame, _arguments: &[Value]) {}
+
+    /// Called with every printed value, before it is written.
+    fn print(&mut self, _value: &Value) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        ast::{Location, Term},
+        interpreter::{Collect, Context, Frame, Interpreter, RuntimeError, Value},
+        parser::parse,
+    };
+
+    use super::EvalObserver;
+
+    #[derive(Default)]
+    struct Events {
+        entered: usize,
+        exited: usize,
+        calls: Vec<String>,
+        prints: Vec<String>,
+    }
+
+    struct Recorder(Rc<RefCell<Events>>);
+
+    impl EvalObserver for Recorder {
+        fn enter(&mut self, _term: &Term) {
+            self.0.borrow_mut().entered += 1;
+        }
+
+        fn exit(&mut self, _location: &Location, _result: &Result<Value, RuntimeError>) {
+            self.0.borrow_mut().exited += 1;
+        }
+
+        fn call(&mut self, frame: &Frame, arguments: &[Value]) {
+            let arguments = arguments.iter().map(Value::to_string).collect::<Vec<_>>();
+            self.0
+                .borrow_mut()
+                .calls
+                .push(format!("{}({})", frame.name, arguments.join(", ")));
+        }
+
+        fn print(&mut self, value: &Value) {
+            self.0.borrow_mut().prints.push(value.to_string());
+        }
+    }
+
+    #[test]
+    fn record_events() {
+        let events = Rc::new(RefCell::new(Events::default()));
+        let file = parse("let f = fn (n) => { n + 1 }; print(f(1))", "tests").unwrap();
+
+        let mut interpreter =
+            Interpreter::new(Collect::default()).with_observer(Recorder(events.clone()));
+        interpreter
+            .eval(file.expression, &mut Context::new())
+            .unwrap();
+
+        let events = events.borrow();
+        assert_eq!(events.entered, events.exited);
+        assert!(events.entered > 0);
+        assert_eq!(events.calls, vec!["f(1)"]);
+        assert_eq!(events.prints, vec!["2"]);
+    }
+}
@@ -0,0 +1,880 @@
+use std::{iter::Peekable, vec::IntoIter};
+
+use crate::{
+    ast::{
+        deeper, Annotation, Binary, BinaryOp, Bool, Call, Element, File, First, Function, If, Int,
+        Let, Location, Node, Print, Second, Str, Term, Tuple, Var,
+    },
+    cst::{Builder, Cst, SyntaxKind},
+    intern::Interner,
+    lexer::{lex, lex_lossless, Spanned, SyntaxError, Token},
+    messages::ErrorCode,
+};
+
+/// Precedence of the binary operators, higher binds tighter.
+fn binary_op(token: &Token) -> Option<(BinaryOp, u8)> {
+    let op = match token {
+        Token::Or => (BinaryOp::Or, 1),
+        Token::And => (BinaryOp::And, 2),
+        Token::EqEq => (BinaryOp::Eq, 3),
+        Token::Neq => (BinaryOp::Neq, 3),
+        Token::Lt => (BinaryOp::Lt, 4),
+        Token::Gt => (BinaryOp::Gt, 4),
+        Token::Lte => (BinaryOp::Lte, 4),
+        Token::Gte => (BinaryOp::Gte, 4),
+        Token::Plus => (BinaryOp::Add, 5),
+        Token::Minus => (BinaryOp::Sub, 5),
+        Token::Star => (BinaryOp::Mul, 6),
+        Token::Slash => (BinaryOp::Div, 6),
+        Token::Percent => (BinaryOp::Rem, 6),
+        _ => return None,
+    };
+
+    Some(op)
+}
+
+/// How deep terms can be nested in the source, besides chains of `let`s,
+/// which are read without nesting. The passes and the other tools walking
+/// the syntax tree recurse into the terms, so deeper ones would overflow
+/// their stack.
+pub const MAX_DEPTH: usize = 1000;
+
+/// Location going from the start of `start` to the end of `end`.
+fn span(start: &Location, end: &Location) -> Location {
+    Location::new(start.start, end.end, &start.filename)
+}
+
+pub(crate) struct Parser<'a> {
+    tokens: Peekable<IntoIter<Spanned>>,
+    /// Location right after the last character, where the file ends.
+    eof: Location,
+    interner: &'a Interner,
+    /// The concrete syntax tree, when it is being built.
+    cst: Option<Builder<'a>>,
+    /// How many terms the next one is nested in.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(tokens: Vec<Spanned>, eof: Location, interner: &'a Interner) -> Self {
+        Parser {
+            tokens: tokens.into_iter().peekable(),
+            eof,
+            interner,
+            cst: None,
+            depth: 0,
+        }
+    }
+
+    /// Where the next node starts in the concrete syntax tree.
+    fn checkpoint(&self) -> usize {
+        self.cst.as_ref().map_or(0, Builder::checkpoint)
+    }
+
+    /// Ends the node of the concrete syntax tree started at `checkpoint`.
+    fn wrap(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        if let Some(cst) = &mut self.cst {
+            cst.wrap(checkpoint, kind);
+        }
+    }
+
+    fn consumed(&mut self, spanned: &Spanned) {
+        if let Some(cst) = &mut self.cst {
+            cst.token(spanned);
+        }
+    }
+
+    pub(crate) fn peek(&mut self) -> Option<&Token> {
+        self.tokens.peek().map(|spanned| &spanned.token)
+    }
+
+    pub(crate) fn next(&mut self) -> Result<Spanned, SyntaxError> {
+        let spanned = self
+            .tokens
+            .next()
+            .ok_or_else(|| SyntaxError::new(ErrorCode::UnexpectedEnd, vec![], self.eof.clone()))?;
+
+        self.consumed(&spanned);
+        Ok(spanned)
+    }
+
+    fn eat(&mut self, expected: &Token) -> Option<Location> {
+        let spanned = self.tokens.next_if(|spanned| spanned.token == *expected)?;
+
+        self.consumed(&spanned);
+        Some(spanned.location)
+    }
+
+    pub(crate) fn expect(&mut self, expected: Token) -> Result<Location, SyntaxError> {
+        let spanned = self.next()?;
+
+        if spanned.token != expected {
+            return Err(SyntaxError::new(
+                ErrorCode::UnexpectedToken,
+                vec![spanned.token.to_string(), expected.to_string()],
+                spanned.location,
+            ));
+        }
+
+        Ok(spanned.location)
+    }
+
+    fn ident(&mut self) -> Result<Var, SyntaxError> {
+        let spanned = self.next()?;
+
+        match spanned.token {
+            Token::Ident(text) => Ok(Var {
+                text: self.interner.intern(&text),
+                annotation: None,
+                location: spanned.location,
+            }),
+            token => Err(SyntaxError::new(
+                ErrorCode::ExpectedName,
+                vec![token.to_string()],
+                spanned.location,
+            )),
+        }
+    }
+
+    /// `name` or `name: annotation`
+    pub(crate) fn binder(&mut self) -> Result<Var, SyntaxError> {
+        let checkpoint = self.checkpoint();
+        let mut var = self.ident()?;
+
+        if self.eat(&Token::Colon).is_some() {
+            var.annotation = Some(self.annotation()?);
+        }
+
+        self.wrap(checkpoint, SyntaxKind::Binder);
+        Ok(var)
+    }
+
+    /// `int`, `str`, `bool`, `(first, second)` or `fn (parameters) => result`
+    fn annotation(&mut self) -> Result<Annotation, SyntaxError> {
+        let checkpoint = self.checkpoint();
+        let spanned = self.next()?;
+
+        let annotation = match spanned.token {
+            Token::Ident(name) if name == "int" => Annotation::Int,
+            Token::Ident(name) if name == "str" => Annotation::Str,
+            Token::Ident(name) if name == "bool" => Annotation::Bool,
+            Token::LParen => {
+                let first = self.annotation()?;
+                self.expect(Token::Comma)?;
+                let second = self.annotation()?;
+                self.eat(&Token::Comma);
+                self.expect(Token::RParen)?;
+
+                Annotation::Tuple {
+                    first: Box::new(first),
+                    second: Box::new(second),
+                }
+            }
+            Token::Fn => {
+                self.expect(Token::LParen)?;
+                let (parameters, _) = self.list(Self::annotation)?;
+                self.expect(Token::Arrow)?;
+                let result = self.annotation()?;
+
+                Annotation::Function {
+                    parameters,
+                    result: Box::new(result),
+                }
+            }
+            token => {
+                return Err(SyntaxError::new(
+                    ErrorCode::ExpectedType,
+                    vec![token.to_string()],
+                    spanned.location,
+                ))
+            }
+        };
+
+        self.wrap(checkpoint, SyntaxKind::Annotation);
+        Ok(annotation)
+    }
+
+    /// `{ term }`, along with the location of the closing brace.
+    fn block(&mut self) -> Result<(Term, Location), SyntaxError> {
+        let checkpoint = self.checkpoint();
+        self.expect(Token::LBrace)?;
+        let term = self.last()?;
+        let end = self.expect(Token::RBrace)?;
+        self.wrap(checkpoint, SyntaxKind::Block);
+
+        Ok((term, end))
+    }
+
+    /// `let name = value; next`, the name can be annotated with its type.
+    ///
+    /// The `let`s that `next` starts with are read by the same loop, so
+    /// programs made of thousands of bindings don't nest a call for each.
+    fn let_(&mut self, start: Location) -> Result<Term, SyntaxError> {
+        // The bindings read so far, the innermost last, along with where
+        // they start in the concrete syntax tree, which the caller knows
+        // for the first one.
+        let mut bindings = Vec::new();
+        let mut binding = (None, start);
+
+        loop {
+            let name = self.binder()?;
+            self.expect(Token::Assign)?;
+            let value = self.term()?;
+            self.expect(Token::Semicolon)?;
+            bindings.push((binding, name, value));
+
+            if self.peek() != Some(&Token::Let) {
+                break;
+            }
+
+            let checkpoint = self.checkpoint();
+            binding = (Some(checkpoint), self.next()?.location);
+        }
+
+        let mut next = self.term()?;
+
+        while let Some(((checkpoint, start), name, value)) = bindings.pop() {
+            next = Term::Let(Let {
+                location: span(&start, next.location()),
+                name,
+                value: Box::new(value),
+                next: Box::new(next),
+            });
+
+            if let Some(checkpoint) = checkpoint {
+                self.wrap(checkpoint, SyntaxKind::Let);
+            }
+        }
+
+        Ok(next)
+    }
+
+    /// `if (condition) { then } else { otherwise }`
+    fn if_(&mut self, start: Location) -> Result<Term, SyntaxError> {
+        let condition = self.term()?;
+        let (then, _) = self.block()?;
+        self.expect(Token::Else)?;
+        let (otherwise, end) = self.block()?;
+
+        Ok(Term::If(If {
+            location: span(&start, &end),
+            condition: Box::new(condition),
+            then: Box::new(then),
+            otherwise: Box::new(otherwise),
+        }))
+    }
+
+    /// `fn (parameters) => { value }`, the parameters and the result can be
+    /// annotated with their types, like in `fn (x: int): int => { x }`
+    fn function(&mut self, start: Location) -> Result<Term, SyntaxError> {
+        self.expect(Token::LParen)?;
+        let (parameters, _) = self.list(Self::binder)?;
+
+        let annotation = match self.eat(&Token::Colon) {
+            Some(_) => Some(self.annotation()?),
+            None => None,
+        };
+
+        self.expect(Token::Arrow)?;
+        let value = self.term()?;
+
+        Ok(Term::Function(Function {
+            location: span(&start, value.location()),
+            parameters,
+            annotation,
+            value: Box::new(value),
+        }))
+    }
+
+    /// Items separated by commas up to the closing parenthesis, whose
+    /// location is returned along with them. The last item can be followed
+    /// by a comma too.
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, SyntaxError>,
+    ) -> Result<(Vec<T>, Location), SyntaxError> {
+        let mut items = Vec::new();
+
+        loop {
+            if let Some(end) = self.eat(&Token::RParen) {
+                return Ok((items, end));
+            }
+
+            items.push(item(self)?);
+
+            if self.eat(&Token::Comma).is_none() {
+                return Ok((items, self.expect(Token::RParen)?));
+            }
+        }
+    }
+
+    /// `(term)` or `(first, second)`, which can end with a comma
+    fn parenthesized(&mut self, start: Location) -> Result<(Term, SyntaxKind), SyntaxError> {
+        let first = self.term()?;
+
+        if self.eat(&Token::Comma).is_none() {
+            self.expect(Token::RParen)?;
+            return Ok((first, SyntaxKind::Paren));
+        }
+
+        let second = self.term()?;
+        self.eat(&Token::Comma);
+        let end = self.expect(Token::RParen)?;
+
+        let tuple = Term::Tuple(Tuple {
+            location: span(&start, &end),
+            first: Box::new(first),
+            second: Box::new(second),
+        });
+
+        Ok((tuple, SyntaxKind::Tuple))
+    }
+
+    /// `print(value)`, `first(value)` and `second(value)`
+    fn builtin(&mut self, start: Location) -> Result<(Box<Term>, Location), SyntaxError> {
+        self.expect(Token::LParen)?;
+        let value = self.term()?;
+        let end = self.expect(Token::RParen)?;
+
+        Ok((Box::new(value), span(&start, &end)))
+    }
+
+    /// `become callee(arguments)`
+    fn become_(&mut self, start: Location) -> Result<Term, SyntaxError> {
+        match self.nested(Self::call)?.into_node() {
+            Node::Call(call) => Ok(Term::Call(Call {
+                location: span(&start, &call.location),
+                tail: true,
+                ..call
+            })),
+            node => Err(SyntaxError::new(
+                ErrorCode::ExpectedCall,
+                vec![],
+                Term::from(node).location().clone(),
+            )),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Term, SyntaxError> {
+        let checkpoint = self.checkpoint();
+        let Spanned { token, location } = self.next()?;
+
+        let (term, kind) = match token {
+            Token::Int(magnitude) => match i64::try_from(magnitude) {
+                Ok(value) => (Term::Int(Int { value, location }), SyntaxKind::Int),
+                Err(_) => {
+                    return Err(SyntaxError::new(
+                        ErrorCode::IntegerOverflow,
+                        vec![magnitude.to_string()],
+                        location,
+                    ))
+                }
+            },
+            // The minus is part of the literal, so that `i64::MIN` can be
+            // written.
+            Token::Minus => match self.next()? {
+                Spanned {
+                    token: Token::Int(magnitude),
+                    location: end,
+                } => (
+                    Term::Int(Int {
+                        value: 0_i64.wrapping_sub_unsigned(magnitude),
+                        location: span(&location, &end),
+                    }),
+                    SyntaxKind::Int,
+                ),
+                Spanned { token, location } => {
+                    return Err(SyntaxError::new(
+                        ErrorCode::ExpectedTerm,
+                        vec![token.to_string()],
+                        location,
+                    ))
+                }
+            },
+            Token::Str(value) => (
+                Term::Str(Str {
+                    value: self.interner.intern(&value),
+                    location,
+                }),
+                SyntaxKind::Str,
+            ),
+            Token::True => (
+                Term::Bool(Bool {
+                    value: true,
+                    location,
+                }),
+                SyntaxKind::Bool,
+            ),
+            Token::False => (
+                Term::Bool(Bool {
+                    value: false,
+                    location,
+                }),
+                SyntaxKind::Bool,
+            ),
+            Token::Ident(text) => (
+                Term::Var(Var {
+                    text: self.interner.intern(&text),
+                    annotation: None,
+                    location,
+                }),
+                SyntaxKind::Var,
+            ),
+            Token::Let => (self.let_(location)?, SyntaxKind::Let),
+            Token::If => (self.if_(location)?, SyntaxKind::If),
+            Token::Fn => (self.function(location)?, SyntaxKind::Function),
+            Token::Become => (self.become_(location)?, SyntaxKind::Call),
+            Token::LParen => self.parenthesized(location)?,
+            Token::LBrace => {
+                let term = self.last()?;
+                self.expect(Token::RBrace)?;
+                (term, SyntaxKind::Block)
+            }
+            Token::Print => {
+                let (value, location) = self.builtin(location)?;
+                (Term::Print(Print { value, location }), SyntaxKind::Print)
+            }
+            Token::First => {
+                let (value, location) = self.builtin(location)?;
+                (Term::First(First { value, location }), SyntaxKind::First)
+            }
+            Token::Second => {
+                let (value, location) = self.builtin(location)?;
+                (Term::Second(Second { value, location }), SyntaxKind::Second)
+            }
+            token => {
+                return Err(SyntaxError::new(
+                    ErrorCode::ExpectedTerm,
+                    vec![token.to_string()],
+                    location,
+                ))
+            }
+        };
+
+        self.wrap(checkpoint, kind);
+        Ok(term)
+    }
+
+    /// A primary term followed by any number of argument lists.
+    fn call(&mut self) -> Result<Term, SyntaxError> {
+        let checkpoint = self.checkpoint();
+        let mut callee = self.primary()?;
+
+        while self.eat(&Token::LParen).is_some() {
+            let (arguments, end) = self.list(Self::term)?;
+
+            callee = Term::Call(Call {
+                location: span(callee.location(), &end),
+                callee: Box::new(callee),
+                arguments,
+                tail: false,
+            });
+            self.wrap(checkpoint, SyntaxKind::Call);
+        }
+
+        Ok(callee)
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Result<Term, SyntaxError> {
+        let checkpoint = self.checkpoint();
+        let mut lhs = self.call()?;
+
+        while let Some((op, precedence)) = self.peek().and_then(binary_op) {
+            if precedence < min_precedence {
+                break;
+            }
+
+            self.next()?;
+            let rhs = self.binary(precedence + 1)?;
+
+            lhs = Term::Binary(Binary {
+                location: span(lhs.location(), rhs.location()),
+                lhs: Box::new(lhs),
+                op,
+                rhs: Box::new(rhs),
+            });
+            self.wrap(checkpoint, SyntaxKind::Binary);
+        }
+
+        Ok(lhs)
+    }
+
+    pub(crate) fn term(&mut self) -> Result<Term, SyntaxError> {
+        self.nested(|parser| parser.binary(0))
+    }
+
+    /// Reads a term with `read`, nested in one more term than the one
+    /// reading it.
+    fn nested(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<Term, SyntaxError>,
+    ) -> Result<Term, SyntaxError> {
+        if self.depth == MAX_DEPTH {
+            let location = match self.tokens.peek() {
+                Some(spanned) => spanned.location.clone(),
+                None => self.eof.clone(),
+            };
+
+            return Err(SyntaxError::new(
+                ErrorCode::TooDeep,
+                vec![MAX_DEPTH.to_string()],
+                location,
+            ));
+        }
+
+        // Each level takes kilobytes of stack in debug builds, more than
+        // threads have for a few hundred levels.
+        self.depth += 1;
+        let term = deeper(|| read(self));
+        self.depth -= 1;
+
+        term
+    }
+
+    /// A term that ends a block or the program, which can be followed by a
+    /// semicolon, like `{ print(x); }`.
+    pub(crate) fn last(&mut self) -> Result<Term, SyntaxError> {
+        let term = self.term()?;
+        self.eat(&Token::Semicolon);
+
+        Ok(term)
+    }
+
+    /// The whole program, which must use every token.
+    fn file(&mut self, source: &str, filename: &str) -> Result<File, SyntaxError> {
+        let expression = self.last()?;
+
+        if let Some(Spanned { token, location }) = self.tokens.next() {
+            return Err(SyntaxError::new(
+                ErrorCode::TrailingInput,
+                vec![token.to_string()],
+                location,
+            ));
+        }
+
+        check_tail_calls(&expression)?;
+
+        Ok(File {
+            name: filename.to_string(),
+            expression,
+            location: Location::new(0, source.len(), filename),
+        })
+    }
+}
+
+/// Checks that every call written `become f(x)` is the last term of a
+/// function, or of the program, where it replaces the call that made it
+/// instead of growing the stack. Programs read from JSON syntax trees are
+/// not parsed, so they are checked on their own.
+pub fn check_tail_calls(term: &Term) -> Result<(), SyntaxError> {
+    // Terms left to check, and whether they are in tail position, the next
+    // one in the source last.
+    let mut terms = vec![(term, true)];
+
+    while let Some((term, tail)) = terms.pop() {
+        match term {
+            Term::Call(call) => {
+                if call.tail && !tail {
+                    return Err(SyntaxError::new(
+                        ErrorCode::MisplacedBecome,
+                        vec![],
+                        call.location.clone(),
+                    ));
+                }
+
+                terms.extend(
+                    call.arguments
+                        .iter()
+                        .rev()
+                        .map(|argument| (argument, false)),
+                );
+                terms.push((&call.callee, false));
+            }
+            Term::Function(function) => terms.push((&function.value, true)),
+            Term::Let(let_) => {
+                terms.push((&let_.next, tail));
+                terms.push((&let_.value, false));
+            }
+            Term::If(if_) => {
+                terms.push((&if_.otherwise, tail));
+                terms.push((&if_.then, tail));
+                terms.push((&if_.condition, false));
+            }
+            Term::Binary(binary) => {
+                terms.push((&binary.rhs, false));
+                terms.push((&binary.lhs, false));
+            }
+            Term::Tuple(tuple) => {
+                terms.push((&tuple.second, false));
+                terms.push((&tuple.first, false));
+            }
+            Term::Print(Print { value, .. })
+            | Term::First(First { value, .. })
+            | Term::Second(Second { value, .. }) => terms.push((value, false)),
+            Term::Int(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_) => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a rinha program from its source code, producing the same syntax tree
+/// that is read from `.rinha.json` files.
+pub fn parse(source: &str, filename: &str) -> Result<File, SyntaxError> {
+    parse_with(source, filename, &Interner::new())
+}
+
+/// Reads a rinha program like [`parse`], sharing its names with the other
+/// terms that went through `interner`.
+pub fn parse_with(source: &str, filename: &str, interner: &Interner) -> Result<File, SyntaxError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("parse", filename).entered();
+
+    let eof = Location::new(source.len(), source.len(), filename);
+    let mut parser = Parser::new(lex(source, filename)?, eof, interner);
+
+    parser.file(source, filename)
+}
+
+/// Reads a rinha program like [`parse`], along with its concrete syntax
+/// tree, which keeps its comments and whitespace.
+///
+/// ```
+/// use lipsum::parser::parse_lossless;
+///
+/// let source = "// the answer\nlet x = 42; /* printed */ print(x)\n";
+/// let (_, cst) = parse_lossless(source, "example.rinha").unwrap();
+///
+/// assert_eq!(cst.to_string(), source);
+/// assert_eq!(cst.comments().count(), 2);
+/// ```
+pub fn parse_lossless(source: &str, filename: &str) -> Result<(File, Cst), SyntaxError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("parse", filename).entered();
+
+    let lossless = lex_lossless(source, filename)?;
+    let eof = Location::new(source.len(), source.len(), filename);
+    let interner = Interner::new();
+
+    let mut parser = Parser::new(lossless.tokens, eof, &interner);
+    parser.cst = Some(Builder::new(source, lossless.leading));
+
+    let file = parser.file(source, filename)?;
+    let cst = parser.cst.take().map(|cst| cst.finish(lossless.trailing));
+
+    Ok((file, cst.expect("the tree was being built")))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Annotation, File, Term},
+        interpreter::{Collect, Context, Interpreter},
+        messages::ErrorCode,
+    };
+
+    use super::{parse, MAX_DEPTH};
+
+    fn run(file: File) -> (String, Vec<String>) {
+        let mut interpreter = Interpreter::new(Collect::default());
+        let value = interpreter
+            .eval(file.expression, &mut Context::new())
+            .unwrap();
+
+        (value.to_string(), interpreter.io.0)
+    }
+
+    fn eval(source: &str) -> String {
+        run(parse(source, "tests").unwrap()).0
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn examples_match_json() {
+        for name in ["combination", "fib", "hello-world", "sum"] {
+            let source = std::fs::read_to_string(format!("examples/{name}.rinha")).unwrap();
+            let json = std::fs::read_to_string(format!("examples/{name}.json")).unwrap();
+            let json: File = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(run(parse(&source, name).unwrap()), run(json), "{name}");
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3 - -4"), "11");
+        assert_eq!(eval("1 < 2 && 2 < 3 || false"), "true");
+        assert_eq!(eval("(1 + 2) * 3"), "9");
+    }
+
+    #[test]
+    fn negative_literals() {
+        assert_eq!(eval("-9223372036854775808"), i64::MIN.to_string());
+        assert_eq!(eval("-0x8000_0000_0000_0000 + 1"), "-9223372036854775807");
+        assert_eq!(eval("(-5, 2 - -3)"), "(-5, 5)");
+
+        for source in ["9223372036854775808", "1 - 9223372036854775808"] {
+            let error = parse(source, "tests").unwrap_err();
+
+            assert_eq!(error.code, ErrorCode::IntegerOverflow, "{source}");
+            assert_eq!(error.location.end, source.len(), "{source}");
+        }
+    }
+
+    #[test]
+    fn tuples_and_calls() {
+        assert_eq!(
+            eval("let f = fn (a, b) => { (a, b) }; second(f(1, 2))"),
+            "2"
+        );
+        assert_eq!(eval("(fn () => { 1 })()"), "1");
+    }
+
+    #[test]
+    fn annotations() {
+        assert_eq!(
+            eval("let f = fn (x: int, y): (int, str) => { (x, y) }; let y: str = \"a\"; f(1, y)"),
+            "(1, a)"
+        );
+
+        let file = parse("let f: fn (int) => bool = fn (x) => x == 1; f", "tests").unwrap();
+        let Term::Let(let_) = &file.expression else {
+            panic!("expected a let");
+        };
+
+        assert_eq!(
+            let_.name.annotation,
+            Some(Annotation::Function {
+                parameters: vec![Annotation::Int],
+                result: Box::new(Annotation::Bool),
+            })
+        );
+    }
+
+    #[test]
+    fn trailing_commas_and_semicolons() {
+        assert_eq!(
+            eval("let f = fn (a, b,) => { (a, b,) }; second(f(1, 2,))"),
+            "2"
+        );
+        assert_eq!(
+            eval("let f: fn (int,) => (int, int,) = fn (x) => (x, x); f(1)"),
+            "(1, 1)"
+        );
+        assert_eq!(eval("let x = 1; if (x == 1) { x + 1; } else { x; };"), "2");
+        assert_eq!(
+            run(parse("print(1);\n", "tests").unwrap()).1,
+            vec!["1".to_string()]
+        );
+
+        let code = |source: &str| parse(source, "tests").unwrap_err().code;
+        assert_eq!(code("(1,)"), ErrorCode::ExpectedTerm);
+        assert_eq!(code("f(,)"), ErrorCode::ExpectedTerm);
+        assert_eq!(code("1;;"), ErrorCode::TrailingInput);
+    }
+
+    #[test]
+    fn become_tail_calls() {
+        let source = "let odd = fn (n, even) => { if (n == 0) { false } else { become even(n - 1) } };
+            let even = fn (n) => { if (n == 0) { true } else { let m = n - 1; become odd(m, even) } };
+            become even(100001)";
+        assert_eq!(eval(source), "false");
+
+        let code = |source: &str| parse(source, "tests").unwrap_err().code;
+        assert_eq!(
+            code("fn (n) => { 1 + become f(n) }"),
+            ErrorCode::MisplacedBecome
+        );
+        assert_eq!(
+            code("fn (n) => { let x = become f(n); x }"),
+            ErrorCode::MisplacedBecome
+        );
+        assert_eq!(code("fn (n) => { (become f)(n) }"), ErrorCode::ExpectedCall);
+        assert_eq!(
+            code("fn (n) => { g(become f(n)) }"),
+            ErrorCode::MisplacedBecome
+        );
+    }
+
+    #[test]
+    fn locations() {
+        let file = parse("let x = 1;\nx", "tests").unwrap();
+
+        match &file.expression {
+            Term::Let(let_) => {
+                assert_eq!((let_.name.location.start, let_.name.location.end), (4, 5));
+                assert_eq!((let_.location.start, let_.location.end), (0, 12));
+            }
+            _ => panic!("expected a let"),
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let code = |source: &str| parse(source, "tests").unwrap_err().code;
+
+        assert_eq!(code("let x = 1 x"), ErrorCode::UnexpectedToken);
+        assert_eq!(code("let = 1; 2"), ErrorCode::ExpectedName);
+        assert_eq!(code("1 +"), ErrorCode::UnexpectedEnd);
+        assert_eq!(code("1 )"), ErrorCode::TrailingInput);
+        assert_eq!(code("* 2"), ErrorCode::ExpectedTerm);
+        assert_eq!(code("let x: float = 1; x"), ErrorCode::ExpectedType);
+    }
+
+    #[test]
+    fn error_trait() {
+        let error: Box<dyn std::error::Error> = Box::new(parse("1 )", "tests").unwrap_err());
+
+        assert_eq!(
+            error.to_string(),
+            "E0109: unexpected \")\" after the program at tests:2..3"
+        );
+    }
+    #[test]
+    fn long_let_chains() {
+        let source = format!("{}x", "let x = 1;\n".repeat(100_000));
+        let file = parse(&source, "tests").unwrap();
+        let mut term = &file.expression;
+        let mut lets = 0;
+
+        while let Term::Let(let_) = term {
+            assert_eq!(let_.location.end, source.len());
+            term = &let_.next;
+            lets += 1;
+        }
+
+        assert_eq!(lets, 100_000);
+        assert!(matches!(term, Term::Var(_)));
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |open: &str, close: &str, depth: usize| {
+            format!("{}1{}", open.repeat(depth), close.repeat(depth))
+        };
+
+        // The integer is nested in one more term.
+        for (open, close) in [
+            ("(", ")"),
+            ("{", "}"),
+            ("f(", ")"),
+            ("1 + (", ")"),
+            ("if true { ", " } else { 0 }"),
+            ("fn () => ", ""),
+            ("print(", ")"),
+        ] {
+            let depth = MAX_DEPTH - 1;
+            assert!(
+                parse(&nested(open, close, depth), "tests").is_ok(),
+                "{open}"
+            );
+
+            let error = parse(&nested(open, close, depth + 1), "tests").unwrap_err();
+            assert_eq!(error.code, ErrorCode::TooDeep, "{open}");
+        }
+
+        let error = parse(&nested("(", ")", 100_000), "tests").unwrap_err();
+        assert_eq!(error.code, ErrorCode::TooDeep);
+        assert_eq!(error.location.start, MAX_DEPTH);
+    }
+}
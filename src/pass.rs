@@ -0,0 +1,401 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    ast::{
+        deeper,
+        visit::{fold_children, TermFolder},
+        BinaryOp, Bool, First, If, Int, Let, Node, Second, Str, Term,
+    },
+    interpreter::Value,
+};
+
+/// A transformation of the program run before evaluating it, which must not
+/// change what the program does.
+pub trait Pass {
+    /// Name used to refer to the pass from the command line.
+    fn name(&self) -> &'static str;
+
+    fn run(&mut self, term: Term) -> Term;
+}
+
+/// Replaces the operations between literals by their result. Operations that
+/// fail are kept, so they still fail when the program runs.
+#[derive(Default)]
+pub struct FoldConstants {
+    /// Whether the operations follow the specification to the letter, see
+    /// [`Value::operate_spec`].
+    pub spec: bool,
+}
+
+fn literal(term: &Term) -> Option<Value> {
+    match term {
+        Term::Int(int) => Some(Value::Int(int.value)),
+        Term::Str(str) => Some(Value::Str(str.value.clone().into())),
+        Term::Bool(bool) => Some(Value::Bool(bool.value)),
+        _ => None,
+    }
+}
+
+impl TermFolder for FoldConstants {
+    fn fold_term(&mut self, term: Term) -> Term {
+        let binary = match fold_children(self, term).into_node() {
+            Node::Binary(binary) => binary,
+            node => return Term::from(node),
+        };
+
+        let (Some(lhs), Some(rhs)) = (literal(&binary.lhs), literal(&binary.rhs)) else {
+            return Term::Binary(binary);
+        };
+
+        let location = binary.location.clone();
+        let value = match self.spec {
+            true => lhs.operate_spec(&binary.op, &rhs, &location),
+            false => lhs.operate(&binary.op, &rhs, &location),
+        };
+
+        match value {
+            Ok(Value::Int(value)) => Term::Int(Int { value, location }),
+            Ok(Value::Str(value)) => Term::Str(Str {
+                value: value.flatten(),
+                location,
+            }),
+            Ok(Value::Bool(value)) => Term::Bool(Bool { value, location }),
+            _ => Term::Binary(binary),
+        }
+    }
+}
+
+impl Pass for FoldConstants {
+    fn name(&self) -> &'static str {
+        "fold-constants"
+    }
+
+    fn run(&mut self, term: Term) -> Term {
+        self.fold_term(term)
+    }
+}
+
+/// Removes small patterns that do work only to undo it right away: a
+/// binding read right after it is made, a tuple built to take one of its
+/// literal elements back out, an `if` testing again the variable the
+/// enclosing one tested, and an `if` turning a comparison into the same
+/// boolean.
+///
+/// The outermost `let`s are kept as they are, since their bindings are left
+/// to the host.
+#[derive(Default)]
+pub struct Peephole;
+
+impl Peephole {
+    /// Keeps the outermost `let`s, simplifying what they bind and the term
+    /// they end in.
+    fn spine(&mut self, term: Term) -> Term {
+        deeper(|| match term.into_node() {
+            Node::Let(let_) => Term::Let(Let {
+                value: Box::new(self.fold_term(*let_.value)),
+                next: Box::new(self.spine(*let_.next)),
+                ..let_
+            }),
+            node => self.fold_term(Term::from(node)),
+        })
+    }
+}
+
+fn is_literal(term: &Term) -> bool {
+    literal(term).is_some()
+}
+
+/// The branch `term` takes when the variable `condition` is `value`, if it
+/// is an `if` testing the same variable.
+fn decided(term: Term, condition: &str, value: bool) -> Term {
+    match term.into_node() {
+        Node::If(if_) if matches!(&*if_.condition, Term::Var(var) if &*var.text == condition) => {
+            match value {
+                true => *if_.then,
+                false => *if_.otherwise,
+            }
+        }
+        node => Term::from(node),
+    }
+}
+
+impl TermFolder for Peephole {
+    fn fold_term(&mut self, term: Term) -> Term {
+        match fold_children(self, term).into_node() {
+            // Closures bind themselves, so they can call themselves by name,
+            // and annotations are still checked by the typechecker.
+            Node::Let(let_)
+                if !matches!(*let_.value, Term::Function(_))
+                    && let_.name.annotation.is_none()
+                    && matches!(&*let_.next, Term::Var(var) if var.text == let_.name.text) =>
+            {
+                *let_.value
+            }
+            Node::First(first) => match first.value.into_node() {
+                Node::Tuple(tuple) if is_literal(&tuple.second) => *tuple.first,
+                value => Term::First(First {
+                    value: Box::new(Term::from(value)),
+                    ..first
+                }),
+            },
+            Node::Second(second) => match second.value.into_node() {
+                Node::Tuple(tuple) if is_literal(&tuple.first) => *tuple.second,
+                value => Term::Second(Second {
+                    value: Box::new(Term::from(value)),
+                    ..second
+                }),
+            },
+            Node::If(if_) => match (if_.condition.into_node(), *if_.then, *if_.otherwise) {
+                (
+                    Node::Binary(condition),
+                    Term::Bool(Bool { value: true, .. }),
+                    Term::Bool(Bool { value: false, .. }),
+                ) if matches!(
+                    condition.op,
+                    BinaryOp::Eq
+                        | BinaryOp::Neq
+                        | BinaryOp::Lt
+                        | BinaryOp::Gt
+                        | BinaryOp::Lte
+                        | BinaryOp::Gte
+                ) =>
+                {
+                    Term::Binary(condition)
+                }
+                (Node::Var(condition), then, otherwise) => Term::If(If {
+                    then: Box::new(decided(then, &condition.text, true)),
+                    otherwise: Box::new(decided(otherwise, &condition.text, false)),
+                    condition: Box::new(Term::Var(condition)),
+                    location: if_.location,
+                }),
+                (condition, then, otherwise) => Term::If(If {
+                    condition: Box::new(Term::from(condition)),
+                    then: Box::new(then),
+                    otherwise: Box::new(otherwise),
+                    location: if_.location,
+                }),
+            },
+            node => Term::from(node),
+        }
+    }
+}
+
+impl Pass for Peephole {
+    fn name(&self) -> &'static str {
+        "peephole"
+    }
+
+    fn run(&mut self, term: Term) -> Term {
+        self.spine(term)
+    }
+}
+
+/// How long a pass took to run.
+#[derive(Debug, Clone)]
+pub struct Timing {
+    pub pass: &'static str,
+    pub time: Duration,
+}
+
+/// Passes run in sequence, each one over the output of the previous.
+pub struct Pipeline {
+    passes: Vec<Box<dyn Pass>>,
+}
+
+impl Default for Pipeline {
+    /// The passes run by the command line before evaluating programs.
+    fn default() -> Self {
+        Self::new()
+            .with_pass(FoldConstants::default())
+            .with_pass(Peephole)
+    }
+}
+
+impl Pipeline {
+    /// The passes run by the command line with `--spec`, folding constants
+    /// with the semantics of the specification.
+    pub fn spec() -> Self {
+        Self::new()
+            .with_pass(FoldConstants { spec: true })
+            .with_pass(Peephole)
+    }
+
+    /// A pipeline without any pass, leaving programs as they are.
+    pub fn new() -> Self {
+        Self { passes: Vec::new() }
+    }
+
+    /// Runs `pass` after the ones added before it.
+    pub fn with_pass<P: Pass + 'static>(mut self, pass: P) -> Self {
+        self.passes.push(Box::new(pass));
+
+        self
+    }
+
+    /// Keeps only the passes named in `names`, in the order they were added,
+    /// failing on names that are not passes of the pipeline.
+    pub fn only(mut self, names: &[&str]) -> Result<Self, String> {
+        if let Some(unknown) = names.iter().find(|name| !self.names().contains(name)) {
+            return Err(format!(
+                "unknown pass {unknown}, expected one of: {}",
+                self.names().join(", ")
+            ));
+        }
+
+        self.passes.retain(|pass| names.contains(&pass.name()));
+
+        Ok(self)
+    }
+
+    /// Names of the passes, in the order they run.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.passes.iter().map(|pass| pass.name()).collect()
+    }
+
+    /// Runs the passes over `term`, stopping after the one named `last` when
+    /// given, and returns the result along with the time taken by each pass.
+    pub fn run(&mut self, mut term: Term, last: Option<&str>) -> (Term, Vec<Timing>) {
+        let mut timings = Vec::new();
+
+        for pass in &mut self.passes {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("pass", name = pass.name()).entered();
+
+            let start = Instant::now();
+            term = pass.run(term);
+
+            timings.push(Timing {
+                pass: pass.name(),
+                time: start.elapsed(),
+            });
+
+            if last == Some(pass.name()) {
+                break;
+            }
+        }
+
+        (term, timings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{build::*, BinaryOp, Term},
+        interpreter::capture,
+    };
+
+    use super::{Pass, Peephole, Pipeline};
+
+    struct Rename;
+
+    impl Pass for Rename {
+        fn name(&self) -> &'static str {
+            "rename"
+        }
+
+        fn run(&mut self, _term: Term) -> Term {
+            var("renamed")
+        }
+    }
+
+    #[test]
+    fn fold_constants() {
+        let program = print(binary(
+            BinaryOp::Add,
+            binary(BinaryOp::Mul, int(2), int(3)),
+            binary(BinaryOp::Add, var("x"), int(1)),
+        ));
+
+        let (folded, timings) = Pipeline::default().run(program.clone(), None);
+        let expected = print(binary(
+            BinaryOp::Add,
+            int(6),
+            binary(BinaryOp::Add, var("x"), int(1)),
+        ));
+
+        assert_eq!(folded, expected);
+        assert_eq!(timings.len(), 2);
+    }
+
+    #[test]
+    fn failing_operations_are_kept() {
+        let program = binary(BinaryOp::Div, int(1), int(0));
+        let (folded, _) = Pipeline::default().run(program.clone(), None);
+
+        assert_eq!(folded, program);
+        assert!(capture(folded).0.is_err());
+
+        // Strings can't be ordered in the specification.
+        let program = binary(BinaryOp::Lt, str("a"), str("b"));
+        assert_eq!(Pipeline::default().run(program.clone(), None).0, bool(true));
+        assert_eq!(Pipeline::spec().run(program.clone(), None).0, program);
+    }
+
+    #[test]
+    fn peephole() {
+        let program = |inner| let_("f", function(&["n"], inner), call(var("f"), vec![int(1)]));
+        let run = |term| Pipeline::new().with_pass(Peephole).run(term, None).0;
+
+        // A binding read right away.
+        let bound = let_("x", binary(BinaryOp::Add, var("n"), int(1)), var("x"));
+        assert_eq!(
+            run(program(bound)),
+            program(binary(BinaryOp::Add, var("n"), int(1)))
+        );
+
+        // A tuple taken apart.
+        let taken = first(tuple(var("n"), int(2)));
+        assert_eq!(run(program(taken)), program(var("n")));
+        let kept = first(tuple(var("n"), var("m")));
+        assert_eq!(run(program(kept.clone())), program(kept));
+
+        // A variable tested again, and a comparison turned into a boolean.
+        let tested = if_(
+            var("n"),
+            if_(var("n"), int(1), int(2)),
+            if_(
+                var("n"),
+                int(3),
+                if_(
+                    binary(BinaryOp::Lt, var("n"), int(0)),
+                    bool(true),
+                    bool(false),
+                ),
+            ),
+        );
+        let expected = if_(var("n"), int(1), binary(BinaryOp::Lt, var("n"), int(0)));
+        assert_eq!(run(program(tested)), program(expected));
+
+        // The outermost bindings and closures binding themselves are kept.
+        for kept in [
+            let_("x", int(1), var("x")),
+            program(let_("g", function(&["m"], var("m")), var("g"))),
+        ] {
+            assert_eq!(run(kept.clone()), kept);
+        }
+    }
+
+    #[test]
+    fn stop_after() {
+        let mut pipeline = Pipeline::default().with_pass(Rename);
+        assert_eq!(
+            pipeline.names(),
+            vec!["fold-constants", "peephole", "rename"]
+        );
+
+        let (term, timings) = pipeline.run(int(1), Some("fold-constants"));
+        assert_eq!(term, int(1));
+        assert_eq!(timings.len(), 1);
+
+        let (term, _) = pipeline.run(int(1), None);
+        assert_eq!(term, var("renamed"));
+
+        let pipeline = Pipeline::default()
+            .with_pass(Rename)
+            .only(&["rename"])
+            .unwrap();
+        assert_eq!(pipeline.names(), vec!["rename"]);
+        assert!(Pipeline::default().only(&["inline"]).is_err());
+    }
+}
@@ -0,0 +1,350 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    ast::{Element, Location, Term},
+    interpreter::{RuntimeError, Value},
+    observer::EvalObserver,
+};
+
+/// A call that returned, or failed.
+#[derive(Debug, Clone)]
+pub struct Span {
+    /// Name the callee was bound to, or `<anonymous>`.
+    pub name: String,
+    /// Where the call is.
+    pub location: Location,
+    /// When the call started, since the profiler was created.
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+/// The calls to a function, see [`Profiler::hottest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hot {
+    /// Name the function was called by, or `<anonymous>@` and the call site.
+    pub name: String,
+    pub calls: u64,
+    /// Time spent in the function and the calls it makes, counting the
+    /// recursive calls once.
+    pub time: Duration,
+}
+
+/// A call being evaluated.
+struct Open {
+    /// The name of the callee, followed by `@` and the call site when it is
+    /// anonymous, so different anonymous functions are told apart.
+    key: String,
+    span: Span,
+    started: Instant,
+    /// Time spent in the calls made by this one.
+    children: Duration,
+}
+
+struct State {
+    origin: Instant,
+    /// Whether the spans and the stacks are kept, or only the totals.
+    detailed: bool,
+    /// Whether each term being evaluated is a call, the innermost last.
+    terms: Vec<bool>,
+    calls: Vec<Open>,
+    spans: Vec<Span>,
+    /// Time spent in each stack of calls, outermost first, not counting the
+    /// calls it makes.
+    stacks: BTreeMap<String, Duration>,
+    /// Calls and time of each function, by key.
+    totals: HashMap<String, (u64, Duration)>,
+    /// How many calls of each function are being evaluated.
+    running: HashMap<String, usize>,
+}
+
+/// Observer that measures how long every call takes, to find where the time
+/// of a program goes, see
+/// [`Interpreter::with_observer`](crate::interpreter::Interpreter::with_observer).
+///
+/// Clones share the measures, so a clone can be given to the interpreter
+/// while the original is kept to read them after the run.
+#[derive(Clone)]
+pub struct Profiler(Rc<RefCell<State>>);
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::with_detail(true)
+    }
+
+    /// Creates a profiler that only keeps the totals of each function, see
+    /// [`Profiler::hottest`], so it doesn't grow with the number of calls.
+    pub fn counting() -> Self {
+        Self::with_detail(false)
+    }
+
+    fn with_detail(detailed: bool) -> Self {
+        Profiler(Rc::new(RefCell::new(State {
+            origin: Instant::now(),
+            detailed,
+            terms: Vec::new(),
+            calls: Vec::new(),
+            spans: Vec::new(),
+            stacks: BTreeMap::new(),
+            totals: HashMap::new(),
+            running: HashMap::new(),
+        })))
+    }
+
+    /// The `count` functions called the most, the most called first.
+    pub fn hottest(&self, count: usize) -> Vec<Hot> {
+        let mut hottest = self
+            .0
+            .borrow()
+            .totals
+            .iter()
+            .map(|(name, (calls, time))| Hot {
+                name: name.clone(),
+                calls: *calls,
+                time: *time,
+            })
+            .collect::<Vec<_>>();
+
+        hottest.sort_by(|a, b| {
+            (b.calls, b.time)
+                .cmp(&(a.calls, a.time))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        hottest.truncate(count);
+
+        hottest
+    }
+
+    /// Every call that returned, in the order they returned.
+    pub fn spans(&self) -> Vec<Span> {
+        self.0.borrow().spans.clone()
+    }
+
+    /// The time spent in each stack of calls in the folded format of
+    /// flamegraph tools, like speedscope or inferno: one line per stack,
+    /// with the names of the calls separated by `;`, outermost first, and
+    /// the microseconds spent in the innermost one.
+    pub fn folded(&self) -> String {
+        let mut folded = String::new();
+
+        for (stack, time) in &self.0.borrow().stacks {
+            let _ = writeln!(folded, "{stack} {}", time.as_micros());
+        }
+
+        folded
+    }
+
+    /// The calls as complete events of the Chrome trace event format, which
+    /// can be opened in chrome://tracing, Perfetto or speedscope.
+    #[cfg(feature = "json")]
+    pub fn chrome_trace(&self) -> serde_json::Value {
+        let events = self
+            .0
+            .borrow()
+            .spans
+            .iter()
+            .map(|span| {
+                serde_json::json!({
+                    "name": span.name,
+                    "cat": "call",
+                    "ph": "X",
+                    "ts": span.start.as_secs_f64() * 1e6,
+                    "dur": span.duration.as_secs_f64() * 1e6,
+                    "pid": 1,
+                    "tid": 1,
+                    "args": { "location": span.location.to_string() },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+    }
+}
+
+impl EvalObserver for Profiler {
+    fn enter(&mut self, term: &Term) {
+        let mut state = self.0.borrow_mut();
+        let Term::Call(call) = term else {
+            state.terms.push(false);
+            return;
+        };
+
+        let name = match call.callee.as_ref() {
+            Term::Var(var) => var.text.to_string(),
+            _ => String::from("<anonymous>"),
+        };
+
+        let key = match call.callee.as_ref() {
+            Term::Var(_) => name.clone(),
+            _ => format!("{name}@{}", call.location),
+        };
+
+        let started = Instant::now();
+        let start = started - state.origin;
+
+        state.totals.entry(key.clone()).or_default().0 += 1;
+        *state.running.entry(key.clone()).or_default() += 1;
+
+        state.terms.push(true);
+        state.calls.push(Open {
+            key,
+            span: Span {
+                name,
+                location: term.location().clone(),
+                start,
+                duration: Duration::ZERO,
+            },
+            started,
+            children: Duration::ZERO,
+        });
+    }
+
+    fn exit(&mut self, _location: &Location, _result: &Result<Value, RuntimeError>) {
+        let mut state = self.0.borrow_mut();
+
+        if state.terms.pop() != Some(true) {
+            return;
+        }
+
+        let Some(mut open) = state.calls.pop() else {
+            return;
+        };
+
+        open.span.duration = open.started.elapsed();
+
+        if let Some(parent) = state.calls.last_mut() {
+            parent.children += open.span.duration;
+        }
+
+        let running = state.running.entry(open.key.clone()).or_default();
+        *running -= 1;
+
+        // The time of recursive calls is already in the outermost one.
+        if *running == 0 {
+            state.totals.entry(open.key.clone()).or_default().1 += open.span.duration;
+        }
+
+        if !state.detailed {
+            return;
+        }
+
+        let stack = state
+            .calls
+            .iter()
+            .map(|call| call.key.as_str())
+            .chain(std::iter::once(open.key.as_str()))
+            .collect::<Vec<_>>()
+            .join(";");
+
+        *state.stacks.entry(stack).or_default() += open.span.duration.saturating_sub(open.children);
+        state.spans.push(open.span);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{Collect, Context, Interpreter},
+        parser::parse,
+    };
+
+    use super::Profiler;
+
+    fn profile(source: &str) -> Profiler {
+        profile_with(Profiler::new(), source)
+    }
+
+    fn profile_with(profiler: Profiler, source: &str) -> Profiler {
+        let file = parse(source, "tests").unwrap();
+        let mut interpreter = Interpreter::new(Collect::default())
+            .with_memoization(false)
+            .with_observer(profiler.clone());
+
+        let _ = interpreter.eval(file.expression, &mut Context::new());
+
+        profiler
+    }
+
+    #[test]
+    fn calls() {
+        let profiler = profile(
+            "let f = fn (n) => { if (n == 0) { 0 } else { f(n - 1) } };
+            let g = fn (n) => { f(n) };
+            g(2)",
+        );
+
+        let spans = profiler.spans();
+        let names = spans.iter().map(|span| &*span.name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["f", "f", "f", "g"]);
+        assert!(spans[3].duration >= spans[2].duration);
+
+        let stacks = profiler
+            .folded()
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(stacks, vec!["g", "g;f", "g;f;f", "g;f;f;f"]);
+    }
+
+    #[test]
+    fn anonymous_calls_and_errors() {
+        let profiler = profile("let f = fn (x) => { x / 0 }; (fn (x) => { f(x) })(1)");
+
+        let stacks = profiler
+            .folded()
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(stacks.len(), 2);
+        assert!(stacks[0].starts_with("<anonymous>@tests:"), "{stacks:?}");
+        assert!(stacks[1].ends_with(";f"), "{stacks:?}");
+    }
+
+    #[test]
+    fn hottest() {
+        let source = "let f = fn (n) => { if (n == 0) { 0 } else { f(n - 1) } };
+            let g = fn (n) => { f(n) };
+            let _ = g(1);
+            g(3)";
+
+        for profiler in [Profiler::new(), Profiler::counting()] {
+            let profiler = profile_with(profiler, source);
+            let hottest = profiler.hottest(10);
+
+            let calls = hottest
+                .iter()
+                .map(|hot| (&*hot.name, hot.calls))
+                .collect::<Vec<_>>();
+            assert_eq!(calls, vec![("f", 6), ("g", 2)]);
+            assert!(hottest[1].time >= hottest[0].time, "{hottest:?}");
+            assert_eq!(profiler.hottest(1).len(), 1);
+        }
+
+        assert!(profile_with(Profiler::counting(), source)
+            .spans()
+            .is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn chrome_trace() {
+        let profiler = profile("let f = fn (n) => { n }; f(1)");
+        let trace = profiler.chrome_trace();
+
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["name"], "f");
+        assert_eq!(events[0]["ph"], "X");
+    }
+}
@@ -0,0 +1,331 @@
+//! Evaluation of programs sent by other processes, shared by `lipsum serve`
+//! and `lipsum stdio`, which answer with the same JSON documents:
+//!
+//! ```json
+//! {
+//!   "prints": ["1"],
+//!   "result": { "kind": "Int", "value": 2 },
+//!   "time": { "parse": 0.05, "eval": 0.12 }
+//! }
+//! ```
+//!
+//! The final value is replaced by an `error` when the program fails, and
+//! the times are in milliseconds.
+
+use std::{
+    io::{self, BufRead, Write},
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, value::RawValue};
+
+use crate::{
+    ast::File, config::Limits, json::read_file, lexer::SyntaxError, messages::Lang,
+    parser::check_tail_calls, sync::SyncInterpreter,
+};
+
+/// A program to evaluate.
+#[derive(Debug, Clone, Copy)]
+pub enum Program<'a> {
+    /// Source code.
+    Source(&'a str),
+    /// The text of a JSON syntax tree.
+    Ast(&'a str),
+}
+
+/// Parses and evaluates `program` in a fresh interpreter with the given
+/// `limits`, answering with the document of its evaluation, or with the
+/// document of the error when it can't be parsed.
+pub fn evaluate(
+    interpreter: &SyncInterpreter,
+    program: Program,
+    limits: Limits,
+    lang: Lang,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let start = Instant::now();
+    let file = load(interpreter, program, lang)?;
+    let parse = start.elapsed();
+
+    let start = Instant::now();
+    let evaluation = interpreter
+        .clone()
+        .with_limits(limits)
+        .eval(file.expression);
+    let eval = start.elapsed();
+
+    let mut body = json!({
+        "prints": evaluation.prints,
+        "time": { "parse": millis(parse), "eval": millis(eval) },
+    });
+
+    match evaluation.result {
+        Ok(value) => body["result"] = value,
+        Err(error) => {
+            body["error"] = json!({
+                "code": error.code.as_str(),
+                "message": error.message(lang),
+                "full_text": error.full_text(lang),
+                "location": error.location,
+            })
+        }
+    }
+
+    Ok(body)
+}
+
+/// The limits of `limits` lowered to the ones in `asked`. Requests can't
+/// raise the limits of the process serving them.
+pub fn lower(limits: &Limits, asked: &Limits) -> Limits {
+    fn lowest<T: Ord + Copy>(limit: Option<T>, asked: Option<T>) -> Option<T> {
+        match (limit, asked) {
+            (Some(limit), Some(asked)) => Some(limit.min(asked)),
+            (limit, asked) => limit.or(asked),
+        }
+    }
+
+    Limits {
+        fuel: lowest(limits.fuel, asked.fuel),
+        max_depth: lowest(limits.max_depth, asked.max_depth),
+        timeout: lowest(limits.timeout, asked.timeout),
+        max_memory: lowest(limits.max_memory, asked.max_memory),
+        detect_loops: lowest(limits.detect_loops, asked.detect_loops),
+    }
+}
+
+/// A line read by [`stdio`].
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Request {
+    /// Copied to the response, to match them when they are pipelined.
+    #[serde(default)]
+    id: serde_json::Value,
+    source: Option<String>,
+    /// Kept as text, to be read by [`read_file`], which reads trees of any
+    /// depth.
+    ast: Option<Box<RawValue>>,
+    #[serde(default)]
+    limits: Limits,
+    lang: Option<String>,
+}
+
+/// Makes the builtins that read input, like `read_line`, fail with
+/// `message`. Programs evaluated for other processes must not read the
+/// input of the one evaluating them, which would wait for it forever.
+pub fn without_input(interpreter: &mut SyncInterpreter, message: &'static str) {
+    for native in ["read_line", "read_all", "read_int"] {
+        interpreter.register_native(native, move |_arguments| Err(String::from(message)));
+    }
+}
+
+/// Answers the requests in the lines of `input`, writing a line with the
+/// response to each one to `output`, until `input` ends.
+///
+/// A request has the `source` of the program or its `ast`, and may lower the
+/// `limits` of `interpreter`, in the format of `rinha.toml`, or ask for the
+/// messages in another `lang`. The response has the `id` of the request,
+/// when given, and the document of [`evaluate`]. Programs have no input,
+/// the lines of `input` are the requests.
+pub fn stdio<R: BufRead, W: Write>(
+    interpreter: &SyncInterpreter,
+    lang: Lang,
+    input: R,
+    mut output: W,
+) -> io::Result<()> {
+    let mut interpreter = interpreter.clone();
+    without_input(&mut interpreter, "the input is the requests");
+
+    for line in input.lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut response = respond(&interpreter, lang, &line).unwrap_or_else(|error| error);
+
+        if let Ok(request) = serde_json::from_str::<Request>(&line) {
+            if !request.id.is_null() {
+                response["id"] = request.id;
+            }
+        }
+
+        writeln!(output, "{response}")?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+fn respond(
+    interpreter: &SyncInterpreter,
+    lang: Lang,
+    line: &str,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let request: Request =
+        serde_json::from_str(line).map_err(|error| message(format!("invalid request: {error}")))?;
+
+    let lang = match &request.lang {
+        Some(lang) => lang.parse().map_err(message)?,
+        None => lang,
+    };
+
+    let program = match (&request.source, &request.ast) {
+        (Some(source), None) => Program::Source(source),
+        (None, Some(ast)) => Program::Ast(ast.get()),
+        _ => return Err(message("a request has either a source or an ast")),
+    };
+
+    let limits = lower(interpreter.limits(), &request.limits);
+
+    evaluate(interpreter, program, limits, lang)
+}
+
+/// Document of an error that has no code.
+pub fn message(message: impl Into<String>) -> serde_json::Value {
+    json!({ "error": { "message": message.into() } })
+}
+
+/// Parses the program, describing what is wrong with it as the document of
+/// an error.
+fn load(
+    interpreter: &SyncInterpreter,
+    program: Program,
+    lang: Lang,
+) -> Result<File, serde_json::Value> {
+    let syntax = |error: SyntaxError| {
+        json!({
+            "error": {
+                "code": error.code.as_str(),
+                "message": error.message(lang),
+                "full_text": error.full_text(lang),
+                "location": error.location,
+            }
+        })
+    };
+
+    match program {
+        Program::Source(source) => interpreter.parse(source, "request.rinha").map_err(syntax),
+        Program::Ast(text) => {
+            let (file, _warnings) = read_file(text, "request.rinha.json")
+                .map_err(|error| message(error.to_string()))?;
+
+            check_tail_calls(&file.expression).map_err(syntax)?;
+
+            Ok(File {
+                expression: interpreter.interner().layer().intern_term(file.expression),
+                ..file
+            })
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{Config, Limits},
+        messages::Lang,
+        sync::SyncInterpreter,
+    };
+
+    use super::stdio;
+
+    #[test]
+    fn json_lines() {
+        let interpreter = SyncInterpreter::new(Config {
+            limits: Limits {
+                fuel: Some(100),
+                ..Limits::default()
+            },
+            ..Config::default()
+        });
+
+        let input = [
+            r#"{"id": 1, "source": "let _ = print(1); 2"}"#,
+            "",
+            r#"{"id": "b", "ast": {"expression": {"kind": "Int", "value": 3}}}"#,
+            r#"{"id": 3, "source": "let f = fn (n) => { n + 1 }; f(1) + f(2)", "limits": {"fuel": 1}}"#,
+            r#"{"id": 4, "source": "let = 1"}"#,
+            r#"{"id": 5}"#,
+            "not json",
+        ]
+        .join("\n");
+
+        let mut output = Vec::new();
+        stdio(&interpreter, Lang::En, input.as_bytes(), &mut output).unwrap();
+
+        let responses = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(responses.len(), 6);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["prints"][0], "1");
+        assert_eq!(responses[0]["result"]["value"], 2);
+        assert_eq!(responses[1]["id"], "b");
+        assert_eq!(responses[1]["result"]["value"], 3);
+        assert_eq!(responses[2]["error"]["code"], "E0017");
+        assert!(responses[3]["error"]["code"].is_string());
+        assert_eq!(responses[4]["id"], 5);
+        assert!(responses[4]["error"]["message"].is_string());
+        assert!(responses[5]["error"]["message"].is_string());
+    }
+
+    /// Responses of `stdio` to the lines of `input`.
+    fn responses(input: &str) -> Vec<serde_json::Value> {
+        let mut output = Vec::new();
+        stdio(
+            &SyncInterpreter::default(),
+            Lang::En,
+            input.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn no_input() {
+        // The program would wait for the next request otherwise.
+        let responses = responses(
+            &[
+                r#"{"id": 1, "source": "print(read_line())"}"#,
+                r#"{"id": 2, "source": "1"}"#,
+            ]
+            .join("\n"),
+        );
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["error"]["code"], "E0019");
+        assert_eq!(responses[1]["result"]["value"], 1);
+    }
+
+    #[test]
+    fn deep_trees() {
+        let location = r#"{ "start": 0, "end": 1, "filename": "deep" }"#;
+        let binding = format!(
+            r#"{{ "kind": "Let", "name": {{ "text": "x", "location": {location} }}, "value": {{ "kind": "Int", "value": 1, "location": {location} }}, "location": {location}, "next": "#
+        );
+
+        let depth = 10_000;
+        let ast = format!(
+            r#"{{ "name": "deep", "location": {location}, "expression": {}{{ "kind": "Var", "text": "x", "location": {location} }}{} }}"#,
+            binding.repeat(depth),
+            " }".repeat(depth),
+        );
+        let responses = responses(&format!(r#"{{"id": 1, "ast": {ast}}}"#));
+
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"]["value"], 1);
+    }
+}
@@ -0,0 +1,504 @@
+//! The interactive prompt of `lipsum repl`, evaluating each input as it is
+//! typed, with the bindings of the outermost `let`s of every input visible
+//! to the next ones.
+//!
+//! On a terminal the line is edited in place, moving through the lines typed
+//! before with the arrows and completing the bound names with Tab. Inputs
+//! left unterminated, like an unclosed brace or a `let` without its body,
+//! continue on the next line.
+//!
+//! Lines starting with a colon are commands of the REPL itself, see
+//! [`HELP`].
+
+use std::{
+    io::{self, BufRead, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    ast::Term,
+    interpreter::{
+        CancelToken, Context, EvalError, InspectLimits, Interpreter, Printer, RuntimeError, Value,
+    },
+    lexer::SyntaxError,
+    messages::{ErrorCode, Lang},
+    parser::parse,
+    typecheck::Checker,
+};
+
+mod editor;
+#[cfg(unix)]
+mod terminal;
+
+pub use editor::{read_key, Edit, Editor, History, Key, HISTORY_LIMIT};
+#[cfg(unix)]
+pub use terminal::Terminal;
+
+static PROMPT: &str = "> ";
+
+static CONTINUATION: &str = "... ";
+
+/// The commands of the REPL.
+pub static HELP: &str = "\
+commands:
+  :ast [expr]   show the syntax tree of the expression, or of the last input
+  :type [expr]  show the inferred type of the expression, or of the last input
+  :time [expr]  evaluate the expression and show how long it took, or show
+                how long the last input took
+  :env          list the names bound so far and their values
+  :set [limit n]
+                show values up to a depth, length or string of n, or show
+                the limits
+  :help         show this message
+  :quit         leave the REPL";
+
+/// Keywords completed along with the bound names.
+static KEYWORDS: [&str; 9] = [
+    "let", "fn", "if", "else", "true", "false", "print", "first", "second",
+];
+
+/// Where the lines of a REPL come from.
+pub trait Input {
+    /// Reads a line after showing `prompt`, completing words with `names`,
+    /// or `None` at the end of the input. Fails with
+    /// [`io::ErrorKind::Interrupted`] when the line is dropped with Ctrl-C.
+    fn read_line(&mut self, prompt: &str, names: &[String]) -> io::Result<Option<String>>;
+}
+
+/// Lines read without editing, from a pipe or a file.
+pub struct Lines<R: BufRead, W: Write> {
+    pub input: R,
+    pub output: W,
+}
+
+impl<R: BufRead, W: Write> Input for Lines<R, W> {
+    fn read_line(&mut self, prompt: &str, _names: &[String]) -> io::Result<Option<String>> {
+        write!(self.output, "{prompt}")?;
+        self.output.flush()?;
+
+        let mut line = String::new();
+
+        match self.input.read_line(&mut line)? {
+            0 => Ok(None),
+            _ => Ok(Some(line.trim_end_matches(['\r', '\n']).to_string())),
+        }
+    }
+}
+
+/// An input evaluated by the REPL.
+struct Evaluated {
+    term: Term,
+    parse: Duration,
+    eval: Duration,
+}
+
+/// Evaluates inputs one after the other in the same interpreter.
+pub struct Repl<I: Printer> {
+    interpreter: Interpreter<I>,
+    context: Context,
+    lang: Lang,
+    cancel: Option<CancelToken>,
+    /// The prelude and the inputs that didn't fail, whose bindings are
+    /// checked again to infer the types of the next ones.
+    bindings: Vec<Term>,
+    last: Option<Evaluated>,
+    /// How much of the values is shown.
+    limits: InspectLimits,
+}
+
+impl<I: Printer> Repl<I> {
+    pub fn new(interpreter: Interpreter<I>, lang: Lang) -> Self {
+        Self {
+            interpreter,
+            context: Context::new(),
+            lang,
+            cancel: None,
+            bindings: Vec::new(),
+            last: None,
+            limits: InspectLimits::default(),
+        }
+    }
+
+    /// Evaluates `prelude` first, so that its bindings are visible to the
+    /// inputs.
+    pub fn with_prelude(mut self, prelude: Term) -> Result<Self, RuntimeError> {
+        self.interpreter.eval(prelude.clone(), &mut self.context)?;
+        self.bindings.push(prelude);
+
+        Ok(self)
+    }
+
+    /// Stops the running input once `token` is cancelled, resetting it for
+    /// the next one.
+    pub fn with_cancel(mut self, token: CancelToken) -> Self {
+        self.interpreter = self.interpreter.with_cancel(token.clone());
+        self.cancel = Some(token);
+
+        self
+    }
+
+    /// The names completed at the prompt: the keywords, the natives and the
+    /// names bound so far, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = KEYWORDS
+            .into_iter()
+            .chain(self.interpreter.natives())
+            .chain(self.context.keys().map(|name| &**name))
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Parses and evaluates `source`, keeping the bindings of its outermost
+    /// `let`s.
+    pub fn eval(&mut self, source: &str) -> Result<Value, EvalError> {
+        let start = Instant::now();
+        let term = parse(source, "<repl>")?.expression;
+        let parse = start.elapsed();
+
+        let start = Instant::now();
+        let result = self.interpreter.eval(term.clone(), &mut self.context);
+        let eval = start.elapsed();
+
+        if let Some(token) = &self.cancel {
+            token.reset();
+        }
+
+        if result.is_ok() {
+            self.bindings.push(term.clone());
+        }
+
+        self.last = Some(Evaluated { term, parse, eval });
+
+        Ok(result?)
+    }
+
+    /// Infers the type of `term`, knowing the types of the names bound so
+    /// far.
+    fn check(&self, term: &Term) -> Result<String, String> {
+        let mut checker = Checker::new();
+
+        // Inputs that don't typecheck still ran, the names they bind are
+        // then of unknown types.
+        for bindings in &self.bindings {
+            let _ = checker.prelude(bindings);
+        }
+
+        checker
+            .check(term)
+            .map(|ty| ty.to_string())
+            .map_err(|errors| {
+                errors
+                    .iter()
+                    .map(|error| format!("error[{}]: {}", error.code, error.message(self.lang)))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+    }
+
+    /// Runs the command of a line starting with a colon, telling whether
+    /// the REPL goes on.
+    fn command<W: Write>(&mut self, line: &str, output: &mut W) -> io::Result<bool> {
+        let line = line.trim();
+        let (name, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim();
+
+        // The expression given, or else the last input.
+        let term = match argument {
+            "" => self.last.as_ref().map(|last| Ok(last.term.clone())),
+            source => Some(parse(source, "<repl>").map(|file| file.expression)),
+        };
+
+        match (name, term) {
+            (":quit" | ":q", _) => return Ok(false),
+            (":help" | ":h", _) => writeln!(output, "{HELP}")?,
+            (":env", _) => {
+                let mut bindings = self
+                    .context
+                    .iter()
+                    .map(|(name, value)| {
+                        format!("{name} = {}", value.inspect().with_limits(self.limits))
+                    })
+                    .collect::<Vec<_>>();
+                bindings.sort();
+
+                for binding in bindings {
+                    writeln!(output, "{binding}")?;
+                }
+            }
+            (":set", _) => {
+                let mut words = argument.split_whitespace();
+
+                match (words.next(), words.next()) {
+                    (None, _) => writeln!(output, "{}", self.limits)?,
+                    (Some(name), Some(value)) => match self.limits.set(name, value) {
+                        Ok(()) => writeln!(output, "{}", self.limits)?,
+                        Err(error) => writeln!(output, "{error}")?,
+                    },
+                    (Some(_), None) => {
+                        writeln!(output, "usage: :set <depth | length | string> <n>")?
+                    }
+                }
+            }
+            (":time", _) if argument.is_empty() => match &self.last {
+                Some(last) => writeln!(
+                    output,
+                    "parse: {:.3}ms, eval: {:.3}ms",
+                    last.parse.as_secs_f64() * 1000.0,
+                    last.eval.as_secs_f64() * 1000.0
+                )?,
+                None => writeln!(output, "nothing was evaluated yet")?,
+            },
+            (":time", _) => {
+                let result = self.eval(argument);
+                let ran = !matches!(result, Err(EvalError::Syntax(_)));
+                self.print(result, output)?;
+
+                if let Some(last) = self.last.as_ref().filter(|_| ran) {
+                    writeln!(output, "{:.3}ms", last.eval.as_secs_f64() * 1000.0)?;
+                }
+            }
+            (":ast" | ":type", None) => writeln!(output, "nothing was evaluated yet")?,
+            (":ast" | ":type", Some(Err(error))) => {
+                self.print(Err(EvalError::Syntax(error)), output)?
+            }
+            (":ast", Some(Ok(term))) => writeln!(output, "{}", ast(&term))?,
+            (":type", Some(Ok(term))) => match self.check(&term) {
+                Ok(ty) => writeln!(output, "{ty}")?,
+                Err(errors) => writeln!(output, "{errors}")?,
+            },
+            _ => writeln!(
+                output,
+                "unknown command {name}, type :help for the available ones"
+            )?,
+        }
+
+        Ok(true)
+    }
+
+    /// Writes the value of an input, or its error.
+    fn print<W: Write>(&self, result: Result<Value, EvalError>, output: &mut W) -> io::Result<()> {
+        match result {
+            Ok(value) => writeln!(output, "{}", value.inspect().with_limits(self.limits)),
+            Err(error) => writeln!(
+                output,
+                "error[{}]: {}\n  {}",
+                error.code(),
+                error.message(self.lang),
+                error.full_text(self.lang)
+            ),
+        }
+    }
+
+    /// Reads inputs from `input` and writes their values or errors to
+    /// `output`, until the input ends.
+    pub fn run<In: Input, W: Write>(&mut self, input: &mut In, mut output: W) -> io::Result<()> {
+        let mut source = String::new();
+
+        loop {
+            let prompt = if source.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION
+            };
+
+            let line = match input.read_line(prompt, &self.names()) {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {
+                    source.clear();
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+
+            if source.is_empty() && line.trim_start().starts_with(':') {
+                if !self.command(&line, &mut output)? {
+                    break;
+                }
+
+                continue;
+            }
+
+            // An empty line evaluates an unterminated input anyway, to show
+            // what is missing.
+            let forced = line.trim().is_empty();
+            source.push_str(&line);
+            source.push('\n');
+
+            if source.trim().is_empty() {
+                source.clear();
+                continue;
+            }
+
+            if !forced && is_incomplete(&source) {
+                continue;
+            }
+
+            let result = self.eval(&source);
+            self.print(result, &mut output)?;
+            source.clear();
+        }
+
+        Ok(())
+    }
+}
+
+/// The syntax tree of `term`, as the JSON AST when it is available.
+#[cfg(feature = "json")]
+fn ast(term: &Term) -> String {
+    serde_json::to_string_pretty(term).unwrap_or_default()
+}
+
+#[cfg(not(feature = "json"))]
+fn ast(term: &Term) -> String {
+    format!("{term:#?}")
+}
+
+/// Whether `source` is only missing what comes after it, like a closing
+/// brace, the body of a `let` or the end of a string.
+pub fn is_incomplete(source: &str) -> bool {
+    matches!(
+        parse(source, "<repl>"),
+        Err(SyntaxError {
+            code: ErrorCode::UnexpectedEnd
+                | ErrorCode::UnterminatedString
+                | ErrorCode::UnterminatedComment,
+            ..
+        })
+    )
+}
+
+/// The names completing the word at the end of `before`, along with the
+/// byte offset where the word starts.
+pub fn completions(before: &str, names: &[String]) -> (usize, Vec<String>) {
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, char)| unicode_ident::is_xid_continue(*char))
+        .last()
+        .map_or(before.len(), |(offset, _)| offset);
+
+    let word = &before[start..];
+    let mut candidates = names
+        .iter()
+        .filter(|name| name.starts_with(word) && name.len() > word.len())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    candidates.sort();
+    candidates.dedup();
+
+    (start, candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{Collect, Interpreter};
+    use crate::messages::Lang;
+
+    use super::{completions, is_incomplete, Lines, Repl};
+
+    #[test]
+    fn unterminated_inputs() {
+        for source in ["let x = 1;", "fn (x) => {", "\"abc", "1 +", "/* x"] {
+            assert!(is_incomplete(source), "{source}");
+        }
+
+        for source in ["let x = 1; x", "1 + 2", "1 +* 2", "}"] {
+            assert!(!is_incomplete(source), "{source}");
+        }
+    }
+
+    #[test]
+    fn completes_words() {
+        let names = ["fib", "first", "print"].map(String::from);
+
+        assert_eq!(
+            completions("print(fi", &names),
+            (6, vec![String::from("fib"), String::from("first")])
+        );
+        assert_eq!(completions("print", &names), (0, vec![]));
+        assert_eq!(completions("1 + ", &names).1.len(), 3);
+    }
+
+    #[test]
+    fn keeps_bindings_across_inputs() {
+        let input = "let double = fn (n) => {\n  n * 2\n};\ndouble\n\
+                     print(double(21))\nlet = 1\n\ndou";
+        let mut lines = Lines {
+            input: input.as_bytes(),
+            output: Vec::new(),
+        };
+        let mut output = Vec::new();
+        let mut repl = Repl::new(Interpreter::new(Collect::default()), Lang::En);
+
+        repl.run(&mut lines, &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let results = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(results[0], "<#closure>");
+        assert_eq!(results[1], "42");
+        assert!(results[2].starts_with("error[E0"));
+        assert!(results[4].starts_with("error[E0013]"));
+        assert_eq!(repl.interpreter.io.0, vec!["42"]);
+        assert!(repl.names().contains(&String::from("double")));
+
+        let prompts = String::from_utf8(lines.output).unwrap();
+        assert_eq!(prompts, "> ... ... ... > > > > > ");
+    }
+
+    #[test]
+    fn commands() {
+        let input = ":type\nlet pair = (1, \"a\"); pair\n:type\n:type first(pair) + 1\n\
+                     :type pair + true\n:time second(pair)\n:env\n:ast\n:nope\n:quit\n1";
+        let mut lines = Lines {
+            input: input.as_bytes(),
+            output: Vec::new(),
+        };
+        let mut output = Vec::new();
+        let mut repl = Repl::new(Interpreter::new(Collect::default()), Lang::En);
+
+        repl.run(&mut lines, &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let results = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(results[0], "nothing was evaluated yet");
+        assert_eq!(results[1], "(1, a)");
+        assert_eq!(results[2], "(Int, Str)");
+        assert_eq!(results[3], "Int");
+        assert!(results[4].starts_with("error[E0"));
+        assert_eq!(results[5], "a");
+        assert!(results[6].ends_with("ms"));
+        assert_eq!(results[7], "pair = (1, a)");
+        #[cfg(feature = "json")]
+        assert!(results[8..].contains(&"  \"kind\": \"Second\","));
+        assert!(results.last().unwrap().starts_with("unknown command :nope"));
+    }
+
+    #[test]
+    fn limits() {
+        let input = ":set length 2\n(1, (2, (3, 4)))\n:set\n:set depth\n";
+        let mut lines = Lines {
+            input: input.as_bytes(),
+            output: Vec::new(),
+        };
+        let mut output = Vec::new();
+        let mut repl = Repl::new(Interpreter::new(Collect::default()), Lang::En);
+
+        repl.run(&mut lines, &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let results = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(results[0], "depth 6, length 2, string 256");
+        assert_eq!(results[1], "(1, (2, ...))");
+        assert_eq!(results[2], results[0]);
+        assert!(results[3].starts_with("usage: :set"));
+    }
+}
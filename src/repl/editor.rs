@@ -0,0 +1,454 @@
+use std::{
+    io::{self, Read, Write},
+    path::PathBuf,
+};
+
+use super::completions;
+
+/// Most lines kept in the history file, the oldest ones are dropped first.
+pub const HISTORY_LIMIT: usize = 1000;
+
+/// Lines typed before, kept in a file across sessions.
+#[derive(Debug, Default)]
+pub struct History {
+    lines: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// Reads the history kept at `path`, starting empty when there is none
+    /// yet. New lines are written back to it.
+    pub fn load(path: PathBuf) -> Self {
+        let lines = std::fs::read_to_string(&path)
+            .map(|text| text.lines().map(String::from).collect())
+            .unwrap_or_default();
+
+        Self {
+            lines,
+            path: Some(path),
+        }
+    }
+
+    /// Adds `line` to the history, unless it is blank or the same as the
+    /// last one, and saves it.
+    pub fn push(&mut self, line: &str) -> io::Result<()> {
+        if line.trim().is_empty() || self.lines.last().is_some_and(|last| last == line) {
+            return Ok(());
+        }
+
+        self.lines.push(line.to_string());
+
+        if self.lines.len() > HISTORY_LIMIT {
+            self.lines.drain(..self.lines.len() - HISTORY_LIMIT);
+        }
+
+        match &self.path {
+            Some(path) => std::fs::write(path, self.lines.join("\n") + "\n"),
+            None => Ok(()),
+        }
+    }
+
+    /// The lines, oldest first.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+}
+
+/// A key pressed at the prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    Up,
+    Down,
+    /// Ctrl-W, deleting the word before the cursor.
+    DeleteWord,
+    /// Ctrl-U, deleting everything before the cursor.
+    DeleteStart,
+    /// Ctrl-K, deleting everything after the cursor.
+    DeleteEnd,
+    /// Ctrl-C.
+    Interrupt,
+    /// Ctrl-D, ending the input on an empty line.
+    Eof,
+    /// A key without meaning at the prompt, ignored.
+    Unknown,
+}
+
+/// Reads the keys encoded in the bytes sent by a terminal in raw mode.
+pub fn read_key<R: Read>(input: &mut R) -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte(input)? else {
+        return Ok(None);
+    };
+
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::Home,
+        0x02 => Key::Left,
+        0x03 => Key::Interrupt,
+        0x04 => Key::Eof,
+        0x05 => Key::End,
+        0x06 => Key::Right,
+        0x0b => Key::DeleteEnd,
+        0x0e => Key::Down,
+        0x10 => Key::Up,
+        0x15 => Key::DeleteStart,
+        0x17 => Key::DeleteWord,
+        0x1b => escape(input)?,
+        byte if byte < 0x20 => Key::Unknown,
+        byte => {
+            // The other bytes of the UTF-8 sequence of the character.
+            let length = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            let mut bytes = vec![byte];
+
+            for _ in 1..length {
+                match read_byte(input)? {
+                    Some(byte) => bytes.push(byte),
+                    None => break,
+                }
+            }
+
+            match std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|str| str.chars().next())
+            {
+                Some(char) => Key::Char(char),
+                None => Key::Unknown,
+            }
+        }
+    };
+
+    Ok(Some(key))
+}
+
+/// The key of an escape sequence, like `ESC [ A` for the up arrow.
+fn escape<R: Read>(input: &mut R) -> io::Result<Key> {
+    let key = match (read_byte(input)?, read_byte(input)?) {
+        (Some(b'[' | b'O'), Some(b'A')) => Key::Up,
+        (Some(b'[' | b'O'), Some(b'B')) => Key::Down,
+        (Some(b'[' | b'O'), Some(b'C')) => Key::Right,
+        (Some(b'[' | b'O'), Some(b'D')) => Key::Left,
+        (Some(b'[' | b'O'), Some(b'H')) => Key::Home,
+        (Some(b'[' | b'O'), Some(b'F')) => Key::End,
+        (Some(b'['), Some(digit @ b'0'..=b'9')) => {
+            // `ESC [ 3 ~` and the like, the parameters end with a letter or
+            // a tilde.
+            let mut last = digit;
+
+            while !last.is_ascii_alphabetic() && last != b'~' {
+                match read_byte(input)? {
+                    Some(byte) => last = byte,
+                    None => break,
+                }
+            }
+
+            match (digit, last) {
+                (b'3', b'~') => Key::Delete,
+                (b'1' | b'7', b'~') => Key::Home,
+                (b'4' | b'8', b'~') => Key::End,
+                _ => Key::Unknown,
+            }
+        }
+        _ => Key::Unknown,
+    };
+
+    Ok(key)
+}
+
+fn read_byte<R: Read>(input: &mut R) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// What a key did to the line being edited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// The line changed or the cursor moved, and is drawn again.
+    Changed,
+    /// The line was entered.
+    Done(String),
+    /// The names that complete the word before the cursor, when there are
+    /// several of them.
+    Candidates(Vec<String>),
+    /// The line was dropped with Ctrl-C.
+    Interrupted,
+    /// The input ended with Ctrl-D.
+    Eof,
+}
+
+/// A line being edited, with a cursor moving in it and through the history.
+#[derive(Debug, Default)]
+pub struct Editor {
+    line: Vec<char>,
+    cursor: usize,
+    /// Line of the history shown, if any.
+    browsing: Option<usize>,
+    /// The line typed before browsing the history, shown again past its end.
+    draft: Vec<char>,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The text of the line.
+    pub fn line(&self) -> String {
+        self.line.iter().collect()
+    }
+
+    /// The characters before the cursor.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Applies `key` to the line, completing with `names` and browsing
+    /// `history`.
+    pub fn edit(&mut self, key: Key, names: &[String], history: &History) -> Edit {
+        match key {
+            Key::Char(char) => {
+                self.line.insert(self.cursor, char);
+                self.cursor += 1;
+            }
+            Key::Enter => return Edit::Done(self.line()),
+            Key::Tab => return self.complete(names),
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.line.remove(self.cursor);
+            }
+            Key::Delete if self.cursor < self.line.len() => {
+                self.line.remove(self.cursor);
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.line.len()),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.line.len(),
+            Key::Up => self.browse(history, -1),
+            Key::Down => self.browse(history, 1),
+            Key::DeleteWord => {
+                let mut start = self.cursor;
+
+                while start > 0 && self.line[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+
+                while start > 0 && !self.line[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+
+                self.line.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            Key::DeleteStart => {
+                self.line.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::DeleteEnd => self.line.truncate(self.cursor),
+            Key::Interrupt => return Edit::Interrupted,
+            Key::Eof if self.line.is_empty() => return Edit::Eof,
+            Key::Eof => {
+                if self.cursor < self.line.len() {
+                    self.line.remove(self.cursor);
+                }
+            }
+            Key::Backspace | Key::Delete | Key::Unknown => (),
+        }
+
+        Edit::Changed
+    }
+
+    /// Shows the line of the history before (`-1`) or after (`1`) the one
+    /// shown.
+    fn browse(&mut self, history: &History, direction: isize) {
+        let lines = history.lines();
+        let current = self.browsing.unwrap_or(lines.len());
+        let next = current.saturating_add_signed(direction).min(lines.len());
+
+        if next == current {
+            return;
+        }
+
+        if self.browsing.is_none() {
+            self.draft = std::mem::take(&mut self.line);
+        }
+
+        self.line = match lines.get(next) {
+            Some(line) => line.chars().collect(),
+            None => std::mem::take(&mut self.draft),
+        };
+        self.browsing = (next < lines.len()).then_some(next);
+        self.cursor = self.line.len();
+    }
+
+    /// Completes the word before the cursor as far as all the names starting
+    /// with it agree.
+    fn complete(&mut self, names: &[String]) -> Edit {
+        let before = self.line[..self.cursor].iter().collect::<String>();
+        let (start, candidates) = completions(&before, names);
+        let typed = self.cursor - before[..start].chars().count();
+
+        let Some(first) = candidates.first() else {
+            return Edit::Changed;
+        };
+
+        let common = candidates
+            .iter()
+            .fold(first.chars().count(), |common, name| {
+                first
+                    .chars()
+                    .zip(name.chars())
+                    .take(common)
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            });
+
+        if common > typed {
+            for char in first.chars().skip(typed).take(common - typed) {
+                self.line.insert(self.cursor, char);
+                self.cursor += 1;
+            }
+
+            return Edit::Changed;
+        }
+
+        match candidates.len() {
+            1 => Edit::Changed,
+            _ => Edit::Candidates(candidates),
+        }
+    }
+
+    /// Draws the line after `prompt`, over the one drawn before, leaving the
+    /// terminal cursor where the cursor of the line is.
+    pub fn draw<W: Write>(&self, prompt: &str, output: &mut W) -> io::Result<()> {
+        write!(output, "\r{prompt}{}\x1b[K", self.line())?;
+
+        let after = self.line.len() - self.cursor;
+
+        if after > 0 {
+            write!(output, "\x1b[{after}D")?;
+        }
+
+        output.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_key, Edit, Editor, History, Key};
+
+    fn keys(mut bytes: &[u8]) -> Vec<Key> {
+        let mut keys = Vec::new();
+
+        while let Some(key) = read_key(&mut bytes).unwrap() {
+            keys.push(key);
+        }
+
+        keys
+    }
+
+    fn type_keys(editor: &mut Editor, keys: &[Key], names: &[String], history: &History) -> Edit {
+        let mut last = Edit::Changed;
+
+        for key in keys {
+            last = editor.edit(*key, names, history);
+        }
+
+        last
+    }
+
+    #[test]
+    fn decodes_keys() {
+        assert_eq!(
+            keys("a\x1b[A\x1b[D\x1b[3~\x1bOHé\x7f\r\x03".as_bytes()),
+            vec![
+                Key::Char('a'),
+                Key::Up,
+                Key::Left,
+                Key::Delete,
+                Key::Home,
+                Key::Char('é'),
+                Key::Backspace,
+                Key::Enter,
+                Key::Interrupt,
+            ]
+        );
+    }
+
+    #[test]
+    fn edits_and_browses_history() {
+        let mut history = History::default();
+        history.push("let x = 1; x").unwrap();
+        history.push("print(x)").unwrap();
+        history.push("print(x)").unwrap();
+        assert_eq!(history.lines().len(), 2);
+
+        let mut editor = Editor::new();
+        let typed = [
+            Key::Char('a'),
+            Key::Char('c'),
+            Key::Left,
+            Key::Char('b'),
+            Key::End,
+            Key::Char('!'),
+            Key::Backspace,
+        ];
+        type_keys(&mut editor, &typed, &[], &history);
+        assert_eq!(editor.line(), "abc");
+
+        type_keys(&mut editor, &[Key::Up, Key::Up, Key::Up], &[], &history);
+        assert_eq!(editor.line(), "let x = 1; x");
+
+        type_keys(&mut editor, &[Key::Down, Key::Down], &[], &history);
+        assert_eq!(editor.line(), "abc");
+
+        let keys = [Key::Left, Key::DeleteStart, Key::Char('x'), Key::DeleteEnd];
+        assert_eq!(type_keys(&mut editor, &keys, &[], &history), Edit::Changed);
+        assert_eq!(editor.line(), "x");
+        assert_eq!(
+            editor.edit(Key::Enter, &[], &history),
+            Edit::Done(String::from("x"))
+        );
+    }
+
+    #[test]
+    fn completes_names() {
+        let names = ["fib", "first", "fold", "print"].map(String::from);
+        let history = History::default();
+
+        let mut editor = Editor::new();
+        type_keys(&mut editor, &[Key::Char('p'), Key::Tab], &names, &history);
+        assert_eq!(editor.line(), "print");
+
+        let mut editor = Editor::new();
+        let keys = [Key::Char('f'), Key::Char('i'), Key::Tab];
+        assert_eq!(
+            type_keys(&mut editor, &keys, &names, &history),
+            Edit::Candidates(vec![String::from("fib"), String::from("first")])
+        );
+
+        type_keys(&mut editor, &[Key::Char('r'), Key::Tab], &names, &history);
+        assert_eq!(editor.line(), "first");
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    io::{self, Write},
+    os::fd::AsRawFd,
+};
+
+use super::{
+    editor::{read_key, Edit, Editor, History},
+    Input,
+};
+
+/// The terminal of stdin, with the line being typed edited in place.
+pub struct Terminal {
+    history: History,
+}
+
+impl Terminal {
+    pub fn new(history: History) -> Self {
+        Self { history }
+    }
+}
+
+impl Input for Terminal {
+    fn read_line(&mut self, prompt: &str, names: &[String]) -> io::Result<Option<String>> {
+        let stdin = io::stdin();
+        let mut stdout = io::stdout();
+        let _raw = RawMode::enable(stdin.as_raw_fd())?;
+        let mut input = stdin.lock();
+        let mut editor = Editor::new();
+
+        editor.draw(prompt, &mut stdout)?;
+
+        while let Some(key) = read_key(&mut input)? {
+            match editor.edit(key, names, &self.history) {
+                Edit::Changed => (),
+                Edit::Done(line) => {
+                    write!(stdout, "\r\n")?;
+                    // A history that can't be saved is only kept for the
+                    // session.
+                    let _ = self.history.push(&line);
+
+                    return Ok(Some(line));
+                }
+                Edit::Candidates(candidates) => {
+                    write!(stdout, "\r\n{}\r\n", candidates.join("  "))?;
+                }
+                Edit::Interrupted => {
+                    write!(stdout, "^C\r\n")?;
+
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                Edit::Eof => break,
+            }
+
+            editor.draw(prompt, &mut stdout)?;
+        }
+
+        write!(stdout, "\r\n")?;
+        Ok(None)
+    }
+}
+
+/// Keeps the terminal in raw mode, passing every key as it is pressed
+/// instead of whole lines, until dropped.
+struct RawMode {
+    fd: i32,
+    original: libc::termios,
+}
+
+impl RawMode {
+    fn enable(fd: i32) -> io::Result<Self> {
+        // SAFETY: `tcgetattr` and `tcsetattr` only read and write the struct
+        // they are given.
+        unsafe {
+            let mut original = std::mem::zeroed::<libc::termios>();
+
+            if libc::tcgetattr(fd, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut raw = original;
+            raw.c_iflag &= !(libc::ICRNL | libc::IXON);
+            raw.c_lflag &= !(libc::ECHO | libc::ICANON | libc::ISIG | libc::IEXTEN);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+
+            if libc::tcsetattr(fd, libc::TCSAFLUSH, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self { fd, original })
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: see `RawMode::enable`.
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSAFLUSH, &self.original);
+        }
+    }
+}
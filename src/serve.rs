@@ -0,0 +1,352 @@
+//! The HTTP server of `lipsum serve`, evaluating the programs POSTed to it,
+//! for playgrounds and automated grading.
+//!
+//! A program is POSTed to `/eval`, as rinha source or, with a
+//! `Content-Type` of `application/json`, as a JSON syntax tree. The answer
+//! is the JSON document of [`evaluate`](crate::protocol::evaluate), with what the program printed, its
+//! final value or the error that stopped it, and how long it took.
+//!
+//! The query string can lower the limits of the server for a request, like
+//! `/eval?fuel=1000&timeout=1`, and choose the language of the messages with
+//! `lang`.
+//!
+//! With [`Server::with_playground`], the server also answers `/` with a page
+//! to write programs in and run them through `/eval`, see `lipsum
+//! playground`.
+
+use std::{io::Read, str::FromStr, sync::Arc, thread};
+
+use crate::{
+    config::Limits,
+    messages::Lang,
+    protocol::{evaluate, lower, message, without_input, Program},
+    sync::SyncInterpreter,
+};
+
+/// Largest program accepted, in bytes.
+pub const MAX_BODY: u64 = 16 << 20;
+
+/// The page of the playground, with an editor and a button running its
+/// program.
+static PLAYGROUND: &str = include_str!("serve/playground.html");
+
+/// Evaluates the programs of the requests, each in a fresh interpreter.
+pub struct Server {
+    interpreter: SyncInterpreter,
+    lang: Lang,
+    playground: bool,
+}
+
+/// Answer to a request, see [`Server::respond`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    /// The JSON document answered, or the text of the page when the content
+    /// type isn't JSON.
+    pub body: serde_json::Value,
+    pub content_type: &'static str,
+}
+
+impl Response {
+    fn new(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            body,
+            content_type: "application/json",
+        }
+    }
+
+    fn page(text: &str) -> Self {
+        Self {
+            status: 200,
+            body: serde_json::Value::String(text.to_string()),
+            content_type: "text/html; charset=utf-8",
+        }
+    }
+
+    fn error(status: u16, text: impl Into<String>) -> Self {
+        Self::new(status, message(text))
+    }
+}
+
+impl Server {
+    /// Creates a server running the programs with `interpreter`, whose
+    /// limits are the highest a request can ask for, and writing messages in
+    /// `lang` unless a request asks for another language. The programs have
+    /// no input, reading the one of the server would hang the request.
+    pub fn new(mut interpreter: SyncInterpreter, lang: Lang) -> Self {
+        without_input(&mut interpreter, "there is no input on the server");
+
+        Self {
+            interpreter,
+            lang,
+            playground: false,
+        }
+    }
+
+    /// Serves the page of the playground at `/`.
+    pub fn with_playground(mut self) -> Self {
+        without_input(&mut self.interpreter, "there is no input in the playground");
+        self.playground = true;
+        self
+    }
+
+    /// Answers a request to `url` with the given `method`, `content_type`
+    /// and `body`.
+    pub fn respond(&self, method: &str, url: &str, content_type: &str, body: &str) -> Response {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+        if self.playground && method == "GET" && matches!(path, "/" | "/index.html") {
+            return Response::page(PLAYGROUND);
+        }
+
+        if path != "/eval" {
+            return Response::error(404, format!("no such endpoint {path}, use /eval"));
+        }
+
+        match method {
+            "POST" => (),
+            "OPTIONS" => return Response::new(204, serde_json::Value::Null),
+            _ => return Response::error(405, "programs are POSTed to /eval"),
+        }
+
+        let (limits, lang) = match self.parameters(query) {
+            Ok(parameters) => parameters,
+            Err(text) => return Response::error(400, text),
+        };
+
+        let program = match content_type.starts_with("application/json") {
+            true => Program::Ast(body),
+            false => Program::Source(body),
+        };
+
+        match evaluate(&self.interpreter, program, limits, lang) {
+            Ok(body) => Response::new(200, body),
+            Err(error) => Response::new(400, error),
+        }
+    }
+
+    /// Reads the limits and the language asked for in the query string. The
+    /// limits can only be lowered, see [`lower`].
+    fn parameters(&self, query: &str) -> Result<(Limits, Lang), String> {
+        let mut asked = Limits::default();
+        let mut lang = self.lang;
+
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+            match name {
+                "fuel" => asked.fuel = Some(number(name, value)?),
+                "max-depth" => asked.max_depth = Some(number(name, value)?),
+                "timeout" => asked.timeout = Some(number(name, value)?),
+                "max-memory" => asked.max_memory = Some(number(name, value)?),
+                "lang" => lang = value.parse()?,
+                _ => return Err(format!("unknown parameter \"{name}\"")),
+            }
+        }
+
+        Ok((lower(self.interpreter.limits(), &asked), lang))
+    }
+
+    /// Answers the requests made to `address` until the process is killed,
+    /// evaluating up to `workers` programs at the same time.
+    pub fn run(self, address: &str, workers: usize) -> Result<(), String> {
+        let http = tiny_http::Server::http(address)
+            .map_err(|error| format!("failed to listen on {address}: {error}"))?;
+        let http = Arc::new(http);
+        let server = Arc::new(self);
+
+        let handles = (0..workers.max(1))
+            .map(|_| {
+                let http = http.clone();
+                let server = server.clone();
+
+                thread::spawn(move || {
+                    for request in http.incoming_requests() {
+                        server.handle(request);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle
+                .join()
+                .map_err(|_| String::from("a worker of the server panicked"))?;
+        }
+
+        Ok(())
+    }
+
+    fn handle(&self, mut request: tiny_http::Request) {
+        let content_type = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Content-Type"))
+            .map(|header| header.value.to_string())
+            .unwrap_or_default();
+
+        let mut body = String::new();
+        let read = request
+            .as_reader()
+            .take(MAX_BODY + 1)
+            .read_to_string(&mut body);
+
+        let response = match read {
+            Err(error) => Response::error(400, format!("failed to read the program: {error}")),
+            Ok(size) if size as u64 > MAX_BODY => {
+                Response::error(413, format!("programs can have at most {MAX_BODY} bytes"))
+            }
+            Ok(_) => self.respond(
+                request.method().as_str(),
+                request.url(),
+                &content_type,
+                &body,
+            ),
+        };
+
+        let body = match response.body {
+            serde_json::Value::Null => String::new(),
+            serde_json::Value::String(text) if response.content_type != "application/json" => text,
+            body => body.to_string(),
+        };
+
+        let headers = [
+            ("Content-Type", response.content_type),
+            ("Access-Control-Allow-Origin", "*"),
+            ("Access-Control-Allow-Methods", "POST, OPTIONS"),
+            ("Access-Control-Allow-Headers", "Content-Type"),
+        ];
+
+        let mut answer = tiny_http::Response::from_string(body).with_status_code(response.status);
+
+        for (field, value) in headers {
+            if let Ok(header) = tiny_http::Header::from_bytes(field, value) {
+                answer.add_header(header);
+            }
+        }
+
+        // The client may be gone, there is no one else to tell.
+        let _ = request.respond(answer);
+    }
+}
+
+fn number<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("\"{value}\" is not a valid {name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{Config, Limits},
+        messages::Lang,
+        sync::SyncInterpreter,
+    };
+
+    use super::Server;
+
+    fn server(limits: Limits) -> Server {
+        let config = Config {
+            limits,
+            ..Config::default()
+        };
+
+        Server::new(SyncInterpreter::new(config), Lang::En)
+    }
+
+    #[test]
+    fn eval_source_and_json() {
+        let server = server(Limits::default());
+
+        let response = server.respond("POST", "/eval", "text/plain", "print(1) + 1");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["prints"][0], "1");
+        assert_eq!(response.body["result"]["value"], 2);
+        assert!(response.body["time"]["eval"].is_f64());
+
+        let program = r#"{ "expression": { "kind": "Int", "value": 3 } }"#;
+        let response = server.respond("POST", "/eval", "application/json", program);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["result"]["value"], 3);
+    }
+
+    #[test]
+    fn errors() {
+        let server = server(Limits::default());
+
+        let response = server.respond("POST", "/eval?lang=pt-BR", "text/plain", "1 / 0");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["error"]["code"], "E0007");
+        assert!(response.body["error"]["message"].is_string());
+
+        let response = server.respond("POST", "/eval", "text/plain", "let = 1");
+        assert_eq!(response.status, 400);
+        assert!(response.body["error"]["code"].is_string());
+
+        let response = server.respond("GET", "/eval", "", "");
+        assert_eq!(response.status, 405);
+
+        let response = server.respond("POST", "/", "text/plain", "1");
+        assert_eq!(response.status, 404);
+
+        let response = server.respond("POST", "/eval?fuel=lots", "text/plain", "1");
+        assert_eq!(response.status, 400);
+    }
+
+    #[test]
+    fn playground() {
+        let response = server(Limits::default()).respond("GET", "/", "", "");
+        assert_eq!(response.status, 404);
+
+        let server = server(Limits::default()).with_playground();
+
+        let response = server.respond("GET", "/", "", "");
+        assert_eq!(response.status, 200);
+        assert!(response.content_type.starts_with("text/html"));
+        assert!(response.body.as_str().unwrap().contains("/eval"));
+
+        let response = server.respond("POST", "/eval", "text/plain", "1 + 1");
+        assert_eq!(response.content_type, "application/json");
+        assert_eq!(response.body["result"]["value"], 2);
+
+        let response = server.respond("POST", "/eval", "text/plain", "read_line()");
+        assert_eq!(response.body["error"]["code"], "E0019");
+        assert_eq!(
+            response.body["error"]["full_text"],
+            "there is no input in the playground"
+        );
+    }
+
+    #[test]
+    fn no_input() {
+        let server = server(Limits::default());
+
+        for program in ["read_line()", "read_all()", "read_int()"] {
+            let response = server.respond("POST", "/eval", "text/plain", program);
+            assert_eq!(response.status, 200);
+            assert_eq!(response.body["error"]["code"], "E0019");
+        }
+    }
+
+    #[test]
+    fn requests_can_only_lower_the_limits() {
+        let program = "let f = fn (n) => { if (n == 0) { 0 } else { 1 + f(n - 1) } }; f(50)";
+        let strict = server(Limits {
+            fuel: Some(10),
+            ..Limits::default()
+        });
+
+        let response = strict.respond("POST", "/eval?fuel=1000", "text/plain", program);
+        assert_eq!(response.body["error"]["code"], "E0017");
+
+        let lenient = server(Limits::default());
+
+        let response = lenient.respond("POST", "/eval?fuel=10", "text/plain", program);
+        assert_eq!(response.body["error"]["code"], "E0017");
+
+        let response = lenient.respond("POST", "/eval", "text/plain", program);
+        assert_eq!(response.body["result"]["value"], 50);
+    }
+}
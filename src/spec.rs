@@ -0,0 +1,138 @@
+//! The semantics of the rinha specification, followed to the letter with
+//! `--spec` where lipsum is more lenient: integers are the `Int32` of the
+//! specification, wrapping around at 32 bits instead of 64, and only
+//! integers can be ordered, where lipsum also orders booleans and strings.
+//!
+//! The rest already follows the specification: `+` concatenates strings
+//! with strings or integers, `==` and `!=` compare values of the same type,
+//! and closures are printed as `<#closure>`.
+
+use crate::{
+    ast::{BinaryOp, Location},
+    interpreter::{RuntimeError, Value},
+    messages::ErrorCode,
+};
+
+impl Value {
+    /// Applies `op` to the value and `rhs` like [`Value::operate`], with the
+    /// semantics of the specification.
+    pub fn operate_spec(
+        &self,
+        op: &BinaryOp,
+        rhs: &Value,
+        location: &Location,
+    ) -> Result<Value, RuntimeError> {
+        let (lhs, rhs) = match (self, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => (*lhs as i32, *rhs as i32),
+            (lhs, rhs) => {
+                return match op {
+                    BinaryOp::Lt | BinaryOp::Lte | BinaryOp::Gt | BinaryOp::Gte => {
+                        Err(RuntimeError::new(
+                            ErrorCode::InvalidComparison,
+                            vec![lhs.to_string(), rhs.to_string()],
+                            location.clone(),
+                        ))
+                    }
+                    _ => lhs.operate(op, rhs, location),
+                };
+            }
+        };
+
+        let int = match op {
+            BinaryOp::Add => lhs.wrapping_add(rhs),
+            BinaryOp::Sub => lhs.wrapping_sub(rhs),
+            BinaryOp::Mul => lhs.wrapping_mul(rhs),
+            BinaryOp::Div if rhs != 0 => lhs.wrapping_div(rhs),
+            BinaryOp::Rem if rhs != 0 => lhs.wrapping_rem(rhs),
+            // Comparisons, and the errors of dividing by zero.
+            _ => return Value::Int(lhs.into()).operate(op, &Value::Int(rhs.into()), location),
+        };
+
+        Ok(Value::Int(int.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{run_with, Collect, Interpreter},
+        messages::ErrorCode,
+    };
+
+    fn run(source: &str) -> (Result<String, ErrorCode>, Vec<String>) {
+        run_with(Interpreter::new(Collect::default()).with_spec(true), source)
+    }
+
+    /// The examples of the table of binary operations of the specification.
+    #[test]
+    fn binary_operations() {
+        let examples = [
+            ("3 + 5", "8"),
+            ("\"a\" + 2", "a2"),
+            ("2 + \"a\"", "2a"),
+            ("\"a\" + \"b\"", "ab"),
+            ("0 - 1", "-1"),
+            ("2 * 2", "4"),
+            ("3 / 2", "1"),
+            ("4 % 2", "0"),
+            ("\"a\" == \"a\"", "true"),
+            ("2 == 1 + 1", "true"),
+            ("true == true", "true"),
+            ("\"a\" != \"b\"", "true"),
+            ("3 != 1 + 1", "true"),
+            ("true != false", "true"),
+            ("1 < 2", "true"),
+            ("2 > 3", "false"),
+            ("1 <= 2", "true"),
+            ("1 >= 2", "false"),
+            ("true && false", "false"),
+            ("false || true", "true"),
+        ];
+
+        for (source, expected) in examples {
+            assert_eq!(run(source).0, Ok(expected.to_string()), "{source}");
+        }
+    }
+
+    #[test]
+    fn only_integers_are_ordered() {
+        for source in ["\"a\" < \"b\"", "false < true", "1 >= \"1\""] {
+            assert_eq!(run(source).0, Err(ErrorCode::InvalidComparison), "{source}");
+        }
+
+        assert_eq!(run("1 == true").0, Err(ErrorCode::InvalidComparison));
+        assert_eq!(run("1 / 0").0, Err(ErrorCode::DivisionByZero));
+    }
+
+    #[test]
+    fn integers_have_32_bits() {
+        assert_eq!(run("2147483647 + 1").0, Ok(String::from("-2147483648")));
+        assert_eq!(run("65536 * 65536").0, Ok(String::from("0")));
+        assert_eq!(run("0 - 2147483647 - 2").0, Ok(String::from("2147483647")));
+    }
+
+    /// The examples of `print`, `first` and `second` of the specification.
+    #[test]
+    fn printing() {
+        let (result, printed) = run("let _ = print(\"Hello world\");
+            let _ = print((1, (\"a\", true)));
+            let _ = print(fn (x) => { x });
+            print(first((1, 2)) + second((1, 2)))");
+
+        assert_eq!(result, Ok(String::from("3")));
+        assert_eq!(
+            printed,
+            vec!["Hello world", "(1, (a, true))", "<#closure>", "3"]
+        );
+    }
+
+    /// The example programs of the specification.
+    #[test]
+    fn examples() {
+        for (name, printed) in [("fib", "55"), ("sum", "15"), ("combination", "45")] {
+            let source = std::fs::read_to_string(format!("examples/{name}.rinha")).unwrap();
+
+            assert_eq!(run(&source).1, vec![printed], "{name}");
+        }
+    }
+}
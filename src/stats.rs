@@ -0,0 +1,250 @@
+use std::{collections::BTreeMap, fmt::Display, sync::Arc};
+
+use crate::{
+    ast::{
+        deeper,
+        visit::{walk_term, TermVisitor},
+        Function, Location, Term,
+    },
+    effects::{self, Effects},
+};
+
+/// The largest function of a program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct Largest {
+    /// Name of the `let` binding the function, if any.
+    pub name: Option<String>,
+    pub location: Location,
+    /// Number of nodes in the body of the function.
+    pub nodes: usize,
+}
+
+/// Measures of a program computed without running it, to tell why it may be
+/// slow before running it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct Stats {
+    /// Number of nodes of each kind, like `Call` or `Int`.
+    pub nodes: BTreeMap<&'static str, usize>,
+    /// How deep the nodes are nested, the program itself being at depth 1.
+    pub depth: usize,
+    /// Number of function literals, each one creating closures when
+    /// evaluated.
+    pub closures: usize,
+    pub largest: Option<Largest>,
+    /// Number of functions whose calls can be memoized, since they don't
+    /// print nor call functions that may, see [`effects`].
+    pub memoizable: usize,
+}
+
+impl Stats {
+    /// Total number of nodes.
+    pub fn total(&self) -> usize {
+        self.nodes.values().sum()
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "nodes: {}", self.total())?;
+
+        for (kind, count) in &self.nodes {
+            writeln!(f, "  {kind}: {count}")?;
+        }
+
+        writeln!(f, "depth: {}", self.depth)?;
+        writeln!(f, "closures: {}", self.closures)?;
+
+        if let Some(largest) = &self.largest {
+            let name = largest.name.as_deref().unwrap_or("<anonymous>");
+            writeln!(
+                f,
+                "largest function: {name} at {} ({} nodes)",
+                largest.location, largest.nodes
+            )?;
+        }
+
+        write!(
+            f,
+            "memoizable: {} of {} functions",
+            self.memoizable, self.closures
+        )
+    }
+}
+
+fn kind(term: &Term) -> &'static str {
+    match term {
+        Term::Int(_) => "Int",
+        Term::Str(_) => "Str",
+        Term::Bool(_) => "Bool",
+        Term::Var(_) => "Var",
+        Term::Call(_) => "Call",
+        Term::Binary(_) => "Binary",
+        Term::Function(_) => "Function",
+        Term::Let(_) => "Let",
+        Term::If(_) => "If",
+        Term::Print(_) => "Print",
+        Term::First(_) => "First",
+        Term::Second(_) => "Second",
+        Term::Tuple(_) => "Tuple",
+    }
+}
+
+/// Counts the nodes of a term.
+#[derive(Default)]
+struct Count(usize);
+
+impl TermVisitor for Count {
+    fn visit_term(&mut self, term: &Term) {
+        self.0 += 1;
+        walk_term(self, term);
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    stats: Stats,
+    depth: usize,
+    /// Effects of calling the functions bound to each name, `None` when
+    /// what is bound is not a function.
+    scopes: Vec<(Arc<str>, Option<Effects>)>,
+}
+
+impl Collector {
+    fn function(&mut self, name: Option<&str>, function: &Function) -> Effects {
+        let mut count = Count::default();
+        count.visit_term(&function.value);
+
+        let larger = match &self.stats.largest {
+            Some(largest) => count.0 > largest.nodes,
+            None => true,
+        };
+
+        if larger {
+            self.stats.largest = Some(Largest {
+                name: name.map(String::from),
+                location: function.location.clone(),
+                nodes: count.0,
+            });
+        }
+
+        let effects = effects::latent(function, &|name| {
+            self.scopes
+                .iter()
+                .rev()
+                .find(|(bound, _)| &**bound == name)
+                .map(|(_, effects)| effects.unwrap_or(Effects::UNKNOWN))
+        });
+
+        if effects.is_pure() {
+            self.stats.memoizable += 1;
+        }
+
+        for parameter in &function.parameters {
+            self.scopes.push((parameter.text.clone(), None));
+        }
+
+        self.visit_term(&function.value);
+
+        self.scopes
+            .truncate(self.scopes.len() - function.parameters.len());
+
+        effects
+    }
+}
+
+impl TermVisitor for Collector {
+    fn visit_term(&mut self, term: &Term) {
+        deeper(|| {
+            self.depth += 1;
+            self.stats.depth = self.stats.depth.max(self.depth);
+            *self.stats.nodes.entry(kind(term)).or_default() += 1;
+
+            match term {
+                Term::Let(let_) => {
+                    let bound = match let_.value.as_ref() {
+                        Term::Function(function) => {
+                            // The function is visited here to know its name, so
+                            // it is counted here too.
+                            self.depth += 1;
+                            self.stats.depth = self.stats.depth.max(self.depth);
+                            *self.stats.nodes.entry("Function").or_default() += 1;
+                            self.stats.closures += 1;
+
+                            let effects = self.function(Some(&let_.name.text), function);
+                            self.depth -= 1;
+
+                            Some(effects)
+                        }
+                        value => {
+                            self.visit_term(value);
+                            None
+                        }
+                    };
+
+                    self.scopes.push((let_.name.text.clone(), bound));
+                    self.visit_term(&let_.next);
+                    self.scopes.pop();
+                }
+                Term::Function(function) => {
+                    self.stats.closures += 1;
+                    self.function(None, function);
+                }
+                term => walk_term(self, term),
+            }
+
+            self.depth -= 1;
+        })
+    }
+}
+
+/// Measures `term` without running it.
+pub fn stats(term: &Term) -> Stats {
+    let mut collector = Collector::default();
+    collector.visit_term(term);
+
+    collector.stats
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+
+    use super::{stats, Stats};
+
+    fn measure(source: &str) -> Stats {
+        stats(&parse(source, "tests").unwrap().expression)
+    }
+
+    #[test]
+    fn nodes_and_depth() {
+        let stats = measure("let x = 1; print(x + 2)");
+
+        assert_eq!(stats.total(), 6);
+        assert_eq!(stats.nodes["Int"], 2);
+        assert_eq!(stats.nodes["Let"], 1);
+        // Let, Print, Binary, Int
+        assert_eq!(stats.depth, 4);
+        assert_eq!(stats.closures, 0);
+        assert_eq!(stats.largest, None);
+    }
+
+    #[test]
+    fn functions() {
+        let stats = measure(
+            "let log = fn (x) => print(x);
+             let fib = fn (n) => if (n < 2) { n } else { fib(n - 1) + fib(n - 2) };
+             let show = fn (n) => log(fib(n));
+             show(10)",
+        );
+
+        assert_eq!(stats.closures, 3);
+        assert_eq!(stats.nodes["Function"], 3);
+        assert_eq!(stats.memoizable, 1);
+
+        let largest = stats.largest.unwrap();
+        assert_eq!(largest.name.as_deref(), Some("fib"));
+        assert_eq!(largest.nodes, 16);
+    }
+}
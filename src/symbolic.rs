@@ -0,0 +1,801 @@
+//! Bounded symbolic execution of programs that read input.
+//!
+//! The pieces of input read by `read_line`, `read_int` and `read_all` are
+//! unknown values, and branching on a comparison between one of them and a
+//! literal constrains the piece. Each path through the branches is explored
+//! up to a budget, then its constraints are solved into a concrete input and
+//! the program is run on it by the interpreter, which tells the runtime
+//! errors that input triggers.
+
+use std::{cell::RefCell, io::Cursor, rc::Rc, sync::Arc};
+
+use crate::{
+    ast::{BinaryOp, Element, Function, Term},
+    interpreter::{Collect, Context, Interpreter, RuntimeError, Value},
+    messages::ErrorCode,
+};
+
+/// How a piece of the input is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Read {
+    /// A line, by `read_line`.
+    Line,
+    /// An integer, by `read_int`.
+    Int,
+    /// What is left of the input, by `read_all`.
+    All,
+}
+
+/// A value a piece of the input is compared with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Literal {
+    Str(String),
+    Int(i64),
+}
+
+impl Literal {
+    /// The input that reads as `self`.
+    fn text(self) -> String {
+        match self {
+            Literal::Str(str) => str,
+            Literal::Int(int) => int.to_string(),
+        }
+    }
+}
+
+/// A comparison between piece `piece` of the input and a literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Test {
+    piece: usize,
+    op: BinaryOp,
+    literal: Literal,
+}
+
+impl Test {
+    /// The test that holds when `self` does not.
+    fn negate(&self) -> Test {
+        let op = match self.op {
+            BinaryOp::Eq => BinaryOp::Neq,
+            BinaryOp::Neq => BinaryOp::Eq,
+            BinaryOp::Lt => BinaryOp::Gte,
+            BinaryOp::Gte => BinaryOp::Lt,
+            BinaryOp::Gt => BinaryOp::Lte,
+            BinaryOp::Lte => BinaryOp::Gt,
+            ref op => op.clone(),
+        };
+
+        Test { op, ..self.clone() }
+    }
+
+    fn holds(&self, piece: &Literal) -> bool {
+        match (piece, &self.literal) {
+            (Literal::Str(piece), Literal::Str(literal)) => compare(&self.op, piece, literal),
+            (Literal::Int(piece), Literal::Int(literal)) => compare(&self.op, piece, literal),
+            _ => false,
+        }
+    }
+}
+
+fn compare<T: Ord + ?Sized>(op: &BinaryOp, lhs: &T, rhs: &T) -> bool {
+    match op {
+        BinaryOp::Eq => lhs == rhs,
+        BinaryOp::Neq => lhs != rhs,
+        BinaryOp::Lt => lhs < rhs,
+        BinaryOp::Lte => lhs <= rhs,
+        BinaryOp::Gt => lhs > rhs,
+        BinaryOp::Gte => lhs >= rhs,
+        _ => false,
+    }
+}
+
+/// A piece of input read with `read` satisfying every test, tried among the
+/// values around the literals they compare with.
+fn solve<'t>(read: Read, tests: impl Iterator<Item = &'t Test> + Clone) -> Option<String> {
+    let mut candidates = match read {
+        Read::Int => vec![Literal::Int(0), Literal::Int(1)],
+        Read::Line | Read::All => {
+            vec![Literal::Str(String::new()), Literal::Str(String::from("a"))]
+        }
+    };
+
+    for test in tests.clone() {
+        match &test.literal {
+            Literal::Str(literal) => {
+                let mut shorter = literal.clone();
+                shorter.pop();
+
+                candidates.push(Literal::Str(literal.clone()));
+                candidates.push(Literal::Str(format!("{literal}a")));
+                candidates.push(Literal::Str(shorter));
+            }
+            Literal::Int(literal) => {
+                let around = [
+                    Some(*literal),
+                    literal.checked_add(1),
+                    literal.checked_sub(1),
+                ];
+                candidates.extend(around.into_iter().flatten().map(Literal::Int));
+            }
+        }
+    }
+
+    // Lines end at the first line break.
+    let fits = |candidate: &Literal| match candidate {
+        Literal::Str(str) => read != Read::Line || !str.contains('\n'),
+        Literal::Int(_) => true,
+    };
+
+    candidates
+        .into_iter()
+        .filter(fits)
+        .find(|candidate| tests.clone().all(|test| test.holds(candidate)))
+        .map(Literal::text)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Int,
+    Str,
+    Bool,
+}
+
+struct Lambda<'a> {
+    function: &'a Function,
+    scope: RefCell<Scope<'a>>,
+}
+
+/// Bindings visible to a term, innermost first.
+type Scope<'a> = Option<Rc<Binding<'a>>>;
+
+struct Binding<'a> {
+    name: Arc<str>,
+    value: Sym<'a>,
+    next: Scope<'a>,
+}
+
+fn bind<'a>(scope: &Scope<'a>, name: &Arc<str>, value: Sym<'a>) -> Scope<'a> {
+    Some(Rc::new(Binding {
+        name: name.clone(),
+        value,
+        next: scope.clone(),
+    }))
+}
+
+fn lookup<'a>(mut scope: &Scope<'a>, name: &str) -> Option<Sym<'a>> {
+    while let Some(binding) = scope {
+        if &*binding.name == name {
+            return Some(binding.value.clone());
+        }
+
+        scope = &binding.next;
+    }
+
+    None
+}
+
+#[derive(Clone)]
+enum Sym<'a> {
+    Int(i64),
+    Str(String),
+    Bool(bool),
+    Tuple(Rc<(Sym<'a>, Sym<'a>)>),
+    Closure(Rc<Lambda<'a>>),
+    Reader(Read),
+    /// A piece of the input read as a string.
+    Text(usize),
+    /// A piece of the input read as an integer.
+    Number(usize),
+    /// Whether a piece of the input passes a test.
+    Test(Test),
+    /// A value only known while running, of the given kind if it is known.
+    Opaque(Option<Kind>),
+}
+
+impl Sym<'_> {
+    fn kind(&self) -> Option<Kind> {
+        match self {
+            Sym::Int(_) | Sym::Number(_) => Some(Kind::Int),
+            Sym::Str(_) | Sym::Text(_) => Some(Kind::Str),
+            Sym::Bool(_) | Sym::Test(_) => Some(Kind::Bool),
+            Sym::Opaque(kind) => *kind,
+            _ => None,
+        }
+    }
+
+    fn value(&self) -> Option<Value> {
+        match self {
+            Sym::Int(int) => Some(Value::Int(*int)),
+            Sym::Str(str) => Some(Value::Str(str.as_str().into())),
+            Sym::Bool(bool) => Some(Value::Bool(*bool)),
+            _ => None,
+        }
+    }
+}
+
+/// Why a path stopped before the end of the program.
+enum Stop {
+    /// The program fails on this path.
+    Error,
+    /// The path contradicts the tests taken before.
+    Infeasible,
+    /// The path goes on past the budget.
+    Budget,
+}
+
+/// The kind of the result of `op`, when it can't fail with operands of the
+/// given kinds.
+fn operation(op: &BinaryOp, lhs: Kind, rhs: Kind) -> Option<Kind> {
+    match op {
+        BinaryOp::Eq
+        | BinaryOp::Neq
+        | BinaryOp::Lt
+        | BinaryOp::Lte
+        | BinaryOp::Gt
+        | BinaryOp::Gte
+            if lhs == rhs =>
+        {
+            Some(Kind::Bool)
+        }
+        BinaryOp::And | BinaryOp::Or if lhs == Kind::Bool && rhs == Kind::Bool => Some(Kind::Bool),
+        BinaryOp::Add => match (lhs, rhs) {
+            (Kind::Int, Kind::Int) => Some(Kind::Int),
+            (Kind::Str | Kind::Int, Kind::Str | Kind::Int) => Some(Kind::Str),
+            _ => None,
+        },
+        BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem
+            if lhs == Kind::Int && rhs == Kind::Int =>
+        {
+            Some(Kind::Int)
+        }
+        _ => None,
+    }
+}
+
+/// Swaps the operands of a comparison.
+fn flip(op: &BinaryOp) -> BinaryOp {
+    match op {
+        BinaryOp::Lt => BinaryOp::Gt,
+        BinaryOp::Gt => BinaryOp::Lt,
+        BinaryOp::Lte => BinaryOp::Gte,
+        BinaryOp::Gte => BinaryOp::Lte,
+        op => op.clone(),
+    }
+}
+
+/// One run of the program, taking the branches given by `decisions` and
+/// making the decisions after them.
+struct Path<'e> {
+    /// Branch taken at each decision.
+    decisions: Vec<bool>,
+    /// How many decisions were taken in this run.
+    made: usize,
+    /// Decisions made in this run that could have gone the other way.
+    forks: Vec<usize>,
+    tests: Vec<Test>,
+    /// How each piece of the input was read.
+    reads: Vec<Read>,
+    /// Whether the last piece was read by `read_int`, which leaves the rest
+    /// of its line unread.
+    rest_of_line: bool,
+    steps: u64,
+    depth: usize,
+    explorer: &'e Explorer,
+}
+
+impl<'e> Path<'e> {
+    fn new(decisions: Vec<bool>, explorer: &'e Explorer) -> Self {
+        Self {
+            decisions,
+            made: 0,
+            forks: Vec::new(),
+            tests: Vec::new(),
+            reads: Vec::new(),
+            rest_of_line: false,
+            steps: 0,
+            depth: 0,
+            explorer,
+        }
+    }
+
+    fn feasible(&self, test: &Test) -> bool {
+        let tests = self
+            .tests
+            .iter()
+            .chain([test])
+            .filter(|other| other.piece == test.piece);
+
+        solve(self.reads[test.piece], tests).is_some()
+    }
+
+    /// Input satisfying the tests of the path.
+    fn input(&self) -> Option<String> {
+        let mut input = String::new();
+
+        for (piece, read) in self.reads.iter().enumerate() {
+            let tests = self.tests.iter().filter(|test| test.piece == piece);
+            input.push_str(&solve(*read, tests)?);
+
+            if *read != Read::All {
+                input.push('\n');
+            }
+        }
+
+        Some(input)
+    }
+
+    /// Reads the next piece of the input.
+    fn read<'a>(&mut self, read: Read) -> Result<Sym<'a>, Stop> {
+        let rest_of_line = std::mem::replace(&mut self.rest_of_line, read == Read::Int);
+
+        match (self.reads.last(), read) {
+            // Nothing is left after `read_all`.
+            (Some(Read::All), Read::All) => return Ok(Sym::Str(String::new())),
+            (Some(Read::All), _) => return Err(Stop::Error),
+            // The rest of the line of an integer is left empty.
+            (_, Read::Line) if rest_of_line => return Ok(Sym::Str(String::new())),
+            _ => (),
+        }
+
+        self.reads.push(read);
+
+        match read {
+            Read::Int => Ok(Sym::Number(self.reads.len() - 1)),
+            // Starting with the line break after an integer.
+            Read::All if rest_of_line => Ok(Sym::Opaque(Some(Kind::Str))),
+            Read::Line | Read::All => Ok(Sym::Text(self.reads.len() - 1)),
+        }
+    }
+
+    /// Takes a branch, constraining the input with `test` when the
+    /// condition depends on it.
+    fn decide(&mut self, test: Option<Test>) -> Result<bool, Stop> {
+        let taken = match self.decisions.get(self.made) {
+            Some(taken) => *taken,
+            None => {
+                let (then, otherwise) = match &test {
+                    Some(test) => (self.feasible(test), self.feasible(&test.negate())),
+                    None => (true, true),
+                };
+
+                if then && otherwise {
+                    self.forks.push(self.decisions.len());
+                }
+
+                self.decisions.push(then);
+                then
+            }
+        };
+
+        self.made += 1;
+
+        if let Some(test) = test {
+            let test = if taken { test } else { test.negate() };
+
+            if !self.feasible(&test) {
+                return Err(Stop::Infeasible);
+            }
+
+            self.tests.push(test);
+        }
+
+        Ok(taken)
+    }
+
+    fn eval<'a>(&mut self, term: &'a Term, scope: &Scope<'a>) -> Result<Sym<'a>, Stop> {
+        self.steps += 1;
+
+        if self.steps > self.explorer.max_steps {
+            return Err(Stop::Budget);
+        }
+
+        match term {
+            Term::Int(int) => Ok(Sym::Int(int.value)),
+            Term::Str(str) => Ok(Sym::Str(str.value.to_string())),
+            Term::Bool(bool) => Ok(Sym::Bool(bool.value)),
+            Term::Var(var) => match lookup(scope, &var.text) {
+                Some(value) => Ok(value),
+                None => match &*var.text {
+                    "read_line" => Ok(Sym::Reader(Read::Line)),
+                    "read_int" => Ok(Sym::Reader(Read::Int)),
+                    "read_all" => Ok(Sym::Reader(Read::All)),
+                    _ => Err(Stop::Error),
+                },
+            },
+            Term::Function(function) => Ok(Sym::Closure(Rc::new(Lambda {
+                function,
+                scope: RefCell::new(scope.clone()),
+            }))),
+            Term::Let(let_) => {
+                let value = self.eval(&let_.value, scope)?;
+                let inner = bind(scope, &let_.name.text, value.clone());
+
+                // Functions can see their own name, so they can call
+                // themselves.
+                if let Sym::Closure(lambda) = &value {
+                    let recursive = bind(&lambda.scope.borrow(), &let_.name.text, value.clone());
+                    *lambda.scope.borrow_mut() = recursive;
+                }
+
+                self.eval(&let_.next, &inner)
+            }
+            Term::If(if_) => {
+                let taken = match self.eval(&if_.condition, scope)? {
+                    Sym::Bool(bool) => bool,
+                    Sym::Test(test) => self.decide(Some(test))?,
+                    Sym::Opaque(Some(Kind::Bool) | None) => self.decide(None)?,
+                    _ => return Err(Stop::Error),
+                };
+
+                match taken {
+                    true => self.eval(&if_.then, scope),
+                    false => self.eval(&if_.otherwise, scope),
+                }
+            }
+            Term::Binary(binary) => {
+                let lhs = self.eval(&binary.lhs, scope)?;
+                let rhs = self.eval(&binary.rhs, scope)?;
+
+                self.binary(&binary.op, lhs, rhs, term)
+            }
+            Term::Call(call) => {
+                let callee = self.eval(&call.callee, scope)?;
+                let count = match &callee {
+                    Sym::Closure(lambda) => lambda.function.parameters.len(),
+                    _ => call.arguments.len(),
+                };
+
+                let arguments = call
+                    .arguments
+                    .iter()
+                    .take(count)
+                    .map(|argument| self.eval(argument, scope))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                self.call(callee, arguments)
+            }
+            Term::Print(print) => self.eval(&print.value, scope),
+            Term::Tuple(tuple) => {
+                let first = self.eval(&tuple.first, scope)?;
+                let second = self.eval(&tuple.second, scope)?;
+
+                Ok(Sym::Tuple(Rc::new((first, second))))
+            }
+            Term::First(first) => match self.eval(&first.value, scope)? {
+                Sym::Tuple(tuple) => Ok(tuple.0.clone()),
+                Sym::Opaque(None) => Ok(Sym::Opaque(None)),
+                _ => Err(Stop::Error),
+            },
+            Term::Second(second) => match self.eval(&second.value, scope)? {
+                Sym::Tuple(tuple) => Ok(tuple.1.clone()),
+                Sym::Opaque(None) => Ok(Sym::Opaque(None)),
+                _ => Err(Stop::Error),
+            },
+        }
+    }
+
+    fn binary<'a>(
+        &mut self,
+        op: &BinaryOp,
+        lhs: Sym<'a>,
+        rhs: Sym<'a>,
+        term: &Term,
+    ) -> Result<Sym<'a>, Stop> {
+        if let (Some(lhs), Some(rhs)) = (lhs.value(), rhs.value()) {
+            return match lhs.operate(op, &rhs, term.location()) {
+                Ok(Value::Int(int)) => Ok(Sym::Int(int)),
+                Ok(Value::Str(str)) => Ok(Sym::Str(str.to_string())),
+                Ok(Value::Bool(bool)) => Ok(Sym::Bool(bool)),
+                _ => Err(Stop::Error),
+            };
+        }
+
+        let comparison = matches!(
+            op,
+            BinaryOp::Eq
+                | BinaryOp::Neq
+                | BinaryOp::Lt
+                | BinaryOp::Lte
+                | BinaryOp::Gt
+                | BinaryOp::Gte
+        );
+
+        let test = match (&lhs, &rhs) {
+            (Sym::Text(piece), Sym::Str(literal)) => {
+                Some((*piece, op.clone(), Literal::Str(literal.clone())))
+            }
+            (Sym::Str(literal), Sym::Text(piece)) => {
+                Some((*piece, flip(op), Literal::Str(literal.clone())))
+            }
+            (Sym::Number(piece), Sym::Int(literal)) => {
+                Some((*piece, op.clone(), Literal::Int(*literal)))
+            }
+            (Sym::Int(literal), Sym::Number(piece)) => {
+                Some((*piece, flip(op), Literal::Int(*literal)))
+            }
+            _ => None,
+        };
+
+        if let (true, Some((piece, op, literal))) = (comparison, test) {
+            return Ok(Sym::Test(Test { piece, op, literal }));
+        }
+
+        match (lhs.kind(), rhs.kind()) {
+            (Some(lhs), Some(rhs)) => match operation(op, lhs, rhs) {
+                Some(kind) => Ok(Sym::Opaque(Some(kind))),
+                None => Err(Stop::Error),
+            },
+            _ => Ok(Sym::Opaque(None)),
+        }
+    }
+
+    fn call<'a>(&mut self, callee: Sym<'a>, arguments: Vec<Sym<'a>>) -> Result<Sym<'a>, Stop> {
+        match callee {
+            Sym::Closure(lambda) => {
+                if self.depth >= self.explorer.max_depth {
+                    return Err(Stop::Budget);
+                }
+
+                let mut scope = lambda.scope.borrow().clone();
+
+                for (parameter, argument) in lambda.function.parameters.iter().zip(arguments) {
+                    scope = bind(&scope, &parameter.text, argument);
+                }
+
+                self.depth += 1;
+                let result = self.eval(&lambda.function.value, &scope);
+                self.depth -= 1;
+
+                result
+            }
+            Sym::Reader(read) => self.read(read),
+            Sym::Opaque(None) => Ok(Sym::Opaque(None)),
+            _ => Err(Stop::Error),
+        }
+    }
+}
+
+/// A runtime error reachable by some input.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub error: RuntimeError,
+    /// Input that makes the program fail with the error.
+    pub input: String,
+}
+
+/// What exploring a program found.
+#[derive(Debug, Clone, Default)]
+pub struct Exploration {
+    /// Runtime errors, each one with an input triggering it, in the order
+    /// they were found.
+    pub findings: Vec<Finding>,
+    /// How many paths were explored.
+    pub paths: usize,
+    /// Whether every path was explored within the budget. Errors on the
+    /// paths left out are not found.
+    pub complete: bool,
+}
+
+/// Explores the paths of programs that read input, looking for the inputs
+/// that make them fail.
+#[derive(Debug, Clone)]
+pub struct Explorer {
+    max_paths: usize,
+    max_steps: u64,
+    max_depth: usize,
+    fuel: u64,
+}
+
+impl Default for Explorer {
+    fn default() -> Self {
+        Self {
+            max_paths: 256,
+            max_steps: 100_000,
+            max_depth: 200,
+            fuel: 1_000_000,
+        }
+    }
+}
+
+/// Errors that come from the limits of the runs, not from the program.
+fn is_limit(error: &RuntimeError) -> bool {
+    match error.code {
+        ErrorCode::Timeout
+        | ErrorCode::OutOfFuel
+        | ErrorCode::StackOverflow
+        | ErrorCode::Cancelled
+        | ErrorCode::MemoryLimit => true,
+        // Abandoned paths may read more input than the one solved for them.
+        ErrorCode::NativeFailed => matches!(
+            error.args.first().map(String::as_str),
+            Some("read_line" | "read_int")
+        ),
+        _ => false,
+    }
+}
+
+impl Explorer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Explores at most `max_paths` paths, 256 by default.
+    pub fn with_max_paths(mut self, max_paths: usize) -> Self {
+        self.max_paths = max_paths;
+
+        self
+    }
+
+    /// Abandons the paths that evaluate more than `max_steps` terms, 100000
+    /// by default.
+    pub fn with_max_steps(mut self, max_steps: u64) -> Self {
+        self.max_steps = max_steps;
+
+        self
+    }
+
+    /// Runs `program` on `input` with the interpreter.
+    fn run(&self, program: &Term, input: &str) -> Result<Value, RuntimeError> {
+        Interpreter::new(Collect::default())
+            .with_input(Cursor::new(input.to_owned()))
+            .with_fuel(self.fuel)
+            .with_max_depth(self.max_depth * 10)
+            .eval(program.clone(), &mut Context::new())
+    }
+
+    pub fn explore(&self, program: &Term) -> Exploration {
+        let mut exploration = Exploration {
+            complete: true,
+            ..Exploration::default()
+        };
+        let mut pending = vec![Vec::new()];
+
+        while let Some(decisions) = pending.pop() {
+            if exploration.paths == self.max_paths {
+                exploration.complete = false;
+                break;
+            }
+
+            exploration.paths += 1;
+
+            let mut path = Path::new(decisions, self);
+            let stop = path.eval(program, &None).err();
+
+            for &fork in path.forks.iter().rev() {
+                let mut decisions = path.decisions[..fork].to_vec();
+                decisions.push(!path.decisions[fork]);
+                pending.push(decisions);
+            }
+
+            // Paths past the budget are still run, the interpreter may find
+            // errors after the point where they were abandoned.
+            match stop {
+                Some(Stop::Infeasible) => continue,
+                Some(Stop::Budget) => exploration.complete = false,
+                Some(Stop::Error) | None => (),
+            }
+
+            let Some(input) = path.input() else { continue };
+            let Err(error) = self.run(program, &input) else {
+                continue;
+            };
+
+            let known = exploration.findings.iter().any(|finding| {
+                finding.error.code == error.code && finding.error.location == error.location
+            });
+
+            if !is_limit(&error) && !known {
+                exploration.findings.push(Finding { error, input });
+            }
+        }
+
+        exploration
+    }
+}
+
+/// Explores `program` with the default budget.
+pub fn explore(program: &Term) -> Exploration {
+    Explorer::default().explore(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{messages::ErrorCode, parser::parse};
+
+    use super::{explore, Exploration};
+
+    fn exploration(source: &str) -> Exploration {
+        explore(&parse(source, "tests").unwrap().expression)
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let exploration = exploration(
+            r#"let name = read_line();
+               let n = if (name == "zero") { 0 } else { 1 };
+               print(10 / n)"#,
+        );
+
+        assert_eq!(exploration.paths, 2);
+        assert!(exploration.complete);
+        assert_eq!(exploration.findings.len(), 1);
+        assert_eq!(
+            exploration.findings[0].error.code,
+            ErrorCode::DivisionByZero
+        );
+        assert_eq!(exploration.findings[0].input, "zero\n");
+    }
+
+    #[test]
+    fn type_errors() {
+        let exploration = exploration(
+            r#"let a = read_line();
+               let b = read_line();
+               if (a < "m") { if ("x" == b) { a - 1 } else { 0 } } else { a }"#,
+        );
+
+        assert_eq!(exploration.paths, 3);
+        assert_eq!(exploration.findings.len(), 1);
+        assert_eq!(
+            exploration.findings[0].error.code,
+            ErrorCode::InvalidSubtraction
+        );
+
+        let input = exploration.findings[0].input.lines().collect::<Vec<_>>();
+        assert!(input[0] < "m");
+        assert_eq!(input[1], "x");
+    }
+
+    #[test]
+    fn integers() {
+        let exploration = exploration(
+            r#"let n = read_int();
+               let line = read_line();
+               let m = read_int();
+               if (n > 5) { if (m == -2) { 1 / (m + 2) } else { n } } else { 0 }"#,
+        );
+
+        assert!(exploration.complete);
+        assert_eq!(exploration.paths, 3);
+        assert_eq!(exploration.findings.len(), 1);
+        assert_eq!(
+            exploration.findings[0].error.code,
+            ErrorCode::DivisionByZero
+        );
+        assert_eq!(exploration.findings[0].input, "6\n-2\n");
+    }
+
+    #[test]
+    fn the_rest_of_the_input() {
+        let exploration = exploration(
+            r#"let name = read_line();
+               let rest = read_all();
+               let more = read_all();
+               if (rest == "zero") { 10 / 0 } else { name + more }"#,
+        );
+
+        assert!(exploration.complete);
+        assert_eq!(exploration.paths, 2);
+        assert_eq!(exploration.findings.len(), 1);
+        assert_eq!(exploration.findings[0].input, "\nzero");
+    }
+
+    #[test]
+    fn contradicting_tests() {
+        let exploration = exploration(
+            r#"let a = read_line();
+               if (a == "x") { if (a != "x") { 1 / 0 } else { 1 } } else { 2 }"#,
+        );
+
+        assert!(exploration.complete);
+        assert!(exploration.findings.is_empty());
+    }
+
+    #[test]
+    fn budget() {
+        let exploration =
+            exploration("let loop = fn (n) => if (n == 0) { 0 } else { loop(n - 1) }; loop(1000)");
+
+        assert!(!exploration.complete);
+        assert!(exploration.findings.is_empty());
+    }
+}
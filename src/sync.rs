@@ -0,0 +1,291 @@
+use std::{fmt::Display, sync::Arc};
+
+use crate::{
+    ast::{File, Location, Term},
+    config::{Config, Limits},
+    intern::Interner,
+    interpreter::{Collect, Context, Frame, Interpreter, Printer, RuntimeError, Snapshot, Value},
+    lexer::SyntaxError,
+    messages::{self, ErrorCode, Lang},
+    parser::parse_with,
+};
+
+/// Native function that can be called from any thread, see
+/// [`SyncInterpreter::register_native`].
+pub type SyncNativeFn = dyn Fn(&[Value]) -> Result<Value, String> + Send + Sync;
+
+/// Interpreter that can be shared between threads, like the workers of a web
+/// server, to evaluate independent programs.
+///
+/// Values hold the closures of the program, which are not thread safe, so it
+/// keeps only the settings and the native functions, and every evaluation
+/// runs in a fresh [`Interpreter`] on the calling thread. What comes out of
+/// it, an [`Evaluation`], is plain data that can be sent anywhere.
+///
+/// The interpreters share an [`Interner`], and the prelude is evaluated once
+/// and restored in each of them, see [`SyncInterpreter::with_prelude`].
+#[derive(Clone, Default)]
+pub struct SyncInterpreter {
+    config: Config,
+    natives: Vec<(String, Arc<SyncNativeFn>)>,
+    interner: Interner,
+    prelude: Option<Arc<Snapshot>>,
+}
+
+/// Error that stopped a program run by a [`SyncInterpreter`]. Unlike a
+/// [`RuntimeError`] it does not keep the bindings of the failing term, so it
+/// can be sent between threads.
+#[derive(Debug, Clone)]
+pub struct SyncError {
+    pub code: ErrorCode,
+    pub args: Vec<String>,
+    pub location: Location,
+    /// Calls that were active when the error happened, innermost first.
+    pub stack: Vec<Frame>,
+}
+
+impl SyncError {
+    /// Short description of the error, rendered in `lang`.
+    pub fn message(&self, lang: Lang) -> String {
+        messages::message(self.code, lang, &self.args)
+    }
+
+    /// Full explanation of the error, rendered in `lang`.
+    pub fn full_text(&self, lang: Lang) -> String {
+        messages::full_text(self.code, lang, &self.args)
+    }
+}
+
+impl From<RuntimeError> for SyncError {
+    fn from(error: RuntimeError) -> Self {
+        Self {
+            code: error.code,
+            args: error.args,
+            location: error.location,
+            stack: error.stack,
+        }
+    }
+}
+
+impl Display for SyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} at {}",
+            self.code,
+            self.message(Lang::En),
+            self.location
+        )
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+/// Result of a program run by a [`SyncInterpreter`]: its final value, in the
+/// JSON format of [`Value`], and the values it printed.
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub result: Result<serde_json::Value, SyncError>,
+    pub prints: Vec<String>,
+}
+
+impl SyncInterpreter {
+    /// Creates an interpreter with the settings of `config`, its prelude
+    /// is not evaluated, see [`SyncInterpreter::with_prelude`].
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            natives: Vec::new(),
+            interner: Interner::new(),
+            prelude: None,
+        }
+    }
+
+    /// Evaluates `prelude`, whose bindings, and memoized results, become
+    /// visible to every program. The native functions it uses must be
+    /// registered before.
+    pub fn with_prelude(mut self, prelude: Term) -> Result<Self, SyncError> {
+        self.prelude = None;
+
+        let mut interpreter = self.interpreter(Collect::default());
+        let mut context = Context::new();
+        interpreter.eval(self.interner.intern_term(prelude), &mut context)?;
+
+        self.prelude = Some(Arc::new(interpreter.snapshot(&context)));
+
+        Ok(self)
+    }
+
+    /// Replaces the limits of the configuration, keeping the prelude, which
+    /// was evaluated with the previous ones.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.config.limits = limits;
+        self
+    }
+
+    /// Limits of the evaluations.
+    pub fn limits(&self) -> &Limits {
+        &self.config.limits
+    }
+
+    /// Names shared by the programs, and the prelude, of every interpreter.
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    /// Parses source code, sharing the names of the prelude. The names the
+    /// prelude doesn't have go away with the program, see
+    /// [`Interner::layer`].
+    pub fn parse(&self, source: &str, filename: &str) -> Result<File, SyntaxError> {
+        parse_with(source, filename, &self.interner.layer())
+    }
+
+    /// Makes `function` callable from programs as `name`, like
+    /// [`Interpreter::register_native`].
+    pub fn register_native<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.natives.push((name.to_string(), Arc::new(function)));
+    }
+
+    /// Creates an interpreter for the calling thread, with the settings, the
+    /// native functions and the prelude of this one.
+    pub fn interpreter<I: Printer>(&self, io: I) -> Interpreter<I> {
+        let mut interpreter = self.config.interpreter(io);
+
+        for (name, function) in &self.natives {
+            let function = function.clone();
+            interpreter.register_native(name, move |arguments| function(arguments));
+        }
+
+        match &self.prelude {
+            Some(prelude) => {
+                // The natives the snapshot refers to were registered above.
+                let bindings = interpreter.restore(prelude).unwrap_or_default();
+                interpreter.with_globals(bindings)
+            }
+            None => interpreter,
+        }
+    }
+
+    /// Evaluates `term` in a fresh interpreter, collecting what it prints.
+    pub fn eval(&self, term: Term) -> Evaluation {
+        let mut interpreter = self.interpreter(Collect::default());
+        let result = interpreter.eval(term, &mut Context::new());
+
+        Evaluation {
+            result: match result {
+                Ok(value) => Ok(serde_json::to_value(&value).unwrap_or_default()),
+                Err(error) => Err(error.into()),
+            },
+            prints: interpreter.io.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use crate::{
+        ast::{build::*, BinaryOp},
+        config::{Config, Limits},
+        interpreter::Value,
+        messages::ErrorCode,
+    };
+
+    use super::{Evaluation, SyncInterpreter};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_and_sync() {
+        assert_send_sync::<SyncInterpreter>();
+        assert_send_sync::<Evaluation>();
+    }
+
+    #[test]
+    fn eval_on_many_threads() {
+        let mut interpreter = SyncInterpreter::new(Config::default());
+        interpreter.register_native("double", |arguments| match arguments {
+            [Value::Int(int)] => Ok(Value::Int(int * 2)),
+            _ => Err(String::from("expected an integer")),
+        });
+        let interpreter = Arc::new(interpreter);
+
+        let handles = (0..4)
+            .map(|n| {
+                let interpreter = interpreter.clone();
+                thread::spawn(move || interpreter.eval(print(call(var("double"), vec![int(n)]))))
+            })
+            .collect::<Vec<_>>();
+
+        for (n, handle) in handles.into_iter().enumerate() {
+            let evaluation = handle.join().unwrap();
+
+            assert_eq!(evaluation.result.unwrap()["value"], n as i64 * 2);
+            assert_eq!(evaluation.prints, vec![(n * 2).to_string()]);
+        }
+    }
+
+    #[test]
+    fn shared_prelude() {
+        let prelude = let_(
+            "triple",
+            function(&["n"], binary(BinaryOp::Mul, var("n"), int(3))),
+            var("triple"),
+        );
+        let interpreter = Arc::new(
+            SyncInterpreter::new(Config::default())
+                .with_prelude(prelude)
+                .unwrap(),
+        );
+
+        let handles = (0..4)
+            .map(|n| {
+                let interpreter = interpreter.clone();
+                thread::spawn(move || {
+                    let file = interpreter.parse(&format!("triple({n})"), "tests").unwrap();
+                    interpreter.eval(file.expression)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for (n, handle) in handles.into_iter().enumerate() {
+            let evaluation = handle.join().unwrap();
+
+            assert_eq!(evaluation.result.unwrap()["value"], n as i64 * 3);
+        }
+
+        // The names of the programs are not kept.
+        interpreter
+            .parse("let other = \"name\"; other", "tests")
+            .unwrap();
+        assert_eq!(interpreter.interner().len(), 2);
+    }
+
+    #[test]
+    fn limits() {
+        let interpreter = SyncInterpreter::new(Config {
+            limits: Limits {
+                fuel: Some(1),
+                ..Limits::default()
+            },
+            ..Config::default()
+        });
+
+        let program = let_(
+            "f",
+            function(&["n"], binary(BinaryOp::Add, var("n"), int(1))),
+            binary(
+                BinaryOp::Add,
+                call(var("f"), vec![int(1)]),
+                call(var("f"), vec![int(2)]),
+            ),
+        );
+        let error = interpreter.eval(program).result.unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::OutOfFuel);
+    }
+}
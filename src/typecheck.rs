@@ -0,0 +1,990 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use crate::{
+    ast::{deeper, Annotation, BinaryOp, Element, Let, Location, Term},
+    messages::{self, ErrorCode, Lang},
+};
+
+/// Type of a rinha value, as inferred by [`typecheck`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Int,
+    Str,
+    Bool,
+    /// A type that is not known, or any type when the value is polymorphic,
+    /// like the parameter of `fn (x) => x`.
+    Var(usize),
+    Tuple(Box<Type>, Box<Type>),
+    Function(Vec<Type>, Box<Type>),
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Str => write!(f, "Str"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Var(index) if *index < 26 => write!(f, "'{}", (b'a' + *index as u8) as char),
+            Type::Var(index) => write!(f, "'t{index}"),
+            Type::Tuple(first, second) => write!(f, "({first}, {second})"),
+            Type::Function(parameters, result) => {
+                write!(f, "(")?;
+
+                for (index, parameter) in parameters.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{parameter}")?;
+                }
+
+                write!(f, ") -> {result}")
+            }
+        }
+    }
+}
+
+impl From<&Annotation> for Type {
+    fn from(annotation: &Annotation) -> Self {
+        match annotation {
+            Annotation::Int => Type::Int,
+            Annotation::Str => Type::Str,
+            Annotation::Bool => Type::Bool,
+            Annotation::Tuple { first, second } => Type::Tuple(
+                Box::new(first.as_ref().into()),
+                Box::new(second.as_ref().into()),
+            ),
+            Annotation::Function { parameters, result } => Type::Function(
+                parameters.iter().map(Type::from).collect(),
+                Box::new(result.as_ref().into()),
+            ),
+        }
+    }
+}
+
+/// A program that would fail at runtime because of the types of its values.
+#[derive(Debug, Clone)]
+pub struct TypeError {
+    pub code: ErrorCode,
+    pub args: Vec<String>,
+    pub location: Location,
+}
+
+impl TypeError {
+    pub fn new(code: ErrorCode, args: Vec<String>, location: Location) -> Self {
+        Self {
+            code,
+            args,
+            location,
+        }
+    }
+
+    /// Short description of the error, rendered in `lang`.
+    pub fn message(&self, lang: Lang) -> String {
+        messages::message(self.code, lang, &self.args)
+    }
+
+    /// Full explanation of the error, rendered in `lang`.
+    pub fn full_text(&self, lang: Lang) -> String {
+        messages::full_text(self.code, lang, &self.args)
+    }
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} at {}",
+            self.code,
+            self.full_text(Lang::En),
+            self.location
+        )
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+enum State {
+    /// Not known yet. Variables created inside more `let` values have higher
+    /// levels, and are the ones generalized when the binding is done.
+    Unbound {
+        level: usize,
+    },
+    Bound(Type),
+}
+
+/// Type of a binding, which is instantiated with fresh variables for `vars`
+/// each time the binding is used, along with the additions and comparisons
+/// of its value that depend on them.
+#[derive(Clone)]
+struct Scheme {
+    vars: Vec<usize>,
+    ty: Type,
+    additions: Vec<Addition>,
+    comparisons: Vec<Comparison>,
+}
+
+impl Scheme {
+    fn monomorphic(ty: Type) -> Self {
+        Self {
+            vars: vec![],
+            ty,
+            additions: vec![],
+            comparisons: vec![],
+        }
+    }
+}
+
+/// `+` whose operands were not known when it was checked.
+#[derive(Clone)]
+struct Addition {
+    lhs: Type,
+    rhs: Type,
+    result: Type,
+    location: Location,
+}
+
+/// Comparison between values whose type was not known when it was checked.
+#[derive(Clone)]
+struct Comparison {
+    lhs: Type,
+    rhs: Type,
+    location: Location,
+}
+
+/// Hindley–Milner type inference for rinha terms, with polymorphic `let`
+/// bindings.
+///
+/// Programs whose `if` branches have different types are rejected, even
+/// though they may run. `+` adds integers and concatenates strings, so when
+/// the types of its operands are only known from how a polymorphic function
+/// is called, like in `fn (a, b) => a + b`, it is checked again at each
+/// call.
+#[derive(Default)]
+pub struct Checker {
+    states: Vec<State>,
+    scopes: Vec<(Arc<str>, Scheme)>,
+    level: usize,
+    additions: Vec<Addition>,
+    comparisons: Vec<Comparison>,
+    /// Errors found so far. Terms that fail get a fresh type, so checking
+    /// goes on with the rest of the program.
+    errors: Vec<TypeError>,
+}
+
+/// Infers the type of `term`, failing with every use of a value of the
+/// wrong type, in the order they appear in the program.
+pub fn typecheck(term: &Term) -> Result<Type, Vec<TypeError>> {
+    Checker::new().check(term)
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks a prelude, keeping the types of its bindings for the next
+    /// programs.
+    pub fn prelude(&mut self, mut term: &Term) -> Result<(), Vec<TypeError>> {
+        while let Term::Let(let_) = term {
+            let scheme = self.binding(let_);
+            self.scopes.push((let_.name.text.clone(), scheme));
+            term = &let_.next;
+        }
+
+        self.infer(term);
+        self.solve();
+        self.finish(())
+    }
+
+    /// Infers the type of `term`.
+    pub fn check(&mut self, term: &Term) -> Result<Type, Vec<TypeError>> {
+        let ty = self.infer(term);
+        self.solve();
+
+        let ty = self.display(&[&ty]).remove(0);
+        self.finish(ty)
+    }
+
+    /// Takes the errors found, sorted by where they are in the program.
+    fn finish<T>(&mut self, value: T) -> Result<T, Vec<TypeError>> {
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.sort_by_key(|error| (error.location.start, error.location.end));
+
+        match errors.is_empty() {
+            true => Ok(value),
+            false => Err(errors),
+        }
+    }
+
+    /// Records `error`, unless the same one was found before, like the
+    /// ones of a polymorphic function checked again at each call.
+    fn report(&mut self, error: TypeError) {
+        let known = self.errors.iter().any(|other| {
+            other.code == error.code && other.args == error.args && other.location == error.location
+        });
+
+        if !known {
+            self.errors.push(error);
+        }
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.states.push(State::Unbound { level: self.level });
+        Type::Var(self.states.len() - 1)
+    }
+
+    /// Follows the bound variables at the root of `ty`.
+    fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Var(var) => match &self.states[*var] {
+                State::Bound(ty) => self.resolve(ty),
+                State::Unbound { .. } => ty.clone(),
+            },
+            _ => ty.clone(),
+        }
+    }
+
+    /// Replaces every bound variable of `ty` by its type.
+    fn zonk(&self, ty: &Type) -> Type {
+        match self.resolve(ty) {
+            Type::Tuple(first, second) => {
+                Type::Tuple(Box::new(self.zonk(&first)), Box::new(self.zonk(&second)))
+            }
+            Type::Function(parameters, result) => Type::Function(
+                parameters
+                    .iter()
+                    .map(|parameter| self.zonk(parameter))
+                    .collect(),
+                Box::new(self.zonk(&result)),
+            ),
+            ty => ty,
+        }
+    }
+
+    /// The types as shown to users, with the variables numbered in the order
+    /// they appear.
+    fn display(&self, types: &[&Type]) -> Vec<Type> {
+        fn rename(ty: Type, names: &mut HashMap<usize, usize>) -> Type {
+            match ty {
+                Type::Var(var) => {
+                    let next = names.len();
+                    Type::Var(*names.entry(var).or_insert(next))
+                }
+                Type::Tuple(first, second) => Type::Tuple(
+                    Box::new(rename(*first, names)),
+                    Box::new(rename(*second, names)),
+                ),
+                Type::Function(parameters, result) => Type::Function(
+                    parameters
+                        .into_iter()
+                        .map(|parameter| rename(parameter, names))
+                        .collect(),
+                    Box::new(rename(*result, names)),
+                ),
+                ty => ty,
+            }
+        }
+
+        let mut names = HashMap::new();
+
+        types
+            .iter()
+            .map(|ty| rename(self.zonk(ty), &mut names))
+            .collect()
+    }
+
+    fn error(&self, code: ErrorCode, types: &[&Type], location: &Location) -> TypeError {
+        let args = self
+            .display(types)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        TypeError::new(code, args, location.clone())
+    }
+
+    /// Fails when `var` appears in `ty`, and lowers the level of the
+    /// variables of `ty` to `level`, so they are not generalized before
+    /// `var` is.
+    fn occurs(&mut self, var: usize, level: usize, ty: &Type) -> Result<(), ()> {
+        match self.resolve(ty) {
+            Type::Var(other) if other == var => Err(()),
+            Type::Var(other) => {
+                if let State::Unbound { level: other_level } = &mut self.states[other] {
+                    *other_level = (*other_level).min(level);
+                }
+
+                Ok(())
+            }
+            Type::Tuple(first, second) => {
+                self.occurs(var, level, &first)?;
+                self.occurs(var, level, &second)
+            }
+            Type::Function(parameters, result) => {
+                for parameter in &parameters {
+                    self.occurs(var, level, parameter)?;
+                }
+
+                self.occurs(var, level, &result)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn unify(&mut self, lhs: &Type, rhs: &Type) -> Result<(), ()> {
+        match (self.resolve(lhs), self.resolve(rhs)) {
+            (Type::Var(lhs), Type::Var(rhs)) if lhs == rhs => Ok(()),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => {
+                let State::Unbound { level } = self.states[var] else {
+                    unreachable!("resolved variables are unbound");
+                };
+
+                self.occurs(var, level, &ty)?;
+                self.states[var] = State::Bound(ty);
+
+                Ok(())
+            }
+            (Type::Int, Type::Int) | (Type::Str, Type::Str) | (Type::Bool, Type::Bool) => Ok(()),
+            (Type::Tuple(lhs_first, lhs_second), Type::Tuple(rhs_first, rhs_second)) => {
+                self.unify(&lhs_first, &rhs_first)?;
+                self.unify(&lhs_second, &rhs_second)
+            }
+            (
+                Type::Function(lhs_parameters, lhs_result),
+                Type::Function(rhs_parameters, rhs_result),
+            ) if lhs_parameters.len() == rhs_parameters.len() => {
+                for (lhs, rhs) in lhs_parameters.iter().zip(&rhs_parameters) {
+                    self.unify(lhs, rhs)?;
+                }
+
+                self.unify(&lhs_result, &rhs_result)
+            }
+            _ => Err(()),
+        }
+    }
+
+    /// Unifies the types, failing with a mismatch between them at `location`.
+    fn expect(
+        &mut self,
+        expected: &Type,
+        found: &Type,
+        location: &Location,
+    ) -> Result<(), TypeError> {
+        self.unify(expected, found)
+            .map_err(|()| self.error(ErrorCode::TypeMismatch, &[expected, found], location))
+    }
+
+    /// The variables of `ty` created inside the value of the binding being
+    /// generalized.
+    fn generalizable(&self, ty: &Type, vars: &mut Vec<usize>) {
+        match self.resolve(ty) {
+            Type::Var(var) => match self.states[var] {
+                State::Unbound { level } if level > self.level && !vars.contains(&var) => {
+                    vars.push(var)
+                }
+                _ => (),
+            },
+            Type::Tuple(first, second) => {
+                self.generalizable(&first, vars);
+                self.generalizable(&second, vars);
+            }
+            Type::Function(parameters, result) => {
+                for parameter in &parameters {
+                    self.generalizable(parameter, vars);
+                }
+
+                self.generalizable(&result, vars);
+            }
+            _ => (),
+        }
+    }
+
+    /// Whether any of `vars` appears in `ty`.
+    fn mentions(&self, ty: &Type, vars: &[usize]) -> bool {
+        match self.resolve(ty) {
+            Type::Var(var) => vars.contains(&var),
+            Type::Tuple(first, second) => {
+                self.mentions(&first, vars) || self.mentions(&second, vars)
+            }
+            Type::Function(parameters, result) => {
+                parameters
+                    .iter()
+                    .any(|parameter| self.mentions(parameter, vars))
+                    || self.mentions(&result, vars)
+            }
+            _ => false,
+        }
+    }
+
+    fn substitute(&self, ty: &Type, vars: &HashMap<usize, Type>) -> Type {
+        match self.resolve(ty) {
+            Type::Var(var) => vars.get(&var).cloned().unwrap_or(Type::Var(var)),
+            Type::Tuple(first, second) => Type::Tuple(
+                Box::new(self.substitute(&first, vars)),
+                Box::new(self.substitute(&second, vars)),
+            ),
+            Type::Function(parameters, result) => Type::Function(
+                parameters
+                    .iter()
+                    .map(|parameter| self.substitute(parameter, vars))
+                    .collect(),
+                Box::new(self.substitute(&result, vars)),
+            ),
+            ty => ty,
+        }
+    }
+
+    /// The type of a use of a binding, whose additions and comparisons are
+    /// checked again with the types of this use.
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let vars = scheme.vars.iter().map(|var| (*var, self.fresh())).collect();
+
+        for addition in &scheme.additions {
+            self.additions.push(Addition {
+                lhs: self.substitute(&addition.lhs, &vars),
+                rhs: self.substitute(&addition.rhs, &vars),
+                result: self.substitute(&addition.result, &vars),
+                location: addition.location.clone(),
+            });
+        }
+
+        for comparison in &scheme.comparisons {
+            self.comparisons.push(Comparison {
+                lhs: self.substitute(&comparison.lhs, &vars),
+                rhs: self.substitute(&comparison.rhs, &vars),
+                location: comparison.location.clone(),
+            });
+        }
+
+        self.substitute(&scheme.ty, &vars)
+    }
+
+    /// Infers the type of the value of `let_`. Functions can call themselves,
+    /// so their name is bound while checking them.
+    fn binding(&mut self, let_: &Let) -> Scheme {
+        self.level += 1;
+
+        let annotation = let_.name.annotation.as_ref().map(Type::from);
+        let ty = match let_.value.as_ref() {
+            Term::Function(_) => {
+                let ty = annotation.clone().unwrap_or_else(|| self.fresh());
+                self.scopes
+                    .push((let_.name.text.clone(), Scheme::monomorphic(ty.clone())));
+                let value = self.infer(&let_.value);
+                self.scopes.pop();
+
+                if let Err(error) = self.expect(&ty, &value, let_.value.location()) {
+                    self.report(error);
+                }
+
+                value
+            }
+            value => self.infer(value),
+        };
+
+        if let Some(annotation) = &annotation {
+            if let Err(error) = self.expect(annotation, &ty, let_.value.location()) {
+                self.report(error);
+            }
+        }
+
+        let mut vars = Vec::new();
+        self.level -= 1;
+        self.generalizable(&ty, &mut vars);
+
+        // The pending checks on the generalized variables move to the scheme,
+        // since those variables now stand for the type of each use.
+        let (additions, pending) =
+            std::mem::take(&mut self.additions)
+                .into_iter()
+                .partition(|addition: &Addition| {
+                    [&addition.lhs, &addition.rhs, &addition.result]
+                        .iter()
+                        .any(|ty| self.mentions(ty, &vars))
+                });
+        self.additions = pending;
+
+        let (comparisons, pending) = std::mem::take(&mut self.comparisons)
+            .into_iter()
+            .partition(|comparison: &Comparison| self.mentions(&comparison.lhs, &vars));
+        self.comparisons = pending;
+
+        Scheme {
+            vars,
+            ty,
+            additions,
+            comparisons,
+        }
+    }
+
+    /// Infers the type of `term`, reporting its errors.
+    fn infer(&mut self, term: &Term) -> Type {
+        match deeper(|| self.infer_term(term)) {
+            Ok(ty) => ty,
+            Err(error) => {
+                self.report(error);
+                self.fresh()
+            }
+        }
+    }
+
+    fn infer_term(&mut self, term: &Term) -> Result<Type, TypeError> {
+        match term {
+            Term::Int(_) => Ok(Type::Int),
+            Term::Str(_) => Ok(Type::Str),
+            Term::Bool(_) => Ok(Type::Bool),
+            Term::Var(var) => {
+                let Some(index) = self.scopes.iter().rposition(|(name, _)| name == &var.text)
+                else {
+                    return Err(TypeError::new(
+                        ErrorCode::UnboundVariable,
+                        vec![var.text.to_string()],
+                        var.location.clone(),
+                    ));
+                };
+
+                let scheme = self.scopes[index].1.clone();
+
+                Ok(self.instantiate(&scheme))
+            }
+            Term::Let(let_) => {
+                let scheme = self.binding(let_);
+                self.scopes.push((let_.name.text.clone(), scheme));
+                let next = self.infer(&let_.next);
+                self.scopes.pop();
+
+                Ok(next)
+            }
+            Term::Function(function) => {
+                let parameters = function
+                    .parameters
+                    .iter()
+                    .map(|parameter| match &parameter.annotation {
+                        Some(annotation) => annotation.into(),
+                        None => self.fresh(),
+                    })
+                    .collect::<Vec<_>>();
+
+                for (parameter, ty) in function.parameters.iter().zip(&parameters) {
+                    self.scopes
+                        .push((parameter.text.clone(), Scheme::monomorphic(ty.clone())));
+                }
+
+                let result = self.infer(&function.value);
+                self.scopes
+                    .truncate(self.scopes.len() - function.parameters.len());
+
+                if let Some(annotation) = &function.annotation {
+                    self.expect(&annotation.into(), &result, function.value.location())?;
+                }
+
+                Ok(Type::Function(parameters, Box::new(result)))
+            }
+            Term::Call(call) => {
+                let callee = self.infer(&call.callee);
+                let arguments = call
+                    .arguments
+                    .iter()
+                    .map(|argument| self.infer(argument))
+                    .collect::<Vec<_>>();
+
+                match self.resolve(&callee) {
+                    Type::Function(parameters, result) if parameters.len() == arguments.len() => {
+                        for ((parameter, argument), term) in
+                            parameters.iter().zip(&arguments).zip(&call.arguments)
+                        {
+                            self.expect(parameter, argument, term.location())?;
+                        }
+
+                        Ok(*result)
+                    }
+                    Type::Int | Type::Str | Type::Bool | Type::Tuple(..) => {
+                        Err(self.error(ErrorCode::InvalidCall, &[&callee], call.callee.location()))
+                    }
+                    _ => {
+                        let result = self.fresh();
+                        let expected = Type::Function(arguments, Box::new(result.clone()));
+                        self.expect(&callee, &expected, &call.location)?;
+
+                        Ok(result)
+                    }
+                }
+            }
+            Term::Binary(binary) => {
+                let lhs = self.infer(&binary.lhs);
+                let rhs = self.infer(&binary.rhs);
+
+                self.binary(&binary.op, lhs, rhs, &binary.location)
+            }
+            Term::If(if_) => {
+                let condition = self.infer(&if_.condition);
+
+                if self.unify(&Type::Bool, &condition).is_err() {
+                    let error = self.error(
+                        ErrorCode::InvalidIfCondition,
+                        &[&condition],
+                        if_.condition.location(),
+                    );
+                    self.report(error);
+                }
+
+                let then = self.infer(&if_.then);
+                let otherwise = self.infer(&if_.otherwise);
+                self.expect(&then, &otherwise, if_.otherwise.location())?;
+
+                Ok(then)
+            }
+            Term::Print(print) => Ok(self.infer(&print.value)),
+            Term::First(first) => self
+                .element(&first.value, ErrorCode::InvalidFirst)
+                .map(|(first, _)| first),
+            Term::Second(second) => self
+                .element(&second.value, ErrorCode::InvalidSecond)
+                .map(|(_, second)| second),
+            Term::Tuple(tuple) => Ok(Type::Tuple(
+                Box::new(self.infer(&tuple.first)),
+                Box::new(self.infer(&tuple.second)),
+            )),
+        }
+    }
+
+    /// Infers the types of the elements of the tuple `term`.
+    fn element(&mut self, term: &Term, code: ErrorCode) -> Result<(Type, Type), TypeError> {
+        let value = self.infer(term);
+        let (first, second) = (self.fresh(), self.fresh());
+        let tuple = Type::Tuple(Box::new(first.clone()), Box::new(second.clone()));
+
+        self.unify(&tuple, &value)
+            .map_err(|()| self.error(code, &[&value], term.location()))?;
+
+        Ok((first, second))
+    }
+
+    fn binary(
+        &mut self,
+        op: &BinaryOp,
+        lhs: Type,
+        rhs: Type,
+        location: &Location,
+    ) -> Result<Type, TypeError> {
+        let integers = |checker: &mut Self, code| {
+            checker
+                .unify(&Type::Int, &lhs)
+                .and_then(|()| checker.unify(&Type::Int, &rhs))
+                .map_err(|()| checker.error(code, &[&lhs, &rhs], location))
+                .map(|()| Type::Int)
+        };
+
+        match op {
+            BinaryOp::Add => self.addition(lhs, rhs, location),
+            BinaryOp::Sub => integers(self, ErrorCode::InvalidSubtraction),
+            BinaryOp::Mul => integers(self, ErrorCode::InvalidMultiplication),
+            BinaryOp::Div => integers(self, ErrorCode::InvalidDivision),
+            BinaryOp::Rem => integers(self, ErrorCode::InvalidRemainder),
+            BinaryOp::And | BinaryOp::Or => {
+                let code = match op {
+                    BinaryOp::And => ErrorCode::InvalidAnd,
+                    _ => ErrorCode::InvalidOr,
+                };
+
+                self.unify(&Type::Bool, &lhs)
+                    .and_then(|()| self.unify(&Type::Bool, &rhs))
+                    .map_err(|()| self.error(code, &[&lhs, &rhs], location))?;
+
+                Ok(Type::Bool)
+            }
+            BinaryOp::Eq
+            | BinaryOp::Neq
+            | BinaryOp::Lt
+            | BinaryOp::Lte
+            | BinaryOp::Gt
+            | BinaryOp::Gte => {
+                self.unify(&lhs, &rhs).map_err(|()| {
+                    self.error(ErrorCode::InvalidComparison, &[&lhs, &rhs], location)
+                })?;
+
+                let comparison = Comparison {
+                    lhs,
+                    rhs,
+                    location: location.clone(),
+                };
+
+                if !self.compare(&comparison)? {
+                    self.comparisons.push(comparison);
+                }
+
+                Ok(Type::Bool)
+            }
+        }
+    }
+
+    /// Checks that the compared values are integers, strings or booleans,
+    /// telling whether their type was known.
+    fn compare(&self, comparison: &Comparison) -> Result<bool, TypeError> {
+        match self.resolve(&comparison.lhs) {
+            Type::Var(_) => Ok(false),
+            Type::Int | Type::Str | Type::Bool => Ok(true),
+            Type::Tuple(..) | Type::Function(..) => Err(self.error(
+                ErrorCode::InvalidComparison,
+                &[&comparison.lhs, &comparison.rhs],
+                &comparison.location,
+            )),
+        }
+    }
+
+    fn addition(&mut self, lhs: Type, rhs: Type, location: &Location) -> Result<Type, TypeError> {
+        let addition = Addition {
+            result: self.fresh(),
+            lhs,
+            rhs,
+            location: location.clone(),
+        };
+        let result = addition.result.clone();
+
+        if !self.add(&addition)? {
+            self.additions.push(addition);
+        }
+
+        Ok(result)
+    }
+
+    /// Checks the operands of `addition` and infers its result when it is
+    /// known, telling whether the operands were known too.
+    fn add(&mut self, addition: &Addition) -> Result<bool, TypeError> {
+        let invalid = |checker: &Self| {
+            checker.error(
+                ErrorCode::InvalidAddition,
+                &[&addition.lhs, &addition.rhs],
+                &addition.location,
+            )
+        };
+
+        // Only integers add up to integers.
+        match self.resolve(&addition.result) {
+            Type::Int => {
+                return self
+                    .unify(&Type::Int, &addition.lhs)
+                    .and_then(|()| self.unify(&Type::Int, &addition.rhs))
+                    .map(|()| true)
+                    .map_err(|()| invalid(self))
+            }
+            Type::Bool | Type::Tuple(..) | Type::Function(..) => return Err(invalid(self)),
+            Type::Str | Type::Var(_) => (),
+        }
+
+        let (result, known) = match (self.resolve(&addition.lhs), self.resolve(&addition.rhs)) {
+            (Type::Int, Type::Int) => (Type::Int, true),
+            (Type::Str, Type::Int | Type::Str) | (Type::Int, Type::Str) => (Type::Str, true),
+            (Type::Str, Type::Var(_)) | (Type::Var(_), Type::Str) => (Type::Str, false),
+            (Type::Int | Type::Var(_), Type::Int | Type::Var(_)) => return Ok(false),
+            _ => return Err(invalid(self)),
+        };
+
+        self.unify(&result, &addition.result)
+            .map_err(|()| invalid(self))?;
+
+        Ok(known)
+    }
+
+    /// Checks the additions and comparisons whose operands became known
+    /// after they were found.
+    fn solve(&mut self) {
+        loop {
+            let additions = std::mem::take(&mut self.additions);
+            let count = additions.len();
+
+            for addition in additions {
+                match self.add(&addition) {
+                    Ok(true) => (),
+                    Ok(false) => self.additions.push(addition),
+                    Err(error) => self.report(error),
+                }
+            }
+
+            if self.additions.len() == count {
+                break;
+            }
+        }
+
+        for comparison in std::mem::take(&mut self.comparisons) {
+            match self.compare(&comparison) {
+                Ok(true) => (),
+                Ok(false) => self.comparisons.push(comparison),
+                Err(error) => self.report(error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{messages::ErrorCode, parser::parse};
+
+    use super::{typecheck, Checker, Type};
+
+    fn check(source: &str) -> Result<String, ErrorCode> {
+        let file = parse(source, "tests").unwrap();
+
+        typecheck(&file.expression)
+            .map(|ty| ty.to_string())
+            .map_err(|errors| errors[0].code)
+    }
+
+    /// Codes of every error found in the program in `source`.
+    fn codes(source: &str) -> Vec<ErrorCode> {
+        let file = parse(source, "tests").unwrap();
+
+        match typecheck(&file.expression) {
+            Ok(_) => vec![],
+            Err(errors) => errors.into_iter().map(|error| error.code).collect(),
+        }
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(check("1"), Ok(String::from("Int")));
+        assert_eq!(check("(\"a\", true)"), Ok(String::from("(Str, Bool)")));
+        assert_eq!(check("first((1, \"a\"))"), Ok(String::from("Int")));
+        assert_eq!(check("print(\"a\" + 1)"), Ok(String::from("Str")));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(
+            check("fn (x, y) => (y, x)"),
+            Ok(String::from("('a, 'b) -> ('b, 'a)"))
+        );
+        assert_eq!(
+            check("let fib = fn (n) => if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }; fib"),
+            Ok(String::from("(Int) -> Int"))
+        );
+        assert_eq!(
+            check("fn (f) => f(1) == 2"),
+            Ok(String::from("((Int) -> Int) -> Bool"))
+        );
+    }
+
+    #[test]
+    fn polymorphic_let() {
+        assert_eq!(
+            check("let id = fn (x) => x; (id(1), id(\"a\"))"),
+            Ok(String::from("(Int, Str)"))
+        );
+        assert_eq!(
+            check("let add = fn (a, b) => a + b; (add(1, 2), add(\"a\", 1))"),
+            Ok(String::from("(Int, Str)"))
+        );
+    }
+
+    #[test]
+    fn deferred_operands() {
+        assert_eq!(
+            check("fn (x) => (x + 1, x == \"a\")"),
+            Ok(String::from("(Str) -> (Str, Bool)"))
+        );
+        assert_eq!(
+            check("fn (x) => (x == (1, 2))"),
+            Err(ErrorCode::InvalidComparison)
+        );
+        assert_eq!(
+            check("fn (x) => if (x + 1 == 0) { 1 } else { if (x) { 2 } else { 3 } }"),
+            Err(ErrorCode::InvalidAddition)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(check("1 < (1, 2)"), Err(ErrorCode::InvalidComparison));
+        assert_eq!(check("1 == \"a\""), Err(ErrorCode::InvalidComparison));
+        assert_eq!(check("let x = 1; x(2)"), Err(ErrorCode::InvalidCall));
+        assert_eq!(check("true + 1"), Err(ErrorCode::InvalidAddition));
+        assert_eq!(
+            check("if (1) { 1 } else { 2 }"),
+            Err(ErrorCode::InvalidIfCondition)
+        );
+        assert_eq!(
+            check("if (true) { 1 } else { \"a\" }"),
+            Err(ErrorCode::TypeMismatch)
+        );
+        assert_eq!(check("second(1)"), Err(ErrorCode::InvalidSecond));
+        assert_eq!(check("(fn (x) => x)(1, 2)"), Err(ErrorCode::TypeMismatch));
+        assert_eq!(check("fn (x) => x(x)"), Err(ErrorCode::TypeMismatch));
+        assert_eq!(check("y"), Err(ErrorCode::UnboundVariable));
+    }
+
+    #[test]
+    fn error_positions() {
+        let source = "let nome = \"ção\";\nlet n = 1;\n  (\"é\", n - nome)";
+        let file = parse(source, "tests").unwrap();
+        let errors = typecheck(&file.expression).unwrap_err();
+
+        assert_eq!(errors[0].location.position(source), "tests:3:9");
+    }
+
+    #[test]
+    fn annotations() {
+        assert_eq!(
+            check("fn (x: int, y) => (x, y)"),
+            Ok(String::from("(Int, 'a) -> (Int, 'a)"))
+        );
+        assert_eq!(
+            check("let id: fn (str) => str = fn (x) => x; id"),
+            Ok(String::from("(Str) -> Str"))
+        );
+        assert_eq!(
+            check("let id = fn (x: str) => x; id(1)"),
+            Err(ErrorCode::TypeMismatch)
+        );
+        assert_eq!(
+            check("fn (x): bool => x + 1"),
+            Err(ErrorCode::InvalidAddition)
+        );
+        assert_eq!(check("let y: str = 1; y"), Err(ErrorCode::TypeMismatch));
+    }
+
+    #[test]
+    fn multiple_errors() {
+        assert_eq!(
+            codes("let x = y; let f = fn (a) => a - 1; (f(\"a\"), x(1, true + 1))"),
+            vec![
+                ErrorCode::UnboundVariable,
+                ErrorCode::TypeMismatch,
+                ErrorCode::InvalidAddition
+            ]
+        );
+
+        // Calls to polymorphic functions are checked again each time, but
+        // the same error is reported once.
+        assert_eq!(
+            codes("let add = fn (a, b) => a + b; (add(true, 1), add(true, 1))"),
+            vec![ErrorCode::InvalidAddition]
+        );
+    }
+
+    #[test]
+    fn error_arguments() {
+        let file = parse("let f = fn (x) => x + 1; f(true)", "tests").unwrap();
+        let errors = typecheck(&file.expression).unwrap_err();
+        let error = &errors[0];
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(error.code, ErrorCode::InvalidAddition);
+        assert_eq!(error.args, vec!["Bool", "Int"]);
+    }
+
+    #[test]
+    fn prelude() {
+        let prelude = parse("let double = fn (x) => x * 2; 0", "prelude").unwrap();
+        let file = parse("double(21)", "tests").unwrap();
+
+        let mut checker = Checker::new();
+        checker.prelude(&prelude.expression).unwrap();
+
+        assert_eq!(checker.check(&file.expression).unwrap(), Type::Int);
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt::Display;
+
+use crate::{
+    ast::Term,
+    interpreter::{Collect, Context, Interpreter},
+    messages::ErrorCode,
+};
+
+/// A way of evaluating programs that must always agree with the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// The interpreter, memoizing calls to pure functions.
+    Memoized,
+    /// The interpreter, evaluating every call.
+    Plain,
+}
+
+impl Engine {
+    pub const ALL: [Engine; 2] = [Engine::Memoized, Engine::Plain];
+
+    /// Adjusts `interpreter` to evaluate programs the way of this engine.
+    pub fn configure(self, interpreter: Interpreter<Collect>) -> Interpreter<Collect> {
+        match self {
+            Engine::Memoized => interpreter.with_memoization(true),
+            Engine::Plain => interpreter.with_memoization(false),
+        }
+    }
+}
+
+impl Display for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Engine::Memoized => write!(f, "memoized"),
+            Engine::Plain => write!(f, "plain"),
+        }
+    }
+}
+
+/// What can be observed from running a program: its result, or the code of
+/// the error it failed with, and the values it printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub result: Result<String, ErrorCode>,
+    pub prints: Vec<String>,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.result {
+            Ok(value) => write!(f, "result {value}")?,
+            Err(code) => write!(f, "error {code}")?,
+        }
+
+        write!(f, ", printed [{}]", self.prints.join(", "))
+    }
+}
+
+/// Two engines that did not agree on a program.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub expected: (Engine, Outcome),
+    pub found: (Engine, Outcome),
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (expected_engine, expected) = &self.expected;
+        let (found_engine, found) = &self.found;
+
+        write!(f, "{expected_engine}: {expected}\n{found_engine}: {found}")
+    }
+}
+
+/// Runs the prelude, if any, and then the program with `engine`.
+pub fn run(
+    engine: Engine,
+    interpreter: Interpreter<Collect>,
+    prelude: Option<&Term>,
+    program: &Term,
+) -> Outcome {
+    let mut interpreter = engine.configure(interpreter);
+    let mut context = Context::new();
+
+    let result = prelude
+        .map(|prelude| interpreter.eval(prelude.clone(), &mut context))
+        .transpose()
+        .and_then(|_| interpreter.eval(program.clone(), &mut context));
+
+    Outcome {
+        result: result
+            .map(|value| value.to_string())
+            .map_err(|error| error.code),
+        prints: interpreter.io.0,
+    }
+}
+
+/// Runs the program with every engine, each one with an interpreter built
+/// by `interpreter`, returning what they all agree on or the first
+/// divergence.
+///
+/// Limits are checked differently by each engine, memoized calls don't use
+/// fuel for example, so programs close to their limits may diverge.
+pub fn verify(
+    interpreter: impl Fn() -> Interpreter<Collect>,
+    prelude: Option<&Term>,
+    program: &Term,
+) -> Result<Outcome, Divergence> {
+    let [first, rest @ ..] = Engine::ALL;
+    let expected = run(first, interpreter(), prelude, program);
+
+    for engine in rest {
+        let found = run(engine, interpreter(), prelude, program);
+
+        if found != expected {
+            return Err(Divergence {
+                expected: (first, expected),
+                found: (engine, found),
+            });
+        }
+    }
+
+    Ok(expected)
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use crate::{
+        ast::File,
+        interpreter::{Collect, Interpreter},
+    };
+
+    use super::verify;
+
+    #[test]
+    fn examples() {
+        let mut paths = std::fs::read_dir("examples")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert!(!paths.is_empty());
+
+        for path in paths {
+            let text = std::fs::read_to_string(&path).unwrap();
+            let file: File = serde_json::from_str(&text).unwrap();
+
+            if let Err(divergence) = verify(
+                || Interpreter::new(Collect::default()),
+                None,
+                &file.expression,
+            ) {
+                panic!("{} diverged\n{divergence}", path.display());
+            }
+        }
+    }
+}
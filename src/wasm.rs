@@ -0,0 +1,89 @@
+//! JavaScript bindings, enabled by the `wasm` feature, built with
+//!
+//! ```text
+//! cargo rustc --lib --release --target wasm32-unknown-unknown \
+//!     --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg \
+//!     target/wasm32-unknown-unknown/release/lipsum.wasm
+//! ```
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    interpreter::{capture, Value},
+    parser::parse,
+};
+
+/// Outcome of [`eval`], seen from JavaScript as an object with `output`,
+/// `result` and `error` properties.
+#[wasm_bindgen]
+pub struct Evaluation {
+    output: String,
+    result: Option<String>,
+    error: Option<String>,
+}
+
+#[wasm_bindgen]
+impl Evaluation {
+    /// Everything the program printed, one value per line.
+    #[wasm_bindgen(getter)]
+    pub fn output(&self) -> String {
+        self.output.clone()
+    }
+
+    /// The final value of the program, or `undefined` when it failed.
+    #[wasm_bindgen(getter)]
+    pub fn result(&self) -> Option<String> {
+        self.result.clone()
+    }
+
+    /// The error that stopped the program, or `undefined` when it did not
+    /// fail.
+    #[wasm_bindgen(getter)]
+    pub fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+}
+
+fn evaluation(output: String, result: Result<Value, String>) -> Evaluation {
+    let (result, error) = match result {
+        Ok(value) => (Some(value.to_string()), None),
+        Err(error) => (None, Some(error)),
+    };
+
+    Evaluation {
+        output,
+        result,
+        error,
+    }
+}
+
+/// Evaluates rinha source code, collecting what it prints.
+#[wasm_bindgen]
+pub fn eval(source: &str) -> Evaluation {
+    let file = match parse(source, "playground.rinha") {
+        Ok(file) => file,
+        Err(error) => return evaluation(String::new(), Err(error.to_string())),
+    };
+
+    let (result, output) = capture(file.expression);
+
+    evaluation(output, result.map_err(|error| error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::eval;
+
+    #[test]
+    fn eval_source() {
+        let evaluation = eval("print(1 + 2)");
+
+        assert_eq!(evaluation.output(), "3\n");
+        assert_eq!(evaluation.result().as_deref(), Some("3"));
+        assert_eq!(evaluation.error(), None);
+
+        let evaluation = eval("1 +");
+        assert!(evaluation.error().unwrap().starts_with("E0110"));
+    }
+}